@@ -0,0 +1,191 @@
+//! Tracks how much stake backs a block (approvals) or a chunk
+//! (endorsements).
+//!
+//! Signature verification of accepted entries may be batched and deferred,
+//! but the all-zero placeholder signature is rejected outright on entry:
+//! even if batch verification later short-circuits, an empty signature can
+//! never have contributed stake.
+
+use near_crypto::Signature;
+use near_primitives::types::{AccountId, Balance, ValidatorStake};
+use std::collections::HashMap;
+
+/// Why a signature was not counted towards the approved stake.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ApprovalError {
+    /// The all-zero placeholder signature; it stands for a missing
+    /// approval and can never carry stake.
+    #[error("{0} submitted the empty placeholder signature")]
+    EmptySignature(AccountId),
+    #[error("{0} is not in the approver set")]
+    UnknownApprover(AccountId),
+    #[error("{0} already approved")]
+    DuplicateApproval(AccountId),
+}
+
+/// Accumulates block approvals and the stake behind them.
+pub struct ApprovalTracker {
+    stakes: HashMap<AccountId, Balance>,
+    approvals: HashMap<AccountId, Signature>,
+    approved_stake: Balance,
+    total_stake: Balance,
+}
+
+impl ApprovalTracker {
+    pub fn new(approvers: &[ValidatorStake]) -> Self {
+        let stakes: HashMap<_, _> = approvers
+            .iter()
+            .map(|approver| (approver.account_id().clone(), approver.stake()))
+            .collect();
+        let total_stake = stakes.values().sum();
+        Self { stakes, approvals: HashMap::new(), approved_stake: 0, total_stake }
+    }
+
+    /// Counts an approval towards the approved stake.
+    ///
+    /// The signature is stored for (possibly batched) verification rather
+    /// than verified here, but the empty placeholder is rejected before it
+    /// can contribute anything.
+    pub fn add_approval(
+        &mut self,
+        account_id: &AccountId,
+        signature: Signature,
+    ) -> Result<(), ApprovalError> {
+        if signature.is_empty() {
+            return Err(ApprovalError::EmptySignature(account_id.clone()));
+        }
+        let stake = *self
+            .stakes
+            .get(account_id)
+            .ok_or_else(|| ApprovalError::UnknownApprover(account_id.clone()))?;
+        if self.approvals.contains_key(account_id) {
+            return Err(ApprovalError::DuplicateApproval(account_id.clone()));
+        }
+        self.approvals.insert(account_id.clone(), signature);
+        self.approved_stake += stake;
+        Ok(())
+    }
+
+    pub fn approved_stake(&self) -> Balance {
+        self.approved_stake
+    }
+
+    pub fn total_stake(&self) -> Balance {
+        self.total_stake
+    }
+
+    /// Whether more than two thirds of the total stake approved.
+    pub fn has_enough_stake(&self) -> bool {
+        self.approved_stake * 3 > self.total_stake * 2
+    }
+}
+
+/// Accumulates the endorsements of one chunk and the stake behind them.
+///
+/// Same bookkeeping as [`ApprovalTracker`] -- including the outright
+/// rejection of empty signatures -- over the chunk's validator assignment
+/// instead of the block producer set.
+pub struct ChunkEndorsementTracker {
+    inner: ApprovalTracker,
+}
+
+impl ChunkEndorsementTracker {
+    pub fn new(chunk_validators: &[ValidatorStake]) -> Self {
+        Self { inner: ApprovalTracker::new(chunk_validators) }
+    }
+
+    pub fn add_endorsement(
+        &mut self,
+        account_id: &AccountId,
+        signature: Signature,
+    ) -> Result<(), ApprovalError> {
+        self.inner.add_approval(account_id, signature)
+    }
+
+    pub fn endorsed_stake(&self) -> Balance {
+        self.inner.approved_stake()
+    }
+
+    /// Whether more than two thirds of the assigned stake endorsed.
+    pub fn is_endorsed(&self) -> bool {
+        self.inner.has_enough_stake()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_crypto::{KeyType, SecretKey};
+
+    fn approvers(stakes: &[(&str, Balance)]) -> Vec<ValidatorStake> {
+        stakes
+            .iter()
+            .map(|(name, stake)| {
+                let public_key = SecretKey::from_seed(KeyType::ED25519, name).public_key();
+                ValidatorStake::new(name.parse().unwrap(), public_key, *stake)
+            })
+            .collect()
+    }
+
+    fn signature_of(name: &str) -> Signature {
+        SecretKey::from_seed(KeyType::ED25519, name).sign(b"approval")
+    }
+
+    #[test]
+    fn test_empty_signature_contributes_no_stake() {
+        let mut tracker = ApprovalTracker::new(&approvers(&[("alice", 60), ("bob", 40)]));
+        let alice = "alice".parse().unwrap();
+        assert_eq!(
+            tracker.add_approval(&alice, Signature::default()),
+            Err(ApprovalError::EmptySignature(alice))
+        );
+        assert_eq!(tracker.approved_stake(), 0);
+        assert!(!tracker.has_enough_stake());
+    }
+
+    #[test]
+    fn test_approvals_accumulate_stake_once_per_approver() {
+        let mut tracker = ApprovalTracker::new(&approvers(&[("alice", 60), ("bob", 40)]));
+        let alice: AccountId = "alice".parse().unwrap();
+        tracker.add_approval(&alice, signature_of("alice")).unwrap();
+        assert_eq!(tracker.approved_stake(), 60);
+        // 60 of 100 is not more than two thirds yet.
+        assert!(!tracker.has_enough_stake());
+
+        assert_eq!(
+            tracker.add_approval(&alice, signature_of("alice")),
+            Err(ApprovalError::DuplicateApproval(alice))
+        );
+        assert_eq!(tracker.approved_stake(), 60);
+
+        tracker.add_approval(&"bob".parse().unwrap(), signature_of("bob")).unwrap();
+        assert_eq!(tracker.approved_stake(), 100);
+        assert!(tracker.has_enough_stake());
+    }
+
+    #[test]
+    fn test_unknown_approver_is_rejected() {
+        let mut tracker = ApprovalTracker::new(&approvers(&[("alice", 60)]));
+        let eve: AccountId = "eve".parse().unwrap();
+        assert_eq!(
+            tracker.add_approval(&eve, signature_of("eve")),
+            Err(ApprovalError::UnknownApprover(eve))
+        );
+        assert_eq!(tracker.approved_stake(), 0);
+    }
+
+    #[test]
+    fn test_endorsements_reject_empty_signature() {
+        let mut tracker = ChunkEndorsementTracker::new(&approvers(&[("alice", 50), ("bob", 50)]));
+        let alice: AccountId = "alice".parse().unwrap();
+        assert_eq!(
+            tracker.add_endorsement(&alice, Signature::default()),
+            Err(ApprovalError::EmptySignature(alice.clone()))
+        );
+        assert_eq!(tracker.endorsed_stake(), 0);
+
+        tracker.add_endorsement(&alice, signature_of("alice")).unwrap();
+        tracker.add_endorsement(&"bob".parse().unwrap(), signature_of("bob")).unwrap();
+        assert!(tracker.is_endorsed());
+    }
+}
@@ -0,0 +1,172 @@
+use crate::chain::Chain;
+use crate::error::Error;
+use near_epoch_manager::{EpochManager, EpochManagerAdapter};
+use near_primitives::block::Block;
+use near_primitives::block_body::{BlockBody, ChunkEndorsementsBitmap};
+use near_primitives::block_header::{
+    BlockHeader, BlockHeaderInnerLite, BlockHeaderInnerRestV5,
+};
+use near_primitives::errors::EpochError;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{BlockHeight, ProtocolVersion};
+use near_primitives::validator_signer::{InMemoryValidatorSigner, ValidatorSigner};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The protocol version this client runs.
+pub const PROTOCOL_VERSION: ProtocolVersion = 1;
+
+/// Orchestrates block production: wires the chain state, the epoch manager
+/// queries and the validator signer into a single `produce_block` entry
+/// point. Pure orchestration -- all protocol logic lives in the pieces it
+/// calls.
+pub struct BlockProducer {
+    chain: Chain,
+    epoch_manager: EpochManager,
+    signer: InMemoryValidatorSigner,
+}
+
+impl BlockProducer {
+    pub fn new(chain: Chain, epoch_manager: EpochManager, signer: InMemoryValidatorSigner) -> Self {
+        Self { chain, epoch_manager, signer }
+    }
+
+    pub fn chain(&self) -> &Chain {
+        &self.chain
+    }
+
+    pub fn epoch_manager(&self) -> &EpochManager {
+        &self.epoch_manager
+    }
+
+    /// Produces the block at the given height on top of the current head, or
+    /// returns `None` if this node is not the expected producer for the
+    /// height.
+    pub fn produce_block(&mut self, height: BlockHeight) -> Result<Option<Block>, Error> {
+        let prev_hash = *self.chain.head().hash();
+        self.produce_block_on(prev_hash, height)
+    }
+
+    /// Produces the block at the given height on top of an explicit previous
+    /// block, used by fork tests to grow non-head branches.
+    pub fn produce_block_on(
+        &mut self,
+        prev_hash: CryptoHash,
+        height: BlockHeight,
+    ) -> Result<Option<Block>, Error> {
+        let prev = self.chain.get_block(&prev_hash)?;
+        let prev_height = prev.header().height();
+        if height <= prev_height {
+            return Err(Error::InvalidBlockHeight { got: height, prev: prev_height });
+        }
+
+        let epoch_id = self.chain.epoch_id_at(height);
+        let next_epoch_id = self.chain.epoch_id_at(height + self.chain.epoch_length());
+        let epoch_info = self
+            .epoch_manager
+            .get_epoch_info_if_exists(&epoch_id)?
+            .ok_or(EpochError::EpochOutOfBounds(epoch_id))?;
+
+        // Not our turn -- nothing to do.
+        let settlement = epoch_info.block_producers_settlement();
+        let producer_id = settlement[(height % settlement.len() as u64) as usize];
+        let producer =
+            epoch_info.get_validator(producer_id).ok_or(EpochError::EpochOutOfBounds(epoch_id))?;
+        if producer.account_id() != self.signer.validator_id() {
+            return Ok(None);
+        }
+
+        // Carry over the chunk headers of the previous block; a real node
+        // would swap in freshly produced chunks here.
+        let chunks = prev.chunks().to_vec();
+        let chunk_mask = vec![false; chunks.len()];
+        let chunk_endorsements = vec![vec![]; chunks.len()];
+
+        // Randomness beacon: evaluate our VRF on the previous random value.
+        let (vrf_value, vrf_proof) =
+            self.signer.compute_vrf_with_proof(prev.header().random_value().as_ref());
+        let body = BlockBody::new(chunks, vrf_value, vrf_proof, chunk_endorsements);
+        let random_value = body.compute_randomness();
+
+        let timestamp_now =
+            SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64;
+        let inner_lite = BlockHeaderInnerLite {
+            height,
+            epoch_id,
+            next_epoch_id,
+            prev_state_root: *prev.header().prev_state_root(),
+            prev_outcome_root: *prev.header().prev_outcome_root(),
+            timestamp: timestamp_now.max(prev.header().timestamp() + 1),
+            next_bp_hash: CryptoHash::hash_borsh(&next_epoch_id),
+            block_merkle_root: CryptoHash::hash_borsh(&(
+                prev.header().block_merkle_root(),
+                prev_hash,
+            )),
+        };
+        let inner_rest = BlockHeaderInnerRestV5 {
+            block_body_hash: CryptoHash::hash_borsh(&body),
+            prev_chunk_outgoing_receipts_root: CryptoHash::hash_borsh(
+                &body.chunks().iter().map(|c| *c.prev_outgoing_receipts_root()).collect::<Vec<_>>(),
+            ),
+            chunk_headers_root: CryptoHash::hash_borsh(&body.chunks()),
+            chunk_tx_root: CryptoHash::hash_borsh(
+                &body.chunks().iter().map(|c| *c.tx_root()).collect::<Vec<_>>(),
+            ),
+            random_value,
+            prev_validator_proposals: vec![],
+            chunk_mask,
+            challenges_result: vec![],
+            gas_price: prev.header().gas_price(),
+            total_supply: prev.header().total_supply(),
+            approvals: vec![None; settlement.len()],
+            latest_protocol_version: PROTOCOL_VERSION,
+            chunk_endorsements: ChunkEndorsementsBitmap::from_endorsement_signatures(
+                body.chunk_endorsements(),
+            ),
+        };
+
+        let hash = BlockHeader::compute_hash(&prev_hash, &inner_lite, &inner_rest);
+        let signature = self.signer.sign_bytes(hash.as_ref());
+        let header = BlockHeader::new(prev_hash, inner_lite, inner_rest, signature);
+        Ok(Some(Block::new(header, body)))
+    }
+
+    /// Validates and applies a block, advancing the chain head.
+    pub fn process_block(&mut self, block: Block) -> Result<(), Error> {
+        self.chain.process_block(&mut self.epoch_manager, block)
+    }
+
+    /// Runs the chain invariant battery; see [`Chain::check_all`].
+    pub fn check_all(&mut self) -> Result<(), crate::invariants::InvariantViolation> {
+        let Self { chain, epoch_manager, .. } = self;
+        chain.check_all(epoch_manager)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::TestChainBuilder;
+
+    #[test]
+    fn test_produce_and_process_blocks_across_epochs() {
+        let mut producer = TestChainBuilder::new().epoch_length(5).validators(&["test0"]).build();
+        for height in 1..=10 {
+            let block = producer.produce_block(height).unwrap().expect("sole producer");
+            producer.process_block(block).unwrap();
+            assert_eq!(producer.chain().head_header().height(), height);
+        }
+        // Heights 1..=10 span more than one epoch.
+        let chain = producer.chain();
+        assert!(chain.epoch_ordinal_at(10) > chain.epoch_ordinal_at(1));
+        assert_eq!(chain.final_head_height(), 8);
+    }
+
+    #[test]
+    fn test_produce_block_returns_none_when_not_our_turn() {
+        let mut producer =
+            TestChainBuilder::new().epoch_length(5).validators(&["test0", "test1"]).build();
+        // The settlement alternates producers by height; test0 produces the
+        // even heights.
+        assert!(producer.produce_block(1).unwrap().is_none());
+        assert!(producer.produce_block(2).unwrap().is_some());
+    }
+}
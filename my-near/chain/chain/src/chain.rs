@@ -0,0 +1,295 @@
+use crate::error::Error;
+use crate::invariants::ChainInvariantChecker;
+use near_epoch_manager::{EpochManager, EpochManagerAdapter};
+use near_primitives::block::Block;
+use near_primitives::block_header::BlockHeader;
+use near_primitives::chunk_extra::ChunkExtra;
+use near_primitives::epoch_manager::block_info::BlockInfo;
+use near_primitives::errors::EpochError;
+use near_primitives::height_math;
+use near_primitives::hash::{CryptoHash, hash};
+use near_primitives::types::{BlockHeight, EpochId, ShardId};
+use near_store::{DBCol, Store};
+use std::collections::{BTreeMap, HashMap};
+
+/// How many blocks behind the head the final head trails in this simplified
+/// finality gadget.
+const FINALITY_DEPTH: BlockHeight = 2;
+
+/// The canonical chain of blocks.
+pub struct Chain {
+    epoch_length: BlockHeight,
+    genesis_hash: CryptoHash,
+    pub(crate) blocks: HashMap<CryptoHash, Block>,
+    /// Canonical chain index: height -> block hash, covering exactly the
+    /// blocks reachable from the head.
+    pub(crate) height_to_hash: BTreeMap<BlockHeight, CryptoHash>,
+    pub(crate) head: CryptoHash,
+    final_head_height: BlockHeight,
+    /// Outcome of applying each chunk, keyed by `(block hash, shard id)`.
+    pub(crate) chunk_extras: HashMap<(CryptoHash, ShardId), ChunkExtra>,
+    /// Processed headers, bodies and the canonical index are mirrored into
+    /// this store, so read-only replicas sharing it can serve queries.
+    store: Store,
+    /// Runs the invariant battery after every processed block; tests only.
+    invariant_checker: Option<ChainInvariantChecker>,
+}
+
+impl Chain {
+    pub fn new(genesis: Block, epoch_length: BlockHeight) -> Self {
+        Self::with_store(genesis, epoch_length, Store::new())
+    }
+
+    /// A chain mirroring processed blocks into the given store; replicas
+    /// built with [`crate::read_only::ChainReadOnly`] can share it.
+    pub fn with_store(genesis: Block, epoch_length: BlockHeight, store: Store) -> Self {
+        let genesis_hash = *genesis.hash();
+        let genesis_height = genesis.header().height();
+        let mut blocks = HashMap::new();
+        blocks.insert(genesis_hash, genesis);
+        let mut height_to_hash = BTreeMap::new();
+        height_to_hash.insert(genesis_height, genesis_hash);
+        let chain = Self {
+            epoch_length,
+            genesis_hash,
+            blocks,
+            height_to_hash,
+            head: genesis_hash,
+            final_head_height: genesis_height,
+            chunk_extras: HashMap::new(),
+            store,
+            invariant_checker: None,
+        };
+        chain.persist_block(&chain.blocks[&genesis_hash]);
+        chain.persist_canonical_index();
+        chain
+    }
+
+    /// A chain that panics on the first violated invariant after every
+    /// processed block; see [`ChainInvariantChecker`]. For tests.
+    pub fn with_invariant_checks(genesis: Block, epoch_length: BlockHeight) -> Self {
+        let mut chain = Self::new(genesis, epoch_length);
+        chain.invariant_checker = Some(ChainInvariantChecker::default());
+        chain
+    }
+
+    /// Runs the full invariant battery against the current state; see
+    /// [`ChainInvariantChecker::check_all`].
+    pub fn check_all(
+        &mut self,
+        epoch_manager: &EpochManager,
+    ) -> Result<(), crate::invariants::InvariantViolation> {
+        match self.invariant_checker.take() {
+            Some(mut checker) => {
+                let result = checker.check_all(self, epoch_manager);
+                self.invariant_checker = Some(checker);
+                result
+            }
+            // One-off check without final-head memory across calls.
+            None => ChainInvariantChecker::default().check_all(self, epoch_manager),
+        }
+    }
+
+    pub fn epoch_length(&self) -> BlockHeight {
+        self.epoch_length
+    }
+
+    /// The store processed blocks are mirrored into.
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    /// Mirrors the header and body of a processed block into the store.
+    fn persist_block(&self, block: &Block) {
+        let mut update = self.store.store_update();
+        let key = block.hash().as_ref();
+        update.set_ser(DBCol::BlockHeader, key, block.header()).expect("serialization is infallible");
+        update.set_ser(DBCol::Block, key, block.body()).expect("serialization is infallible");
+        update.commit().expect("in-memory store commit cannot fail");
+    }
+
+    /// Mirrors the canonical height index into the store, dropping entries
+    /// of a branch that lost fork choice.
+    fn persist_canonical_index(&self) {
+        let mut update = self.store.store_update();
+        for (height, _) in self.store.iter(DBCol::BlockHeight) {
+            update.delete(DBCol::BlockHeight, &height);
+        }
+        for (height, hash) in &self.height_to_hash {
+            update.set(DBCol::BlockHeight, &height.to_le_bytes(), hash.as_ref().to_vec());
+        }
+        update.commit().expect("in-memory store commit cannot fail");
+    }
+
+    pub fn genesis_hash(&self) -> &CryptoHash {
+        &self.genesis_hash
+    }
+
+    pub fn head(&self) -> &Block {
+        &self.blocks[&self.head]
+    }
+
+    pub fn head_header(&self) -> &BlockHeader {
+        self.head().header()
+    }
+
+    pub fn final_head_height(&self) -> BlockHeight {
+        self.final_head_height
+    }
+
+    pub fn get_block(&self, hash: &CryptoHash) -> Result<&Block, Error> {
+        self.blocks.get(hash).ok_or(Error::BlockMissing(*hash))
+    }
+
+    pub fn get_block_by_height(&self, height: BlockHeight) -> Result<&Block, Error> {
+        let hash = self
+            .height_to_hash
+            .get(&height)
+            .ok_or(Error::BlockMissing(CryptoHash::default()))?;
+        self.get_block(hash)
+    }
+
+    /// The recorded outcome of applying the chunk of the given shard in the
+    /// given block.
+    pub fn get_chunk_extra(&self, block_hash: &CryptoHash, shard_id: ShardId) -> Option<&ChunkExtra> {
+        self.chunk_extras.get(&(*block_hash, shard_id))
+    }
+
+    /// Height of the genesis block; this chain starts at height zero.
+    pub fn genesis_height(&self) -> BlockHeight {
+        0
+    }
+
+    /// Ordinal of the epoch that contains blocks at the given height.
+    pub fn epoch_ordinal_at(&self, height: BlockHeight) -> u64 {
+        height_math::epoch_index_for_height(height, self.genesis_height(), self.epoch_length)
+            .expect("processed heights are at or above genesis")
+    }
+
+    /// First height of the epoch that contains blocks at the given height.
+    pub fn epoch_start_at(&self, height: BlockHeight) -> BlockHeight {
+        height_math::first_height_of_epoch(
+            self.epoch_ordinal_at(height),
+            self.genesis_height(),
+            self.epoch_length,
+        )
+        .expect("the epoch of an existing height starts at a valid height")
+    }
+
+    /// The epoch id of blocks at the given height.
+    ///
+    /// In this simplified chain the epoch id is derived from the epoch
+    /// ordinal rather than from a past block hash.
+    pub fn epoch_id_at(&self, height: BlockHeight) -> EpochId {
+        EpochId(hash(&self.epoch_ordinal_at(height).to_le_bytes()))
+    }
+
+    /// Whether a block at the given height is the last block of its epoch.
+    pub fn is_epoch_end(&self, height: BlockHeight) -> bool {
+        height_math::is_epoch_boundary(height, self.epoch_start_at(height), self.epoch_length)
+    }
+
+    /// Validates a block against the chain and the epoch manager, stores it
+    /// and advances the head and final head.
+    pub fn process_block(
+        &mut self,
+        epoch_manager: &mut EpochManager,
+        block: Block,
+    ) -> Result<(), Error> {
+        let header = block.header();
+        let height = header.height();
+
+        if !header.check_hash() {
+            return Err(Error::InvalidBlockHash(height));
+        }
+        if !block.check_block_body_hash() {
+            return Err(Error::InvalidBlockBody);
+        }
+
+        let prev = self
+            .blocks
+            .get(header.prev_hash())
+            .ok_or(Error::Orphan(*header.prev_hash()))?;
+        let prev_height = prev.header().height();
+        if height <= prev_height {
+            return Err(Error::InvalidBlockHeight { got: height, prev: prev_height });
+        }
+
+        let epoch_id = self.epoch_id_at(height);
+        if header.epoch_id() != &epoch_id {
+            return Err(Error::InvalidEpochId(height));
+        }
+
+        // The producer must be the one the epoch settlement expects for this
+        // height, and must have signed the header and the randomness beacon.
+        let epoch_info = epoch_manager
+            .get_epoch_info_if_exists(&epoch_id)?
+            .ok_or(EpochError::EpochOutOfBounds(epoch_id))?;
+        let settlement = epoch_info.block_producers_settlement();
+        let producer_id = settlement[(height % settlement.len() as u64) as usize];
+        let producer = epoch_info
+            .get_validator(producer_id)
+            .ok_or(EpochError::EpochOutOfBounds(epoch_id))?;
+        if !header.verify_block_producer(producer.public_key()) {
+            return Err(Error::InvalidSignature);
+        }
+        let prev_random_value = *prev.header().random_value();
+        if !block.body().verify_vrf(producer.public_key(), prev_random_value.as_ref())
+            || &block.body().compute_randomness() != header.random_value()
+        {
+            return Err(Error::InvalidRandomnessBeacon);
+        }
+
+        let hash = *block.hash();
+        // "Apply" the chunks: record a chunk extra per shard of the block.
+        // Chunks are carried over in this simplified chain, so application
+        // reproduces the state root the header already commits to.
+        for chunk in block.chunks() {
+            let extra = ChunkExtra::new(*chunk.prev_state_root(), chunk.prev_gas_used());
+            self.chunk_extras.insert((hash, chunk.shard_id()), extra);
+        }
+        let block_info = BlockInfo::from_header(block.header(), self.final_head_height);
+        self.persist_block(&block);
+        self.blocks.insert(hash, block);
+
+        // Fork choice: the highest block wins; ties keep the current head.
+        if height > self.head_header().height() {
+            self.head = hash;
+            self.rebuild_canonical_index();
+            self.persist_canonical_index();
+        }
+
+        self.final_head_height =
+            self.final_head_height.max(self.head_header().height().saturating_sub(FINALITY_DEPTH));
+        epoch_manager.update_largest_final_height(self.final_head_height);
+        epoch_manager.save_epoch_start_height(&epoch_id, self.epoch_start_at(height));
+        if self.is_epoch_end(height) {
+            epoch_manager.save_epoch_end_height(&epoch_id, height);
+        }
+        epoch_manager.record_block_info(block_info)?;
+
+        if let Some(mut checker) = self.invariant_checker.take() {
+            let result = checker.check_all(self, epoch_manager);
+            self.invariant_checker = Some(checker);
+            if let Err(violation) = result {
+                panic!("{violation}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Recomputes the canonical height index by walking back from the head,
+    /// dropping entries of the branch that just lost fork choice.
+    fn rebuild_canonical_index(&mut self) {
+        self.height_to_hash.clear();
+        let mut current = self.head;
+        loop {
+            let block = &self.blocks[&current];
+            self.height_to_hash.insert(block.header().height(), current);
+            if current == self.genesis_hash {
+                break;
+            }
+            current = *block.header().prev_hash();
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Bookkeeping of the chunk production lifecycle, for the debug RPC.
+//!
+//! For every expected chunk -- keyed by [`ChunkProductionKey`] -- the ledger
+//! records who was supposed to produce it and how far it got through the
+//! produce -> endorse -> include lifecycle. The ledger only keeps a bounded
+//! window of recent heights; entries older than the window are evicted as
+//! new heights arrive.
+
+use near_primitives::sharding::ChunkProductionKey;
+use near_primitives::types::{AccountId, BlockHeight};
+use std::collections::BTreeMap;
+
+/// How far a chunk got through its production lifecycle.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChunkProductionStatus {
+    /// The producer the epoch settlement assigned to this chunk.
+    pub expected_producer: AccountId,
+    /// Whether the producer's header arrived.
+    pub header_received: bool,
+    /// Whether endorsements reached the stake threshold.
+    pub endorsed: bool,
+    /// Whether the chunk ended up included in a block.
+    pub included: bool,
+}
+
+/// Records the production status of recent chunks.
+pub struct ChunkProductionLedger {
+    /// Heights kept before eviction; entries more than this many heights
+    /// behind the newest recorded one are dropped.
+    num_recent_heights: BlockHeight,
+    statuses: BTreeMap<ChunkProductionKey, ChunkProductionStatus>,
+    newest_height: BlockHeight,
+}
+
+impl ChunkProductionLedger {
+    pub fn new(num_recent_heights: BlockHeight) -> Self {
+        assert!(num_recent_heights > 0, "the ledger must keep at least one height");
+        Self { num_recent_heights, statuses: BTreeMap::new(), newest_height: 0 }
+    }
+
+    /// Opens an entry for a chunk the epoch settlement expects, recording
+    /// its assigned producer. Must be called before the lifecycle updates
+    /// below; they ignore keys that were never expected.
+    pub fn record_expected(&mut self, key: ChunkProductionKey, expected_producer: AccountId) {
+        self.statuses.insert(
+            key,
+            ChunkProductionStatus {
+                expected_producer,
+                header_received: false,
+                endorsed: false,
+                included: false,
+            },
+        );
+        if key.height_created > self.newest_height {
+            self.newest_height = key.height_created;
+            let cutoff = self.newest_height.saturating_sub(self.num_recent_heights);
+            self.statuses.retain(|key, _| key.height_created > cutoff);
+        }
+    }
+
+    pub fn record_header_received(&mut self, key: &ChunkProductionKey) {
+        if let Some(status) = self.statuses.get_mut(key) {
+            status.header_received = true;
+        }
+    }
+
+    pub fn record_endorsed(&mut self, key: &ChunkProductionKey) {
+        if let Some(status) = self.statuses.get_mut(key) {
+            status.endorsed = true;
+        }
+    }
+
+    pub fn record_included(&mut self, key: &ChunkProductionKey) {
+        if let Some(status) = self.statuses.get_mut(key) {
+            status.included = true;
+        }
+    }
+
+    /// The recorded status of the given chunk; `None` once it fell out of
+    /// the recent window (or was never expected).
+    pub fn get_chunk_production_status(
+        &self,
+        key: &ChunkProductionKey,
+    ) -> Option<&ChunkProductionStatus> {
+        self.statuses.get(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_primitives::hash::hash;
+    use near_primitives::types::EpochId;
+
+    fn key(height_created: BlockHeight, shard_id: u64) -> ChunkProductionKey {
+        ChunkProductionKey { epoch_id: EpochId(hash(b"epoch")), height_created, shard_id }
+    }
+
+    #[test]
+    fn test_lifecycle_transitions() {
+        let mut ledger = ChunkProductionLedger::new(10);
+        let key = key(5, 0);
+        ledger.record_expected(key, "alice".parse().unwrap());
+
+        let status = ledger.get_chunk_production_status(&key).unwrap();
+        assert_eq!(status.expected_producer.as_str(), "alice");
+        assert!(!status.header_received && !status.endorsed && !status.included);
+
+        ledger.record_header_received(&key);
+        assert!(ledger.get_chunk_production_status(&key).unwrap().header_received);
+        assert!(!ledger.get_chunk_production_status(&key).unwrap().endorsed);
+
+        ledger.record_endorsed(&key);
+        assert!(ledger.get_chunk_production_status(&key).unwrap().endorsed);
+        assert!(!ledger.get_chunk_production_status(&key).unwrap().included);
+
+        ledger.record_included(&key);
+        assert!(ledger.get_chunk_production_status(&key).unwrap().included);
+    }
+
+    #[test]
+    fn test_updates_for_unexpected_chunks_are_ignored() {
+        let mut ledger = ChunkProductionLedger::new(10);
+        ledger.record_header_received(&key(5, 0));
+        assert_eq!(ledger.get_chunk_production_status(&key(5, 0)), None);
+    }
+
+    #[test]
+    fn test_old_heights_are_evicted() {
+        let mut ledger = ChunkProductionLedger::new(3);
+        for height in 1..=10 {
+            ledger.record_expected(key(height, 0), "alice".parse().unwrap());
+        }
+        // Only the window of the 3 newest heights survives.
+        for height in 1..=7 {
+            assert_eq!(ledger.get_chunk_production_status(&key(height, 0)), None);
+        }
+        for height in 8..=10 {
+            assert!(ledger.get_chunk_production_status(&key(height, 0)).is_some());
+        }
+    }
+}
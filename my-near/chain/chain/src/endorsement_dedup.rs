@@ -0,0 +1,172 @@
+//! Deduplication of gossiped chunk endorsements.
+//!
+//! Every chunk endorsement reaches a node several times, once per gossip
+//! peer. The cache in this module remembers which `(chunk hash, validator)`
+//! pairs have been seen so the processing path can drop duplicates before
+//! paying for signature verification. Entries are bucketed by height, so
+//! eviction as the head advances is O(1) amortized, and each bucket is
+//! capped so a flood of unique fake endorsements cannot grow the cache
+//! beyond `horizon * cap` entries.
+
+use near_primitives::sharding::{ChunkEndorsement, ChunkHash};
+use near_primitives::types::{AccountId, BlockHeight};
+use std::collections::{BTreeMap, HashSet};
+
+/// Counters the cache exposes for monitoring.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DedupCacheStats {
+    /// Endorsements admitted as new.
+    pub inserted: u64,
+    /// Endorsements rejected as already seen.
+    pub duplicates: u64,
+    /// Endorsements dropped because their height bucket was full.
+    pub overflow_dropped: u64,
+}
+
+impl DedupCacheStats {
+    /// Fraction of lookups answered from the cache, in `0.0..=1.0`.
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.inserted + self.duplicates;
+        if lookups == 0 { 0.0 } else { self.duplicates as f64 / lookups as f64 }
+    }
+}
+
+/// Remembers recently seen chunk endorsements, keyed by chunk hash and
+/// endorsing account, with height-based eviction.
+pub struct EndorsementDedupCache {
+    /// Heights further than this behind the head are evicted.
+    horizon: BlockHeight,
+    /// Maximum entries admitted per height; the rest are dropped.
+    per_height_cap: usize,
+    seen: HashSet<(ChunkHash, AccountId)>,
+    /// The keys admitted at each height, for bulk eviction.
+    buckets: BTreeMap<BlockHeight, Vec<(ChunkHash, AccountId)>>,
+    stats: DedupCacheStats,
+}
+
+impl EndorsementDedupCache {
+    pub fn new(horizon: BlockHeight, per_height_cap: usize) -> Self {
+        Self {
+            horizon,
+            per_height_cap,
+            seen: HashSet::new(),
+            buckets: BTreeMap::new(),
+            stats: DedupCacheStats::default(),
+        }
+    }
+
+    /// Records the endorsement if it has not been seen yet; returns whether
+    /// the caller should process it. An endorsement dropped because its
+    /// height bucket is full also returns `false` -- under that kind of
+    /// flood, processing unverifiable extras is the wrong side to err on.
+    pub fn insert_if_new(&mut self, endorsement: &ChunkEndorsement, height: BlockHeight) -> bool {
+        let key = (endorsement.chunk_hash.clone(), endorsement.account_id.clone());
+        if self.seen.contains(&key) {
+            self.stats.duplicates += 1;
+            return false;
+        }
+        let bucket = self.buckets.entry(height).or_default();
+        if bucket.len() >= self.per_height_cap {
+            self.stats.overflow_dropped += 1;
+            return false;
+        }
+        bucket.push(key.clone());
+        self.seen.insert(key);
+        self.stats.inserted += 1;
+        true
+    }
+
+    /// Evicts every entry at heights older than `head_height - horizon`.
+    pub fn update_head(&mut self, head_height: BlockHeight) {
+        let oldest_kept = head_height.saturating_sub(self.horizon);
+        while let Some(entry) = self.buckets.first_entry() {
+            if *entry.key() >= oldest_kept {
+                break;
+            }
+            for key in entry.remove() {
+                self.seen.remove(&key);
+            }
+        }
+    }
+
+    /// Number of endorsements currently remembered.
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    pub fn stats(&self) -> &DedupCacheStats {
+        &self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_crypto::Signature;
+    use near_primitives::hash::hash;
+
+    fn endorsement(chunk: &str, account: &str) -> ChunkEndorsement {
+        ChunkEndorsement {
+            chunk_hash: ChunkHash(hash(chunk.as_bytes())),
+            account_id: account.parse().unwrap(),
+            signature: Signature::default(),
+        }
+    }
+
+    #[test]
+    fn test_duplicates_are_filtered() {
+        let mut cache = EndorsementDedupCache::new(10, 100);
+        let first = endorsement("chunk1", "alice");
+
+        assert!(cache.insert_if_new(&first, 5));
+        // The same pair again -- even at another height -- is a duplicate.
+        assert!(!cache.insert_if_new(&first, 5));
+        assert!(!cache.insert_if_new(&first, 6));
+        // Another validator endorsing the same chunk is new.
+        assert!(cache.insert_if_new(&endorsement("chunk1", "bob"), 5));
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.stats().inserted, 2);
+        assert_eq!(cache.stats().duplicates, 2);
+        assert_eq!(cache.stats().hit_rate(), 0.5);
+    }
+
+    #[test]
+    fn test_eviction_follows_the_head() {
+        let mut cache = EndorsementDedupCache::new(2, 100);
+        for height in 1..=5 {
+            let chunk = format!("chunk{height}");
+            assert!(cache.insert_if_new(&endorsement(&chunk, "alice"), height));
+        }
+        assert_eq!(cache.len(), 5);
+
+        // Head at 5 with horizon 2 keeps heights 3..=5.
+        cache.update_head(5);
+        assert_eq!(cache.len(), 3);
+        // Evicted endorsements count as new again if they somehow reappear.
+        assert!(cache.insert_if_new(&endorsement("chunk1", "alice"), 5));
+
+        cache.update_head(100);
+        assert_eq!(cache.len(), 0);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_per_height_cap_bounds_adversarial_floods() {
+        let mut cache = EndorsementDedupCache::new(10, 3);
+        for i in 0..50 {
+            let chunk = format!("fake{i}");
+            let admitted = cache.insert_if_new(&endorsement(&chunk, "mallory"), 7);
+            assert_eq!(admitted, i < 3, "entry {i} past the cap must be dropped");
+        }
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.stats().overflow_dropped, 47);
+
+        // Other heights have their own budget.
+        assert!(cache.insert_if_new(&endorsement("fake50", "mallory"), 8));
+    }
+}
@@ -0,0 +1,57 @@
+use near_primitives::errors::EpochError;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::BlockHeight;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The previous block of the processed block is not known.
+    #[error("orphan block: previous block {0} is missing")]
+    Orphan(CryptoHash),
+    /// The block is not known.
+    #[error("block {0} is missing")]
+    BlockMissing(CryptoHash),
+    #[error("invalid block height {got}, previous block is at {prev}")]
+    InvalidBlockHeight { got: BlockHeight, prev: BlockHeight },
+    #[error("header hash does not match header contents for block at height {0}")]
+    InvalidBlockHash(BlockHeight),
+    #[error("block body does not match the hash in the header")]
+    InvalidBlockBody,
+    #[error("invalid epoch id for block at height {0}")]
+    InvalidEpochId(BlockHeight),
+    #[error("invalid block producer signature")]
+    InvalidSignature,
+    #[error("invalid randomness beacon output")]
+    InvalidRandomnessBeacon,
+    #[error(transparent)]
+    EpochError(#[from] EpochError),
+    /// Error from the underlying storage.
+    #[error("IO error: {0}")]
+    IOErr(String),
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::IOErr(error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_primitives::hash::hash;
+    use near_primitives::types::EpochId;
+
+    #[test]
+    fn test_epoch_error_converts_transparently() {
+        // `?` on an epoch manager call lifts the error unchanged...
+        let epoch_id = EpochId(hash(b"epoch"));
+        let lifted = || -> Result<(), Error> {
+            Err(EpochError::EpochOutOfBounds(epoch_id))?;
+            Ok(())
+        }()
+        .unwrap_err();
+        assert_eq!(lifted, Error::EpochError(EpochError::EpochOutOfBounds(epoch_id)));
+        // ...and Display shows the inner message without a wrapper prefix.
+        assert_eq!(lifted.to_string(), EpochError::EpochOutOfBounds(epoch_id).to_string());
+    }
+}
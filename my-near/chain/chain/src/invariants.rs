@@ -0,0 +1,257 @@
+//! A battery of chain invariants for tests.
+//!
+//! A [`Chain`] built with [`Chain::with_invariant_checks`] runs the whole
+//! battery after every processed block and panics on the first violation;
+//! [`Chain::check_all`] runs it on demand. Violations name the invariant and
+//! the offending hash or height so a failing stress test points straight at
+//! the bug.
+
+use crate::chain::Chain;
+use near_epoch_manager::EpochManager;
+use near_primitives::types::BlockHeight;
+use std::collections::BTreeMap;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("invariant `{invariant}` violated: {details}")]
+pub struct InvariantViolation {
+    pub invariant: &'static str,
+    pub details: String,
+}
+
+fn violation(invariant: &'static str, details: String) -> Result<(), InvariantViolation> {
+    Err(InvariantViolation { invariant, details })
+}
+
+/// Verifies structural invariants of a [`Chain`] and its epoch manager.
+///
+/// The checker is stateful: it remembers the final head height it saw last
+/// so it can detect the final head moving backwards across checks.
+#[derive(Default)]
+pub struct ChainInvariantChecker {
+    last_final_head_height: BlockHeight,
+}
+
+impl ChainInvariantChecker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn check_all(
+        &mut self,
+        chain: &Chain,
+        epoch_manager: &EpochManager,
+    ) -> Result<(), InvariantViolation> {
+        self.check_head_is_max_canonical_height(chain)?;
+        self.check_canonical_index_matches_head_ancestry(chain)?;
+        self.check_final_head(chain)?;
+        self.check_block_integrity(chain)?;
+        self.check_chunk_extras(chain)?;
+        self.check_epoch_start_records(chain, epoch_manager)?;
+        Ok(())
+    }
+
+    fn check_head_is_max_canonical_height(&self, chain: &Chain) -> Result<(), InvariantViolation> {
+        let head_height = chain.head_header().height();
+        let max_height =
+            *chain.height_to_hash.last_key_value().expect("the index always has genesis").0;
+        if head_height != max_height {
+            return violation(
+                "head-is-max-canonical-height",
+                format!("head is at height {head_height} but the index goes up to {max_height}"),
+            );
+        }
+        Ok(())
+    }
+
+    fn check_canonical_index_matches_head_ancestry(
+        &self,
+        chain: &Chain,
+    ) -> Result<(), InvariantViolation> {
+        let mut ancestry = BTreeMap::new();
+        let mut current = chain.head;
+        loop {
+            let block = match chain.blocks.get(&current) {
+                Some(block) => block,
+                None => {
+                    return violation(
+                        "canonical-index-matches-head-ancestry",
+                        format!("ancestor {current} of the head is not stored"),
+                    );
+                }
+            };
+            ancestry.insert(block.header().height(), current);
+            if &current == chain.genesis_hash() {
+                break;
+            }
+            current = *block.header().prev_hash();
+        }
+        if ancestry != chain.height_to_hash {
+            let offending = ancestry
+                .iter()
+                .find(|(height, hash)| chain.height_to_hash.get(height) != Some(hash))
+                .map(|(height, hash)| format!("height {height} should map to {hash}"))
+                .unwrap_or_else(|| "index contains heights not reachable from head".to_string());
+            return violation("canonical-index-matches-head-ancestry", offending);
+        }
+        Ok(())
+    }
+
+    fn check_final_head(&mut self, chain: &Chain) -> Result<(), InvariantViolation> {
+        let final_height = chain.final_head_height();
+        if final_height < self.last_final_head_height {
+            return violation(
+                "final-head-never-moves-backwards",
+                format!(
+                    "final head dropped from {} to {final_height}",
+                    self.last_final_head_height
+                ),
+            );
+        }
+        self.last_final_head_height = final_height;
+        let head_height = chain.head_header().height();
+        if final_height > head_height {
+            return violation(
+                "final-head-never-moves-backwards",
+                format!("final head {final_height} is above the head {head_height}"),
+            );
+        }
+        Ok(())
+    }
+
+    fn check_block_integrity(&self, chain: &Chain) -> Result<(), InvariantViolation> {
+        for (hash, block) in &chain.blocks {
+            if block.hash() != hash {
+                return violation(
+                    "block-hash-matches-contents",
+                    format!("block stored under {hash} reports hash {}", block.hash()),
+                );
+            }
+            if !block.header().check_hash() || !block.check_block_body_hash() {
+                return violation(
+                    "block-hash-matches-contents",
+                    format!("block {hash} does not hash to its header hash"),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn check_chunk_extras(&self, chain: &Chain) -> Result<(), InvariantViolation> {
+        for (height, hash) in &chain.height_to_hash {
+            if hash == chain.genesis_hash() {
+                continue;
+            }
+            let block = &chain.blocks[hash];
+            for chunk in block.chunks() {
+                if chain.get_chunk_extra(hash, chunk.shard_id()).is_none() {
+                    return violation(
+                        "chunk-extras-exist-for-canonical-blocks",
+                        format!(
+                            "no chunk extra for shard {} of block {hash} at height {height}",
+                            chunk.shard_id()
+                        ),
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_epoch_start_records(
+        &self,
+        chain: &Chain,
+        epoch_manager: &EpochManager,
+    ) -> Result<(), InvariantViolation> {
+        let genesis_height = chain.blocks[chain.genesis_hash()].header().height();
+        for (&height, hash) in &chain.height_to_hash {
+            if height == genesis_height {
+                continue;
+            }
+            let epoch_id = chain.epoch_id_at(height);
+            let expected_start = chain.epoch_start_at(height);
+            match epoch_manager.epoch_start_height(&epoch_id) {
+                Some(start) if start == expected_start => {}
+                Some(start) => {
+                    return violation(
+                        "epoch-start-records-match-boundaries",
+                        format!(
+                            "epoch of block {hash} at height {height} starts at {expected_start} \
+                             but the epoch manager recorded {start}"
+                        ),
+                    );
+                }
+                None => {
+                    return violation(
+                        "epoch-start-records-match-boundaries",
+                        format!("no epoch start recorded for block {hash} at height {height}"),
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::{TestChainBuilder, genesis_block, test_epoch_info};
+    use near_epoch_manager::EpochManager;
+    use near_primitives::hash::hash;
+    use near_primitives::types::{AccountId, EpochId};
+    use near_store::Store;
+
+    /// Grows two competing branches by pseudo-random lengths, switching the
+    /// head back and forth; the invariant battery runs after every processed
+    /// block and explicitly after every switch.
+    #[test]
+    fn test_reorg_stress_with_invariant_checks() {
+        let mut producer = TestChainBuilder::new().epoch_length(5).invariant_checks().build();
+        let genesis_hash = *producer.chain().genesis_hash();
+        let mut branches = [(genesis_hash, 0u64), (genesis_hash, 0u64)];
+        let mut diverged = false;
+        for round in 0..12u64 {
+            let branch = (round % 2) as usize;
+            let extend = 1 + (hash(&round.to_le_bytes()).as_bytes()[0] % 3) as u64;
+            for _ in 0..extend {
+                let (prev_hash, prev_height) = branches[branch];
+                // The second branch skips one height on its first block so
+                // the branches diverge from genesis.
+                let height = if branch == 1 && !diverged {
+                    diverged = true;
+                    prev_height + 2
+                } else {
+                    prev_height + 1
+                };
+                let block =
+                    producer.produce_block_on(prev_hash, height).unwrap().expect("sole producer");
+                branches[branch] = (*block.hash(), height);
+                producer.process_block(block).unwrap();
+            }
+            producer.check_all().unwrap();
+        }
+        // Both branches got long enough to win fork choice repeatedly.
+        assert!(branches[0].1 >= 6 && branches[1].1 >= 6);
+        let head_height = producer.chain().head_header().height();
+        assert_eq!(head_height, branches[0].1.max(branches[1].1));
+    }
+
+    #[test]
+    fn test_violation_names_the_invariant() {
+        let validator: AccountId = "test0".parse().unwrap();
+        let mut chain = crate::chain::Chain::new(genesis_block(&validator), 5);
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        epoch_manager
+            .save_epoch_info(
+                &EpochId(hash(&0u64.to_le_bytes())),
+                test_epoch_info(0, std::slice::from_ref(&validator)),
+            )
+            .unwrap();
+        chain.check_all(&epoch_manager).unwrap();
+
+        // Corrupt the canonical index with a height above the head.
+        chain.height_to_hash.insert(99, hash(b"bogus"));
+        let violation = chain.check_all(&epoch_manager).unwrap_err();
+        assert_eq!(violation.invariant, "head-is-max-canonical-height");
+        assert!(violation.to_string().contains("99"), "{violation}");
+    }
+}
@@ -0,0 +1,13 @@
+pub mod approval_tracking;
+pub mod block_producer;
+pub mod chain;
+pub mod chunk_production;
+pub mod endorsement_dedup;
+pub mod error;
+pub mod invariants;
+pub mod read_only;
+pub mod test_utils;
+
+pub use block_producer::BlockProducer;
+pub use chain::Chain;
+pub use error::Error;
@@ -0,0 +1,211 @@
+//! A read-only chain facade for RPC replicas.
+//!
+//! Replicas share a store written by another process and must answer
+//! queries and validate incoming headers without mutating chain or epoch
+//! manager state. The facade is built from a [`ReadOnlyStore`] -- so a
+//! write path is a compile error, not a runtime surprise -- and uses the
+//! epoch manager strictly through read locks. There is deliberately no
+//! `process_block` here.
+
+use crate::error::Error;
+use near_epoch_manager::{EpochManagerAdapter, EpochManagerHandle};
+use near_primitives::block::Block;
+use near_primitives::block_body::BlockBody;
+use near_primitives::block_header::BlockHeader;
+use near_primitives::chunk_extra::ChunkExtra;
+use near_primitives::errors::EpochError;
+use near_primitives::hash::{CryptoHash, hash};
+use near_primitives::height_math;
+use near_primitives::types::{BlockHeight, EpochId};
+use near_store::{DBCol, ReadOnlyStore};
+
+/// Read-only view of a chain persisted in a shared store.
+pub struct ChainReadOnly {
+    store: ReadOnlyStore,
+    epoch_manager: EpochManagerHandle,
+    epoch_length: BlockHeight,
+}
+
+impl ChainReadOnly {
+    pub fn new(
+        store: ReadOnlyStore,
+        epoch_manager: EpochManagerHandle,
+        epoch_length: BlockHeight,
+    ) -> Self {
+        Self { store, epoch_manager, epoch_length }
+    }
+
+    /// The epoch id of blocks at the given height; same derivation as
+    /// [`crate::chain::Chain::epoch_id_at`].
+    pub fn epoch_id_at(&self, height: BlockHeight) -> EpochId {
+        let ordinal = height_math::epoch_index_for_height(height, 0, self.epoch_length)
+            .expect("processed heights are at or above genesis");
+        EpochId(hash(&ordinal.to_le_bytes()))
+    }
+
+    /// Validates a header without touching any state: hash integrity, epoch
+    /// id continuity with the header's height, and the producer expected by
+    /// the epoch settlement, including its signature.
+    pub fn validate_header(&self, header: &BlockHeader) -> Result<(), Error> {
+        let height = header.height();
+        if !header.check_hash() {
+            return Err(Error::InvalidBlockHash(height));
+        }
+        let epoch_id = self.epoch_id_at(height);
+        if header.epoch_id() != &epoch_id
+            || header.next_epoch_id() != &self.epoch_id_at(height + self.epoch_length)
+        {
+            return Err(Error::InvalidEpochId(height));
+        }
+
+        let epoch_manager = self.epoch_manager.read();
+        let epoch_info = epoch_manager
+            .get_epoch_info_if_exists(&epoch_id)?
+            .ok_or(EpochError::EpochOutOfBounds(epoch_id))?;
+        let settlement = epoch_info.block_producers_settlement();
+        let producer_id = settlement[(height % settlement.len() as u64) as usize];
+        let producer = epoch_info
+            .get_validator(producer_id)
+            .ok_or(EpochError::EpochOutOfBounds(epoch_id))?;
+        if !header.verify_block_producer(producer.public_key()) {
+            return Err(Error::InvalidSignature);
+        }
+        Ok(())
+    }
+
+    /// The stored header of the given block.
+    pub fn get_block_header(&self, block_hash: &CryptoHash) -> Result<BlockHeader, Error> {
+        let mut header: BlockHeader = self
+            .store
+            .get_ser(DBCol::BlockHeader, block_hash.as_ref())?
+            .ok_or(Error::BlockMissing(*block_hash))?;
+        header.init();
+        Ok(header)
+    }
+
+    /// The stored block: its header and body, re-assembled.
+    pub fn get_block(&self, block_hash: &CryptoHash) -> Result<Block, Error> {
+        let header = self.get_block_header(block_hash)?;
+        let body: BlockBody = self
+            .store
+            .get_ser(DBCol::Block, block_hash.as_ref())?
+            .ok_or(Error::BlockMissing(*block_hash))?;
+        Ok(Block::new(header, body))
+    }
+
+    /// The canonical block hash at the given height, if one exists.
+    pub fn get_block_hash_by_height(
+        &self,
+        height: BlockHeight,
+    ) -> Result<Option<CryptoHash>, Error> {
+        let Some(bytes) = self.store.get(DBCol::BlockHeight, &height.to_le_bytes())? else {
+            return Ok(None);
+        };
+        let bytes: [u8; 32] =
+            bytes.try_into().map_err(|_| Error::BlockMissing(CryptoHash::default()))?;
+        Ok(Some(CryptoHash(bytes)))
+    }
+
+    /// The recorded outcome of applying the chunk of the given shard in the
+    /// given block, once outcomes are mirrored into the store.
+    pub fn get_chunk_extra(
+        &self,
+        block_hash: &CryptoHash,
+        shard_id: u64,
+    ) -> Result<Option<ChunkExtra>, Error> {
+        let mut key = block_hash.as_ref().to_vec();
+        key.extend_from_slice(&shard_id.to_le_bytes());
+        Ok(self.store.get_ser(DBCol::ChunkExtra, &key)?)
+    }
+
+    /// The highest canonical height the writer has persisted.
+    pub fn head_height(&self) -> Option<BlockHeight> {
+        self.store
+            .iter(DBCol::BlockHeight)
+            .into_iter()
+            .filter_map(|(key, _)| Some(BlockHeight::from_le_bytes(key.try_into().ok()?)))
+            .max()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::Chain;
+    use crate::test_utils::{genesis_block, test_epoch_info};
+    use near_epoch_manager::EpochManager;
+    use near_primitives::types::AccountId;
+    use near_primitives::validator_signer::{InMemoryValidatorSigner, ValidatorSigner};
+    use near_store::Store;
+
+    /// A writer producing blocks into a shared store, and a replica facade
+    /// reading from the same store through its own epoch manager handle.
+    fn writer_and_replica() -> (crate::BlockProducer, ChainReadOnly) {
+        let epoch_length = 5;
+        let validator: AccountId = "test0".parse().unwrap();
+        let store = Store::new();
+
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        let mut replica_epoch_manager = EpochManager::new(Store::new(), 1);
+        for ordinal in 0..10u64 {
+            let epoch_id = EpochId(hash(&ordinal.to_le_bytes()));
+            let epoch_info = test_epoch_info(ordinal, std::slice::from_ref(&validator));
+            epoch_manager.save_epoch_info(&epoch_id, epoch_info.clone()).unwrap();
+            replica_epoch_manager.save_epoch_info(&epoch_id, epoch_info).unwrap();
+        }
+
+        let chain = Chain::with_store(genesis_block(&validator), epoch_length, store.clone());
+        let signer = InMemoryValidatorSigner::from_seed(validator);
+        let writer = crate::BlockProducer::new(chain, epoch_manager, signer);
+        let replica = ChainReadOnly::new(
+            store.read_only(),
+            EpochManagerHandle::new(replica_epoch_manager),
+            epoch_length,
+        );
+        (writer, replica)
+    }
+
+    #[test]
+    fn test_replica_validates_writer_headers_and_serves_queries() {
+        let (mut writer, replica) = writer_and_replica();
+        for height in 1..=7 {
+            let block = writer.produce_block(height).unwrap().expect("sole producer");
+            replica.validate_header(block.header()).unwrap();
+            writer.process_block(block).unwrap();
+        }
+
+        // The replica sees what the writer persisted into the shared store.
+        assert_eq!(replica.head_height(), Some(7));
+        let head_hash = replica.get_block_hash_by_height(7).unwrap().unwrap();
+        assert_eq!(&head_hash, writer.chain().head().hash());
+        let block = replica.get_block(&head_hash).unwrap();
+        assert_eq!(&block, writer.chain().head());
+        assert_eq!(replica.get_block_hash_by_height(8).unwrap(), None);
+    }
+
+    #[test]
+    fn test_replica_rejects_forged_headers() {
+        let (mut writer, replica) = writer_and_replica();
+        let block = writer.produce_block(1).unwrap().expect("sole producer");
+
+        // A header signed by someone who is not the expected producer.
+        let forger = InMemoryValidatorSigner::from_seed("forger99".parse().unwrap());
+        let BlockHeader::V5(header) = block.header().clone();
+        let forged = BlockHeader::new(
+            header.prev_hash,
+            header.inner_lite.clone(),
+            header.inner_rest.clone(),
+            forger.sign_bytes(header.hash.as_ref()),
+        );
+        assert_eq!(replica.validate_header(&forged), Err(Error::InvalidSignature));
+
+        // A header claiming the wrong epoch for its height.
+        let mut inner_lite = header.inner_lite;
+        inner_lite.epoch_id = EpochId(hash(b"wrong epoch"));
+        let wrong_epoch =
+            BlockHeader::new(header.prev_hash, inner_lite, header.inner_rest, header.signature);
+        assert_eq!(replica.validate_header(&wrong_epoch), Err(Error::InvalidEpochId(1)));
+
+        replica.validate_header(block.header()).unwrap();
+    }
+}
@@ -0,0 +1,148 @@
+//! Utilities for setting up small chains in tests.
+
+use crate::block_producer::{BlockProducer, PROTOCOL_VERSION};
+use crate::chain::Chain;
+use near_crypto::{KeyType, SecretKey, Signature};
+use near_epoch_manager::EpochManager;
+use near_primitives::block::Block;
+use near_primitives::block_body::BlockBody;
+use near_primitives::block_header::{
+    BlockHeader, BlockHeaderInnerLite, BlockHeaderInnerRestV5,
+};
+use near_primitives::epoch_manager::epoch_info::EpochInfo;
+use near_primitives::hash::{CryptoHash, hash};
+use near_primitives::types::{AccountId, Balance, BlockHeight, EpochId, ValidatorStake};
+use near_primitives::validator_signer::InMemoryValidatorSigner;
+use near_store::Store;
+use std::collections::BTreeMap;
+
+pub const TEST_STAKE: Balance = 1_000_000;
+pub const TEST_TOTAL_SUPPLY: Balance = 1_000_000_000;
+
+/// Builds a chain with a genesis block and an epoch manager pre-populated
+/// with identical validator sets for a number of epochs ahead.
+pub struct TestChainBuilder {
+    epoch_length: BlockHeight,
+    validators: Vec<AccountId>,
+    epochs_ahead: u64,
+    invariant_checks: bool,
+}
+
+impl TestChainBuilder {
+    pub fn new() -> Self {
+        Self {
+            epoch_length: 5,
+            validators: vec!["test0".parse().unwrap()],
+            epochs_ahead: 10,
+            invariant_checks: false,
+        }
+    }
+
+    pub fn epoch_length(mut self, epoch_length: BlockHeight) -> Self {
+        self.epoch_length = epoch_length;
+        self
+    }
+
+    pub fn validators(mut self, seeds: &[&str]) -> Self {
+        self.validators = seeds.iter().map(|seed| seed.parse().unwrap()).collect();
+        self
+    }
+
+    /// How many epochs from genesis get their epoch info pre-registered.
+    pub fn epochs_ahead(mut self, epochs_ahead: u64) -> Self {
+        self.epochs_ahead = epochs_ahead;
+        self
+    }
+
+    /// Runs the [`crate::invariants::ChainInvariantChecker`] battery after
+    /// every processed block.
+    pub fn invariant_checks(mut self) -> Self {
+        self.invariant_checks = true;
+        self
+    }
+
+    /// Builds a [`BlockProducer`] signing as the first validator.
+    pub fn build(self) -> BlockProducer {
+        let signer = InMemoryValidatorSigner::from_seed(self.validators[0].clone());
+        self.build_with_signer(signer)
+    }
+
+    pub fn build_with_signer(self, signer: InMemoryValidatorSigner) -> BlockProducer {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        for ordinal in 0..self.epochs_ahead {
+            let epoch_id = EpochId(hash(&ordinal.to_le_bytes()));
+            epoch_manager
+                .save_epoch_info(&epoch_id, test_epoch_info(ordinal, &self.validators))
+                .unwrap();
+        }
+        let genesis = genesis_block(&self.validators[0]);
+        let chain = if self.invariant_checks {
+            Chain::with_invariant_checks(genesis, self.epoch_length)
+        } else {
+            Chain::new(genesis, self.epoch_length)
+        };
+        BlockProducer::new(chain, epoch_manager, signer)
+    }
+}
+
+impl Default for TestChainBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Epoch info with one seat per validator, keys derived from account ids.
+pub fn test_epoch_info(epoch_height: u64, validators: &[AccountId]) -> EpochInfo {
+    let validators: Vec<_> = validators
+        .iter()
+        .map(|account_id| {
+            let public_key =
+                SecretKey::from_seed(KeyType::ED25519, account_id.as_str()).public_key();
+            ValidatorStake::new(account_id.clone(), public_key, TEST_STAKE)
+        })
+        .collect();
+    let validator_to_index =
+        validators.iter().enumerate().map(|(i, v)| (v.account_id().clone(), i as u64)).collect();
+    let block_producers_settlement: Vec<u64> = (0..validators.len() as u64).collect();
+    let chunk_producers_settlement = vec![block_producers_settlement.clone()];
+    EpochInfo::new(
+        epoch_height,
+        validators,
+        validator_to_index,
+        block_producers_settlement,
+        chunk_producers_settlement,
+        BTreeMap::new(),
+        0,
+        TEST_STAKE,
+        BTreeMap::new(),
+        PROTOCOL_VERSION,
+        [0; 32],
+    )
+}
+
+/// A genesis block at height 0; its VRF output is derived from the first
+/// validator's key so that block 1 can build on its random value.
+pub fn genesis_block(producer: &AccountId) -> Block {
+    let secret_key = SecretKey::from_seed(KeyType::ED25519, producer.as_str());
+    let (vrf_value, vrf_proof) = secret_key.compute_vrf_with_proof(b"genesis");
+    let body = BlockBody::new(vec![], vrf_value, vrf_proof, vec![]);
+    let random_value = body.compute_randomness();
+    let inner_lite = BlockHeaderInnerLite {
+        height: 0,
+        epoch_id: EpochId(hash(&0u64.to_le_bytes())),
+        next_epoch_id: EpochId(hash(&1u64.to_le_bytes())),
+        timestamp: 1,
+        ..Default::default()
+    };
+    let inner_rest = BlockHeaderInnerRestV5 {
+        block_body_hash: CryptoHash::hash_borsh(&body),
+        random_value,
+        gas_price: 100,
+        total_supply: TEST_TOTAL_SUPPLY,
+        latest_protocol_version: PROTOCOL_VERSION,
+        ..Default::default()
+    };
+    let header =
+        BlockHeader::new(CryptoHash::default(), inner_lite, inner_rest, Signature::default());
+    Block::new(header, body)
+}
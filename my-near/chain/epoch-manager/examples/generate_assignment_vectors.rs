@@ -0,0 +1,26 @@
+//! Regenerates the canonical chunk validator assignment vector file.
+//!
+//! Writes the file to the path given as the first argument, or to stdout
+//! when none is given:
+//!
+//! ```text
+//! cargo run --example generate_assignment_vectors -- \
+//!     chain/epoch-manager/res/chunk_validator_assignment_vectors.json
+//! ```
+
+use near_epoch_manager::assignment_vectors::{
+    AssignmentVectorFile, canonical_inputs, canonical_json, compute_assignment_vectors,
+};
+
+fn main() {
+    let inputs = canonical_inputs();
+    let vectors = compute_assignment_vectors(&inputs);
+    let json = canonical_json(&AssignmentVectorFile { inputs, vectors });
+    match std::env::args().nth(1) {
+        Some(path) => {
+            std::fs::write(&path, json).expect("failed to write the vector file");
+            eprintln!("wrote {path}");
+        }
+        None => print!("{json}"),
+    }
+}
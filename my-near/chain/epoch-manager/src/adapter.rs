@@ -0,0 +1,87 @@
+use crate::{AssignmentSeed, ReshardingInfo};
+use near_primitives::epoch_manager::epoch_info::EpochInfo;
+use near_primitives::errors::EpochError;
+use near_primitives::hash::CryptoHash;
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::types::{
+    AccountId, BlockHeight, EpochId, ProtocolVersion, ShardId, SlashState, ValidatorStake,
+};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// A trait that abstracts the interface of the [`crate::EpochManager`] for
+/// components that only need to query it.
+///
+/// Every method takes `&self`: implementations keep their caches behind
+/// interior mutability, so a shared reference -- e.g. the read guard of
+/// [`crate::EpochManagerHandle`] -- suffices for the whole query surface.
+pub trait EpochManagerAdapter {
+    /// Cheap existence probe: whether this node has information for the given
+    /// epoch. Never errors and never logs; missing epochs are an expected
+    /// outcome here, not a failure.
+    fn epoch_exists(&self, epoch_id: &EpochId) -> bool;
+
+    /// Whether all blocks of the given epoch are at or below the final head.
+    fn is_epoch_final(&self, epoch_id: &EpochId) -> Result<bool, EpochError>;
+
+    /// Fetches the information of an epoch if it is available.
+    ///
+    /// The three outcomes are deliberately kept apart:
+    /// * `Ok(Some(_))` -- the epoch is known;
+    /// * `Ok(None)` -- the epoch has not been computed yet, e.g. probing the
+    ///   next-next epoch before the current one is finalized;
+    /// * `Err(EpochError::EpochGarbageCollected(_))` -- the epoch existed but
+    ///   its information has been garbage collected;
+    /// * `Err(_)` -- the store failed.
+    fn get_epoch_info_if_exists(
+        &self,
+        epoch_id: &EpochId,
+    ) -> Result<Option<Arc<EpochInfo>>, EpochError>;
+
+    /// The shard layout the chain runs under the given protocol version.
+    fn get_shard_layout_from_protocol_version(
+        &self,
+        protocol_version: ProtocolVersion,
+    ) -> ShardLayout;
+
+    /// Whether a shard layout change is pending after the epoch of the given
+    /// block.
+    ///
+    /// Returns `Some(_)` exactly when the epoch following the block's epoch
+    /// activates a different layout, so the chain can build child-shard state
+    /// one epoch in advance; `None` when no change is pending or the next
+    /// epoch has not been computed yet.
+    fn get_resharding_info(
+        &self,
+        parent_hash: &CryptoHash,
+    ) -> Result<Option<ReshardingInfo>, EpochError>;
+
+    /// Everyone slashed as seen from the given block: the slashes along its
+    /// ancestry, so header validation can reject approvals from validators
+    /// already slashed within the same epoch.
+    fn get_slash_state_for_block(
+        &self,
+        block_hash: &CryptoHash,
+    ) -> Result<BTreeMap<AccountId, SlashState>, EpochError>;
+
+    /// Chunk validators assigned to a shard at a height, with the default
+    /// height-only seed derivation (see [`AssignmentSeed::from_height`]).
+    fn get_chunk_validator_assignments(
+        &self,
+        epoch_id: &EpochId,
+        shard_id: ShardId,
+        height: BlockHeight,
+    ) -> Result<Arc<Vec<ValidatorStake>>, EpochError> {
+        self.get_chunk_validator_assignments_with_seed(AssignmentSeed::from_height(
+            *epoch_id, shard_id, height,
+        ))
+    }
+
+    /// Chunk validators assigned for an explicit sampling seed. The seed is
+    /// the cache key, so assignments derived from different randomness at
+    /// the same height (forks) get separate entries.
+    fn get_chunk_validator_assignments_with_seed(
+        &self,
+        seed: AssignmentSeed,
+    ) -> Result<Arc<Vec<ValidatorStake>>, EpochError>;
+}
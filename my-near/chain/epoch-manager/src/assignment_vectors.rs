@@ -0,0 +1,208 @@
+//! Canonical chunk validator assignment vectors, for checking that other
+//! implementations of the mandate sampling reproduce it exactly.
+//!
+//! External teams re-implement the sampling in other languages; a checked-in
+//! vector file pins the protocol so a drift -- theirs or ours -- shows up as
+//! a failing comparison instead of a consensus split. The vectors depend on
+//! three things, all of which are part of the protocol and must be matched
+//! bit for bit by a re-implementation:
+//!
+//! * the mandate layout: whole mandates in ascending validator-id order,
+//!   each validator contributing `stake / stake_per_mandate` of them,
+//!   followed by at most one partial mandate per validator for the
+//!   remainder, again in ascending validator-id order;
+//! * the draw order: [`ValidatorMandates::sample`] draws one shard index
+//!   per whole mandate first, then one per partial mandate, in exactly the
+//!   layout order above;
+//! * the RNG: rand's `StdRng` seeded with the 32 bytes of
+//!   `CryptoHash::hash_borsh(&(rng_seed, height))`, each shard index drawn
+//!   via `gen_range(0..num_shards)`.
+
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{Balance, BlockHeight, ValidatorId, ValidatorStake};
+use near_primitives::validator_mandates::{ValidatorMandates, ValidatorMandatesConfig};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The inputs a vector file was generated from, sufficient to recompute it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AssignmentVectorInputs {
+    /// Stake of each validator; the validator id is the index.
+    pub stakes: Vec<Balance>,
+    pub stake_per_mandate: Balance,
+    pub target_mandates_per_shard: usize,
+    pub num_shards: usize,
+    /// The epoch-level seed the per-height sampling seeds are derived from.
+    pub rng_seed: [u8; 32],
+    /// The heights the file carries one vector for.
+    pub heights: Vec<BlockHeight>,
+}
+
+/// The expected assignment at one height: per shard, the assigned
+/// validators with the stake backing the shard through them. Maps are
+/// ordered by validator id so the serialized form is canonical.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AssignmentVector {
+    pub height: BlockHeight,
+    pub shards: Vec<BTreeMap<ValidatorId, Balance>>,
+}
+
+/// A vector file: the inputs plus the assignments they must produce.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AssignmentVectorFile {
+    pub inputs: AssignmentVectorInputs,
+    pub vectors: Vec<AssignmentVector>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum AssignmentVectorError {
+    #[error("failed to read the vector file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("the vector file is not valid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("the file carries {found} vectors but its inputs list {expected} heights")]
+    HeightCountMismatch { expected: usize, found: usize },
+    #[error("the recomputed assignment at height {height} differs from the stored vector")]
+    Mismatch { height: BlockHeight },
+}
+
+/// The inputs of the checked-in canonical vector file. Shared by the
+/// generator example and the regeneration test so both pin the same file.
+pub fn canonical_inputs() -> AssignmentVectorInputs {
+    AssignmentVectorInputs {
+        stakes: vec![1000, 850, 300, 75, 20],
+        stake_per_mandate: 100,
+        target_mandates_per_shard: 3,
+        num_shards: 3,
+        rng_seed: std::array::from_fn(|i| i as u8),
+        heights: vec![0, 1, 2, 5, 10],
+    }
+}
+
+/// Recomputes the assignment vectors the given inputs must produce.
+pub fn compute_assignment_vectors(inputs: &AssignmentVectorInputs) -> Vec<AssignmentVector> {
+    let validators: Vec<ValidatorStake> = inputs
+        .stakes
+        .iter()
+        .enumerate()
+        .map(|(i, stake)| {
+            // The sampling only looks at the stakes; the accounts and keys
+            // merely make the stakes a well-formed validator set.
+            let name = format!("validator{i}");
+            let public_key =
+                near_crypto::SecretKey::from_seed(near_crypto::KeyType::ED25519, &name)
+                    .public_key();
+            ValidatorStake::new(name.parse().unwrap(), public_key, *stake)
+        })
+        .collect();
+    let config = ValidatorMandatesConfig::new(
+        inputs.stake_per_mandate,
+        inputs.target_mandates_per_shard,
+        inputs.num_shards,
+    );
+    let mandates = ValidatorMandates::new(config, &validators);
+    inputs
+        .heights
+        .iter()
+        .map(|&height| {
+            let sampling_seed = CryptoHash::hash_borsh(&(inputs.rng_seed, height));
+            let mut rng = StdRng::from_seed(*sampling_seed.as_bytes());
+            let shards = mandates
+                .sample(&mut rng)
+                .into_iter()
+                .map(|shard| shard.into_iter().collect())
+                .collect();
+            AssignmentVector { height, shards }
+        })
+        .collect()
+}
+
+/// The canonical serialized form of a vector file: pretty-printed JSON with
+/// a trailing newline. Byte-for-byte comparisons go through this.
+pub fn canonical_json(file: &AssignmentVectorFile) -> String {
+    let mut json =
+        serde_json::to_string_pretty(file).expect("vector files always serialize");
+    json.push('\n');
+    json
+}
+
+/// Reads a vector file, recomputes the assignments from its inputs and
+/// compares them against the stored vectors. Intended to run in CI, so a
+/// protocol-affecting change to the sampling requires a deliberate vector
+/// update instead of slipping through.
+pub fn verify_assignment_vectors(path: &Path) -> Result<(), AssignmentVectorError> {
+    let contents = std::fs::read_to_string(path)?;
+    let file: AssignmentVectorFile = serde_json::from_str(&contents)?;
+    if file.vectors.len() != file.inputs.heights.len() {
+        return Err(AssignmentVectorError::HeightCountMismatch {
+            expected: file.inputs.heights.len(),
+            found: file.vectors.len(),
+        });
+    }
+    let recomputed = compute_assignment_vectors(&file.inputs);
+    for (stored, fresh) in file.vectors.iter().zip(&recomputed) {
+        if stored != fresh {
+            return Err(AssignmentVectorError::Mismatch { height: fresh.height });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canonical_file() -> AssignmentVectorFile {
+        let inputs = canonical_inputs();
+        let vectors = compute_assignment_vectors(&inputs);
+        AssignmentVectorFile { inputs, vectors }
+    }
+
+    fn checked_in_path() -> &'static Path {
+        Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/res/chunk_validator_assignment_vectors.json"
+        ))
+    }
+
+    #[test]
+    fn test_checked_in_vectors_regenerate_byte_for_byte() {
+        let checked_in = std::fs::read_to_string(checked_in_path()).unwrap();
+        // A difference here means the sampling protocol changed: update the
+        // file with the generator example -- deliberately -- or revert.
+        assert_eq!(checked_in, canonical_json(&canonical_file()));
+        verify_assignment_vectors(checked_in_path()).unwrap();
+    }
+
+    #[test]
+    fn test_verification_catches_a_tampered_vector() {
+        let mut file = canonical_file();
+        // Move one validator's stake to another shard at the last height.
+        let tampered = file.vectors.last_mut().unwrap();
+        let (validator_id, stake) =
+            tampered.shards[0].pop_first().expect("the canonical vectors are non-trivial");
+        tampered.shards[1].insert(validator_id, stake);
+
+        let path = std::env::temp_dir().join("tampered_assignment_vectors.json");
+        std::fs::write(&path, canonical_json(&file)).unwrap();
+        assert!(matches!(
+            verify_assignment_vectors(&path),
+            Err(AssignmentVectorError::Mismatch { height: 10 })
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_vectors_conserve_the_total_stake() {
+        let file = canonical_file();
+        let total: Balance = file.inputs.stakes.iter().sum();
+        for vector in &file.vectors {
+            let assigned: Balance =
+                vector.shards.iter().flat_map(|shard| shard.values()).sum();
+            assert_eq!(assigned, total, "height {}", vector.height);
+        }
+    }
+}
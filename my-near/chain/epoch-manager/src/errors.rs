@@ -0,0 +1,48 @@
+//! The error type of this crate.
+//!
+//! [`EpochError`] is defined in `near-primitives` rather than here because
+//! primitives types reference it themselves -- e.g. the
+//! `EpochInfoProvider` trait returns it -- and a definition in this crate
+//! would make the dependency circular. This module re-exports it so
+//! downstream code can name it as `near_epoch_manager::errors::EpochError`
+//! without caring where it lives.
+
+pub use near_primitives::errors::EpochError;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_primitives::hash::hash;
+    use near_primitives::types::EpochId;
+
+    #[test]
+    fn test_display_formatting() {
+        let epoch_id = EpochId(hash(b"epoch"));
+        assert_eq!(
+            EpochError::EpochOutOfBounds(epoch_id).to_string(),
+            format!("epoch {epoch_id:?} is out of bounds")
+        );
+        assert_eq!(
+            EpochError::MissingBlock(hash(b"block")).to_string(),
+            format!("block {} is missing", hash(b"block"))
+        );
+        assert_eq!(
+            EpochError::NotAValidator("alice".parse().unwrap(), epoch_id).to_string(),
+            format!("alice is not a validator in epoch {epoch_id:?}")
+        );
+        assert_eq!(
+            EpochError::ShardingError("no shard 7".to_string()).to_string(),
+            "sharding error: no shard 7"
+        );
+    }
+
+    #[test]
+    fn test_io_error_conversion() {
+        let io_error = std::io::Error::new(std::io::ErrorKind::InvalidData, "truncated");
+        assert_eq!(EpochError::from(io_error), EpochError::IOErr("truncated".to_string()));
+        // `borsh::io` re-exports `std::io`, so borsh deserialization errors
+        // take the same path.
+        let borsh_error = borsh::io::Error::new(borsh::io::ErrorKind::InvalidData, "truncated");
+        assert_eq!(EpochError::from(borsh_error), EpochError::IOErr("truncated".to_string()));
+    }
+}
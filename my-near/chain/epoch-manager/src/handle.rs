@@ -0,0 +1,172 @@
+//! Shared handle to the epoch manager and the epoch change subscription
+//! API.
+//!
+//! Components like the shard tracker or metrics want to react when an epoch
+//! switches or when the next epoch's information becomes available, without
+//! polling. Events are delivered through bounded per-subscriber queues with
+//! drop-oldest semantics: a slow subscriber loses its oldest events -- and
+//! can see how many through [`EpochChangeReceiver::dropped`] -- but never
+//! blocks the epoch manager.
+
+use crate::EpochManager;
+use near_primitives::epoch_manager::block_info::BlockInfo;
+use near_primitives::epoch_manager::epoch_info::EpochInfo;
+use near_primitives::errors::EpochError;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{
+    AccountId, Balance, EpochHeight, EpochId, EpochInfoProvider, ValidatorStake,
+};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// How many events a subscriber may lag behind before its oldest events are
+/// dropped.
+pub(crate) const EVENT_QUEUE_CAPACITY: usize = 16;
+
+/// A change of the epoch state worth reacting to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EpochChangeEvent {
+    /// The first block of a new epoch has been recorded.
+    EpochStarted {
+        epoch_id: EpochId,
+        epoch_height: EpochHeight,
+        first_block_hash: CryptoHash,
+    },
+    /// The information of a freshly computed epoch has been saved.
+    NextEpochInfoComputed { epoch_id: EpochId },
+}
+
+/// The bounded event queue of one subscriber; the writing side lives in the
+/// epoch manager.
+pub(crate) struct SubscriberQueue {
+    queue: Mutex<VecDeque<EpochChangeEvent>>,
+    /// How many events were dropped because the subscriber lagged behind.
+    dropped: AtomicU64,
+}
+
+impl SubscriberQueue {
+    pub(crate) fn new() -> Arc<Self> {
+        Arc::new(Self { queue: Mutex::new(VecDeque::new()), dropped: AtomicU64::new(0) })
+    }
+
+    /// Enqueues an event, dropping the oldest one instead of blocking when
+    /// the subscriber is not keeping up.
+    pub(crate) fn push(&self, event: EpochChangeEvent) {
+        let mut queue = self.queue.lock().expect("subscriber queue poisoned");
+        if queue.len() == EVENT_QUEUE_CAPACITY {
+            queue.pop_front();
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        queue.push_back(event);
+    }
+}
+
+/// The receiving end of an epoch change subscription.
+pub struct EpochChangeReceiver {
+    queue: Arc<SubscriberQueue>,
+}
+
+impl EpochChangeReceiver {
+    pub(crate) fn new(queue: Arc<SubscriberQueue>) -> Self {
+        Self { queue }
+    }
+
+    /// The oldest undelivered event, if any. Never blocks.
+    pub fn try_recv(&self) -> Option<EpochChangeEvent> {
+        self.queue.queue.lock().expect("subscriber queue poisoned").pop_front()
+    }
+
+    /// All undelivered events, oldest first.
+    pub fn drain(&self) -> Vec<EpochChangeEvent> {
+        self.queue.queue.lock().expect("subscriber queue poisoned").drain(..).collect()
+    }
+
+    /// How many events this subscriber lost by lagging more than the queue
+    /// capacity behind.
+    pub fn dropped(&self) -> u64 {
+        self.queue.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// A shared, thread-safe handle to the [`EpochManager`].
+#[derive(Clone)]
+pub struct EpochManagerHandle {
+    epoch_manager: Arc<RwLock<EpochManager>>,
+}
+
+impl EpochManagerHandle {
+    pub fn new(epoch_manager: EpochManager) -> Self {
+        Self { epoch_manager: Arc::new(RwLock::new(epoch_manager)) }
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, EpochManager> {
+        self.epoch_manager.read().expect("epoch manager lock poisoned")
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, EpochManager> {
+        self.epoch_manager.write().expect("epoch manager lock poisoned")
+    }
+
+    /// The information of the given epoch. Acquires and releases the read
+    /// lock internally; see [`Self::read`] for multi-call consistency.
+    pub fn epoch_info(&self, epoch_id: &EpochId) -> Result<Arc<EpochInfo>, EpochError> {
+        self.read().get_epoch_info(epoch_id)
+    }
+
+    /// The recorded information of the given block.
+    pub fn block_info(&self, hash: &CryptoHash) -> Result<Arc<BlockInfo>, EpochError> {
+        self.read().get_block_info(hash)
+    }
+
+    /// The validators of the given epoch in their settlement order: highest
+    /// stake first, ties broken by account id.
+    pub fn validators_ordered(
+        &self,
+        epoch_id: &EpochId,
+    ) -> Result<Vec<ValidatorStake>, EpochError> {
+        Ok(self.read().get_epoch_info(epoch_id)?.validators().to_vec())
+    }
+
+    /// Records a block's information, finalizing its epoch when the block
+    /// crosses an epoch boundary.
+    pub fn record_block_info(&self, block_info: BlockInfo) -> Result<(), EpochError> {
+        self.write().record_block_info(block_info)
+    }
+
+    /// Subscribes to epoch change events from now on.
+    pub fn subscribe(&self) -> EpochChangeReceiver {
+        self.write().subscribe(false)
+    }
+
+    /// Subscribes to epoch change events, with the start of the current
+    /// epoch -- if one has started -- delivered immediately as the first
+    /// event.
+    pub fn subscribe_with_snapshot(&self) -> EpochChangeReceiver {
+        self.write().subscribe(true)
+    }
+}
+
+/// Each call acquires the read lock on its own; a missing epoch surfaces as
+/// the [`EpochError`] the underlying query reports.
+impl EpochInfoProvider for EpochManagerHandle {
+    fn validator_stake(
+        &self,
+        epoch_id: &EpochId,
+        account_id: &AccountId,
+    ) -> Result<Option<Balance>, EpochError> {
+        let epoch_info = self.read().get_epoch_info(epoch_id)?;
+        Ok(epoch_info.get_validator_by_account(account_id).map(|validator| validator.stake()))
+    }
+
+    fn validator_total_stake(&self, epoch_id: &EpochId) -> Result<Balance, EpochError> {
+        let epoch_info = self.read().get_epoch_info(epoch_id)?;
+        Ok(epoch_info.validators().iter().map(|validator| validator.stake()).sum())
+    }
+
+    fn minimum_stake(&self, prev_block_hash: &CryptoHash) -> Result<Balance, EpochError> {
+        let epoch_manager = self.read();
+        let epoch_id = epoch_manager.get_epoch_id_from_prev_block(prev_block_hash)?;
+        Ok(epoch_manager.get_epoch_info(&epoch_id)?.seat_price())
+    }
+}
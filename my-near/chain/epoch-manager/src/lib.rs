@@ -0,0 +1,3549 @@
+use near_primitives::block_header::BlockHeader;
+use near_primitives::epoch_manager::block_info::BlockInfo;
+use near_primitives::epoch_manager::epoch_info::EpochInfo;
+use near_primitives::epoch_manager::epoch_info_aggregator::EpochInfoAggregator;
+use near_primitives::errors::EpochError;
+use near_primitives::hash::CryptoHash;
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::sharding::ShardChunkHeader;
+use near_primitives::types::{
+    AccountId, Balance, BlockChunkValidatorStats, BlockHeight, EpochHeight, EpochId, NumShards,
+    ProtocolVersion, ShardId, SlashState, ValidatorKickoutReason, ValidatorStake, ValidatorStats,
+};
+use near_primitives::validator_mandates::{
+    ChunkValidatorAssignments, ValidatorMandates, ValidatorMandatesConfig,
+};
+use near_store::{DBCol, Store, StoreUpdate};
+use num_rational::Rational32;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+pub mod adapter;
+pub mod assignment_vectors;
+pub mod errors;
+pub mod handle;
+pub mod validator_selection;
+
+pub use adapter::EpochManagerAdapter;
+pub use handle::{EpochChangeEvent, EpochChangeReceiver, EpochManagerHandle};
+
+/// The inputs that feed chunk validator assignment sampling for one
+/// `(shard, height)` slot.
+///
+/// Kept explicit -- and used verbatim as the cache key -- so that when
+/// prev-block randomness starts feeding the sampler, the cache key changes
+/// with it instead of silently sharing entries across forks at the same
+/// height.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AssignmentSeed {
+    pub epoch_id: EpochId,
+    pub shard_id: ShardId,
+    pub height: BlockHeight,
+    /// Randomness mixed into the sampling, e.g. the random value of the
+    /// previous block; `None` selects the legacy height-only derivation.
+    pub randomness: Option<CryptoHash>,
+}
+
+impl AssignmentSeed {
+    /// The default derivation used today: height only, no randomness.
+    pub fn from_height(epoch_id: EpochId, shard_id: ShardId, height: BlockHeight) -> Self {
+        Self { epoch_id, shard_id, height, randomness: None }
+    }
+
+    pub fn with_randomness(
+        epoch_id: EpochId,
+        shard_id: ShardId,
+        height: BlockHeight,
+        randomness: CryptoHash,
+    ) -> Self {
+        Self { epoch_id, shard_id, height, randomness: Some(randomness) }
+    }
+
+    /// The settlement index the rotation starts at, for a settlement of the
+    /// given length.
+    fn start_index(&self, settlement_len: usize) -> usize {
+        match &self.randomness {
+            None => (self.height % settlement_len as u64) as usize,
+            Some(randomness) => {
+                let mixed = CryptoHash::hash_borsh(&(self.height, randomness));
+                let ordinal = u64::from_le_bytes(
+                    mixed.as_bytes()[..8].try_into().expect("hash is 32 bytes"),
+                );
+                (ordinal % settlement_len as u64) as usize
+            }
+        }
+    }
+}
+
+/// Everything the chain needs to schedule state resharding for an upcoming
+/// shard layout change, exposed one epoch before the new layout activates.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReshardingInfo {
+    /// The layout the chain is running under right now.
+    pub current_shard_layout: ShardLayout,
+    /// The layout that activates in `activation_epoch_id`.
+    pub next_shard_layout: ShardLayout,
+    /// The epoch in which `next_shard_layout` takes effect.
+    pub activation_epoch_id: EpochId,
+    /// Maps each parent shard of the current layout to the child shards it
+    /// splits into under the next layout.
+    pub split_map: Vec<Vec<ShardId>>,
+}
+
+/// Validators paired with whether each is slashed, shared by the
+/// settlement-order accessors and their caches.
+pub type ValidatorsWithSlashFlag = Arc<[(ValidatorStake, bool)]>;
+
+/// A block producer that produced less than this percentage of the blocks
+/// expected of it during an epoch is kicked out of the next one.
+const BLOCK_PRODUCER_KICKOUT_THRESHOLD_PERCENT: u64 = 90;
+
+/// Number of block heights per epoch assumed until
+/// [`EpochManager::set_epoch_length`] configures the chain's actual value.
+const DEFAULT_EPOCH_LENGTH: BlockHeight = 100;
+
+/// How many block heights the final head may advance before the epoch info
+/// aggregator is persisted again, bounding how much a restart replays.
+const AGGREGATOR_SAVE_PERIOD: u64 = 1000;
+
+/// Key of the persisted epoch info aggregator in [`DBCol::BlockMisc`].
+const EPOCH_INFO_AGGREGATOR_KEY: &[u8] = b"EPOCH_INFO_AGGREGATOR";
+
+/// How many whole mandates each shard should ideally be backed by when
+/// sampling chunk validator mandate assignments.
+const TARGET_MANDATES_PER_SHARD: usize = 68;
+
+/// How many seconds a year counts as when scaling the annualized inflation
+/// down to an epoch's actual duration.
+const NUM_SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+
+const NANOSECONDS_PER_SECOND: u128 = 1_000_000_000;
+
+/// Computes the tokens minted at the end of an epoch and how they are split
+/// among the epoch's block producers.
+///
+/// The mint is a fixed fraction of the total supply per epoch, distributed
+/// proportionally to the number of blocks each validator produced. The
+/// default calculator mints nothing, so reward payout is opt-in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RewardCalculator {
+    /// Numerator of the fraction of the total supply minted per epoch.
+    pub epoch_inflation_numerator: u64,
+    /// Denominator of the fraction; must not be zero.
+    pub epoch_inflation_denominator: u64,
+    /// Annualized inflation cap of the duration-scaled computation in
+    /// [`Self::calculate_reward`].
+    pub max_inflation_rate: Rational32,
+    /// See [`NUM_SECONDS_PER_YEAR`]; configurable so tests can use round
+    /// numbers.
+    pub num_seconds_per_year: u64,
+    /// Fraction of every mint carved out for the protocol treasury.
+    pub protocol_reward_rate: Rational32,
+    /// Account the protocol treasury cut is paid to.
+    pub protocol_treasury_account: AccountId,
+}
+
+impl Default for RewardCalculator {
+    fn default() -> Self {
+        Self {
+            epoch_inflation_numerator: 0,
+            epoch_inflation_denominator: 1,
+            max_inflation_rate: Rational32::new(0, 1),
+            num_seconds_per_year: NUM_SECONDS_PER_YEAR,
+            protocol_reward_rate: Rational32::new(1, 10),
+            protocol_treasury_account: "treasury".parse().expect("a valid account id"),
+        }
+    }
+}
+
+impl RewardCalculator {
+    pub fn new(epoch_inflation_numerator: u64, epoch_inflation_denominator: u64) -> Self {
+        assert_ne!(epoch_inflation_denominator, 0, "inflation denominator must not be zero");
+        Self { epoch_inflation_numerator, epoch_inflation_denominator, ..Self::default() }
+    }
+
+    /// The reward of each validator over an epoch and the total amount
+    /// minted, which is the sum of the rewards -- rounding always truncates
+    /// down, so the minted amount never exceeds the intended mint.
+    ///
+    /// The mint is `total_supply * max_inflation_rate` per year, scaled to
+    /// the epoch's actual duration. `protocol_reward_rate` of it goes to the
+    /// protocol treasury account; the rest is split by stake, each
+    /// validator's share scaled by its production ratio -- the average of
+    /// its block and chunk ratios, where a dimension with nothing expected
+    /// drops out of the average instead of counting as a miss. Everything is
+    /// integer arithmetic on [`Rational32`] ratios; there are no floats to
+    /// drift across platforms.
+    pub fn calculate_reward(
+        &self,
+        validator_block_chunk_stats: HashMap<AccountId, BlockChunkValidatorStats>,
+        validator_stake: &HashMap<AccountId, Balance>,
+        total_supply: Balance,
+        epoch_duration_nanos: u64,
+    ) -> (HashMap<AccountId, Balance>, Balance) {
+        let max_yearly_mint = total_supply * (*self.max_inflation_rate.numer()).max(0) as u128
+            / (*self.max_inflation_rate.denom()) as u128;
+        let epoch_mint = max_yearly_mint * epoch_duration_nanos as u128
+            / (self.num_seconds_per_year as u128 * NANOSECONDS_PER_SECOND);
+        let protocol_reward = epoch_mint * (*self.protocol_reward_rate.numer()).max(0) as u128
+            / (*self.protocol_reward_rate.denom()) as u128;
+        let validator_pool = epoch_mint - protocol_reward;
+
+        let total_stake: Balance = validator_block_chunk_stats
+            .keys()
+            .map(|account_id| validator_stake.get(account_id).copied().unwrap_or_default())
+            .sum();
+        let mut rewards = HashMap::new();
+        for (account_id, stats) in validator_block_chunk_stats {
+            let blocks = stats.block_stats;
+            let chunks = stats.chunk_stats;
+            let ratio = match (blocks.expected, chunks.expected) {
+                (0, 0) => Rational32::new(0, 1),
+                (0, _) => Rational32::new(chunks.produced as i32, chunks.expected as i32),
+                (_, 0) => Rational32::new(blocks.produced as i32, blocks.expected as i32),
+                _ => {
+                    (Rational32::new(blocks.produced as i32, blocks.expected as i32)
+                        + Rational32::new(chunks.produced as i32, chunks.expected as i32))
+                        / 2
+                }
+            };
+            let stake = validator_stake.get(&account_id).copied().unwrap_or_default();
+            let reward = (validator_pool * stake)
+                .checked_div(total_stake)
+                .map_or(0, |share| share * (*ratio.numer()) as u128 / (*ratio.denom()) as u128);
+            rewards.insert(account_id, reward);
+        }
+        *rewards.entry(self.protocol_treasury_account.clone()).or_default() += protocol_reward;
+        let minted = rewards.values().sum();
+        (rewards, minted)
+    }
+
+    /// The reward of each block producer and the total amount minted, which
+    /// is the sum of the rewards -- rounding remainders stay unminted. This
+    /// is the simpler per-epoch-fraction path epoch finalization runs on.
+    pub fn calculate_produced_blocks_reward(
+        &self,
+        produced_blocks: &BTreeMap<AccountId, u64>,
+        total_supply: Balance,
+    ) -> (BTreeMap<AccountId, Balance>, Balance) {
+        let mint = total_supply * self.epoch_inflation_numerator as u128
+            / self.epoch_inflation_denominator as u128;
+        let total_produced: u64 = produced_blocks.values().sum();
+        if mint == 0 || total_produced == 0 {
+            return (BTreeMap::new(), 0);
+        }
+        let rewards: BTreeMap<AccountId, Balance> = produced_blocks
+            .iter()
+            .filter(|(_, produced)| **produced > 0)
+            .map(|(account_id, produced)| {
+                (account_id.clone(), mint * *produced as u128 / total_produced as u128)
+            })
+            .collect();
+        let minted = rewards.values().sum();
+        (rewards, minted)
+    }
+}
+
+/// The production thresholds a validator must meet to keep its seat, plus
+/// the stake floor guarding validator-set continuity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OnlineThresholdsConfig {
+    /// A validator producing less than this percentage of its expected
+    /// blocks is kicked out.
+    pub block_producer_kickout_threshold_percent: u64,
+    /// A validator producing less than this percentage of its expected
+    /// chunks is kicked out.
+    pub chunk_producer_kickout_threshold_percent: u64,
+    /// The fraction of the total stake that must stay in the validator set.
+    /// Kickouts are applied worst producer first, and any kickout that would
+    /// drop the remaining stake below this floor is waived -- with
+    /// `Rational32::new(1, 1)` nobody can be kicked out.
+    pub minimum_stake_ratio: Rational32,
+}
+
+impl Default for OnlineThresholdsConfig {
+    fn default() -> Self {
+        Self {
+            block_producer_kickout_threshold_percent: BLOCK_PRODUCER_KICKOUT_THRESHOLD_PERCENT,
+            chunk_producer_kickout_threshold_percent: BLOCK_PRODUCER_KICKOUT_THRESHOLD_PERCENT,
+            minimum_stake_ratio: Rational32::new(1, 3),
+        }
+    }
+}
+
+/// Which of the ended epoch's validators lose their seat in the next epoch
+/// over their production statistics, to feed into the next
+/// [`EpochInfo::validator_kickout`].
+///
+/// A validator is a kickout candidate when its produced/expected ratio falls
+/// below the block or chunk threshold; meeting a threshold exactly keeps the
+/// seat, and a dimension with nothing expected cannot fail. Candidates are
+/// then kicked out worst overall production first, skipping any whose
+/// removal would drop the remaining stake below
+/// [`OnlineThresholdsConfig::minimum_stake_ratio`] of the total -- so even
+/// if every validator underperformed, the heaviest ones keep the chain
+/// running.
+pub fn compute_kickout_info(
+    epoch_info: &EpochInfo,
+    block_stats: &HashMap<AccountId, ValidatorStats>,
+    chunk_stats: &HashMap<AccountId, ValidatorStats>,
+    online_thresholds: OnlineThresholdsConfig,
+) -> HashMap<AccountId, ValidatorKickoutReason> {
+    let total_stake: Balance = epoch_info.validators().iter().map(|v| v.stake()).sum();
+    let mut candidates = Vec::new();
+    for validator in epoch_info.validators() {
+        let blocks = block_stats.get(validator.account_id()).copied().unwrap_or_default();
+        let chunks = chunk_stats.get(validator.account_id()).copied().unwrap_or_default();
+        let below_blocks = blocks.produced * 100
+            < blocks.expected * online_thresholds.block_producer_kickout_threshold_percent;
+        let below_chunks = chunks.produced * 100
+            < chunks.expected * online_thresholds.chunk_producer_kickout_threshold_percent;
+        let reason = if below_blocks {
+            ValidatorKickoutReason::NotEnoughBlocks {
+                produced: blocks.produced,
+                expected: blocks.expected,
+            }
+        } else if below_chunks {
+            ValidatorKickoutReason::NotEnoughChunks {
+                produced: chunks.produced,
+                expected: chunks.expected,
+            }
+        } else {
+            continue;
+        };
+        // The overall production ratio orders the candidates; a dimension
+        // with nothing expected drops out, as in reward calculation.
+        let ratio = match (blocks.expected, chunks.expected) {
+            (0, 0) => Rational32::new(0, 1),
+            (0, _) => Rational32::new(chunks.produced as i32, chunks.expected as i32),
+            (_, 0) => Rational32::new(blocks.produced as i32, blocks.expected as i32),
+            _ => {
+                (Rational32::new(blocks.produced as i32, blocks.expected as i32)
+                    + Rational32::new(chunks.produced as i32, chunks.expected as i32))
+                    / 2
+            }
+        };
+        candidates.push((ratio, validator.stake(), validator.account_id().clone(), reason));
+    }
+    candidates.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+
+    let floor_numerator = (*online_thresholds.minimum_stake_ratio.numer()).max(0) as u128;
+    let floor_denominator = (*online_thresholds.minimum_stake_ratio.denom()) as u128;
+    let mut kickout = HashMap::new();
+    let mut remaining_stake = total_stake;
+    for (_, stake, account_id, reason) in candidates {
+        if (remaining_stake - stake) * floor_denominator < total_stake * floor_numerator {
+            continue;
+        }
+        remaining_stake -= stake;
+        kickout.insert(account_id, reason);
+    }
+    kickout
+}
+
+/// Tracks epochs of the chain: which validators run each epoch, when epochs
+/// end and what the next epoch looks like.
+///
+/// Old epoch information can be garbage collected, with one exception: the
+/// genesis epoch (epoch height zero) is always resolvable, since syncing
+/// from scratch starts there.
+///
+/// The query caches live behind interior mutexes, so every getter whose
+/// only mutation is populating a cache takes `&self`; holding
+/// [`EpochManagerHandle::read`] suffices for the whole query surface, and
+/// `&mut self` is reserved for methods that genuinely change epoch state.
+pub struct EpochManager {
+    store: Store,
+    /// Number of shards the chain runs, used to sanity-check loaded epoch
+    /// information.
+    num_shards: NumShards,
+    /// Number of block heights per epoch, used to tell when the next block
+    /// crosses an epoch boundary.
+    epoch_length: BlockHeight,
+    /// Cache of epoch information keyed by epoch id.
+    epochs_info: Mutex<HashMap<EpochId, Arc<EpochInfo>>>,
+    /// Which epoch id runs at each epoch ordinal, filled as epoch
+    /// information is computed; used to walk from an epoch to its successor.
+    epoch_ids_by_height: HashMap<EpochHeight, EpochId>,
+    /// Cache of block bookkeeping, keyed by block hash.
+    block_infos: Mutex<HashMap<CryptoHash, Arc<BlockInfo>>>,
+    /// The cumulative slash state as seen from each block, i.e. everyone
+    /// slashed along the block's ancestry up to the previous epoch.
+    slash_states: HashMap<CryptoHash, BTreeMap<AccountId, SlashState>>,
+    /// Which shard layout each protocol version runs, sorted by the version
+    /// the layout first applies to. Always has an entry for version zero.
+    shard_layout_schedule: Vec<(ProtocolVersion, ShardLayout)>,
+    /// Tombstones for epochs whose information has been garbage collected.
+    garbage_collected_epochs: HashSet<EpochId>,
+    /// Computes the rewards paid out when an epoch is finalized.
+    reward_calculator: RewardCalculator,
+    /// Running statistics of the epoch the final head is in, advanced by
+    /// [`Self::update_epoch_info_aggregator_upto_final`] as blocks become
+    /// final and extended speculatively by
+    /// [`Self::get_epoch_info_aggregator_upto_last`] for queries about
+    /// not-yet-final blocks.
+    epoch_info_aggregator: EpochInfoAggregator,
+    /// The height the aggregator was last persisted at; see
+    /// [`AGGREGATOR_SAVE_PERIOD`].
+    aggregator_saved_at_height: BlockHeight,
+    /// Number of blocks the aggregator traversals have visited, a white-box
+    /// hook for tests asserting the walks stay incremental.
+    pub(crate) epoch_info_aggregator_loop_counter: u64,
+    /// Height of the first block of each epoch the chain has entered.
+    epoch_start_heights: HashMap<EpochId, BlockHeight>,
+    /// Height of the last block of each epoch that has ended.
+    epoch_end_heights: HashMap<EpochId, BlockHeight>,
+    /// Height of the highest final block seen.
+    largest_final_height: BlockHeight,
+    /// Cache of chunk validator assignments, keyed by the full sampling seed.
+    chunk_validators_cache: Mutex<HashMap<AssignmentSeed, Arc<Vec<ValidatorStake>>>>,
+    /// Cache of sampled chunk validator mandate assignments; see
+    /// [`EpochManager::get_chunk_validator_mandate_assignments`].
+    mandate_assignments_cache:
+        Mutex<HashMap<(EpochId, ShardId, BlockHeight), Arc<ChunkValidatorAssignments>>>,
+    /// Cache of block producer settlements with their slashed flags; keyed
+    /// by the epoch and the block the slashes were read from.
+    epoch_validators_ordered: Mutex<HashMap<(EpochId, CryptoHash), ValidatorsWithSlashFlag>>,
+    /// Like `epoch_validators_ordered` with repeated seats deduplicated.
+    epoch_validators_ordered_unique: Mutex<HashMap<(EpochId, CryptoHash), ValidatorsWithSlashFlag>>,
+    /// Event queues of the epoch change subscribers; see [`handle`].
+    subscribers: Vec<Arc<handle::SubscriberQueue>>,
+    /// The `EpochStarted` event of the epoch the chain is currently in,
+    /// replayed to subscribers that ask for a snapshot.
+    last_epoch_started: Option<EpochChangeEvent>,
+}
+
+/// Locks one of the epoch manager's interior caches. The caches are leaf
+/// locks held only across a lookup or an insert -- never while another
+/// cache or the store is touched -- so they cannot deadlock; poisoning is
+/// the only failure mode.
+fn lock_cache<T>(cache: &Mutex<T>) -> MutexGuard<'_, T> {
+    cache.lock().expect("epoch manager cache poisoned")
+}
+
+impl EpochManager {
+    pub fn new(store: Store, num_shards: NumShards) -> Self {
+        let epoch_info_aggregator = store
+            .get_ser(DBCol::BlockMisc, EPOCH_INFO_AGGREGATOR_KEY)
+            .unwrap_or_default()
+            .unwrap_or_default();
+        Self {
+            store,
+            num_shards,
+            epoch_length: DEFAULT_EPOCH_LENGTH,
+            epochs_info: Mutex::new(HashMap::new()),
+            epoch_ids_by_height: HashMap::new(),
+            block_infos: Mutex::new(HashMap::new()),
+            slash_states: HashMap::new(),
+            shard_layout_schedule: vec![(0, ShardLayout::single_shard())],
+            garbage_collected_epochs: HashSet::new(),
+            reward_calculator: RewardCalculator::default(),
+            epoch_info_aggregator,
+            aggregator_saved_at_height: 0,
+            epoch_info_aggregator_loop_counter: 0,
+            epoch_start_heights: HashMap::new(),
+            epoch_end_heights: HashMap::new(),
+            largest_final_height: 0,
+            chunk_validators_cache: Mutex::new(HashMap::new()),
+            mandate_assignments_cache: Mutex::new(HashMap::new()),
+            epoch_validators_ordered: Mutex::new(HashMap::new()),
+            epoch_validators_ordered_unique: Mutex::new(HashMap::new()),
+            subscribers: Vec::new(),
+            last_epoch_started: None,
+        }
+    }
+
+    /// Bootstraps a manager from the genesis validators: builds the first
+    /// epoch's [`EpochInfo`] under [`EpochId::default()`] and persists it,
+    /// so block processing can start at height zero.
+    ///
+    /// The top `num_block_producer_seats` validators by stake fill the
+    /// block producer settlement -- all of them when there are fewer -- and
+    /// every shard's chunk producer settlement mirrors it, matching how
+    /// later epochs are finalized. Validator mandates are derived on demand
+    /// from the saved info, like for any other epoch.
+    ///
+    /// Re-running on an already initialized store keeps the existing
+    /// genesis epoch info: a restart must not rewrite history that blocks
+    /// were already produced against.
+    pub fn init_genesis(
+        store: Store,
+        num_shards: NumShards,
+        num_block_producer_seats: u64,
+        genesis_protocol_version: ProtocolVersion,
+        reward_calculator: RewardCalculator,
+        validators: Vec<ValidatorStake>,
+    ) -> Result<Self, EpochError> {
+        assert!(num_block_producer_seats > 0, "there must be at least one block producer seat");
+        let mut epoch_manager = Self::new(store, num_shards);
+        epoch_manager.set_reward_calculator(reward_calculator);
+        let genesis_epoch_id = EpochId::default();
+        if epoch_manager.get_epoch_info_if_exists(&genesis_epoch_id)?.is_some() {
+            return Ok(epoch_manager);
+        }
+
+        let mut validators = validators;
+        validators.sort_by(|a, b| {
+            b.stake().cmp(&a.stake()).then_with(|| a.account_id().cmp(b.account_id()))
+        });
+        let validator_to_index: HashMap<AccountId, u64> = validators
+            .iter()
+            .enumerate()
+            .map(|(index, validator)| (validator.account_id().clone(), index as u64))
+            .collect();
+        let num_seated = (validators.len() as u64).min(num_block_producer_seats);
+        let block_producers_settlement: Vec<u64> = (0..num_seated).collect();
+        let chunk_producers_settlement =
+            vec![block_producers_settlement.clone(); num_shards as usize];
+        let stake_change = validators
+            .iter()
+            .map(|validator| (validator.account_id().clone(), validator.stake()))
+            .collect();
+        let seat_price = validators
+            .iter()
+            .take(num_seated as usize)
+            .map(|validator| validator.stake())
+            .min()
+            .unwrap_or_default();
+        let epoch_info = EpochInfo::new(
+            0,
+            validators,
+            validator_to_index,
+            block_producers_settlement,
+            chunk_producers_settlement,
+            stake_change,
+            0,
+            seat_price,
+            BTreeMap::new(),
+            genesis_protocol_version,
+            [0; 32],
+        );
+        epoch_info
+            .validate_settlements(num_shards)
+            .map_err(|err| EpochError::CorruptedEpochInfo(genesis_epoch_id, err.to_string()))?;
+        epoch_manager.save_epoch_info(&genesis_epoch_id, epoch_info)?;
+        Ok(epoch_manager)
+    }
+
+    /// Subscribes to epoch change events; when `snapshot` is set, the start
+    /// of the current epoch -- if one has started -- is delivered first.
+    pub fn subscribe(&mut self, snapshot: bool) -> EpochChangeReceiver {
+        let queue = handle::SubscriberQueue::new();
+        if snapshot && let Some(event) = &self.last_epoch_started {
+            queue.push(event.clone());
+        }
+        self.subscribers.push(Arc::clone(&queue));
+        EpochChangeReceiver::new(queue)
+    }
+
+    /// Delivers an event to every subscriber; never blocks, slow
+    /// subscribers lose their oldest events instead.
+    fn emit(&mut self, event: EpochChangeEvent) {
+        for subscriber in &self.subscribers {
+            subscriber.push(event.clone());
+        }
+    }
+
+    /// Records the information of a freshly computed epoch, both in the cache
+    /// and in the store.
+    pub fn save_epoch_info(
+        &mut self,
+        epoch_id: &EpochId,
+        epoch_info: EpochInfo,
+    ) -> Result<(), EpochError> {
+        let mut update = self.store.store_update();
+        update.set_ser(DBCol::EpochInfo, epoch_id.0.as_ref(), &epoch_info)?;
+        update.commit()?;
+        self.garbage_collected_epochs.remove(epoch_id);
+        self.epoch_ids_by_height.insert(epoch_info.epoch_height(), *epoch_id);
+        lock_cache(&self.epochs_info).insert(*epoch_id, Arc::new(epoch_info));
+        self.emit(EpochChangeEvent::NextEpochInfoComputed { epoch_id: *epoch_id });
+        Ok(())
+    }
+
+    /// Records the bookkeeping of a processed block, both in the cache and in
+    /// the store.
+    pub fn save_block_info(&mut self, block_info: BlockInfo) -> Result<(), EpochError> {
+        let mut update = self.store.store_update();
+        update.set_ser(DBCol::BlockInfo, block_info.hash().as_ref(), &block_info)?;
+        update.commit()?;
+        lock_cache(&self.block_infos).insert(*block_info.hash(), Arc::new(block_info));
+        Ok(())
+    }
+
+    /// Records a processed block, applies the slashes its challenges
+    /// produced, and -- when the block is the first of a new epoch --
+    /// finalizes the epoch that just ended; see [`Self::finalize_epoch`].
+    ///
+    /// The slash state of a block is its previous block's slash state --
+    /// demoted to [`SlashState::AlreadySlashed`] across an epoch boundary,
+    /// so a slash in block B affects the validator set no later than the
+    /// epoch after B's epoch -- merged with the block's own slashes, the
+    /// strongest penalty winning. Re-recording an already known block is a
+    /// no-op, so forks and replays cannot double-slash or double-finalize.
+    pub fn record_block_info(&mut self, mut block_info: BlockInfo) -> Result<(), EpochError> {
+        if lock_cache(&self.block_infos).contains_key(block_info.hash()) {
+            return Ok(());
+        }
+        let prev_block_info = lock_cache(&self.block_infos).get(block_info.prev_hash()).cloned();
+        let mut slash_state = match &prev_block_info {
+            Some(prev) if prev.epoch_id() == block_info.epoch_id() => {
+                self.slash_states.get(prev.hash()).cloned().unwrap_or_default()
+            }
+            Some(prev) => {
+                // Crossing into a new epoch: the penalties of everyone
+                // slashed so far have been applied to this epoch's
+                // validator set.
+                let prev_state = self.slash_states.get(prev.hash()).cloned().unwrap_or_default();
+                prev_state.into_keys().map(|account| (account, SlashState::AlreadySlashed)).collect()
+            }
+            // The previous block is unknown (e.g. genesis): start clean.
+            None => BTreeMap::new(),
+        };
+        for slashed in block_info.slashed() {
+            let new_state =
+                if slashed.is_double_sign { SlashState::DoubleSign } else { SlashState::Other };
+            slash_state
+                .entry(slashed.account_id.clone())
+                .and_modify(|state| {
+                    // The strongest penalty wins; a validator whose penalty
+                    // was already applied stays that way.
+                    if *state == SlashState::Other && new_state == SlashState::DoubleSign {
+                        *state = SlashState::DoubleSign;
+                    }
+                })
+                .or_insert(new_state);
+        }
+        self.slash_states.insert(*block_info.hash(), slash_state);
+        let starts_epoch = match &prev_block_info {
+            Some(prev) => prev.epoch_id() != block_info.epoch_id(),
+            None => true,
+        };
+        // Track each epoch's first block on its blocks, so queries can walk
+        // from any block to its epoch's start without scanning the ancestry.
+        *block_info.epoch_first_block_mut() = match &prev_block_info {
+            Some(prev) if !starts_epoch => *prev.epoch_first_block(),
+            _ => *block_info.hash(),
+        };
+        if starts_epoch {
+            self.save_epoch_start_height(block_info.epoch_id(), block_info.height());
+            let prev_hash = *block_info.prev_hash();
+            if self.has_block_info(&prev_hash) {
+                self.finalize_epoch(&prev_hash, &block_info)?;
+            }
+            let epoch_height = self
+                .get_epoch_info_if_exists(block_info.epoch_id())
+                .ok()
+                .flatten()
+                .map(|epoch_info| epoch_info.epoch_height())
+                .unwrap_or_default();
+            let event = EpochChangeEvent::EpochStarted {
+                epoch_id: *block_info.epoch_id(),
+                epoch_height,
+                first_block_hash: *block_info.hash(),
+            };
+            self.last_epoch_started = Some(event.clone());
+            self.emit(event);
+        }
+        self.save_block_info(block_info)
+    }
+
+    /// Finalizes the epoch that ended with `last_block_hash`, triggered by
+    /// the first recorded block of the next epoch.
+    ///
+    /// Walks the ended epoch's blocks along the ancestry of its last block
+    /// -- so only the fork actually being extended counts, and forks
+    /// abandoned inside the epoch cannot leak proposals or production
+    /// statistics -- and computes the next epoch's information: the ended
+    /// epoch's validators with their rewards added, overridden by the
+    /// epoch's stake proposals, minus kickouts for unstaking, producing too
+    /// few blocks or getting slashed. The next epoch's sampling seed is
+    /// derived from the hash of the ended epoch's last block.
+    ///
+    /// If the next epoch's information already exists -- pre-provisioned at
+    /// genesis, or computed when another fork crossed the boundary first --
+    /// or the ended epoch's information is not available, only the epoch
+    /// height bookkeeping is updated.
+    fn finalize_epoch(
+        &mut self,
+        last_block_hash: &CryptoHash,
+        first_block_of_next: &BlockInfo,
+    ) -> Result<(), EpochError> {
+        let last_block = self.get_block_info(last_block_hash)?;
+        let ended_epoch_id = *last_block.epoch_id();
+        let next_epoch_id = *first_block_of_next.epoch_id();
+        self.save_epoch_end_height(&ended_epoch_id, last_block.height());
+        if self.get_epoch_info_if_exists(&next_epoch_id)?.is_some() {
+            return Ok(());
+        }
+        let Ok(Some(ended_epoch_info)) = self.get_epoch_info_if_exists(&ended_epoch_id) else {
+            // The ended epoch's information is unknown or already garbage
+            // collected -- e.g. while catching up from state sync -- so
+            // there is nothing to compute the next epoch from.
+            return Ok(());
+        };
+
+        // Aggregate the epoch along the ancestry of its last block: the
+        // latest proposal per account wins, and each produced block is its
+        // producer's protocol version vote.
+        let mut proposals: BTreeMap<AccountId, ValidatorStake> = BTreeMap::new();
+        let mut version_per_height: BTreeMap<BlockHeight, ProtocolVersion> = BTreeMap::new();
+        let mut cursor = Arc::clone(&last_block);
+        loop {
+            version_per_height.insert(cursor.height(), cursor.latest_protocol_version());
+            for proposal in cursor.proposals() {
+                proposals.entry(proposal.account_id().clone()).or_insert_with(|| proposal.clone());
+            }
+            let prev_hash = *cursor.prev_hash();
+            if !self.has_block_info(&prev_hash) {
+                break;
+            }
+            let prev = self.get_block_info(&prev_hash)?;
+            if prev.epoch_id() != &ended_epoch_id {
+                break;
+            }
+            cursor = prev;
+        }
+        let start_height = cursor.height();
+
+        // Block production statistics: a height whose block is missing from
+        // the ancestry counts against the producer expected at that height.
+        let mut produced: BTreeMap<AccountId, u64> = BTreeMap::new();
+        let mut expected: BTreeMap<AccountId, u64> = BTreeMap::new();
+        let mut version_votes: HashMap<AccountId, ProtocolVersion> = HashMap::new();
+        for height in start_height..=last_block.height() {
+            let producer = self.sample_block_producer(&ended_epoch_id, height)?;
+            *expected.entry(producer.account_id().clone()).or_default() += 1;
+            if let Some(version) = version_per_height.get(&height) {
+                *produced.entry(producer.account_id().clone()).or_default() += 1;
+                version_votes.insert(producer.account_id().clone(), *version);
+            }
+        }
+
+        // Kickouts for missed blocks. If every validator missed the
+        // threshold the chain must still go on: the best producer keeps its
+        // seat.
+        let mut kickout: BTreeMap<AccountId, ValidatorKickoutReason> = BTreeMap::new();
+        for validator in ended_epoch_info.validators() {
+            let produced_count = produced.get(validator.account_id()).copied().unwrap_or_default();
+            let expected_count = expected.get(validator.account_id()).copied().unwrap_or_default();
+            if produced_count * 100 < expected_count * BLOCK_PRODUCER_KICKOUT_THRESHOLD_PERCENT {
+                kickout.insert(
+                    validator.account_id().clone(),
+                    ValidatorKickoutReason::NotEnoughBlocks {
+                        produced: produced_count,
+                        expected: expected_count,
+                    },
+                );
+            }
+        }
+        if kickout.len() == ended_epoch_info.validators().len()
+            && let Some(best) = ended_epoch_info.validators().iter().max_by_key(|validator| {
+                produced.get(validator.account_id()).copied().unwrap_or_default()
+            })
+        {
+            kickout.remove(best.account_id());
+        }
+
+        // The next validator set: the ended epoch's stakes with rewards
+        // added, overridden by the proposals -- a zero-stake proposal
+        // unstakes -- minus everyone kicked out or slashed along the
+        // epoch's ancestry.
+        let (rewards, minted_amount) =
+            self.reward_calculator.calculate_produced_blocks_reward(&produced, last_block.total_supply());
+        let mut next_stakes: BTreeMap<AccountId, ValidatorStake> = BTreeMap::new();
+        for validator in ended_epoch_info.validators() {
+            let stake =
+                validator.stake() + rewards.get(validator.account_id()).copied().unwrap_or_default();
+            next_stakes.insert(
+                validator.account_id().clone(),
+                ValidatorStake::new(
+                    validator.account_id().clone(),
+                    validator.public_key().clone(),
+                    stake,
+                ),
+            );
+        }
+        for (account_id, proposal) in &proposals {
+            if proposal.stake() == 0 {
+                kickout.insert(account_id.clone(), ValidatorKickoutReason::Unstaked);
+            } else {
+                next_stakes.insert(account_id.clone(), proposal.clone());
+            }
+        }
+        for account_id in kickout.keys() {
+            next_stakes.remove(account_id);
+        }
+        if let Some(slash_state) = self.slash_states.get(last_block.hash()) {
+            for account_id in slash_state.keys() {
+                next_stakes.remove(account_id);
+            }
+        }
+        if next_stakes.is_empty() {
+            // Validator-set continuity beats the penalties: with everyone
+            // gone the chain would halt, so the ended set carries over.
+            for validator in ended_epoch_info.validators() {
+                next_stakes.insert(validator.account_id().clone(), validator.clone());
+            }
+        }
+
+        let protocol_version = self.compute_next_protocol_version(&ended_epoch_id, &version_votes)?;
+        let mut validators: Vec<ValidatorStake> = next_stakes.into_values().collect();
+        validators.sort_by(|a, b| {
+            b.stake().cmp(&a.stake()).then_with(|| a.account_id().cmp(b.account_id()))
+        });
+        let validator_to_index: HashMap<AccountId, u64> = validators
+            .iter()
+            .enumerate()
+            .map(|(index, validator)| (validator.account_id().clone(), index as u64))
+            .collect();
+        let block_producers_settlement: Vec<u64> = (0..validators.len() as u64).collect();
+        let chunk_producers_settlement =
+            vec![block_producers_settlement.clone(); self.num_shards as usize];
+        let stake_change = validators
+            .iter()
+            .map(|validator| (validator.account_id().clone(), validator.stake()))
+            .collect();
+        let seat_price = validators.iter().map(|validator| validator.stake()).min().unwrap_or_default();
+        let epoch_info = EpochInfo::new(
+            ended_epoch_info.epoch_height() + 1,
+            validators,
+            validator_to_index,
+            block_producers_settlement,
+            chunk_producers_settlement,
+            stake_change,
+            minted_amount,
+            seat_price,
+            kickout,
+            protocol_version,
+            *last_block.hash().as_bytes(),
+        );
+        self.save_epoch_info(&next_epoch_id, epoch_info)
+    }
+
+    /// Replaces the protocol-version → shard-layout schedule. The schedule
+    /// must cover protocol version zero so every version maps to a layout.
+    pub fn set_shard_layout_schedule(
+        &mut self,
+        mut schedule: Vec<(ProtocolVersion, ShardLayout)>,
+    ) {
+        schedule.sort_by_key(|(version, _)| *version);
+        assert_eq!(
+            schedule.first().map(|(version, _)| *version),
+            Some(0),
+            "shard layout schedule must start at protocol version zero"
+        );
+        self.shard_layout_schedule = schedule;
+    }
+
+    /// Replaces the reward calculator used when epochs are finalized.
+    pub fn set_reward_calculator(&mut self, reward_calculator: RewardCalculator) {
+        self.reward_calculator = reward_calculator;
+    }
+
+    /// How many blocks the aggregator traversals have visited in total, so
+    /// tests -- including cross-crate ones, behind the `test_utils` feature
+    /// -- can assert an aggregation stayed incremental instead of
+    /// re-scanning the epoch.
+    #[cfg(any(test, feature = "test_utils"))]
+    pub fn aggregator_loop_iterations(&self) -> usize {
+        self.epoch_info_aggregator_loop_counter as usize
+    }
+
+    /// Configures the chain's number of block heights per epoch, which
+    /// [`Self::is_next_block_epoch_start`] measures boundaries against.
+    pub fn set_epoch_length(&mut self, epoch_length: BlockHeight) {
+        assert_ne!(epoch_length, 0, "epoch length must not be zero");
+        self.epoch_length = epoch_length;
+    }
+
+    /// Whether the block built on top of the given block crosses into a new
+    /// epoch: the parent sits at or past the last height of its epoch, as
+    /// measured from the epoch's first block.
+    pub fn is_next_block_epoch_start(
+        &self,
+        parent_hash: &CryptoHash,
+    ) -> Result<bool, EpochError> {
+        let block_info = self.get_block_info(parent_hash)?;
+        let first_block_info = self.get_block_info(block_info.epoch_first_block())?;
+        Ok(block_info.height() + 1 >= first_block_info.height() + self.epoch_length)
+    }
+
+    /// The epoch id of a block whose previous block is `parent_hash`: the
+    /// parent's epoch, or -- when the next block crosses the boundary -- the
+    /// id of the epoch after it.
+    pub fn get_epoch_id_from_prev_block(
+        &self,
+        parent_hash: &CryptoHash,
+    ) -> Result<EpochId, EpochError> {
+        if self.is_next_block_epoch_start(parent_hash)? {
+            self.get_next_epoch_id(parent_hash)
+        } else {
+            Ok(*self.get_block_info(parent_hash)?.epoch_id())
+        }
+    }
+
+    /// The next epoch id as seen from a block whose previous block is
+    /// `parent_hash`, i.e. the epoch after
+    /// [`Self::get_epoch_id_from_prev_block`].
+    pub fn get_next_epoch_id_from_prev_block(
+        &self,
+        parent_hash: &CryptoHash,
+    ) -> Result<EpochId, EpochError> {
+        if self.is_next_block_epoch_start(parent_hash)? {
+            // The parent is the last block of its epoch, so per the id
+            // convention it names the epoch after the one just entered.
+            Ok(EpochId(*parent_hash))
+        } else {
+            self.get_next_epoch_id(parent_hash)
+        }
+    }
+
+    /// The id of the epoch after the given block's epoch. `EpochId` of epoch
+    /// T is the hash of the last block of T-2, so the epoch after the
+    /// block's epoch T is named by the last block of T-1 -- the block right
+    /// before T's first block.
+    fn get_next_epoch_id(&self, hash: &CryptoHash) -> Result<EpochId, EpochError> {
+        let block_info = self.get_block_info(hash)?;
+        let first_block_info = self.get_block_info(block_info.epoch_first_block())?;
+        Ok(EpochId(*first_block_info.prev_hash()))
+    }
+
+    /// Records the height at which an epoch starts; the first record for an
+    /// epoch wins, later calls are no-ops.
+    pub fn save_epoch_start_height(&mut self, epoch_id: &EpochId, height: BlockHeight) {
+        self.epoch_start_heights.entry(*epoch_id).or_insert(height);
+    }
+
+    pub fn epoch_start_height(&self, epoch_id: &EpochId) -> Option<BlockHeight> {
+        self.epoch_start_heights.get(epoch_id).copied()
+    }
+
+    /// Records the height of the last block of an epoch once the epoch has
+    /// ended.
+    pub fn save_epoch_end_height(&mut self, epoch_id: &EpochId, height: BlockHeight) {
+        self.epoch_end_heights.insert(*epoch_id, height);
+    }
+
+    /// Updates the height of the highest final block seen.
+    ///
+    /// `largest_final_height` is monotonically increasing: finality cannot
+    /// regress, so a lower height -- e.g. fed from a reorged-away fork -- is
+    /// ignored rather than applied.
+    pub fn update_largest_final_height(&mut self, height: BlockHeight) {
+        if height < self.largest_final_height {
+            return;
+        }
+        self.largest_final_height = height;
+    }
+
+    pub fn largest_final_height(&self) -> BlockHeight {
+        self.largest_final_height
+    }
+
+    /// Walks blocks backwards from `last_block_hash` until it reaches the
+    /// aggregator's last block, the start of the block's epoch or the edge
+    /// of recorded bookkeeping, and returns their merged statistics as a
+    /// fresh aggregator for that block's epoch.
+    fn aggregate_epoch_info_upto(
+        &mut self,
+        last_block_hash: &CryptoHash,
+    ) -> Result<EpochInfoAggregator, EpochError> {
+        let last_block = self.get_block_info(last_block_hash)?;
+        let epoch_id = *last_block.epoch_id();
+        let epoch_info = self.get_epoch_info(&epoch_id)?;
+
+        // Collect the not-yet-covered stretch, newest first, remembering the
+        // height the stretch starts above so skipped heights get tallied.
+        let mut blocks: Vec<Arc<BlockInfo>> = Vec::new();
+        let mut base_height = None;
+        let mut cursor = Arc::clone(&last_block);
+        loop {
+            if *cursor.hash() == self.epoch_info_aggregator.last_block_hash {
+                base_height = Some(cursor.height());
+                break;
+            }
+            self.epoch_info_aggregator_loop_counter += 1;
+            blocks.push(Arc::clone(&cursor));
+            let prev_hash = *cursor.prev_hash();
+            if !self.has_block_info(&prev_hash) {
+                break;
+            }
+            let prev = self.get_block_info(&prev_hash)?;
+            if prev.epoch_id() != &epoch_id {
+                base_height = Some(prev.height());
+                break;
+            }
+            cursor = prev;
+        }
+
+        let mut aggregator = EpochInfoAggregator::new(epoch_id, *last_block.hash());
+        for block in blocks.iter().rev() {
+            aggregator.update_tally(&epoch_info, block, base_height);
+            base_height = Some(block.height());
+        }
+        Ok(aggregator)
+    }
+
+    /// Advances the aggregator to the given final block, merging the
+    /// statistics of every newly final block of the same epoch. When the
+    /// final head crossed an epoch boundary -- possibly several, e.g. while
+    /// catching up -- the aggregator snaps to the head's epoch and starts
+    /// from that epoch's first block; the crossed epochs were already
+    /// finalized block by block. Persisted every [`AGGREGATOR_SAVE_PERIOD`]
+    /// heights so a restart does not replay the whole epoch.
+    pub fn update_epoch_info_aggregator_upto_final(
+        &mut self,
+        last_final_block_hash: &CryptoHash,
+    ) -> Result<(), EpochError> {
+        let extension = self.aggregate_epoch_info_upto(last_final_block_hash)?;
+        if extension.epoch_id == self.epoch_info_aggregator.epoch_id {
+            self.epoch_info_aggregator.merge(extension);
+        } else {
+            self.epoch_info_aggregator = extension;
+        }
+        let final_height = self.get_block_info(last_final_block_hash)?.height();
+        if final_height.saturating_sub(self.aggregator_saved_at_height) >= AGGREGATOR_SAVE_PERIOD {
+            let mut update = self.store.store_update();
+            self.save_epoch_info_aggregator(&mut update)?;
+            update.commit()?;
+            self.aggregator_saved_at_height = final_height;
+        }
+        Ok(())
+    }
+
+    /// Stages the current aggregator into the given update, under the key
+    /// the constructor resumes from. The periodic flush in
+    /// [`Self::update_epoch_info_aggregator_upto_final`] uses this; callers
+    /// that want a snapshot at a specific point -- e.g. right before a
+    /// planned shutdown -- can stage one into their own batch.
+    pub fn save_epoch_info_aggregator(
+        &self,
+        update: &mut StoreUpdate,
+    ) -> Result<(), EpochError> {
+        update.set_ser(DBCol::BlockMisc, EPOCH_INFO_AGGREGATOR_KEY, &self.epoch_info_aggregator)?;
+        Ok(())
+    }
+
+    /// A copy of the aggregator extended -- but not persisted -- with the
+    /// blocks between its last block and the given one, for queries about
+    /// the not-yet-final tip. The given block must descend from the
+    /// aggregator's last block, which every chain tip does once the
+    /// aggregator only advances with finality.
+    pub fn get_epoch_info_aggregator_upto_last(
+        &mut self,
+        last_block_hash: &CryptoHash,
+    ) -> Result<EpochInfoAggregator, EpochError> {
+        let extension = self.aggregate_epoch_info_upto(last_block_hash)?;
+        if extension.epoch_id == self.epoch_info_aggregator.epoch_id {
+            let mut aggregator = self.epoch_info_aggregator.clone();
+            aggregator.merge(extension);
+            Ok(aggregator)
+        } else {
+            Ok(extension)
+        }
+    }
+
+    /// The seat prices of the most recent `num_epochs` known epochs, newest
+    /// first.
+    ///
+    /// Epochs whose information has been garbage collected end the history
+    /// early: everything older is gone as well.
+    pub fn get_seat_price_history(
+        &self,
+        num_epochs: usize,
+    ) -> Result<Vec<(EpochHeight, Balance)>, EpochError> {
+        let mut heights: Vec<EpochHeight> = self.epoch_ids_by_height.keys().copied().collect();
+        heights.sort_unstable_by(|a, b| b.cmp(a));
+        let mut history = Vec::new();
+        for height in heights {
+            if history.len() == num_epochs {
+                break;
+            }
+            let epoch_id = self.epoch_ids_by_height[&height];
+            match self.get_epoch_info_if_exists(&epoch_id) {
+                Ok(Some(epoch_info)) => history.push((height, epoch_info.seat_price())),
+                Ok(None) => {}
+                Err(EpochError::EpochGarbageCollected(_)) => break,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(history)
+    }
+
+    /// An estimate of the next epoch's seat price as seen from the given
+    /// block: the current validators' stakes rolled over, overridden by the
+    /// stake proposals aggregated along the block's ancestry.
+    ///
+    /// This is an estimate, not a commitment -- later proposals, kickouts
+    /// and rewards keep moving it until the epoch's last block is final.
+    /// It only reads the incremental aggregator plus the blocks past it, so
+    /// it is cheap enough to serve from an RPC endpoint.
+    pub fn estimate_next_seat_price(
+        &mut self,
+        block_hash: &CryptoHash,
+    ) -> Result<Balance, EpochError> {
+        let block_info = self.get_block_info(block_hash)?;
+        let epoch_id = *block_info.epoch_id();
+        let epoch_info = self
+            .get_epoch_info_if_exists(&epoch_id)?
+            .ok_or(EpochError::EpochOutOfBounds(epoch_id))?;
+        let aggregator = self.get_epoch_info_aggregator_upto_last(block_hash)?;
+        let mut stakes: BTreeMap<AccountId, Balance> = epoch_info
+            .validators()
+            .iter()
+            .map(|validator| (validator.account_id().clone(), validator.stake()))
+            .collect();
+        for (account_id, proposal) in &aggregator.all_proposals {
+            if proposal.stake() == 0 {
+                stakes.remove(account_id);
+            } else {
+                stakes.insert(account_id.clone(), proposal.stake());
+            }
+        }
+        let rolled_over_minimum =
+            epoch_info.validators().iter().map(|validator| validator.stake()).min();
+        Ok(stakes
+            .into_values()
+            .min()
+            // Everyone unstaking carries the current set over, exactly as
+            // finalization would.
+            .or(rolled_over_minimum)
+            .unwrap_or_default())
+    }
+
+    /// Decides the protocol version of the next epoch from the validators'
+    /// stake-weighted version votes.
+    ///
+    /// `votes` holds the latest protocol version each validator announced;
+    /// validators without an entry implicitly vote for the current version.
+    /// The next version is the highest one that more than two thirds of the
+    /// stake supports (a vote for a version supports every version below
+    /// it). Protocol versions are monotonic per chain: votes below the
+    /// current version are discarded before the tally, and if two thirds of
+    /// the stake is behind a lower version -- so the raw tally would
+    /// regress -- this returns [`EpochError::ProtocolVersionRegression`]
+    /// instead of silently clamping.
+    pub fn compute_next_protocol_version(
+        &self,
+        epoch_id: &EpochId,
+        votes: &HashMap<AccountId, ProtocolVersion>,
+    ) -> Result<ProtocolVersion, EpochError> {
+        let epoch_info = self
+            .get_epoch_info_if_exists(epoch_id)?
+            .ok_or(EpochError::EpochOutOfBounds(*epoch_id))?;
+        let current = epoch_info.protocol_version();
+
+        let mut total_stake: u128 = 0;
+        let mut downgrade_stake: u128 = 0;
+        // Stake per voted version, votes below the current version excluded.
+        let mut stake_per_version: BTreeMap<ProtocolVersion, u128> = BTreeMap::new();
+        for validator in epoch_info.validators() {
+            let vote = votes.get(validator.account_id()).copied().unwrap_or(current);
+            total_stake += validator.stake();
+            if vote < current {
+                downgrade_stake += validator.stake();
+            } else {
+                *stake_per_version.entry(vote).or_default() += validator.stake();
+            }
+        }
+
+        if downgrade_vote_warrants_warning(downgrade_stake, total_stake) {
+            tracing::warn!(
+                current,
+                downgrade_stake,
+                total_stake,
+                "more than 10% of stake votes for a protocol version below the current one",
+            );
+        }
+        if downgrade_stake * 3 > total_stake * 2 {
+            let proposed = votes
+                .values()
+                .filter(|version| **version < current)
+                .max()
+                .copied()
+                .unwrap_or_default();
+            return Err(EpochError::ProtocolVersionRegression { current, proposed });
+        }
+
+        // Walk versions from the highest down, accumulating the stake that
+        // supports at least that version.
+        let mut supporting_stake: u128 = 0;
+        for (version, stake) in stake_per_version.iter().rev() {
+            supporting_stake += stake;
+            if supporting_stake * 3 > total_stake * 2 {
+                return Ok(*version);
+            }
+        }
+        // Not even the current version has a two-thirds majority (too much
+        // stake voted lower); stay where we are.
+        Ok(current)
+    }
+
+    /// Whether the account is a validator in both the given epoch and the
+    /// next one.
+    ///
+    /// Errors if either epoch's information is not available yet -- the next
+    /// epoch's validator set is only known once its info is computed.
+    pub fn is_validator_continuing(
+        &self,
+        epoch_id: &EpochId,
+        account_id: &AccountId,
+    ) -> Result<bool, EpochError> {
+        let epoch_info = self
+            .get_epoch_info_if_exists(epoch_id)?
+            .ok_or(EpochError::EpochOutOfBounds(*epoch_id))?;
+        let next_epoch_id = self
+            .epoch_ids_by_height
+            .get(&(epoch_info.epoch_height() + 1))
+            .ok_or(EpochError::EpochOutOfBounds(*epoch_id))?;
+        let next_epoch_info = self
+            .get_epoch_info_if_exists(next_epoch_id)?
+            .ok_or(EpochError::EpochOutOfBounds(*next_epoch_id))?;
+        Ok(epoch_info.account_is_validator(account_id)
+            && next_epoch_info.account_is_validator(account_id))
+    }
+
+    /// The epoch's validators in block producer settlement order -- one
+    /// entry per seat, so a validator holding several seats appears several
+    /// times -- each flagged with whether it is slashed as seen from
+    /// `last_known_block_hash`.
+    pub fn get_all_block_producers_settlement(
+        &self,
+        epoch_id: &EpochId,
+        last_known_block_hash: &CryptoHash,
+    ) -> Result<ValidatorsWithSlashFlag, EpochError> {
+        let key = (*epoch_id, *last_known_block_hash);
+        if let Some(settlement) = lock_cache(&self.epoch_validators_ordered).get(&key) {
+            return Ok(Arc::clone(settlement));
+        }
+        let epoch_info = self
+            .get_epoch_info_if_exists(epoch_id)?
+            .ok_or(EpochError::EpochOutOfBounds(*epoch_id))?;
+        let slash_state = self.get_slash_state_for_block(last_known_block_hash)?;
+        let settlement: ValidatorsWithSlashFlag = epoch_info
+            .block_producers_settlement()
+            .iter()
+            .map(|&validator_id| {
+                let validator = epoch_info
+                    .get_validator(validator_id)
+                    .expect("settlements are validated against the validator set")
+                    .clone();
+                let is_slashed = slash_state.contains_key(validator.account_id());
+                (validator, is_slashed)
+            })
+            .collect();
+        lock_cache(&self.epoch_validators_ordered).insert(key, Arc::clone(&settlement));
+        Ok(settlement)
+    }
+
+    /// Like [`Self::get_all_block_producers_settlement`] with each validator
+    /// listed once, in the order of its first seat.
+    pub fn get_all_block_producers_ordered(
+        &self,
+        epoch_id: &EpochId,
+        last_known_block_hash: &CryptoHash,
+    ) -> Result<ValidatorsWithSlashFlag, EpochError> {
+        let key = (*epoch_id, *last_known_block_hash);
+        if let Some(validators) = lock_cache(&self.epoch_validators_ordered_unique).get(&key) {
+            return Ok(Arc::clone(validators));
+        }
+        let settlement = self.get_all_block_producers_settlement(epoch_id, last_known_block_hash)?;
+        let mut seen = HashSet::new();
+        let validators: ValidatorsWithSlashFlag = settlement
+            .iter()
+            .filter(|(validator, _)| seen.insert(validator.account_id().clone()))
+            .cloned()
+            .collect();
+        lock_cache(&self.epoch_validators_ordered_unique).insert(key, Arc::clone(&validators));
+        Ok(validators)
+    }
+
+    /// The validator expected to produce the block at the given height of the
+    /// given epoch: round-robin over the block producer settlement.
+    pub fn sample_block_producer(
+        &self,
+        epoch_id: &EpochId,
+        height: BlockHeight,
+    ) -> Result<ValidatorStake, EpochError> {
+        let epoch_info = self
+            .get_epoch_info_if_exists(epoch_id)?
+            .ok_or(EpochError::EpochOutOfBounds(*epoch_id))?;
+        let settlement = epoch_info.block_producers_settlement();
+        let producer_id = settlement[(height % settlement.len() as u64) as usize];
+        epoch_info
+            .get_validator(producer_id)
+            .cloned()
+            .ok_or(EpochError::EpochOutOfBounds(*epoch_id))
+    }
+
+    /// Whether the header is signed by the block producer expected for its
+    /// epoch and height.
+    pub fn verify_block_signature(&self, header: &BlockHeader) -> Result<bool, EpochError> {
+        let producer = self.sample_block_producer(header.epoch_id(), header.height())?;
+        Ok(header.verify_block_producer(producer.public_key()))
+    }
+
+    /// The validator expected to produce the chunk of the given shard at the
+    /// given height: round-robin over the shard's chunk producer settlement.
+    pub fn sample_chunk_producer(
+        &self,
+        epoch_id: &EpochId,
+        shard_id: ShardId,
+        height: BlockHeight,
+    ) -> Result<ValidatorStake, EpochError> {
+        let epoch_info = self
+            .get_epoch_info_if_exists(epoch_id)?
+            .ok_or(EpochError::EpochOutOfBounds(*epoch_id))?;
+        let settlement = epoch_info
+            .chunk_producers_settlement()
+            .get(shard_id as usize)
+            .filter(|settlement| !settlement.is_empty())
+            .ok_or_else(|| {
+                EpochError::ShardingError(format!(
+                    "no chunk producers for shard {shard_id} in epoch {epoch_id:?}"
+                ))
+            })?;
+        let producer_id = settlement[(height % settlement.len() as u64) as usize];
+        epoch_info
+            .get_validator(producer_id)
+            .cloned()
+            .ok_or(EpochError::EpochOutOfBounds(*epoch_id))
+    }
+
+    /// The full validator information of the block producer expected at the
+    /// given height; see [`Self::sample_block_producer`].
+    pub fn get_block_producer_info(
+        &self,
+        epoch_id: &EpochId,
+        height: BlockHeight,
+    ) -> Result<ValidatorStake, EpochError> {
+        self.sample_block_producer(epoch_id, height)
+    }
+
+    /// The full validator information of the chunk producer expected for the
+    /// shard at the given height. Unlike the raw sampler, a failure to
+    /// select a producer surfaces as
+    /// [`EpochError::ChunkProducerSelectionError`] carrying the shard and
+    /// height, so callers need not reparse a sharding message.
+    pub fn get_chunk_producer_info(
+        &self,
+        epoch_id: &EpochId,
+        height: BlockHeight,
+        shard_id: ShardId,
+    ) -> Result<ValidatorStake, EpochError> {
+        self.sample_chunk_producer(epoch_id, shard_id, height).map_err(|err| match err {
+            EpochError::ShardingError(_) => {
+                EpochError::ChunkProducerSelectionError { shard_id, height }
+            }
+            other => other,
+        })
+    }
+
+    /// The chunk validators backing the given shard at the given height,
+    /// sampled from the epoch's stake mandates.
+    ///
+    /// The sampler is seeded by hashing the epoch's `rng_seed` with the
+    /// shard id and height, so two nodes holding the same epoch info arrive
+    /// at identical assignments without exchanging anything. Results are
+    /// cached per `(epoch, shard, height)`; repeated queries return the
+    /// cached value instead of re-sampling.
+    pub fn get_chunk_validator_mandate_assignments(
+        &self,
+        epoch_id: &EpochId,
+        shard_id: ShardId,
+        height: BlockHeight,
+    ) -> Result<Arc<ChunkValidatorAssignments>, EpochError> {
+        let key = (*epoch_id, shard_id, height);
+        if let Some(assignments) = lock_cache(&self.mandate_assignments_cache).get(&key) {
+            return Ok(Arc::clone(assignments));
+        }
+        let epoch_info = self
+            .get_epoch_info_if_exists(epoch_id)?
+            .ok_or(EpochError::EpochOutOfBounds(*epoch_id))?;
+        if shard_id >= self.num_shards {
+            return Err(EpochError::ShardingError(format!(
+                "shard {shard_id} is out of range, only {} shards",
+                self.num_shards
+            )));
+        }
+        let config = ValidatorMandatesConfig::new(
+            epoch_info.seat_price().max(1),
+            TARGET_MANDATES_PER_SHARD,
+            self.num_shards as usize,
+        );
+        let mandates = ValidatorMandates::new(config, epoch_info.validators());
+        let sampling_seed = CryptoHash::hash_borsh(&(epoch_info.rng_seed(), shard_id, height));
+        let mut rng = StdRng::from_seed(*sampling_seed.as_bytes());
+        let stake_assignment = mandates.sample(&mut rng);
+        let assignments = stake_assignment[shard_id as usize]
+            .iter()
+            .map(|(&validator_id, &stake)| {
+                let validator = epoch_info
+                    .get_validator(validator_id)
+                    .expect("mandates are built from the validator set");
+                (validator.account_id().clone(), stake)
+            })
+            .collect();
+        let assignments = Arc::new(ChunkValidatorAssignments::new(assignments));
+        lock_cache(&self.mandate_assignments_cache).insert(key, Arc::clone(&assignments));
+        Ok(assignments)
+    }
+
+    /// Whether the chunk header is signed by the chunk producer expected for
+    /// its shard at the given height.
+    pub fn verify_chunk_header_signature(
+        &self,
+        header: &ShardChunkHeader,
+        epoch_id: &EpochId,
+        height: BlockHeight,
+    ) -> Result<bool, EpochError> {
+        let producer = self.sample_chunk_producer(epoch_id, header.shard_id(), height)?;
+        Ok(header.verify(producer.public_key()))
+    }
+
+    /// The information of the given epoch, from the cache or -- on a miss --
+    /// from the store, populating the cache for the next caller.
+    pub fn get_epoch_info(&self, epoch_id: &EpochId) -> Result<Arc<EpochInfo>, EpochError> {
+        let epoch_info = self
+            .get_epoch_info_if_exists(epoch_id)?
+            .ok_or(EpochError::EpochOutOfBounds(*epoch_id))?;
+        lock_cache(&self.epochs_info).entry(*epoch_id).or_insert_with(|| Arc::clone(&epoch_info));
+        Ok(epoch_info)
+    }
+
+    /// The bookkeeping of the given block, from the cache or -- on a miss --
+    /// from the store, populating the cache for the next caller. Used by the
+    /// chain to walk from a block header back to its epoch's first block.
+    pub fn get_block_info(&self, hash: &CryptoHash) -> Result<Arc<BlockInfo>, EpochError> {
+        if let Some(block_info) = lock_cache(&self.block_infos).get(hash) {
+            return Ok(Arc::clone(block_info));
+        }
+        let block_info: BlockInfo = self
+            .store
+            .get_ser(DBCol::BlockInfo, hash.as_ref())?
+            .ok_or(EpochError::MissingBlock(*hash))?;
+        let block_info = Arc::new(block_info);
+        lock_cache(&self.block_infos).insert(*hash, Arc::clone(&block_info));
+        Ok(block_info)
+    }
+
+    /// Whether the block's bookkeeping is known, without loading it.
+    pub fn has_block_info(&self, hash: &CryptoHash) -> bool {
+        lock_cache(&self.block_infos).contains_key(hash)
+            || self.store.exists(DBCol::BlockInfo, hash.as_ref()).unwrap_or(false)
+    }
+
+    /// The epoch ids currently resident in the epoch info cache, in no
+    /// particular order. The cache is unbounded and only shrinks through
+    /// [`Self::gc_epoch_info`]; this is for diagnostics and tests asserting
+    /// what garbage collection evicted, not for protocol logic.
+    pub fn cached_epoch_ids(&self) -> Vec<EpochId> {
+        lock_cache(&self.epochs_info).keys().copied().collect()
+    }
+
+    /// The block hashes currently resident in the block info cache, in no
+    /// particular order. Diagnostics only, like [`Self::cached_epoch_ids`].
+    pub fn cached_block_hashes(&self) -> Vec<CryptoHash> {
+        lock_cache(&self.block_infos).keys().copied().collect()
+    }
+
+    /// Removes the information of an old epoch, leaving a tombstone so that
+    /// later queries can tell "garbage collected" from "never computed".
+    /// Collecting the genesis epoch is a no-op; see the struct doc.
+    pub fn gc_epoch_info(&mut self, epoch_id: &EpochId) -> Result<(), EpochError> {
+        if let Some(epoch_info) = self.get_epoch_info_if_exists(epoch_id)?
+            && epoch_info.epoch_height() == 0
+        {
+            return Ok(());
+        }
+        let mut update = self.store.store_update();
+        update.delete(DBCol::EpochInfo, epoch_id.0.as_ref());
+        update.commit()?;
+        lock_cache(&self.epochs_info).remove(epoch_id);
+        self.epoch_end_heights.remove(epoch_id);
+        self.garbage_collected_epochs.insert(*epoch_id);
+        Ok(())
+    }
+}
+
+/// Whether enough stake (more than 10%) votes for a version below the
+/// current one to warrant warning the node operator.
+pub(crate) fn downgrade_vote_warrants_warning(downgrade_stake: u128, total_stake: u128) -> bool {
+    downgrade_stake * 10 > total_stake
+}
+
+impl EpochManagerAdapter for EpochManager {
+    fn epoch_exists(&self, epoch_id: &EpochId) -> bool {
+        if lock_cache(&self.epochs_info).contains_key(epoch_id) {
+            return true;
+        }
+        self.store.exists(DBCol::EpochInfo, epoch_id.0.as_ref()).unwrap_or(false)
+    }
+
+    fn is_epoch_final(&self, epoch_id: &EpochId) -> Result<bool, EpochError> {
+        if !self.epoch_exists(epoch_id) {
+            return Err(EpochError::EpochOutOfBounds(*epoch_id));
+        }
+        // An epoch is final once its last block is at or below the final
+        // head; an epoch that has not even ended cannot be final.
+        match self.epoch_end_heights.get(epoch_id) {
+            Some(end_height) => Ok(*end_height <= self.largest_final_height),
+            None => Ok(false),
+        }
+    }
+
+    fn get_epoch_info_if_exists(
+        &self,
+        epoch_id: &EpochId,
+    ) -> Result<Option<Arc<EpochInfo>>, EpochError> {
+        if let Some(epoch_info) = lock_cache(&self.epochs_info).get(epoch_id) {
+            return Ok(Some(Arc::clone(epoch_info)));
+        }
+        if self.garbage_collected_epochs.contains(epoch_id) {
+            return Err(EpochError::EpochGarbageCollected(*epoch_id));
+        }
+        let epoch_info: Option<EpochInfo> =
+            self.store.get_ser(DBCol::EpochInfo, epoch_id.0.as_ref())?;
+        // Detect store corruption when the info is first loaded, instead of
+        // panicking later deep inside validator sampling.
+        if let Some(epoch_info) = &epoch_info {
+            epoch_info.validate_settlements(self.num_shards).map_err(|inconsistency| {
+                EpochError::CorruptedEpochInfo(*epoch_id, inconsistency.to_string())
+            })?;
+        }
+        Ok(epoch_info.map(Arc::new))
+    }
+
+    fn get_shard_layout_from_protocol_version(
+        &self,
+        protocol_version: ProtocolVersion,
+    ) -> ShardLayout {
+        self.shard_layout_schedule
+            .iter()
+            .rev()
+            .find(|(version, _)| *version <= protocol_version)
+            .map(|(_, layout)| layout.clone())
+            .expect("the schedule always covers protocol version zero")
+    }
+
+    fn get_resharding_info(
+        &self,
+        parent_hash: &CryptoHash,
+    ) -> Result<Option<ReshardingInfo>, EpochError> {
+        let block_info = lock_cache(&self.block_infos)
+            .get(parent_hash)
+            .cloned()
+            .ok_or(EpochError::MissingBlock(*parent_hash))?;
+        let epoch_info = self
+            .get_epoch_info_if_exists(block_info.epoch_id())?
+            .ok_or(EpochError::EpochOutOfBounds(*block_info.epoch_id()))?;
+        let current_layout =
+            self.get_shard_layout_from_protocol_version(epoch_info.protocol_version());
+        // The change becomes visible only once the next epoch has been
+        // computed, i.e. exactly one epoch before the new layout activates.
+        let Some(next_epoch_id) =
+            self.epoch_ids_by_height.get(&(epoch_info.epoch_height() + 1))
+        else {
+            return Ok(None);
+        };
+        let Some(next_epoch_info) = self.get_epoch_info_if_exists(next_epoch_id)? else {
+            return Ok(None);
+        };
+        let next_layout =
+            self.get_shard_layout_from_protocol_version(next_epoch_info.protocol_version());
+        if next_layout.version() == current_layout.version() {
+            return Ok(None);
+        }
+        let split_map = next_layout.shards_split_map().cloned().ok_or_else(|| {
+            EpochError::ShardingError(format!(
+                "shard layout v{} has no split map",
+                next_layout.version()
+            ))
+        })?;
+        Ok(Some(ReshardingInfo {
+            current_shard_layout: current_layout,
+            next_shard_layout: next_layout,
+            activation_epoch_id: *next_epoch_id,
+            split_map,
+        }))
+    }
+
+    fn get_slash_state_for_block(
+        &self,
+        block_hash: &CryptoHash,
+    ) -> Result<BTreeMap<AccountId, SlashState>, EpochError> {
+        self.slash_states
+            .get(block_hash)
+            .cloned()
+            .ok_or(EpochError::MissingBlock(*block_hash))
+    }
+
+    fn get_chunk_validator_assignments_with_seed(
+        &self,
+        seed: AssignmentSeed,
+    ) -> Result<Arc<Vec<ValidatorStake>>, EpochError> {
+        if let Some(assignments) = lock_cache(&self.chunk_validators_cache).get(&seed) {
+            return Ok(Arc::clone(assignments));
+        }
+        let epoch_info = self
+            .get_epoch_info_if_exists(&seed.epoch_id)?
+            .ok_or(EpochError::EpochOutOfBounds(seed.epoch_id))?;
+        let settlement = epoch_info
+            .chunk_producers_settlement()
+            .get(seed.shard_id as usize)
+            .filter(|settlement| !settlement.is_empty())
+            .ok_or_else(|| {
+                EpochError::ShardingError(format!(
+                    "no chunk producers for shard {} in epoch {:?}",
+                    seed.shard_id, seed.epoch_id
+                ))
+            })?;
+        let start = seed.start_index(settlement.len());
+        let assignments: Vec<_> = (0..settlement.len())
+            .map(|offset| {
+                let validator_id = settlement[(start + offset) % settlement.len()];
+                epoch_info
+                    .get_validator(validator_id)
+                    .expect("settlements are validated against the validator set")
+                    .clone()
+            })
+            .collect();
+        let assignments = Arc::new(assignments);
+        lock_cache(&self.chunk_validators_cache).insert(seed, Arc::clone(&assignments));
+        Ok(assignments)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use super::*;
+    use near_crypto::{KeyType, SecretKey};
+    use near_primitives::hash::hash;
+    use near_primitives::types::{AccountId, SlashedValidator, ValidatorStake};
+    use std::collections::BTreeMap;
+
+    pub(crate) fn account(name: &str) -> AccountId {
+        name.parse().unwrap()
+    }
+
+    pub(crate) fn stake(name: &str, amount: u128) -> ValidatorStake {
+        let public_key = SecretKey::from_seed(KeyType::ED25519, name).public_key();
+        ValidatorStake::new(account(name), public_key, amount)
+    }
+
+    pub(crate) fn epoch_id(ordinal: u64) -> EpochId {
+        EpochId(hash(&ordinal.to_le_bytes()))
+    }
+
+    pub(crate) fn epoch_info(epoch_height: u64, accounts: &[(&str, u128)]) -> EpochInfo {
+        epoch_info_with_version(epoch_height, accounts, 1)
+    }
+
+    pub(crate) fn epoch_info_with_version(
+        epoch_height: u64,
+        accounts: &[(&str, u128)],
+        protocol_version: ProtocolVersion,
+    ) -> EpochInfo {
+        let validators: Vec<_> =
+            accounts.iter().map(|(name, amount)| stake(name, *amount)).collect();
+        let validator_to_index =
+            validators.iter().enumerate().map(|(i, v)| (v.account_id().clone(), i as u64)).collect();
+        let block_producers_settlement: Vec<u64> = (0..validators.len() as u64).collect();
+        let chunk_producers_settlement = vec![block_producers_settlement.clone()];
+        // The same seat price finalization would commit: the lowest staked
+        // seat.
+        let seat_price =
+            validators.iter().map(|validator| validator.stake()).min().unwrap_or_default();
+        EpochInfo::new(
+            epoch_height,
+            validators,
+            validator_to_index,
+            block_producers_settlement,
+            chunk_producers_settlement,
+            BTreeMap::new(),
+            0,
+            seat_price,
+            BTreeMap::new(),
+            protocol_version,
+            [0; 32],
+        )
+    }
+
+    /// A minimal block info: a block at the given height that belongs to the
+    /// given epoch.
+    pub(crate) fn block_info(hash: CryptoHash, height: u64, epoch_id: EpochId) -> BlockInfo {
+        block_info_with_slashes(hash, CryptoHash::default(), height, epoch_id, &[])
+    }
+
+    /// A block info with an explicit previous block and slashes, for
+    /// exercising the slashing pipeline across forks.
+    pub(crate) fn block_info_with_slashes(
+        hash: CryptoHash,
+        prev_hash: CryptoHash,
+        height: u64,
+        epoch_id: EpochId,
+        slashes: &[(&str, bool)],
+    ) -> BlockInfo {
+        let slashed = slashes
+            .iter()
+            .map(|(name, is_double_sign)| SlashedValidator::new(account(name), *is_double_sign))
+            .collect();
+        let mut block_info = BlockInfo::new(
+            hash,
+            height,
+            0,
+            CryptoHash::default(),
+            prev_hash,
+            vec![],
+            vec![],
+            slashed,
+            1,
+            0,
+            0,
+        );
+        *block_info.epoch_id_mut() = epoch_id;
+        block_info
+    }
+
+    /// A block info carrying stake proposals and a total supply, for
+    /// exercising epoch finalization.
+    pub(crate) fn block_info_with_proposals(
+        hash: CryptoHash,
+        prev_hash: CryptoHash,
+        height: u64,
+        epoch_id: EpochId,
+        proposals: &[(&str, u128)],
+        total_supply: u128,
+    ) -> BlockInfo {
+        let proposals = proposals.iter().map(|(name, amount)| stake(name, *amount)).collect();
+        let mut block_info = BlockInfo::new(
+            hash,
+            height,
+            0,
+            CryptoHash::default(),
+            prev_hash,
+            proposals,
+            vec![],
+            vec![],
+            1,
+            total_supply,
+            0,
+        );
+        *block_info.epoch_id_mut() = epoch_id;
+        block_info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+    use near_primitives::hash::hash;
+
+    #[test]
+    fn test_existing_epoch_is_returned() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        let epoch_id = epoch_id(1);
+        let epoch_info = epoch_info(1, &[("alice", 100)]);
+        epoch_manager.save_epoch_info(&epoch_id, epoch_info.clone()).unwrap();
+
+        assert!(epoch_manager.epoch_exists(&epoch_id));
+        let found = epoch_manager.get_epoch_info_if_exists(&epoch_id).unwrap().unwrap();
+        assert_eq!(*found, epoch_info);
+    }
+
+    #[test]
+    fn test_not_yet_computed_epoch_returns_none() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        epoch_manager.save_epoch_info(&epoch_id(1), epoch_info(1, &[("alice", 100)])).unwrap();
+
+        // The next-next epoch has not been computed yet; probing it is not an
+        // error, it just yields nothing.
+        let next_next = epoch_id(3);
+        assert!(!epoch_manager.epoch_exists(&next_next));
+        assert_eq!(epoch_manager.get_epoch_info_if_exists(&next_next).unwrap(), None);
+        assert_eq!(
+            epoch_manager.is_epoch_final(&next_next),
+            Err(EpochError::EpochOutOfBounds(next_next))
+        );
+    }
+
+    #[test]
+    fn test_get_epoch_info_cache_and_store_fallback() {
+        let store = Store::new();
+        let mut writer = EpochManager::new(store.clone(), 1);
+        let epoch_id = epoch_id(1);
+        let info = epoch_info(1, &[("alice", 100)]);
+        writer.save_epoch_info(&epoch_id, info.clone()).unwrap();
+
+        // Cache hit: saving already populated the cache.
+        assert_eq!(*writer.get_epoch_info(&epoch_id).unwrap(), info);
+
+        // Cache miss, store hit: a fresh manager over the same store loads
+        // the info and keeps it cached for the next call.
+        let reader = EpochManager::new(store, 1);
+        assert!(!lock_cache(&reader.epochs_info).contains_key(&epoch_id));
+        assert_eq!(*reader.get_epoch_info(&epoch_id).unwrap(), info);
+        assert!(lock_cache(&reader.epochs_info).contains_key(&epoch_id));
+
+        // Neither cache nor store has it.
+        let missing = super::test_utils::epoch_id(9);
+        assert_eq!(
+            reader.get_epoch_info(&missing),
+            Err(EpochError::EpochOutOfBounds(missing))
+        );
+    }
+
+    #[test]
+    fn test_get_block_info_cache_and_store_fallback() {
+        let store = Store::new();
+        let mut writer = EpochManager::new(store.clone(), 1);
+        let block_hash = hash(b"block");
+        let info = block_info(block_hash, 7, epoch_id(1));
+        writer.save_block_info(info.clone()).unwrap();
+
+        // A fresh manager over the same store misses the cache, loads from
+        // the store and keeps the info cached for the next call.
+        let reader = EpochManager::new(store, 1);
+        assert!(reader.has_block_info(&block_hash));
+        assert!(!lock_cache(&reader.block_infos).contains_key(&block_hash));
+        assert_eq!(*reader.get_block_info(&block_hash).unwrap(), info);
+        assert!(lock_cache(&reader.block_infos).contains_key(&block_hash));
+        assert_eq!(*reader.get_block_info(&block_hash).unwrap(), info);
+
+        let missing = hash(b"unknown block");
+        assert!(!reader.has_block_info(&missing));
+        assert_eq!(
+            reader.get_block_info(&missing),
+            Err(EpochError::MissingBlock(missing))
+        );
+    }
+
+    #[test]
+    fn test_genesis_epoch_survives_garbage_collection() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        let genesis = epoch_id(0);
+        epoch_manager.save_epoch_info(&genesis, epoch_info(0, &[("alice", 100)])).unwrap();
+        let old = epoch_id(1);
+        epoch_manager.save_epoch_info(&old, epoch_info(1, &[("alice", 100)])).unwrap();
+
+        epoch_manager.gc_epoch_info(&genesis).unwrap();
+        epoch_manager.gc_epoch_info(&old).unwrap();
+
+        // The genesis epoch is still there; the old one left a tombstone.
+        assert_eq!(epoch_manager.get_epoch_info(&genesis).unwrap().epoch_height(), 0);
+        assert_eq!(
+            epoch_manager.get_epoch_info(&old),
+            Err(EpochError::EpochGarbageCollected(old))
+        );
+    }
+
+    #[test]
+    fn test_cached_ids_track_population_and_garbage_collection() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        assert!(epoch_manager.cached_epoch_ids().is_empty());
+        assert!(epoch_manager.cached_block_hashes().is_empty());
+
+        let oldest = epoch_id(1);
+        for height in 1..=3 {
+            let id = epoch_id(height);
+            epoch_manager
+                .save_epoch_info(&id, epoch_info(height, &[("alice", 100)]))
+                .unwrap();
+        }
+        epoch_manager.save_block_info(block_info(hash(b"b1"), 1, oldest)).unwrap();
+
+        let mut cached = epoch_manager.cached_epoch_ids();
+        cached.sort();
+        let mut expected = vec![epoch_id(1), epoch_id(2), epoch_id(3)];
+        expected.sort();
+        assert_eq!(cached, expected);
+        assert_eq!(epoch_manager.cached_block_hashes(), vec![hash(b"b1")]);
+
+        // Garbage collection is the only thing that shrinks the cache; the
+        // collected id drops out of the listing while the rest stay.
+        epoch_manager.gc_epoch_info(&oldest).unwrap();
+        let mut cached = epoch_manager.cached_epoch_ids();
+        cached.sort();
+        let mut expected = vec![epoch_id(2), epoch_id(3)];
+        expected.sort();
+        assert_eq!(cached, expected);
+    }
+
+    #[test]
+    fn test_producer_info_lookups() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        let epoch_id = epoch_id(1);
+        // Settlement order is by descending stake: alice, bob, carol.
+        epoch_manager
+            .save_epoch_info(
+                &epoch_id,
+                epoch_info(1, &[("alice", 300), ("bob", 200), ("carol", 100)]),
+            )
+            .unwrap();
+
+        // Round-robin over the settlement, pinned so a change to the
+        // rotation shows up here.
+        let producers: Vec<_> = (0..6)
+            .map(|height| {
+                epoch_manager
+                    .get_block_producer_info(&epoch_id, height)
+                    .unwrap()
+                    .account_id()
+                    .clone()
+            })
+            .collect();
+        assert_eq!(
+            producers,
+            ["alice", "bob", "carol", "alice", "bob", "carol"].map(account)
+        );
+
+        let chunk_producer =
+            epoch_manager.get_chunk_producer_info(&epoch_id, 4, 0).unwrap();
+        assert_eq!(chunk_producer.account_id(), &account("bob"));
+        assert_eq!(chunk_producer.stake(), 200);
+
+        // A shard outside the layout names itself in the error.
+        assert_eq!(
+            epoch_manager.get_chunk_producer_info(&epoch_id, 4, 7),
+            Err(EpochError::ChunkProducerSelectionError { shard_id: 7, height: 4 })
+        );
+        // An unknown epoch stays an epoch error, not a selection error.
+        let missing = super::test_utils::epoch_id(9);
+        assert_eq!(
+            epoch_manager.get_chunk_producer_info(&missing, 4, 0),
+            Err(EpochError::EpochOutOfBounds(missing))
+        );
+    }
+
+    #[test]
+    fn test_epoch_info_provider_queries_through_the_handle() {
+        use near_primitives::types::EpochInfoProvider;
+
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        let epoch_id = epoch_id(1);
+        epoch_manager
+            .save_epoch_info(&epoch_id, epoch_info(1, &[("alice", 200), ("bob", 100)]))
+            .unwrap();
+        epoch_manager
+            .record_block_info(block_info_with_slashes(
+                hash(b"b0"),
+                hash(b"before b0"),
+                0,
+                epoch_id,
+                &[],
+            ))
+            .unwrap();
+        let handle = EpochManagerHandle::new(epoch_manager);
+
+        // Consumers see only the trait, not the concrete epoch manager.
+        let provider: &dyn EpochInfoProvider = &handle;
+        assert_eq!(provider.validator_stake(&epoch_id, &account("alice")).unwrap(), Some(200));
+        // A non-validator is a None, not an error.
+        assert_eq!(provider.validator_stake(&epoch_id, &account("zoe")).unwrap(), None);
+        assert_eq!(provider.validator_total_stake(&epoch_id).unwrap(), 300);
+        // One block into the epoch, the minimum stake is its seat price.
+        assert_eq!(provider.minimum_stake(&hash(b"b0")).unwrap(), 100);
+
+        let missing = super::test_utils::epoch_id(9);
+        assert_eq!(
+            provider.validator_total_stake(&missing),
+            Err(EpochError::EpochOutOfBounds(missing))
+        );
+    }
+
+    #[test]
+    fn test_epoch_id_derivation_from_the_previous_block() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        epoch_manager.set_epoch_length(3);
+
+        // Two three-block epochs; the epoch ids follow the naming
+        // convention, so the derived ids line up with the recorded chain:
+        // the epoch after the genesis epoch is named by the block before
+        // the genesis epoch's first block.
+        let before_genesis = hash(b"before genesis");
+        let genesis_epoch = epoch_id(0);
+        let second_epoch = EpochId(before_genesis);
+        let chain = [
+            (hash(b"b0"), before_genesis, 0, genesis_epoch),
+            (hash(b"b1"), hash(b"b0"), 1, genesis_epoch),
+            (hash(b"b2"), hash(b"b1"), 2, genesis_epoch),
+            (hash(b"b3"), hash(b"b2"), 3, second_epoch),
+            (hash(b"b4"), hash(b"b3"), 4, second_epoch),
+            (hash(b"b5"), hash(b"b4"), 5, second_epoch),
+        ];
+        for (hash, prev_hash, height, epoch_id) in chain {
+            epoch_manager
+                .record_block_info(block_info_with_slashes(hash, prev_hash, height, epoch_id, &[]))
+                .unwrap();
+        }
+
+        // Mid-epoch the parent's epoch carries over; at the boundary the
+        // next epoch begins.
+        assert!(!epoch_manager.is_next_block_epoch_start(&hash(b"b1")).unwrap());
+        assert!(epoch_manager.is_next_block_epoch_start(&hash(b"b2")).unwrap());
+        assert!(!epoch_manager.is_next_block_epoch_start(&hash(b"b3")).unwrap());
+        assert!(epoch_manager.is_next_block_epoch_start(&hash(b"b5")).unwrap());
+
+        assert_eq!(
+            epoch_manager.get_epoch_id_from_prev_block(&hash(b"b1")).unwrap(),
+            genesis_epoch
+        );
+        assert_eq!(
+            epoch_manager.get_epoch_id_from_prev_block(&hash(b"b2")).unwrap(),
+            second_epoch
+        );
+        assert_eq!(
+            epoch_manager.get_epoch_id_from_prev_block(&hash(b"b3")).unwrap(),
+            second_epoch
+        );
+
+        // The epoch after the second one is named by the last block of the
+        // genesis epoch, whichever side of the boundary the parent is on.
+        let third_epoch = EpochId(hash(b"b2"));
+        assert_eq!(
+            epoch_manager.get_next_epoch_id_from_prev_block(&hash(b"b1")).unwrap(),
+            second_epoch
+        );
+        assert_eq!(
+            epoch_manager.get_next_epoch_id_from_prev_block(&hash(b"b2")).unwrap(),
+            third_epoch
+        );
+        assert_eq!(
+            epoch_manager.get_next_epoch_id_from_prev_block(&hash(b"b4")).unwrap(),
+            third_epoch
+        );
+
+        // A fork right at the boundary: both sides enter the same epoch --
+        // its first blocks share the ancestry naming it -- but they name
+        // different epochs after it.
+        epoch_manager
+            .record_block_info(block_info_with_slashes(
+                hash(b"b2 fork"),
+                hash(b"b1"),
+                2,
+                genesis_epoch,
+                &[],
+            ))
+            .unwrap();
+        assert!(epoch_manager.is_next_block_epoch_start(&hash(b"b2 fork")).unwrap());
+        assert_eq!(
+            epoch_manager.get_epoch_id_from_prev_block(&hash(b"b2 fork")).unwrap(),
+            second_epoch
+        );
+        assert_eq!(
+            epoch_manager.get_next_epoch_id_from_prev_block(&hash(b"b2 fork")).unwrap(),
+            EpochId(hash(b"b2 fork"))
+        );
+
+        // An unknown parent cannot name any epoch.
+        assert_eq!(
+            epoch_manager.get_epoch_id_from_prev_block(&hash(b"unknown")),
+            Err(EpochError::MissingBlock(hash(b"unknown")))
+        );
+    }
+
+    #[test]
+    fn test_verify_block_signature() {
+        use near_primitives::block_header::{
+            BlockHeader, BlockHeaderInnerLite, BlockHeaderInnerRestV5,
+        };
+        use near_primitives::validator_signer::{InMemoryValidatorSigner, ValidatorSigner};
+
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        let epoch_id = epoch_id(1);
+        epoch_manager
+            .save_epoch_info(&epoch_id, epoch_info(1, &[("test0", 100), ("test1", 100)]))
+            .unwrap();
+
+        // Settlement is [test0, test1]; height 4 falls on test0.
+        let height = 4;
+        let inner_lite = BlockHeaderInnerLite { height, epoch_id, ..Default::default() };
+        let inner_rest = BlockHeaderInnerRestV5::default();
+        let hash =
+            BlockHeader::compute_hash(&CryptoHash::default(), &inner_lite, &inner_rest);
+        let sign = |seed: &str| {
+            InMemoryValidatorSigner::from_seed(seed.parse().unwrap()).sign_bytes(hash.as_ref())
+        };
+
+        let signed = BlockHeader::new(
+            CryptoHash::default(),
+            inner_lite.clone(),
+            inner_rest.clone(),
+            sign("test0"),
+        );
+        assert_eq!(epoch_manager.verify_block_signature(&signed), Ok(true));
+
+        // test1 is a validator, but not the producer of this height.
+        let wrong_producer =
+            BlockHeader::new(CryptoHash::default(), inner_lite, inner_rest, sign("test1"));
+        assert_eq!(epoch_manager.verify_block_signature(&wrong_producer), Ok(false));
+
+        // An unknown epoch is an error, not a failed verification.
+        let mut other_epoch = wrong_producer;
+        let BlockHeader::V5(header) = &mut other_epoch;
+        header.inner_lite.epoch_id = super::test_utils::epoch_id(9);
+        other_epoch.init();
+        assert_eq!(
+            epoch_manager.verify_block_signature(&other_epoch),
+            Err(EpochError::EpochOutOfBounds(super::test_utils::epoch_id(9)))
+        );
+    }
+
+    #[test]
+    fn test_verify_chunk_header_signature() {
+        use near_primitives::congestion_info::CongestionInfo;
+        use near_primitives::sharding::{
+            ShardChunkHeader, ShardChunkHeaderInner, ShardChunkHeaderInnerV3, ShardChunkHeaderV3,
+        };
+        use near_primitives::validator_signer::{InMemoryValidatorSigner, ValidatorSigner};
+
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        let epoch_id = epoch_id(1);
+        epoch_manager
+            .save_epoch_info(&epoch_id, epoch_info(1, &[("test0", 100), ("test1", 100)]))
+            .unwrap();
+
+        // Shard 0's settlement is [test0, test1]; height 4 falls on test0.
+        let height = 4;
+        let inner = ShardChunkHeaderInnerV3 {
+            prev_block_hash: CryptoHash::default(),
+            prev_state_root: CryptoHash::default(),
+            prev_outcome_root: CryptoHash::default(),
+            encoded_merkle_root: CryptoHash::default(),
+            encoded_length: 0,
+            height_created: height,
+            shard_id: 0,
+            prev_gas_used: 0,
+            gas_limit: 0,
+            prev_balance_burnt: 0,
+            prev_outgoing_receipts_root: CryptoHash::default(),
+            tx_root: CryptoHash::default(),
+            prev_validator_proposals: vec![],
+            congestion_info: CongestionInfo::default(),
+        };
+        let chunk_hash =
+            ShardChunkHeaderV3::compute_hash(&ShardChunkHeaderInner::V3(inner.clone()));
+        let sign = |seed: &str| {
+            InMemoryValidatorSigner::from_seed(seed.parse().unwrap())
+                .sign_bytes(chunk_hash.as_bytes())
+        };
+
+        let signed = ShardChunkHeader::V3(ShardChunkHeaderV3::new(inner.clone(), sign("test0")));
+        assert_eq!(
+            epoch_manager.verify_chunk_header_signature(&signed, &epoch_id, height),
+            Ok(true)
+        );
+
+        // test1 is a validator, but not this height's chunk producer.
+        let wrong_producer =
+            ShardChunkHeader::V3(ShardChunkHeaderV3::new(inner.clone(), sign("test1")));
+        assert_eq!(
+            epoch_manager.verify_chunk_header_signature(&wrong_producer, &epoch_id, height),
+            Ok(false)
+        );
+
+        // A shard without a settlement is an error, not a failed verification.
+        let mut missing_shard = inner;
+        missing_shard.shard_id = 7;
+        let header = ShardChunkHeader::V3(ShardChunkHeaderV3::new(missing_shard, sign("test0")));
+        assert!(matches!(
+            epoch_manager.verify_chunk_header_signature(&header, &epoch_id, height),
+            Err(EpochError::ShardingError(_))
+        ));
+    }
+
+    #[test]
+    fn test_next_protocol_version_needs_two_thirds() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        let epoch = epoch_id(1);
+        epoch_manager
+            .save_epoch_info(
+                &epoch,
+                epoch_info_with_version(1, &[("alice", 60), ("bob", 30), ("carol", 10)], 5),
+            )
+            .unwrap();
+
+        // Alice and bob vote for 6: 90 of 100 stake, more than two thirds.
+        let votes = HashMap::from([(account("alice"), 6), (account("bob"), 6)]);
+        assert_eq!(epoch_manager.compute_next_protocol_version(&epoch, &votes), Ok(6));
+
+        // Alice alone is not enough; carol's implicit vote keeps 5.
+        let votes = HashMap::from([(account("alice"), 6)]);
+        assert_eq!(epoch_manager.compute_next_protocol_version(&epoch, &votes), Ok(5));
+    }
+
+    #[test]
+    fn test_next_protocol_version_never_regresses() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        let epoch = epoch_id(1);
+        epoch_manager
+            .save_epoch_info(
+                &epoch,
+                epoch_info_with_version(1, &[("alice", 60), ("bob", 30), ("carol", 10)], 5),
+            )
+            .unwrap();
+
+        // A minority voting lower is discarded, the version stays put.
+        let votes = HashMap::from([(account("carol"), 4)]);
+        assert_eq!(epoch_manager.compute_next_protocol_version(&epoch, &votes), Ok(5));
+
+        // Two thirds of stake behind a lower version is a regression the
+        // operator must see, not a silent clamp.
+        let votes = HashMap::from([(account("alice"), 4), (account("bob"), 3)]);
+        assert_eq!(
+            epoch_manager.compute_next_protocol_version(&epoch, &votes),
+            Err(EpochError::ProtocolVersionRegression { current: 5, proposed: 4 })
+        );
+    }
+
+    #[test]
+    fn test_downgrade_warning_triggers_above_ten_percent() {
+        assert!(!downgrade_vote_warrants_warning(0, 100));
+        assert!(!downgrade_vote_warrants_warning(10, 100));
+        assert!(downgrade_vote_warrants_warning(11, 100));
+    }
+
+    #[test]
+    fn test_is_validator_continuing() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        let epoch = epoch_id(1);
+        let next_epoch = epoch_id(2);
+        epoch_manager
+            .save_epoch_info(&epoch, epoch_info(1, &[("alice", 100), ("bob", 100)]))
+            .unwrap();
+        epoch_manager.save_epoch_info(&next_epoch, epoch_info(2, &[("alice", 100)])).unwrap();
+
+        // Alice stays a validator; bob drops out of the next epoch.
+        let alice = account("alice");
+        assert_eq!(epoch_manager.is_validator_continuing(&epoch, &alice), Ok(true));
+        assert_eq!(epoch_manager.is_validator_continuing(&epoch, &account("bob")), Ok(false));
+        // A non-validator is trivially not continuing.
+        assert_eq!(epoch_manager.is_validator_continuing(&epoch, &account("eve")), Ok(false));
+        // The next epoch after epoch 2 is not computed yet.
+        assert_eq!(
+            epoch_manager.is_validator_continuing(&next_epoch, &alice),
+            Err(EpochError::EpochOutOfBounds(next_epoch))
+        );
+    }
+
+    #[test]
+    fn test_garbage_collected_epoch_is_distinguished() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        let old_epoch = epoch_id(1);
+        epoch_manager.save_epoch_info(&old_epoch, epoch_info(1, &[("alice", 100)])).unwrap();
+        epoch_manager.gc_epoch_info(&old_epoch).unwrap();
+
+        assert!(!epoch_manager.epoch_exists(&old_epoch));
+        assert_eq!(
+            epoch_manager.get_epoch_info_if_exists(&old_epoch),
+            Err(EpochError::EpochGarbageCollected(old_epoch))
+        );
+    }
+
+    #[test]
+    fn test_is_epoch_final_follows_final_head() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        let epoch = epoch_id(1);
+        epoch_manager.save_epoch_info(&epoch, epoch_info(1, &[("alice", 100)])).unwrap();
+
+        // The epoch has not ended yet.
+        assert_eq!(epoch_manager.is_epoch_final(&epoch), Ok(false));
+
+        epoch_manager.save_epoch_end_height(&epoch, 10);
+        assert_eq!(epoch_manager.is_epoch_final(&epoch), Ok(false));
+
+        epoch_manager.update_largest_final_height(9);
+        assert_eq!(epoch_manager.is_epoch_final(&epoch), Ok(false));
+        epoch_manager.update_largest_final_height(10);
+        assert_eq!(epoch_manager.is_epoch_final(&epoch), Ok(true));
+    }
+
+    #[test]
+    fn test_largest_final_height_never_regresses() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        epoch_manager.update_largest_final_height(10);
+        assert_eq!(epoch_manager.largest_final_height(), 10);
+
+        // A lower final height -- e.g. from a reorged-away fork -- is
+        // ignored; equal and higher heights are applied.
+        epoch_manager.update_largest_final_height(7);
+        assert_eq!(epoch_manager.largest_final_height(), 10);
+        epoch_manager.update_largest_final_height(10);
+        assert_eq!(epoch_manager.largest_final_height(), 10);
+        epoch_manager.update_largest_final_height(11);
+        assert_eq!(epoch_manager.largest_final_height(), 11);
+    }
+
+    #[test]
+    fn test_subscribers_see_the_same_event_sequence() {
+        let handle = EpochManagerHandle::new(EpochManager::new(Store::new(), 1));
+        let first = handle.subscribe();
+        let second = handle.subscribe();
+
+        // A two-epoch run: both epochs get computed, then their first blocks
+        // get recorded.
+        {
+            let mut epoch_manager = handle.write();
+            epoch_manager.save_epoch_info(&epoch_id(1), epoch_info(1, &[("alice", 100)])).unwrap();
+            epoch_manager.save_epoch_info(&epoch_id(2), epoch_info(2, &[("alice", 100)])).unwrap();
+            epoch_manager.record_block_info(block_info(hash(b"b1"), 10, epoch_id(1))).unwrap();
+            epoch_manager
+                .record_block_info(block_info_with_slashes(
+                    hash(b"b2"),
+                    hash(b"b1"),
+                    11,
+                    epoch_id(1),
+                    &[],
+                ))
+                .unwrap();
+            epoch_manager
+                .record_block_info(block_info_with_slashes(
+                    hash(b"c1"),
+                    hash(b"b2"),
+                    12,
+                    epoch_id(2),
+                    &[],
+                ))
+                .unwrap();
+        }
+
+        let expected = vec![
+            EpochChangeEvent::NextEpochInfoComputed { epoch_id: epoch_id(1) },
+            EpochChangeEvent::NextEpochInfoComputed { epoch_id: epoch_id(2) },
+            EpochChangeEvent::EpochStarted {
+                epoch_id: epoch_id(1),
+                epoch_height: 1,
+                first_block_hash: hash(b"b1"),
+            },
+            // The block in the middle of epoch 1 emits nothing.
+            EpochChangeEvent::EpochStarted {
+                epoch_id: epoch_id(2),
+                epoch_height: 2,
+                first_block_hash: hash(b"c1"),
+            },
+        ];
+        assert_eq!(first.drain(), expected);
+        assert_eq!(second.drain(), expected);
+
+        // A late subscriber with a snapshot request catches up on the
+        // current epoch.
+        let snapshot = handle.subscribe_with_snapshot();
+        assert_eq!(snapshot.drain(), expected[3..]);
+        assert_eq!(handle.subscribe().drain(), vec![]);
+    }
+
+    #[test]
+    fn test_slow_subscriber_drops_oldest_without_blocking() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        let slow = epoch_manager.subscribe(false);
+
+        // Emit well past the queue capacity without the subscriber reading.
+        let emitted = handle::EVENT_QUEUE_CAPACITY as u64 + 5;
+        for ordinal in 1..=emitted {
+            epoch_manager
+                .save_epoch_info(&epoch_id(ordinal), epoch_info(ordinal, &[("alice", 100)]))
+                .unwrap();
+        }
+
+        // The writer was never blocked; the subscriber lost the oldest
+        // events and kept the newest ones.
+        assert_eq!(slow.dropped(), 5);
+        let events = slow.drain();
+        assert_eq!(events.len(), handle::EVENT_QUEUE_CAPACITY);
+        assert_eq!(
+            events.first(),
+            Some(&EpochChangeEvent::NextEpochInfoComputed { epoch_id: epoch_id(6) })
+        );
+        assert_eq!(
+            events.last(),
+            Some(&EpochChangeEvent::NextEpochInfoComputed { epoch_id: epoch_id(emitted) })
+        );
+    }
+
+    #[test]
+    fn test_block_producers_settlement_dedups_seats_and_flags_slashes() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        let epoch = epoch_id(1);
+        // Alice holds two of the three seats.
+        let validators = vec![stake("alice", 200), stake("bob", 100)];
+        let validator_to_index =
+            [(account("alice"), 0), (account("bob"), 1)].into_iter().collect();
+        let info = EpochInfo::new(
+            1,
+            validators,
+            validator_to_index,
+            vec![0, 1, 0],
+            vec![vec![0, 1, 0]],
+            BTreeMap::new(),
+            0,
+            100,
+            BTreeMap::new(),
+            1,
+            [0; 32],
+        );
+        epoch_manager.save_epoch_info(&epoch, info).unwrap();
+        let b0 = hash(b"b0");
+        epoch_manager
+            .record_block_info(block_info_with_slashes(
+                b0,
+                CryptoHash::default(),
+                0,
+                epoch,
+                &[("bob", true)],
+            ))
+            .unwrap();
+
+        // One entry per seat, with bob's slash propagated into the flag.
+        let settlement = epoch_manager.get_all_block_producers_settlement(&epoch, &b0).unwrap();
+        assert_eq!(
+            settlement.as_ref(),
+            &[
+                (stake("alice", 200), false),
+                (stake("bob", 100), true),
+                (stake("alice", 200), false),
+            ]
+        );
+        // The deduplicated list keeps the first-seat order.
+        let unique = epoch_manager.get_all_block_producers_ordered(&epoch, &b0).unwrap();
+        assert_eq!(unique.as_ref(), &[(stake("alice", 200), false), (stake("bob", 100), true)]);
+
+        // Both answers are cached.
+        let settlement_again =
+            epoch_manager.get_all_block_producers_settlement(&epoch, &b0).unwrap();
+        assert!(Arc::ptr_eq(&settlement, &settlement_again));
+        let unique_again = epoch_manager.get_all_block_producers_ordered(&epoch, &b0).unwrap();
+        assert!(Arc::ptr_eq(&unique, &unique_again));
+    }
+
+    #[test]
+    fn test_handle_forwarding_methods_run_concurrently() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        epoch_manager
+            .save_epoch_info(&epoch_id(1), epoch_info(1, &[("alice", 100), ("bob", 50)]))
+            .unwrap();
+        let handle = EpochManagerHandle::new(epoch_manager);
+        handle.record_block_info(block_info(hash(b"b0"), 0, epoch_id(1))).unwrap();
+
+        // Two threads hammer different forwarding methods; each call locks
+        // and unlocks internally, so neither can deadlock the other.
+        let heights = {
+            let handle = handle.clone();
+            std::thread::spawn(move || {
+                (0..100)
+                    .map(|_| handle.epoch_info(&epoch_id(1)).unwrap().epoch_height())
+                    .collect::<Vec<_>>()
+            })
+        };
+        let validator_sets = {
+            let handle = handle.clone();
+            std::thread::spawn(move || {
+                (0..100).map(|_| handle.validators_ordered(&epoch_id(1)).unwrap()).collect::<Vec<_>>()
+            })
+        };
+        assert!(heights.join().unwrap().iter().all(|height| *height == 1));
+        assert!(
+            validator_sets
+                .join()
+                .unwrap()
+                .iter()
+                .all(|validators| validators == &[stake("alice", 100), stake("bob", 50)])
+        );
+        assert_eq!(handle.block_info(&hash(b"b0")).unwrap().height(), 0);
+    }
+
+    /// Compile-level check that the whole query surface works through a
+    /// shared reference, i.e. under the handle's read guard. A getter
+    /// regressing to `&mut self` stops compiling here.
+    fn queries_need_only_a_shared_reference(
+        epoch_manager: &EpochManager,
+        epoch_id: &EpochId,
+        block_hash: &CryptoHash,
+    ) {
+        let _ = epoch_manager.get_epoch_info(epoch_id);
+        let _ = epoch_manager.get_block_info(block_hash);
+        let _ = epoch_manager.get_all_block_producers_settlement(epoch_id, block_hash);
+        let _ = epoch_manager.get_all_block_producers_ordered(epoch_id, block_hash);
+        let _ = epoch_manager.get_chunk_validator_mandate_assignments(epoch_id, 0, 0);
+        let _ = epoch_manager.get_chunk_validator_assignments(epoch_id, 0, 0);
+    }
+
+    #[test]
+    fn test_concurrent_readers_query_while_the_writer_records_blocks() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        epoch_manager
+            .save_epoch_info(&epoch_id(1), epoch_info(1, &[("alice", 100), ("bob", 50)]))
+            .unwrap();
+        let handle = EpochManagerHandle::new(epoch_manager);
+        handle.record_block_info(block_info(hash(b"b0"), 0, epoch_id(1))).unwrap();
+
+        // Several readers issue mixed queries under the read guard alone --
+        // cache misses and hits alike -- while the writer below records a
+        // stretch of blocks through the write lock.
+        let readers: Vec<_> = (0..4u64)
+            .map(|reader_id| {
+                let handle = handle.clone();
+                std::thread::spawn(move || {
+                    for round in 0..50u64 {
+                        let guard = handle.read();
+                        assert_eq!(guard.get_epoch_info(&epoch_id(1)).unwrap().epoch_height(), 1);
+                        assert_eq!(guard.get_block_info(&hash(b"b0")).unwrap().height(), 0);
+                        let ordered = guard
+                            .get_all_block_producers_ordered(&epoch_id(1), &hash(b"b0"))
+                            .unwrap();
+                        assert_eq!(
+                            ordered.as_ref(),
+                            &[(stake("alice", 100), false), (stake("bob", 50), false)]
+                        );
+                        // Spread the heights so the readers both share and
+                        // create cache entries concurrently.
+                        let height = reader_id * 25 + round % 50;
+                        let assignments = guard
+                            .get_chunk_validator_assignments(&epoch_id(1), 0, height)
+                            .unwrap();
+                        assert_eq!(assignments.len(), 2);
+                        guard
+                            .get_chunk_validator_mandate_assignments(&epoch_id(1), 0, height)
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for height in 1..=50u64 {
+            let prev =
+                if height == 1 { b"b0".to_vec() } else { format!("b{}", height - 1).into_bytes() };
+            handle
+                .record_block_info(block_info_with_slashes(
+                    hash(format!("b{height}").as_bytes()),
+                    hash(&prev),
+                    height,
+                    epoch_id(1),
+                    &[],
+                ))
+                .unwrap();
+        }
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        assert_eq!(handle.read().get_block_info(&hash(b"b50")).unwrap().height(), 50);
+        queries_need_only_a_shared_reference(&handle.read(), &epoch_id(1), &hash(b"b0"));
+    }
+
+    #[test]
+    fn test_chunk_validator_assignments_same_seed_hits_cache() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        let epoch = epoch_id(1);
+        epoch_manager
+            .save_epoch_info(&epoch, epoch_info(1, &[("alice", 100), ("bob", 100)]))
+            .unwrap();
+
+        let first = epoch_manager.get_chunk_validator_assignments(&epoch, 0, 5).unwrap();
+        let again = epoch_manager.get_chunk_validator_assignments(&epoch, 0, 5).unwrap();
+        assert!(Arc::ptr_eq(&first, &again));
+        assert_eq!(lock_cache(&epoch_manager.chunk_validators_cache).len(), 1);
+
+        // The default derivation rotates by height.
+        let rotated = epoch_manager.get_chunk_validator_assignments(&epoch, 0, 6).unwrap();
+        assert_ne!(first[0].account_id(), rotated[0].account_id());
+    }
+
+    #[test]
+    fn test_chunk_validator_assignments_randomness_splits_cache_entries() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        let epoch = epoch_id(1);
+        epoch_manager
+            .save_epoch_info(&epoch, epoch_info(1, &[("alice", 100), ("bob", 100)]))
+            .unwrap();
+
+        // Two forks at the same height with different randomness must not
+        // share a cache entry.
+        let seed_a = AssignmentSeed::with_randomness(epoch, 0, 5, hash(b"fork a"));
+        let seed_b = AssignmentSeed::with_randomness(epoch, 0, 5, hash(b"fork b"));
+        let fork_a = epoch_manager.get_chunk_validator_assignments_with_seed(seed_a.clone()).unwrap();
+        let fork_b = epoch_manager.get_chunk_validator_assignments_with_seed(seed_b).unwrap();
+        assert!(!Arc::ptr_eq(&fork_a, &fork_b));
+        assert_eq!(lock_cache(&epoch_manager.chunk_validators_cache).len(), 2);
+
+        // Re-asking with the same seed hits the cache.
+        let again = epoch_manager.get_chunk_validator_assignments_with_seed(seed_a).unwrap();
+        assert!(Arc::ptr_eq(&fork_a, &again));
+        assert_eq!(lock_cache(&epoch_manager.chunk_validators_cache).len(), 2);
+    }
+
+    #[test]
+    fn test_mandate_assignments_agree_across_nodes() {
+        let epoch = epoch_id(1);
+        let info = epoch_info(1, &[("alice", 100), ("bob", 70), ("carol", 30)]);
+        let mut node_a = EpochManager::new(Store::new(), 2);
+        let mut node_b = EpochManager::new(Store::new(), 2);
+        node_a.save_epoch_info(&epoch, info.clone()).unwrap();
+        node_b.save_epoch_info(&epoch, info).unwrap();
+
+        // Two nodes holding the same epoch info sample identical
+        // assignments from the seed alone.
+        let mut non_empty = 0;
+        for shard_id in 0..2 {
+            for height in 1..=5 {
+                let from_a =
+                    node_a.get_chunk_validator_mandate_assignments(&epoch, shard_id, height).unwrap();
+                let from_b =
+                    node_b.get_chunk_validator_mandate_assignments(&epoch, shard_id, height).unwrap();
+                assert_eq!(from_a, from_b);
+                // Only validators of the epoch appear, each backing the
+                // shard with a positive stake.
+                for (account_id, stake) in from_a.assignments() {
+                    assert!(["alice", "bob", "carol"].contains(&account_id.as_str()));
+                    assert!(*stake > 0);
+                }
+                non_empty += !from_a.assignments().is_empty() as u32;
+            }
+        }
+        assert!(non_empty > 0, "the mandates must land somewhere");
+    }
+
+    #[test]
+    fn test_mandate_assignments_are_cached() {
+        let epoch = epoch_id(1);
+        let mut epoch_manager = EpochManager::new(Store::new(), 2);
+        epoch_manager
+            .save_epoch_info(&epoch, epoch_info(1, &[("alice", 100), ("bob", 100)]))
+            .unwrap();
+
+        let first = epoch_manager.get_chunk_validator_mandate_assignments(&epoch, 0, 5).unwrap();
+        let again = epoch_manager.get_chunk_validator_mandate_assignments(&epoch, 0, 5).unwrap();
+        assert!(Arc::ptr_eq(&first, &again), "the second query must not re-sample");
+        assert_eq!(lock_cache(&epoch_manager.mandate_assignments_cache).len(), 1);
+
+        epoch_manager.get_chunk_validator_mandate_assignments(&epoch, 1, 5).unwrap();
+        assert_eq!(lock_cache(&epoch_manager.mandate_assignments_cache).len(), 2);
+
+        let missing = epoch_id(9);
+        assert_eq!(
+            epoch_manager.get_chunk_validator_mandate_assignments(&missing, 0, 5),
+            Err(EpochError::EpochOutOfBounds(missing))
+        );
+    }
+
+    #[test]
+    fn test_chunk_validator_assignments_unknown_shard_errors() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        let epoch = epoch_id(1);
+        epoch_manager.save_epoch_info(&epoch, epoch_info(1, &[("alice", 100)])).unwrap();
+        assert!(matches!(
+            epoch_manager.get_chunk_validator_assignments(&epoch, 7, 0),
+            Err(EpochError::ShardingError(_))
+        ));
+    }
+
+    #[test]
+    fn test_shard_layout_schedule_lookup() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        let split_layout =
+            ShardLayout::v1(vec![account("mm")], Some(vec![vec![0, 1]]), 2);
+        epoch_manager.set_shard_layout_schedule(vec![
+            (0, ShardLayout::single_shard()),
+            (2, split_layout.clone()),
+        ]);
+
+        assert_eq!(
+            epoch_manager.get_shard_layout_from_protocol_version(0),
+            ShardLayout::single_shard()
+        );
+        assert_eq!(
+            epoch_manager.get_shard_layout_from_protocol_version(1),
+            ShardLayout::single_shard()
+        );
+        assert_eq!(epoch_manager.get_shard_layout_from_protocol_version(2), split_layout);
+        assert_eq!(epoch_manager.get_shard_layout_from_protocol_version(7), split_layout);
+    }
+
+    #[test]
+    fn test_resharding_info_appears_one_epoch_before_activation() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        let split_layout =
+            ShardLayout::v1(vec![account("mm")], Some(vec![vec![0, 1]]), 2);
+        epoch_manager.set_shard_layout_schedule(vec![
+            (0, ShardLayout::single_shard()),
+            // The split activates with protocol version 2, which epoch 3
+            // below is the first to run.
+            (2, split_layout.clone()),
+        ]);
+        epoch_manager.save_epoch_info(&epoch_id(1), epoch_info(1, &[("alice", 100)])).unwrap();
+        epoch_manager.save_epoch_info(&epoch_id(2), epoch_info(2, &[("alice", 100)])).unwrap();
+
+        // A block in epoch 1: epoch 2 keeps the old layout, nothing pending.
+        let b1 = hash(b"b1");
+        epoch_manager.save_block_info(block_info(b1, 10, epoch_id(1))).unwrap();
+        assert_eq!(epoch_manager.get_resharding_info(&b1), Ok(None));
+
+        // A block in epoch 2: epoch 3 is not computed yet, so the change is
+        // not visible...
+        let b2 = hash(b"b2");
+        epoch_manager.save_block_info(block_info(b2, 20, epoch_id(2))).unwrap();
+        assert_eq!(epoch_manager.get_resharding_info(&b2), Ok(None));
+
+        // ...and becomes visible the moment it is, one epoch in advance.
+        epoch_manager
+            .save_epoch_info(&epoch_id(3), epoch_info_with_version(3, &[("alice", 100)], 2))
+            .unwrap();
+        let info = epoch_manager.get_resharding_info(&b2).unwrap().unwrap();
+        assert_eq!(info.current_shard_layout, ShardLayout::single_shard());
+        assert_eq!(info.next_shard_layout, split_layout);
+        assert_eq!(info.activation_epoch_id, epoch_id(3));
+        assert_eq!(info.split_map, vec![vec![0, 1]]);
+
+        // A block in epoch 3 runs the new layout already; epoch 4 keeps it,
+        // so the resharding info is gone again.
+        let b3 = hash(b"b3");
+        epoch_manager.save_block_info(block_info(b3, 30, epoch_id(3))).unwrap();
+        epoch_manager
+            .save_epoch_info(&epoch_id(4), epoch_info_with_version(4, &[("alice", 100)], 2))
+            .unwrap();
+        assert_eq!(epoch_manager.get_resharding_info(&b3), Ok(None));
+
+        // Unknown parent blocks are an error, not an empty answer.
+        assert_eq!(
+            epoch_manager.get_resharding_info(&hash(b"unknown")),
+            Err(EpochError::MissingBlock(hash(b"unknown")))
+        );
+    }
+
+    #[test]
+    fn test_slashes_are_fork_local_until_one_fork_wins() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        let root = hash(b"root");
+        epoch_manager.record_block_info(block_info(root, 10, epoch_id(1))).unwrap();
+
+        // Fork A slashes alice for a double sign; fork B sees no challenge.
+        let a1 = hash(b"a1");
+        epoch_manager
+            .record_block_info(block_info_with_slashes(
+                a1,
+                root,
+                11,
+                epoch_id(1),
+                &[("alice", true)],
+            ))
+            .unwrap();
+        let b1 = hash(b"b1");
+        epoch_manager
+            .record_block_info(block_info_with_slashes(b1, root, 11, epoch_id(1), &[]))
+            .unwrap();
+
+        assert_eq!(
+            epoch_manager.get_slash_state_for_block(&a1),
+            Ok(BTreeMap::from([(account("alice"), SlashState::DoubleSign)]))
+        );
+        assert_eq!(epoch_manager.get_slash_state_for_block(&b1), Ok(BTreeMap::new()));
+
+        // The slashed fork wins: every descendant keeps seeing the slash,
+        // while the abandoned fork block still answers for its own ancestry.
+        let a2 = hash(b"a2");
+        epoch_manager
+            .record_block_info(block_info_with_slashes(a2, a1, 12, epoch_id(1), &[]))
+            .unwrap();
+        assert_eq!(
+            epoch_manager.get_slash_state_for_block(&a2),
+            Ok(BTreeMap::from([(account("alice"), SlashState::DoubleSign)]))
+        );
+        assert_eq!(epoch_manager.get_slash_state_for_block(&b1), Ok(BTreeMap::new()));
+    }
+
+    #[test]
+    fn test_reprocessing_a_block_does_not_double_slash() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        let root = hash(b"root");
+        epoch_manager.record_block_info(block_info(root, 10, epoch_id(1))).unwrap();
+        let slashing_block =
+            block_info_with_slashes(hash(b"b1"), root, 11, epoch_id(1), &[("alice", false)]);
+        epoch_manager.record_block_info(slashing_block.clone()).unwrap();
+        let before = epoch_manager.get_slash_state_for_block(&hash(b"b1")).unwrap();
+
+        epoch_manager.record_block_info(slashing_block).unwrap();
+        assert_eq!(epoch_manager.get_slash_state_for_block(&hash(b"b1")).unwrap(), before);
+        assert_eq!(before, BTreeMap::from([(account("alice"), SlashState::Other)]));
+    }
+
+    #[test]
+    fn test_strongest_penalty_wins_and_epoch_boundary_demotes() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        let root = hash(b"root");
+        epoch_manager.record_block_info(block_info(root, 10, epoch_id(1))).unwrap();
+
+        // First a plain slash, then a double sign in a later block: the
+        // penalty is upgraded, never downgraded.
+        let b1 = hash(b"b1");
+        epoch_manager
+            .record_block_info(block_info_with_slashes(b1, root, 11, epoch_id(1), &[("alice", false)]))
+            .unwrap();
+        let b2 = hash(b"b2");
+        epoch_manager
+            .record_block_info(block_info_with_slashes(b2, b1, 12, epoch_id(1), &[("alice", true)]))
+            .unwrap();
+        assert_eq!(
+            epoch_manager.get_slash_state_for_block(&b2),
+            Ok(BTreeMap::from([(account("alice"), SlashState::DoubleSign)]))
+        );
+
+        // The first block of the next epoch sees the penalty as already
+        // applied to the validator set.
+        let c1 = hash(b"c1");
+        epoch_manager
+            .record_block_info(block_info_with_slashes(c1, b2, 13, epoch_id(2), &[]))
+            .unwrap();
+        assert_eq!(
+            epoch_manager.get_slash_state_for_block(&c1),
+            Ok(BTreeMap::from([(account("alice"), SlashState::AlreadySlashed)]))
+        );
+    }
+
+    #[test]
+    fn test_finalization_computes_next_epoch_from_proposals() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        epoch_manager
+            .save_epoch_info(&epoch_id(0), epoch_info(0, &[("alice", 100), ("bob", 100)]))
+            .unwrap();
+
+        // A first epoch in which carol stakes and alice unstakes.
+        let b0 = hash(b"b0");
+        epoch_manager.record_block_info(block_info(b0, 0, epoch_id(0))).unwrap();
+        let b1 = hash(b"b1");
+        epoch_manager
+            .record_block_info(block_info_with_proposals(
+                b1,
+                b0,
+                1,
+                epoch_id(0),
+                &[("carol", 300)],
+                0,
+            ))
+            .unwrap();
+        let b2 = hash(b"b2");
+        epoch_manager
+            .record_block_info(block_info_with_proposals(b2, b1, 2, epoch_id(0), &[("alice", 0)], 0))
+            .unwrap();
+        let b3 = hash(b"b3");
+        epoch_manager
+            .record_block_info(block_info_with_proposals(b3, b2, 3, epoch_id(0), &[], 0))
+            .unwrap();
+        assert_eq!(epoch_manager.get_epoch_info_if_exists(&epoch_id(1)), Ok(None));
+
+        // The first block of the second epoch finalizes the first; the new
+        // validator set reflects the proposals.
+        let c1 = hash(b"c1");
+        epoch_manager
+            .record_block_info(block_info_with_proposals(c1, b3, 4, epoch_id(1), &[], 0))
+            .unwrap();
+        let next = epoch_manager.get_epoch_info_if_exists(&epoch_id(1)).unwrap().unwrap();
+        assert_eq!(next.epoch_height(), 1);
+        assert_eq!(next.validators(), &[stake("carol", 300), stake("bob", 100)]);
+        assert_eq!(
+            next.validator_kickout(),
+            &BTreeMap::from([(account("alice"), ValidatorKickoutReason::Unstaked)])
+        );
+        assert_eq!(
+            next.stake_change(),
+            &BTreeMap::from([(account("bob"), 100), (account("carol"), 300)])
+        );
+        assert_eq!(next.rng_seed(), *b3.as_bytes());
+
+        // The epoch height bookkeeping followed the boundary.
+        assert_eq!(epoch_manager.epoch_start_height(&epoch_id(1)), Some(4));
+        epoch_manager.update_largest_final_height(3);
+        assert_eq!(epoch_manager.is_epoch_final(&epoch_id(0)), Ok(true));
+    }
+
+    #[test]
+    fn test_finalization_first_fork_wins_and_is_idempotent() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        epoch_manager
+            .save_epoch_info(&epoch_id(0), epoch_info(0, &[("alice", 100), ("bob", 100)]))
+            .unwrap();
+        let b0 = hash(b"b0");
+        epoch_manager.record_block_info(block_info(b0, 0, epoch_id(0))).unwrap();
+
+        // Two forks inside the first epoch, carrying different proposals.
+        let a1 = hash(b"a1");
+        epoch_manager
+            .record_block_info(block_info_with_proposals(
+                a1,
+                b0,
+                1,
+                epoch_id(0),
+                &[("carol", 300)],
+                0,
+            ))
+            .unwrap();
+        let b1 = hash(b"b1");
+        epoch_manager
+            .record_block_info(block_info_with_proposals(b1, b0, 1, epoch_id(0), &[("dave", 400)], 0))
+            .unwrap();
+
+        // Fork A crosses the epoch boundary first: only its ancestry feeds
+        // the next epoch, so dave's proposal on fork B is not counted.
+        let ca = hash(b"ca");
+        let boundary_a = block_info_with_proposals(ca, a1, 2, epoch_id(1), &[], 0);
+        epoch_manager.record_block_info(boundary_a.clone()).unwrap();
+        let next = epoch_manager.get_epoch_info_if_exists(&epoch_id(1)).unwrap().unwrap();
+        assert!(next.account_is_validator(&account("carol")));
+        assert!(!next.account_is_validator(&account("dave")));
+
+        // Fork B crossing later, or fork A being re-recorded, changes
+        // nothing.
+        let cb = hash(b"cb");
+        epoch_manager
+            .record_block_info(block_info_with_proposals(cb, b1, 2, epoch_id(1), &[], 0))
+            .unwrap();
+        epoch_manager.record_block_info(boundary_a).unwrap();
+        let after = epoch_manager.get_epoch_info_if_exists(&epoch_id(1)).unwrap().unwrap();
+        assert_eq!(after, next);
+    }
+
+    #[test]
+    fn test_cached_aggregation_loops_zero_times() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        epoch_manager.save_epoch_info(&epoch_id(0), epoch_info(0, &[("alice", 100)])).unwrap();
+        let b0 = hash(b"b0");
+        epoch_manager.record_block_info(block_info(b0, 0, epoch_id(0))).unwrap();
+        let b1 = hash(b"b1");
+        epoch_manager
+            .record_block_info(block_info_with_proposals(b1, b0, 1, epoch_id(0), &[], 0))
+            .unwrap();
+
+        epoch_manager.update_epoch_info_aggregator_upto_final(&b1).unwrap();
+        let iterations = epoch_manager.aggregator_loop_iterations();
+
+        // The aggregator already sits at b1; asking again finds the cached
+        // head immediately and visits no blocks at all.
+        epoch_manager.update_epoch_info_aggregator_upto_final(&b1).unwrap();
+        assert_eq!(epoch_manager.aggregator_loop_iterations(), iterations);
+    }
+
+    #[test]
+    fn test_aggregator_advances_incrementally_with_finality() {
+        use near_primitives::epoch_manager::epoch_info_aggregator::ValidatorProductionTally;
+
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        epoch_manager
+            .save_epoch_info(&epoch_id(0), epoch_info(0, &[("alice", 100), ("bob", 100)]))
+            .unwrap();
+        let b0 = hash(b"b0");
+        epoch_manager.record_block_info(block_info(b0, 0, epoch_id(0))).unwrap();
+        let b1 = hash(b"b1");
+        epoch_manager
+            .record_block_info(block_info_with_proposals(
+                b1,
+                b0,
+                1,
+                epoch_id(0),
+                &[("carol", 300)],
+                0,
+            ))
+            .unwrap();
+
+        epoch_manager.update_epoch_info_aggregator_upto_final(&b1).unwrap();
+        assert_eq!(epoch_manager.epoch_info_aggregator_loop_counter, 2);
+        assert_eq!(
+            epoch_manager.epoch_info_aggregator.block_tracker,
+            BTreeMap::from([
+                (0, ValidatorProductionTally { produced: 1, expected: 1 }),
+                (1, ValidatorProductionTally { produced: 1, expected: 1 }),
+            ])
+        );
+        assert!(epoch_manager.epoch_info_aggregator.all_proposals.contains_key(&account("carol")));
+
+        // Advancing finality only walks the blocks recorded since.
+        let b2 = hash(b"b2");
+        epoch_manager
+            .record_block_info(block_info_with_proposals(b2, b1, 2, epoch_id(0), &[], 0))
+            .unwrap();
+        let b3 = hash(b"b3");
+        epoch_manager
+            .record_block_info(block_info_with_proposals(b3, b2, 3, epoch_id(0), &[], 0))
+            .unwrap();
+        epoch_manager.update_epoch_info_aggregator_upto_final(&b3).unwrap();
+        assert_eq!(epoch_manager.epoch_info_aggregator_loop_counter, 4);
+        assert_eq!(epoch_manager.epoch_info_aggregator.last_block_hash, b3);
+        assert_eq!(
+            epoch_manager.epoch_info_aggregator.block_tracker,
+            BTreeMap::from([
+                (0, ValidatorProductionTally { produced: 2, expected: 2 }),
+                (1, ValidatorProductionTally { produced: 2, expected: 2 }),
+            ])
+        );
+
+        // A speculative query past the final head extends a copy, not the
+        // aggregator itself.
+        let b4 = hash(b"b4");
+        epoch_manager
+            .record_block_info(block_info_with_proposals(b4, b3, 4, epoch_id(0), &[("dave", 400)], 0))
+            .unwrap();
+        let speculative = epoch_manager.get_epoch_info_aggregator_upto_last(&b4).unwrap();
+        assert_eq!(speculative.last_block_hash, b4);
+        assert!(speculative.all_proposals.contains_key(&account("dave")));
+        assert_eq!(
+            speculative.block_tracker[&0],
+            ValidatorProductionTally { produced: 3, expected: 3 }
+        );
+        assert_eq!(epoch_manager.epoch_info_aggregator.last_block_hash, b3);
+        assert!(!epoch_manager.epoch_info_aggregator.all_proposals.contains_key(&account("dave")));
+    }
+
+    #[test]
+    fn test_aggregator_snaps_when_behind_multiple_epoch_boundaries() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        for ordinal in 0..3 {
+            epoch_manager
+                .save_epoch_info(
+                    &epoch_id(ordinal),
+                    epoch_info(ordinal, &[("alice", 100), ("bob", 100)]),
+                )
+                .unwrap();
+        }
+        let b0 = hash(b"b0");
+        epoch_manager.record_block_info(block_info(b0, 0, epoch_id(0))).unwrap();
+        let b1 = hash(b"b1");
+        epoch_manager
+            .record_block_info(block_info_with_proposals(b1, b0, 1, epoch_id(0), &[], 0))
+            .unwrap();
+        epoch_manager.update_epoch_info_aggregator_upto_final(&b1).unwrap();
+        assert_eq!(epoch_manager.epoch_info_aggregator.epoch_id, epoch_id(0));
+
+        // The final head then jumps two epochs ahead: the aggregator snaps
+        // to the head's epoch and carries only that epoch's statistics.
+        let mut prev = b1;
+        for (ordinal, height) in [(1, 2), (1, 3), (2, 4), (2, 5)] {
+            let block = hash(format!("block {height}").as_bytes());
+            epoch_manager
+                .record_block_info(block_info_with_proposals(
+                    block,
+                    prev,
+                    height,
+                    epoch_id(ordinal),
+                    &[],
+                    0,
+                ))
+                .unwrap();
+            prev = block;
+        }
+        epoch_manager.update_epoch_info_aggregator_upto_final(&prev).unwrap();
+        let aggregator = &epoch_manager.epoch_info_aggregator;
+        assert_eq!(aggregator.epoch_id, epoch_id(2));
+        assert_eq!(aggregator.last_block_hash, prev);
+        // Heights 4 and 5 only: one block per producer, nothing leaked from
+        // the earlier epochs.
+        assert_eq!(aggregator.block_tracker[&0].produced, 1);
+        assert_eq!(aggregator.block_tracker[&1].produced, 1);
+    }
+
+    #[test]
+    fn test_aggregator_is_persisted_periodically() {
+        let store = Store::new();
+        let mut epoch_manager = EpochManager::new(store.clone(), 1);
+        epoch_manager
+            .save_epoch_info(&epoch_id(0), epoch_info(0, &[("alice", 100), ("bob", 100)]))
+            .unwrap();
+        let b0 = hash(b"b0");
+        epoch_manager.record_block_info(block_info(b0, 0, epoch_id(0))).unwrap();
+        epoch_manager.update_epoch_info_aggregator_upto_final(&b0).unwrap();
+
+        // Below the save period nothing is persisted yet: a fresh manager
+        // starts from an empty aggregator.
+        assert_eq!(
+            EpochManager::new(store.clone(), 1).epoch_info_aggregator,
+            EpochInfoAggregator::default()
+        );
+
+        // Once the final head has advanced far enough the aggregator is
+        // persisted and survives a restart.
+        let far = hash(b"far");
+        epoch_manager
+            .record_block_info(block_info_with_proposals(far, b0, 1200, epoch_id(0), &[], 0))
+            .unwrap();
+        epoch_manager.update_epoch_info_aggregator_upto_final(&far).unwrap();
+        assert_eq!(
+            EpochManager::new(store, 1).epoch_info_aggregator,
+            epoch_manager.epoch_info_aggregator
+        );
+    }
+
+    #[test]
+    fn test_init_genesis_with_fewer_validators_than_seats() {
+        let epoch_manager = EpochManager::init_genesis(
+            Store::new(),
+            2,
+            4,
+            0,
+            RewardCalculator::default(),
+            vec![stake("alice", 300), stake("bob", 200)],
+        )
+        .unwrap();
+        let epoch_info = epoch_manager.get_epoch_info(&EpochId::default()).unwrap();
+
+        // Both validators are seated; the empty seats are simply not there.
+        assert_eq!(epoch_info.block_producers_settlement(), &[0, 1]);
+        assert_eq!(epoch_info.chunk_producers_settlement(), &[vec![0, 1], vec![0, 1]]);
+        assert_eq!(epoch_info.seat_price(), 200);
+        assert_eq!(epoch_info.epoch_height(), 0);
+        // Production rotates over the seated validators from height zero.
+        let producer = epoch_manager.get_block_producer_info(&EpochId::default(), 1).unwrap();
+        assert_eq!(producer.account_id(), &account("bob"));
+    }
+
+    #[test]
+    fn test_init_genesis_seats_by_stake() {
+        let epoch_manager = EpochManager::init_genesis(
+            Store::new(),
+            1,
+            2,
+            0,
+            RewardCalculator::default(),
+            vec![stake("carol", 10), stake("whale", 1_000_000), stake("bob", 20)],
+        )
+        .unwrap();
+        let epoch_info = epoch_manager.get_epoch_info(&EpochId::default()).unwrap();
+
+        // The whale's stake dwarfing the rest does not monopolize the
+        // settlement: the two seats go to the two largest stakes, and carol
+        // stays a validator without a production seat.
+        assert_eq!(epoch_info.validators()[0].account_id(), &account("whale"));
+        assert_eq!(epoch_info.block_producers_settlement(), &[0, 1]);
+        assert_eq!(epoch_info.seat_price(), 20);
+        assert!(epoch_info.account_is_validator(&account("carol")));
+        let producers: HashSet<AccountId> = (0..4)
+            .map(|height| {
+                epoch_manager
+                    .get_block_producer_info(&EpochId::default(), height)
+                    .unwrap()
+                    .account_id()
+                    .clone()
+            })
+            .collect();
+        assert_eq!(producers, HashSet::from([account("whale"), account("bob")]));
+    }
+
+    #[test]
+    fn test_init_genesis_does_not_overwrite_an_initialized_store() {
+        let store = Store::new();
+        let first = EpochManager::init_genesis(
+            store.clone(),
+            1,
+            10,
+            0,
+            RewardCalculator::default(),
+            vec![stake("alice", 100)],
+        )
+        .unwrap();
+        let original = first.get_epoch_info(&EpochId::default()).unwrap();
+        drop(first);
+
+        // A rerun with a different validator set keeps the recorded epoch.
+        let rerun = EpochManager::init_genesis(
+            store,
+            1,
+            10,
+            0,
+            RewardCalculator::default(),
+            vec![stake("mallory", 999)],
+        )
+        .unwrap();
+        let kept = rerun.get_epoch_info(&EpochId::default()).unwrap();
+        assert!(kept.semantic_eq(&original));
+        assert!(!kept.account_is_validator(&account("mallory")));
+    }
+
+    #[test]
+    fn test_explicit_aggregator_snapshot_survives_a_restart() {
+        let store = Store::new();
+        let mut epoch_manager = EpochManager::new(store.clone(), 1);
+        epoch_manager
+            .save_epoch_info(&epoch_id(0), epoch_info(0, &[("alice", 100), ("bob", 100)]))
+            .unwrap();
+        let mut prev = hash(b"b0");
+        epoch_manager.record_block_info(block_info(prev, 0, epoch_id(0))).unwrap();
+        for height in 1..5 {
+            let block_hash = hash(format!("b{height}").as_bytes());
+            epoch_manager
+                .record_block_info(block_info_with_proposals(
+                    block_hash,
+                    prev,
+                    height,
+                    epoch_id(0),
+                    &[],
+                    0,
+                ))
+                .unwrap();
+            prev = block_hash;
+        }
+        epoch_manager.update_epoch_info_aggregator_upto_final(&prev).unwrap();
+
+        // Well below the save period, so only the explicit snapshot
+        // persists the state.
+        let mut update = store.store_update();
+        epoch_manager.save_epoch_info_aggregator(&mut update).unwrap();
+        update.commit().unwrap();
+        let expected = epoch_manager.epoch_info_aggregator.clone();
+        drop(epoch_manager);
+
+        let restarted = EpochManager::new(store, 1);
+        assert_eq!(restarted.epoch_info_aggregator, expected);
+        assert_eq!(restarted.epoch_info_aggregator.last_block_hash, prev);
+    }
+
+    #[test]
+    fn test_estimate_next_seat_price_converges_to_the_final_price() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        epoch_manager
+            .save_epoch_info(&epoch_id(0), epoch_info(0, &[("alice", 100), ("bob", 400)]))
+            .unwrap();
+
+        // Before any proposals the current stakes simply roll over.
+        let b0 = hash(b"b0");
+        epoch_manager.record_block_info(block_info(b0, 0, epoch_id(0))).unwrap();
+        assert_eq!(epoch_manager.estimate_next_seat_price(&b0), Ok(100));
+
+        // Carol's proposal does not move the minimum yet.
+        let b1 = hash(b"b1");
+        epoch_manager
+            .record_block_info(block_info_with_proposals(
+                b1,
+                b0,
+                1,
+                epoch_id(0),
+                &[("carol", 300)],
+                0,
+            ))
+            .unwrap();
+        assert_eq!(epoch_manager.estimate_next_seat_price(&b1), Ok(100));
+
+        // Alice unstaking lifts the estimated price to carol's stake.
+        let b2 = hash(b"b2");
+        epoch_manager
+            .record_block_info(block_info_with_proposals(b2, b1, 2, epoch_id(0), &[("alice", 0)], 0))
+            .unwrap();
+        assert_eq!(epoch_manager.estimate_next_seat_price(&b2), Ok(300));
+        let b3 = hash(b"b3");
+        epoch_manager
+            .record_block_info(block_info_with_proposals(b3, b2, 3, epoch_id(0), &[], 0))
+            .unwrap();
+        let estimate_at_last_block = epoch_manager.estimate_next_seat_price(&b3).unwrap();
+
+        // At the epoch's last block the estimate matches what finalization
+        // commits.
+        let c1 = hash(b"c1");
+        epoch_manager
+            .record_block_info(block_info_with_proposals(c1, b3, 4, epoch_id(1), &[], 0))
+            .unwrap();
+        let next = epoch_manager.get_epoch_info_if_exists(&epoch_id(1)).unwrap().unwrap();
+        assert_eq!(estimate_at_last_block, next.seat_price());
+        assert_eq!(next.seat_price(), 300);
+    }
+
+    #[test]
+    fn test_seat_price_history_is_newest_first() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        for (ordinal, stake) in [(1, 100), (2, 250), (3, 175)] {
+            epoch_manager
+                .save_epoch_info(&epoch_id(ordinal), epoch_info(ordinal, &[("alice", stake)]))
+                .unwrap();
+        }
+
+        assert_eq!(epoch_manager.get_seat_price_history(2), Ok(vec![(3, 175), (2, 250)]));
+        // Asking for more epochs than exist returns what is known.
+        assert_eq!(
+            epoch_manager.get_seat_price_history(10),
+            Ok(vec![(3, 175), (2, 250), (1, 100)])
+        );
+    }
+
+    #[test]
+    fn test_finalization_kicks_out_idle_producer_and_pays_rewards() {
+        let mut epoch_manager = EpochManager::new(Store::new(), 1);
+        epoch_manager.set_reward_calculator(RewardCalculator::new(10, 100));
+        epoch_manager
+            .save_epoch_info(&epoch_id(0), epoch_info(0, &[("alice", 100), ("bob", 100)]))
+            .unwrap();
+
+        // Alice produces all her blocks (even heights); bob produces none of
+        // his, so the heights 1 and 3 have no blocks at all.
+        let b0 = hash(b"b0");
+        epoch_manager.record_block_info(block_info(b0, 0, epoch_id(0))).unwrap();
+        let b2 = hash(b"b2");
+        epoch_manager
+            .record_block_info(block_info_with_proposals(b2, b0, 2, epoch_id(0), &[], 0))
+            .unwrap();
+        let b4 = hash(b"b4");
+        epoch_manager
+            .record_block_info(block_info_with_proposals(b4, b2, 4, epoch_id(0), &[], 1000))
+            .unwrap();
+        let c1 = hash(b"c1");
+        epoch_manager
+            .record_block_info(block_info_with_proposals(c1, b4, 5, epoch_id(1), &[], 1000))
+            .unwrap();
+
+        // Bob is kicked for missing both his heights; alice collects the
+        // whole mint -- 10% of the supply -- on top of her stake.
+        let next = epoch_manager.get_epoch_info_if_exists(&epoch_id(1)).unwrap().unwrap();
+        assert_eq!(next.validators(), &[stake("alice", 200)]);
+        assert_eq!(
+            next.validator_kickout(),
+            &BTreeMap::from([(
+                account("bob"),
+                ValidatorKickoutReason::NotEnoughBlocks { produced: 0, expected: 2 }
+            )])
+        );
+        assert_eq!(next.minted_amount(), 100);
+        assert_eq!(next.seat_price(), 200);
+    }
+
+    fn annualized_calculator() -> RewardCalculator {
+        RewardCalculator {
+            // A 5% yearly cap over a 1000-second "year" keeps the numbers
+            // round.
+            max_inflation_rate: Rational32::new(1, 20),
+            num_seconds_per_year: 1000,
+            protocol_reward_rate: Rational32::new(1, 10),
+            ..RewardCalculator::default()
+        }
+    }
+
+    fn stats(
+        blocks: (u64, u64),
+        chunks: (u64, u64),
+    ) -> near_primitives::types::BlockChunkValidatorStats {
+        use near_primitives::types::ValidatorStats;
+        BlockChunkValidatorStats {
+            block_stats: ValidatorStats { produced: blocks.0, expected: blocks.1 },
+            chunk_stats: ValidatorStats { produced: chunks.0, expected: chunks.1 },
+        }
+    }
+
+    #[test]
+    fn test_calculate_reward_scales_by_duration_stake_and_uptime() {
+        let calculator = annualized_calculator();
+        let production = HashMap::from([
+            (account("alice"), stats((2, 2), (4, 4))),
+            (account("bob"), stats((1, 2), (0, 4))),
+        ]);
+        let stakes = HashMap::from([(account("alice"), 300), (account("bob"), 100)]);
+
+        // Half a year: 5% of the supply halved is an epoch mint of
+        // 25_000_000, of which 1/10 goes to the treasury.
+        let half_year_nanos = 500 * 1_000_000_000;
+        let (rewards, minted) =
+            calculator.calculate_reward(production, &stakes, 1_000_000_000, half_year_nanos);
+        assert_eq!(rewards[&account("treasury")], 2_500_000);
+        // Alice holds 3/4 of the stake at full uptime.
+        assert_eq!(rewards[&account("alice")], 16_875_000);
+        // Bob holds 1/4 of the stake at uptime (1/2 + 0/4) / 2 = 1/4.
+        assert_eq!(rewards[&account("bob")], 1_406_250);
+        assert_eq!(minted, rewards.values().sum::<Balance>());
+
+        // Nothing produced at all still pays the treasury, but no
+        // validator.
+        let production = HashMap::from([(account("alice"), stats((0, 2), (0, 4)))]);
+        let (rewards, minted) =
+            calculator.calculate_reward(production, &stakes, 1_000_000_000, half_year_nanos);
+        assert_eq!(rewards[&account("alice")], 0);
+        assert_eq!(minted, rewards[&account("treasury")]);
+    }
+
+    #[test]
+    fn test_calculate_reward_ignores_dimensions_with_nothing_expected() {
+        let calculator = annualized_calculator();
+        // Carol was never assigned a chunk; her uptime is her block ratio
+        // alone, not an average with a phantom zero.
+        let production = HashMap::from([(account("carol"), stats((1, 2), (0, 0)))]);
+        let stakes = HashMap::from([(account("carol"), 100)]);
+        let (rewards, _) =
+            calculator.calculate_reward(production, &stakes, 1_000_000_000, 500 * 1_000_000_000);
+        assert_eq!(rewards[&account("carol")], 22_500_000 / 2);
+    }
+
+    fn produced_of_expected(produced: u64, expected: u64) -> ValidatorStats {
+        ValidatorStats { produced, expected }
+    }
+
+    #[test]
+    fn test_kickout_spares_the_heaviest_stake_when_everyone_underperforms() {
+        let epoch_info = epoch_info(1, &[("alice", 1000), ("bob", 100), ("carol", 100)]);
+        let block_stats = HashMap::from([
+            (account("alice"), produced_of_expected(0, 10)),
+            (account("bob"), produced_of_expected(1, 10)),
+            (account("carol"), produced_of_expected(0, 10)),
+        ]);
+        let thresholds = OnlineThresholdsConfig {
+            minimum_stake_ratio: Rational32::new(1, 2),
+            ..OnlineThresholdsConfig::default()
+        };
+
+        let kickout =
+            compute_kickout_info(&epoch_info, &block_stats, &HashMap::new(), thresholds);
+        // Everyone is below the threshold, but kicking alice would leave
+        // 200 of 1200 staked -- below the 1/2 floor -- so despite being the
+        // joint-worst producer she keeps her seat.
+        assert_eq!(
+            kickout,
+            HashMap::from([
+                (
+                    account("bob"),
+                    ValidatorKickoutReason::NotEnoughBlocks { produced: 1, expected: 10 }
+                ),
+                (
+                    account("carol"),
+                    ValidatorKickoutReason::NotEnoughBlocks { produced: 0, expected: 10 }
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_kickout_thresholds_are_met_exactly_and_blocks_take_precedence() {
+        let epoch_info =
+            epoch_info(1, &[("alice", 100), ("bob", 100), ("carol", 100), ("dave", 100)]);
+        let block_stats = HashMap::from([
+            (account("alice"), produced_of_expected(9, 10)),
+            (account("bob"), produced_of_expected(8, 10)),
+            (account("dave"), produced_of_expected(0, 10)),
+        ]);
+        let chunk_stats = HashMap::from([
+            (account("carol"), produced_of_expected(17, 20)),
+            (account("dave"), produced_of_expected(0, 20)),
+        ]);
+        let thresholds = OnlineThresholdsConfig {
+            minimum_stake_ratio: Rational32::new(0, 1),
+            ..OnlineThresholdsConfig::default()
+        };
+
+        let kickout = compute_kickout_info(&epoch_info, &block_stats, &chunk_stats, thresholds);
+        // Alice meets the 90% block threshold exactly and stays; carol
+        // fails only the chunk threshold; dave fails both and the block
+        // reason wins.
+        assert_eq!(
+            kickout,
+            HashMap::from([
+                (
+                    account("bob"),
+                    ValidatorKickoutReason::NotEnoughBlocks { produced: 8, expected: 10 }
+                ),
+                (
+                    account("carol"),
+                    ValidatorKickoutReason::NotEnoughChunks { produced: 17, expected: 20 }
+                ),
+                (
+                    account("dave"),
+                    ValidatorKickoutReason::NotEnoughBlocks { produced: 0, expected: 10 }
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_kickout_with_no_statistics_kicks_nobody() {
+        let epoch_info = epoch_info(1, &[("alice", 100), ("bob", 100)]);
+        // Nothing was expected of anyone, so nobody can have fallen short.
+        let kickout = compute_kickout_info(
+            &epoch_info,
+            &HashMap::new(),
+            &HashMap::new(),
+            OnlineThresholdsConfig::default(),
+        );
+        assert!(kickout.is_empty());
+    }
+
+    #[test]
+    fn test_calculate_reward_rounding_never_exceeds_the_mint() {
+        let calculator = annualized_calculator();
+        let production = HashMap::from([
+            (account("alice"), stats((2, 3), (5, 7))),
+            (account("bob"), stats((1, 3), (2, 7))),
+            (account("carol"), stats((3, 3), (7, 7))),
+        ]);
+        let stakes = HashMap::from([
+            (account("alice"), 101),
+            (account("bob"), 67),
+            (account("carol"), 31),
+        ]);
+        let (rewards, minted) =
+            calculator.calculate_reward(production, &stakes, 1_000_003, 333 * 1_000_000_000);
+        // Truncation only loses dust: the payout adds up to the minted
+        // amount and stays within the duration-scaled inflation cap.
+        assert_eq!(minted, rewards.values().sum::<Balance>());
+        let epoch_mint = 1_000_003 / 20 * 333 / 1000;
+        assert!(minted <= epoch_mint, "minted {minted} exceeds the cap {epoch_mint}");
+    }
+}
+
+#[cfg(test)]
+mod corruption_tests {
+    use super::test_utils::*;
+    use super::*;
+    use near_primitives::epoch_manager::epoch_info::EpochInfoV1;
+
+    /// Writes an epoch info straight to the store, bypassing the epoch
+    /// manager, the way a corrupted or hand-edited database would look.
+    fn store_with_epoch_info(epoch_info: &EpochInfo) -> Store {
+        let store = Store::new();
+        let mut update = store.store_update();
+        update.set_ser(DBCol::EpochInfo, epoch_id(1).0.as_ref(), epoch_info).unwrap();
+        update.commit().unwrap();
+        store
+    }
+
+    fn v1(epoch_info: &mut EpochInfo) -> &mut EpochInfoV1 {
+        match epoch_info {
+            EpochInfo::V1(v1) => v1,
+        }
+    }
+
+    fn assert_corrupted(epoch_info: EpochInfo) {
+        let epoch_manager = EpochManager::new(store_with_epoch_info(&epoch_info), 1);
+        match epoch_manager.get_epoch_info_if_exists(&epoch_id(1)) {
+            Err(EpochError::CorruptedEpochInfo(id, _)) => assert_eq!(id, epoch_id(1)),
+            other => panic!("expected CorruptedEpochInfo, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_valid_epoch_info_loads() {
+        let epoch_info = epoch_info(1, &[("alice", 100)]);
+        let epoch_manager = EpochManager::new(store_with_epoch_info(&epoch_info), 1);
+        let loaded = epoch_manager.get_epoch_info_if_exists(&epoch_id(1)).unwrap().unwrap();
+        assert_eq!(*loaded, epoch_info);
+    }
+
+    #[test]
+    fn test_wrong_shard_count_detected() {
+        let mut corrupted = epoch_info(1, &[("alice", 100)]);
+        v1(&mut corrupted).chunk_producers_settlement.push(vec![0]);
+        assert_corrupted(corrupted);
+    }
+
+    #[test]
+    fn test_empty_shard_settlement_detected() {
+        let mut corrupted = epoch_info(1, &[("alice", 100)]);
+        v1(&mut corrupted).chunk_producers_settlement[0].clear();
+        assert_corrupted(corrupted);
+    }
+
+    #[test]
+    fn test_out_of_range_validator_id_detected() {
+        let mut corrupted = epoch_info(1, &[("alice", 100)]);
+        v1(&mut corrupted).chunk_producers_settlement[0].push(17);
+        assert_corrupted(corrupted);
+
+        let mut corrupted = epoch_info(1, &[("alice", 100)]);
+        v1(&mut corrupted).block_producers_settlement.push(17);
+        assert_corrupted(corrupted);
+    }
+
+    #[test]
+    fn test_empty_block_producer_settlement_detected() {
+        let mut corrupted = epoch_info(1, &[("alice", 100)]);
+        v1(&mut corrupted).block_producers_settlement.clear();
+        assert_corrupted(corrupted);
+    }
+}
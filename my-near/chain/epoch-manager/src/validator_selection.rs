@@ -0,0 +1,293 @@
+//! The election function: turns the stake proposals gathered during an
+//! epoch into the next epoch's validator sets and settlements.
+//!
+//! Kept free of `EpochManager` state so it can be exercised -- and
+//! reimplemented by external verifiers -- as a pure function of the
+//! previous epoch's info and the aggregated proposals.
+
+use crate::errors::EpochError;
+use near_primitives::epoch_manager::epoch_info::EpochInfo;
+use near_primitives::types::{
+    AccountId, Balance, NumShards, ProtocolVersion, RngSeed, ValidatorKickoutReason,
+    ValidatorStake,
+};
+use near_primitives::validator_mandates::{ValidatorMandates, ValidatorMandatesConfig};
+use std::collections::{BTreeMap, HashMap};
+
+/// The configuration the election runs under; per epoch, so a protocol
+/// upgrade can change it at an epoch boundary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EpochConfig {
+    /// Number of shards the epoch runs.
+    pub num_shards: NumShards,
+    /// How many block producer seats there are; validators beyond this
+    /// many -- by stake -- are kicked out for not making the seat price.
+    pub num_block_producer_seats: u64,
+    /// How many mandates each shard should ideally be backed by; see
+    /// [`ValidatorMandatesConfig`].
+    pub target_mandates_per_shard: usize,
+}
+
+/// Computes the next epoch's [`EpochInfo`] from the previous epoch and the
+/// proposals, kickouts and rewards aggregated over it.
+///
+/// The next validator pool is the previous epoch's stakes with
+/// `validator_reward` added, overridden by `proposals` -- a zero-stake
+/// proposal unstakes -- minus every account in `kickout`. The top
+/// `num_block_producer_seats` of the pool by stake are seated; the lowest
+/// seated stake becomes the seat price and everyone below it joins the
+/// kickouts with [`ValidatorKickoutReason::NotEnoughStake`]. Every shard's
+/// chunk producer settlement mirrors the block producer settlement,
+/// matching the rest of the crate.
+///
+/// When kickouts would empty the pool, the previous epoch's validators
+/// carry over instead: validator-set continuity beats the penalties.
+#[allow(clippy::too_many_arguments)]
+pub fn proposals_to_epoch_info(
+    config: &EpochConfig,
+    rng_seed: RngSeed,
+    prev_epoch_info: &EpochInfo,
+    proposals: Vec<ValidatorStake>,
+    kickout: HashMap<AccountId, ValidatorKickoutReason>,
+    validator_reward: HashMap<AccountId, Balance>,
+    minted_amount: Balance,
+    next_protocol_version: ProtocolVersion,
+) -> Result<EpochInfo, EpochError> {
+    assert!(
+        config.num_block_producer_seats > 0,
+        "there must be at least one block producer seat"
+    );
+    let mut kickout: BTreeMap<AccountId, ValidatorKickoutReason> = kickout.into_iter().collect();
+
+    // The merged stake pool: previous stakes plus rewards, overridden by
+    // the proposals, minus the kicked.
+    let mut pool: BTreeMap<AccountId, ValidatorStake> = BTreeMap::new();
+    for validator in prev_epoch_info.validators() {
+        let stake = validator.stake()
+            + validator_reward.get(validator.account_id()).copied().unwrap_or_default();
+        pool.insert(
+            validator.account_id().clone(),
+            ValidatorStake::new(
+                validator.account_id().clone(),
+                validator.public_key().clone(),
+                stake,
+            ),
+        );
+    }
+    for proposal in proposals {
+        if proposal.stake() == 0 {
+            kickout.insert(proposal.account_id().clone(), ValidatorKickoutReason::Unstaked);
+        } else {
+            pool.insert(proposal.account_id().clone(), proposal);
+        }
+    }
+    for account_id in kickout.keys() {
+        pool.remove(account_id);
+    }
+    if pool.is_empty() {
+        for validator in prev_epoch_info.validators() {
+            pool.insert(validator.account_id().clone(), validator.clone());
+        }
+        kickout.clear();
+    }
+
+    let mut candidates: Vec<ValidatorStake> = pool.into_values().collect();
+    candidates.sort_by(|a, b| {
+        b.stake().cmp(&a.stake()).then_with(|| a.account_id().cmp(b.account_id()))
+    });
+
+    // Seat the top of the pool; whoever does not make the seat price is
+    // kicked rather than silently dropped.
+    let num_seated = candidates.len().min(config.num_block_producer_seats as usize);
+    let seat_price =
+        candidates.iter().take(num_seated).map(|validator| validator.stake()).min().unwrap_or_default();
+    let validators: Vec<ValidatorStake> = candidates.drain(..num_seated).collect();
+    for unseated in candidates {
+        kickout.insert(
+            unseated.account_id().clone(),
+            ValidatorKickoutReason::NotEnoughStake {
+                stake: unseated.stake(),
+                threshold: seat_price,
+            },
+        );
+    }
+
+    let validator_to_index: HashMap<AccountId, u64> = validators
+        .iter()
+        .enumerate()
+        .map(|(index, validator)| (validator.account_id().clone(), index as u64))
+        .collect();
+    let block_producers_settlement: Vec<u64> = (0..validators.len() as u64).collect();
+    let chunk_producers_settlement =
+        vec![block_producers_settlement.clone(); config.num_shards as usize];
+    let stake_change = validators
+        .iter()
+        .map(|validator| (validator.account_id().clone(), validator.stake()))
+        .collect();
+
+    // The mandates are derived on demand from the saved info; building them
+    // here surfaces an unworkable config as an error instead of a panic
+    // deep in sampling.
+    let mandates_config = ValidatorMandatesConfig::try_new(
+        seat_price.max(1),
+        config.target_mandates_per_shard,
+        config.num_shards as usize,
+    )
+    .map_err(|err| EpochError::ShardingError(err.to_string()))?;
+    let _ = ValidatorMandates::new(mandates_config, &validators);
+
+    Ok(EpochInfo::new(
+        prev_epoch_info.epoch_height() + 1,
+        validators,
+        validator_to_index,
+        block_producers_settlement,
+        chunk_producers_settlement,
+        stake_change,
+        minted_amount,
+        seat_price,
+        kickout,
+        next_protocol_version,
+        rng_seed,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{account, epoch_info, stake};
+
+    fn config(num_block_producer_seats: u64) -> EpochConfig {
+        EpochConfig { num_shards: 2, num_block_producer_seats, target_mandates_per_shard: 4 }
+    }
+
+    #[test]
+    fn test_proposals_override_and_rewards_accrue() {
+        let prev = epoch_info(3, &[("alice", 300), ("bob", 200)]);
+        let next = proposals_to_epoch_info(
+            &config(10),
+            [7; 32],
+            &prev,
+            vec![stake("carol", 250), stake("bob", 500)],
+            HashMap::new(),
+            HashMap::from([(account("alice"), 50)]),
+            50,
+            1,
+        )
+        .unwrap();
+
+        assert_eq!(next.epoch_height(), 4);
+        assert_eq!(next.minted_amount(), 50);
+        assert_eq!(next.rng_seed(), [7; 32]);
+        // Bob's proposal replaces his rolled-over stake; Alice's reward
+        // accrued onto hers.
+        assert_eq!(next.get_validator_by_account(&account("bob")).unwrap().stake(), 500);
+        assert_eq!(next.get_validator_by_account(&account("alice")).unwrap().stake(), 350);
+        assert_eq!(next.get_validator_by_account(&account("carol")).unwrap().stake(), 250);
+        assert_eq!(next.seat_price(), 250);
+        // Every shard's settlement mirrors the block producer settlement.
+        assert_eq!(next.block_producers_settlement(), &[0, 1, 2]);
+        assert_eq!(next.chunk_producers_settlement(), &[vec![0, 1, 2], vec![0, 1, 2]]);
+    }
+
+    #[test]
+    fn test_below_seat_price_becomes_a_kickout() {
+        let prev = epoch_info(0, &[("alice", 300), ("bob", 200), ("carol", 10)]);
+        let next = proposals_to_epoch_info(
+            &config(2),
+            [0; 32],
+            &prev,
+            vec![],
+            HashMap::new(),
+            HashMap::new(),
+            0,
+            0,
+        )
+        .unwrap();
+
+        assert_eq!(next.seat_price(), 200);
+        assert!(!next.account_is_validator(&account("carol")));
+        assert_eq!(
+            next.validator_kickout().get(&account("carol")),
+            Some(&ValidatorKickoutReason::NotEnoughStake { stake: 10, threshold: 200 })
+        );
+    }
+
+    /// Property over a spread of elections: the seated stake plus the stake
+    /// turned away equals the merged pool -- the election moves stake
+    /// around but never mints or loses any.
+    #[test]
+    fn test_total_stake_is_conserved() {
+        let prev = epoch_info(0, &[("alice", 300), ("bob", 200), ("carol", 100)]);
+        for seats in 1..=4 {
+            for proposal_stake in [0, 50, 150, 400] {
+                let next = proposals_to_epoch_info(
+                    &config(seats),
+                    [0; 32],
+                    &prev,
+                    vec![stake("dave", proposal_stake), stake("bob", 250)],
+                    HashMap::from([(
+                        account("carol"),
+                        ValidatorKickoutReason::NotEnoughBlocks { produced: 0, expected: 5 },
+                    )]),
+                    HashMap::from([(account("alice"), 25)]),
+                    25,
+                    0,
+                )
+                .unwrap();
+
+                // The merged pool: alice 325, bob 250, and dave unless he
+                // unstaked; carol is kicked before seating.
+                let expected_pool = 325 + 250 + proposal_stake;
+                let seated: Balance = next.validators().iter().map(|v| v.stake()).sum();
+                let turned_away: Balance = next
+                    .validator_kickout()
+                    .values()
+                    .map(|reason| match reason {
+                        ValidatorKickoutReason::NotEnoughStake { stake, .. } => *stake,
+                        _ => 0,
+                    })
+                    .sum();
+                assert_eq!(seated + turned_away, expected_pool, "seats {seats}");
+                assert_eq!(seated, next.stake_change().values().sum::<Balance>());
+            }
+        }
+    }
+
+    /// Property over the same spread: nobody in the kickout map -- passed
+    /// in or added by the election -- holds a slot in any settlement.
+    #[test]
+    fn test_kicked_accounts_hold_no_settlement_slot() {
+        let prev = epoch_info(0, &[("alice", 300), ("bob", 200), ("carol", 100)]);
+        for seats in 1..=4 {
+            let next = proposals_to_epoch_info(
+                &config(seats),
+                [0; 32],
+                &prev,
+                vec![stake("bob", 0), stake("dave", 150)],
+                HashMap::from([(
+                    account("alice"),
+                    ValidatorKickoutReason::NotEnoughChunks { produced: 1, expected: 9 },
+                )]),
+                HashMap::new(),
+                0,
+                0,
+            )
+            .unwrap();
+
+            assert!(!next.validator_kickout().is_empty());
+            let settlements = std::iter::once(next.block_producers_settlement())
+                .chain(next.chunk_producers_settlement().iter().map(|s| s.as_slice()));
+            for settlement in settlements {
+                assert!(!settlement.is_empty());
+                for &validator_id in settlement {
+                    let seated = next.get_validator(validator_id).unwrap();
+                    assert!(
+                        !next.validator_kickout().contains_key(seated.account_id()),
+                        "{} is kicked out but seated with {seats} seats",
+                        seated.account_id()
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("unknown key type '{0}'")]
+pub struct ParseKeyTypeError(pub String);
+
+/// The signature scheme of a key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum KeyType {
+    ED25519 = 0,
+}
+
+impl fmt::Display for KeyType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyType::ED25519 => write!(f, "ed25519"),
+        }
+    }
+}
+
+impl FromStr for KeyType {
+    type Err = ParseKeyTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "ed25519" => Ok(KeyType::ED25519),
+            _ => Err(ParseKeyTypeError(s.to_string())),
+        }
+    }
+}
+
+/// Splits a key string of the form `<key type>:<base58 data>`. A string
+/// without a key type prefix defaults to ed25519.
+pub(crate) fn split_key_type_data(value: &str) -> Result<(KeyType, &str), ParseKeyTypeError> {
+    match value.split_once(':') {
+        Some((key_type, data)) => Ok((KeyType::from_str(key_type)?, data)),
+        None => Ok((KeyType::ED25519, value)),
+    }
+}
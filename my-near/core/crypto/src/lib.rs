@@ -0,0 +1,10 @@
+pub mod key_type;
+pub mod public_key;
+pub mod secret_key;
+pub mod signature;
+pub mod vrf;
+
+pub use key_type::KeyType;
+pub use public_key::PublicKey;
+pub use secret_key::SecretKey;
+pub use signature::Signature;
@@ -0,0 +1,97 @@
+use crate::key_type::{KeyType, split_key_type_data};
+use borsh::{BorshDeserialize, BorshSerialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("invalid public key: {0}")]
+pub struct ParsePublicKeyError(pub String);
+
+/// A public key, tagged with its signature scheme.
+#[derive(
+    BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+pub enum PublicKey {
+    /// 32 bytes of an ed25519 public key.
+    ED25519([u8; 32]),
+}
+
+impl PublicKey {
+    /// An all-zero key of the given type, useful as a placeholder in tests.
+    pub fn empty(key_type: KeyType) -> Self {
+        match key_type {
+            KeyType::ED25519 => PublicKey::ED25519([0; 32]),
+        }
+    }
+
+    pub fn key_type(&self) -> KeyType {
+        match self {
+            PublicKey::ED25519(_) => KeyType::ED25519,
+        }
+    }
+
+    pub fn key_data(&self) -> &[u8] {
+        match self {
+            PublicKey::ED25519(data) => data,
+        }
+    }
+}
+
+impl fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.key_type(), bs58::encode(self.key_data()).into_string())
+    }
+}
+
+impl FromStr for PublicKey {
+    type Err = ParsePublicKeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key_type, data) =
+            split_key_type_data(s).map_err(|err| ParsePublicKeyError(err.to_string()))?;
+        let bytes = bs58::decode(data)
+            .into_vec()
+            .map_err(|_| ParsePublicKeyError(format!("invalid base58 in '{s}'")))?;
+        match key_type {
+            KeyType::ED25519 => {
+                let bytes: [u8; 32] = bytes
+                    .try_into()
+                    .map_err(|_| ParsePublicKeyError(format!("wrong key length in '{s}'")))?;
+                Ok(PublicKey::ED25519(bytes))
+            }
+        }
+    }
+}
+
+impl serde::Serialize for PublicKey {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for PublicKey {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <String as serde::Deserialize>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_from_str_round_trip() {
+        let key = PublicKey::ED25519([7; 32]);
+        let displayed = key.to_string();
+        assert!(displayed.starts_with("ed25519:"));
+        assert_eq!(PublicKey::from_str(&displayed).unwrap(), key);
+    }
+
+    #[test]
+    fn test_from_str_without_prefix_defaults_to_ed25519() {
+        let key = PublicKey::ED25519([7; 32]);
+        let data = bs58::encode(key.key_data()).into_string();
+        assert_eq!(PublicKey::from_str(&data).unwrap(), key);
+    }
+}
@@ -0,0 +1,76 @@
+use crate::key_type::KeyType;
+use crate::public_key::PublicKey;
+use crate::signature::Signature;
+use ed25519_dalek::Signer as _;
+use sha2::Digest;
+
+/// A secret key, tagged with its signature scheme.
+#[derive(Clone)]
+pub enum SecretKey {
+    /// 32-byte ed25519 seed.
+    ED25519([u8; 32]),
+}
+
+impl SecretKey {
+    pub fn from_random(key_type: KeyType) -> Self {
+        match key_type {
+            KeyType::ED25519 => SecretKey::ED25519(
+                ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng).to_bytes(),
+            ),
+        }
+    }
+
+    /// Deterministically derives a key from a seed string; intended for tests.
+    pub fn from_seed(key_type: KeyType, seed: &str) -> Self {
+        match key_type {
+            KeyType::ED25519 => SecretKey::ED25519(sha2::Sha256::digest(seed.as_bytes()).into()),
+        }
+    }
+
+    pub fn key_type(&self) -> KeyType {
+        match self {
+            SecretKey::ED25519(_) => KeyType::ED25519,
+        }
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        match self {
+            SecretKey::ED25519(seed) => {
+                let signing_key = ed25519_dalek::SigningKey::from_bytes(seed);
+                PublicKey::ED25519(signing_key.verifying_key().to_bytes())
+            }
+        }
+    }
+
+    pub fn sign(&self, data: &[u8]) -> Signature {
+        match self {
+            SecretKey::ED25519(seed) => {
+                let signing_key = ed25519_dalek::SigningKey::from_bytes(seed);
+                Signature::ED25519(signing_key.sign(data).to_bytes())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_verify() {
+        let secret_key = SecretKey::from_random(KeyType::ED25519);
+        let data = b"hello";
+        let signature = secret_key.sign(data);
+        assert!(signature.verify(data, &secret_key.public_key()));
+        assert!(!signature.verify(b"other", &secret_key.public_key()));
+    }
+
+    #[test]
+    fn test_from_seed_is_deterministic() {
+        let a = SecretKey::from_seed(KeyType::ED25519, "alice");
+        let b = SecretKey::from_seed(KeyType::ED25519, "alice");
+        let c = SecretKey::from_seed(KeyType::ED25519, "bob");
+        assert_eq!(a.public_key(), b.public_key());
+        assert_ne!(a.public_key(), c.public_key());
+    }
+}
@@ -0,0 +1,168 @@
+use crate::key_type::{KeyType, split_key_type_data};
+use crate::public_key::PublicKey;
+use borsh::{BorshDeserialize, BorshSerialize};
+use ed25519_dalek::Verifier as _;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("invalid signature: {0}")]
+pub struct ParseSignatureError(pub String);
+
+/// A signature, tagged with its signature scheme.
+#[derive(BorshSerialize, BorshDeserialize, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Signature {
+    /// 64 bytes of an ed25519 signature.
+    ED25519([u8; 64]),
+}
+
+impl Signature {
+    /// An all-zero signature of the given type. It never verifies; real
+    /// absence of a signature should be modelled with `Option` instead.
+    pub fn empty(key_type: KeyType) -> Self {
+        match key_type {
+            KeyType::ED25519 => Signature::ED25519([0; 64]),
+        }
+    }
+
+    /// Whether this is the all-zero placeholder produced by
+    /// [`Signature::empty`] / `Signature::default`. Such a signature never
+    /// verifies and must never be counted as an approval or endorsement.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Signature::ED25519(signature) => signature == &[0; 64],
+        }
+    }
+
+    pub fn key_type(&self) -> KeyType {
+        match self {
+            Signature::ED25519(_) => KeyType::ED25519,
+        }
+    }
+
+    /// The raw signature bytes without the scheme tag -- 64 bytes for
+    /// ed25519 -- for interop with external verifiers.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Signature::ED25519(signature) => signature.to_vec(),
+        }
+    }
+
+    /// Rebuilds a signature from its scheme and raw bytes, the inverse of
+    /// [`Signature::to_bytes`]; the byte length must match the scheme.
+    pub fn from_parts(key_type: KeyType, bytes: &[u8]) -> Result<Self, ParseSignatureError> {
+        match key_type {
+            KeyType::ED25519 => {
+                let bytes: [u8; 64] = bytes.try_into().map_err(|_| {
+                    ParseSignatureError(format!(
+                        "expected 64 bytes for an ed25519 signature, got {}",
+                        bytes.len()
+                    ))
+                })?;
+                Ok(Signature::ED25519(bytes))
+            }
+        }
+    }
+
+    /// Verifies this signature over `data` against the given public key.
+    pub fn verify(&self, data: &[u8], public_key: &PublicKey) -> bool {
+        match (self, public_key) {
+            (Signature::ED25519(signature), PublicKey::ED25519(public_key)) => {
+                let Ok(verifying_key) = ed25519_dalek::VerifyingKey::from_bytes(public_key)
+                else {
+                    return false;
+                };
+                let signature = ed25519_dalek::Signature::from_bytes(signature);
+                verifying_key.verify(data, &signature).is_ok()
+            }
+        }
+    }
+}
+
+impl Default for Signature {
+    fn default() -> Self {
+        Signature::empty(KeyType::ED25519)
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let data = match self {
+            Signature::ED25519(signature) => bs58::encode(&signature[..]).into_string(),
+        };
+        write!(f, "{}:{}", self.key_type(), data)
+    }
+}
+
+impl fmt::Debug for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl FromStr for Signature {
+    type Err = ParseSignatureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key_type, data) =
+            split_key_type_data(s).map_err(|err| ParseSignatureError(err.to_string()))?;
+        let bytes = bs58::decode(data)
+            .into_vec()
+            .map_err(|_| ParseSignatureError(format!("invalid base58 in '{s}'")))?;
+        match key_type {
+            KeyType::ED25519 => {
+                let bytes: [u8; 64] = bytes
+                    .try_into()
+                    .map_err(|_| ParseSignatureError(format!("wrong length in '{s}'")))?;
+                Ok(Signature::ED25519(bytes))
+            }
+        }
+    }
+}
+
+impl serde::Serialize for Signature {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Signature {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <String as serde::Deserialize>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secret_key::SecretKey;
+
+    #[test]
+    fn test_display_from_str_round_trip() {
+        let signature = SecretKey::from_seed(KeyType::ED25519, "test").sign(b"data");
+        assert_eq!(Signature::from_str(&signature.to_string()).unwrap(), signature);
+    }
+
+    #[test]
+    fn test_raw_bytes_round_trip() {
+        let secret_key = SecretKey::from_seed(KeyType::ED25519, "test");
+        let signature = secret_key.sign(b"data");
+        let bytes = signature.to_bytes();
+        assert_eq!(bytes.len(), 64);
+
+        let rebuilt = Signature::from_parts(KeyType::ED25519, &bytes).unwrap();
+        assert_eq!(rebuilt, signature);
+        assert!(rebuilt.verify(b"data", &secret_key.public_key()));
+
+        // Wrong lengths are rejected, not truncated or padded.
+        assert!(Signature::from_parts(KeyType::ED25519, &bytes[..63]).is_err());
+        assert!(Signature::from_parts(KeyType::ED25519, &[0; 65]).is_err());
+    }
+
+    #[test]
+    fn test_empty_signature_never_verifies() {
+        let public_key = SecretKey::from_seed(KeyType::ED25519, "test").public_key();
+        assert!(!Signature::empty(KeyType::ED25519).verify(b"data", &public_key));
+    }
+}
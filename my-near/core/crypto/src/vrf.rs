@@ -0,0 +1,70 @@
+//! A verifiable random function built on ed25519.
+//!
+//! Ed25519 signatures are deterministic, so the signature over an input is a
+//! unique value only the key holder can compute and anyone can verify. The
+//! VRF output is the hash of that signature and the signature itself is the
+//! proof.
+
+use crate::public_key::PublicKey;
+use crate::secret_key::SecretKey;
+use crate::signature::Signature;
+use borsh::{BorshDeserialize, BorshSerialize};
+use sha2::Digest;
+
+/// The output of the VRF: unpredictable without the secret key, but uniquely
+/// determined by the key and the input.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Value(pub [u8; 32]);
+
+/// The proof that a [`Value`] was computed correctly.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Proof(pub [u8; 64]);
+
+impl SecretKey {
+    /// Computes the VRF output and its proof for the given input.
+    pub fn compute_vrf_with_proof(&self, data: &[u8]) -> (Value, Proof) {
+        let Signature::ED25519(signature) = self.sign(data);
+        let value = Value(sha2::Sha256::digest(signature).into());
+        (value, Proof(signature))
+    }
+}
+
+impl PublicKey {
+    /// Checks that `value` is the VRF output of the holder of the secret key
+    /// for this public key on input `data`.
+    pub fn is_vrf_valid(&self, data: &[u8], value: &Value, proof: &Proof) -> bool {
+        if !Signature::ED25519(proof.0).verify(data, self) {
+            return false;
+        }
+        value.0 == <[u8; 32]>::from(sha2::Sha256::digest(proof.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key_type::KeyType;
+
+    #[test]
+    fn test_vrf_round_trip() {
+        let secret_key = SecretKey::from_seed(KeyType::ED25519, "producer");
+        let (value, proof) = secret_key.compute_vrf_with_proof(b"prev random value");
+        assert!(secret_key.public_key().is_vrf_valid(b"prev random value", &value, &proof));
+        // Wrong input, wrong key and tampered value must all fail.
+        assert!(!secret_key.public_key().is_vrf_valid(b"other input", &value, &proof));
+        let other_key = SecretKey::from_seed(KeyType::ED25519, "other").public_key();
+        assert!(!other_key.is_vrf_valid(b"prev random value", &value, &proof));
+        let mut tampered = value;
+        tampered.0[0] ^= 1;
+        assert!(!secret_key.public_key().is_vrf_valid(b"prev random value", &tampered, &proof));
+    }
+
+    #[test]
+    fn test_vrf_is_deterministic() {
+        let secret_key = SecretKey::from_seed(KeyType::ED25519, "producer");
+        assert_eq!(
+            secret_key.compute_vrf_with_proof(b"input"),
+            secret_key.compute_vrf_with_proof(b"input")
+        );
+    }
+}
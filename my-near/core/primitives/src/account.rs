@@ -0,0 +1,449 @@
+use crate::hash::CryptoHash;
+use crate::types::{AccountId, Balance, Nonce, ProtocolVersion, StorageUsage};
+use borsh::{BorshDeserialize, BorshSerialize};
+use std::io;
+
+/// The protocol version that introduced [`AccountV2`]; accounts built for
+/// earlier versions stay V1.
+pub const ACCOUNT_V2_PROTOCOL_VERSION: ProtocolVersion = 2;
+
+/// The first 16 bytes of a serialized V2-or-later account. No real account
+/// can hold `u128::MAX` tokens, so the sentinel tells the versioned layout
+/// apart from a legacy V1 account, which starts with its plain amount.
+const SERIALIZATION_SENTINEL: u128 = u128::MAX;
+
+/// Per-account state stored in the trie. Use the accessors below instead of
+/// matching on the version to reach inner fields.
+///
+/// The Borsh layout is hand-rolled for compatibility with accounts written
+/// before versioning existed: a V1 account serializes as its bare fields
+/// with no version tag, while V2 and later write
+/// [`SERIALIZATION_SENTINEL`], a version byte, and then the fields.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Account {
+    V1(AccountV1),
+    V2(AccountV2),
+}
+
+impl BorshSerialize for Account {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            // The legacy layout: bare fields, no sentinel, no version.
+            Self::V1(account) => account.serialize(writer),
+            Self::V2(account) => {
+                SERIALIZATION_SENTINEL.serialize(writer)?;
+                2u8.serialize(writer)?;
+                account.serialize(writer)
+            }
+        }
+    }
+}
+
+impl BorshDeserialize for Account {
+    fn deserialize_reader<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        let amount = u128::deserialize_reader(reader)?;
+        if amount != SERIALIZATION_SENTINEL {
+            let locked = u128::deserialize_reader(reader)?;
+            let code_hash = CryptoHash::deserialize_reader(reader)?;
+            let storage_usage = StorageUsage::deserialize_reader(reader)?;
+            return Ok(Self::V1(AccountV1 { amount, locked, code_hash, storage_usage }));
+        }
+        match u8::deserialize_reader(reader)? {
+            2 => Ok(Self::V2(AccountV2::deserialize_reader(reader)?)),
+            version => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unknown account version {version}"),
+            )),
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AccountV1 {
+    /// The spendable amount of tokens on the account.
+    pub amount: Balance,
+    /// Tokens locked for staking; not spendable until unstaked.
+    pub locked: Balance,
+    /// Hash of the deployed contract code, zero for accounts without one.
+    pub code_hash: CryptoHash,
+    /// Bytes of state the account occupies, for storage staking.
+    pub storage_usage: StorageUsage,
+}
+
+/// V2 adds storage paid for permanently at account creation, which is not
+/// subject to storage staking.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AccountV2 {
+    pub amount: Balance,
+    pub locked: Balance,
+    pub code_hash: CryptoHash,
+    pub storage_usage: StorageUsage,
+    pub permanent_storage_bytes: StorageUsage,
+}
+
+impl Account {
+    pub fn new(
+        amount: Balance,
+        locked: Balance,
+        code_hash: CryptoHash,
+        storage_usage: StorageUsage,
+    ) -> Self {
+        Self::builder()
+            .amount(amount)
+            .locked(locked)
+            .code_hash(code_hash)
+            .storage_usage(storage_usage)
+            .build()
+    }
+
+    /// A builder over the positional constructor, so the two balances and
+    /// two storage sizes cannot be transposed silently. Every field
+    /// defaults to zero and the protocol version to zero, i.e. a V1
+    /// account.
+    pub fn builder() -> AccountBuilder {
+        AccountBuilder {
+            amount: 0,
+            locked: 0,
+            code_hash: CryptoHash::default(),
+            storage_usage: 0,
+            permanent_storage_bytes: 0,
+            protocol_version: 0,
+        }
+    }
+
+    pub fn amount(&self) -> Balance {
+        match self {
+            Self::V1(account) => account.amount,
+            Self::V2(account) => account.amount,
+        }
+    }
+
+    pub fn locked(&self) -> Balance {
+        match self {
+            Self::V1(account) => account.locked,
+            Self::V2(account) => account.locked,
+        }
+    }
+
+    pub fn code_hash(&self) -> &CryptoHash {
+        match self {
+            Self::V1(account) => &account.code_hash,
+            Self::V2(account) => &account.code_hash,
+        }
+    }
+
+    pub fn storage_usage(&self) -> StorageUsage {
+        match self {
+            Self::V1(account) => account.storage_usage,
+            Self::V2(account) => account.storage_usage,
+        }
+    }
+
+    /// Storage paid for permanently at creation; zero for V1 accounts, which
+    /// predate the concept.
+    pub fn permanent_storage_bytes(&self) -> StorageUsage {
+        match self {
+            Self::V1(_) => 0,
+            Self::V2(account) => account.permanent_storage_bytes,
+        }
+    }
+
+    /// Whether this account is still in the legacy V1 layout.
+    pub fn is_legacy(&self) -> bool {
+        matches!(self, Self::V1(_))
+    }
+
+    /// Migrates a V1 account to V2 in place, assigning its permanent
+    /// storage bytes. The serialized form switches from the bare legacy
+    /// layout to the sentinel-prefixed one, so this must only run as part
+    /// of a coordinated state migration.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the account is already V2 or later: re-running a
+    /// migration would silently overwrite the permanent storage bytes.
+    #[cfg(feature = "protocol_feature_nonrefundable_transfer_nep491")]
+    pub fn upgrade_to_v2(&mut self, permanent_storage_bytes: StorageUsage) {
+        let Self::V1(account) = self else {
+            panic!("only a V1 account can be upgraded to V2");
+        };
+        *self = Self::V2(AccountV2 {
+            amount: account.amount,
+            locked: account.locked,
+            code_hash: account.code_hash,
+            storage_usage: account.storage_usage,
+            permanent_storage_bytes,
+        });
+    }
+}
+
+/// Builds an [`Account`]; see [`Account::builder`].
+#[derive(Clone, Debug)]
+pub struct AccountBuilder {
+    amount: Balance,
+    locked: Balance,
+    code_hash: CryptoHash,
+    storage_usage: StorageUsage,
+    permanent_storage_bytes: StorageUsage,
+    protocol_version: ProtocolVersion,
+}
+
+impl AccountBuilder {
+    pub fn amount(mut self, amount: Balance) -> Self {
+        self.amount = amount;
+        self
+    }
+
+    pub fn locked(mut self, locked: Balance) -> Self {
+        self.locked = locked;
+        self
+    }
+
+    pub fn code_hash(mut self, code_hash: CryptoHash) -> Self {
+        self.code_hash = code_hash;
+        self
+    }
+
+    pub fn storage_usage(mut self, storage_usage: StorageUsage) -> Self {
+        self.storage_usage = storage_usage;
+        self
+    }
+
+    pub fn permanent_storage_bytes(mut self, permanent_storage_bytes: StorageUsage) -> Self {
+        self.permanent_storage_bytes = permanent_storage_bytes;
+        self
+    }
+
+    /// Selects the account version: [`ACCOUNT_V2_PROTOCOL_VERSION`] and
+    /// later build a V2 account, earlier versions a V1.
+    pub fn protocol_version(mut self, protocol_version: ProtocolVersion) -> Self {
+        self.protocol_version = protocol_version;
+        self
+    }
+
+    /// Builds the account.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `permanent_storage_bytes` is nonzero but the protocol
+    /// version predates [`AccountV2`]: a V1 account cannot represent it and
+    /// dropping it silently would lose paid-for storage.
+    pub fn build(self) -> Account {
+        let Self {
+            amount,
+            locked,
+            code_hash,
+            storage_usage,
+            permanent_storage_bytes,
+            protocol_version,
+        } = self;
+        if protocol_version >= ACCOUNT_V2_PROTOCOL_VERSION {
+            Account::V2(AccountV2 {
+                amount,
+                locked,
+                code_hash,
+                storage_usage,
+                permanent_storage_bytes,
+            })
+        } else {
+            assert_eq!(
+                permanent_storage_bytes, 0,
+                "a V1 account cannot carry permanent storage bytes"
+            );
+            Account::V1(AccountV1 { amount, locked, code_hash, storage_usage })
+        }
+    }
+}
+
+/// What kind of account an account id names, for routing and validation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccountIdType {
+    /// A human-chosen name, created by a `CreateAccount` action.
+    Named,
+    /// 64 lowercase hex characters: an account derived from an ed25519
+    /// public key, usable without being created first.
+    NearImplicit,
+    /// `0x` followed by 40 lowercase hex characters: an account derived
+    /// from an Ethereum address.
+    EthImplicit,
+}
+
+/// Classifies an account id as named, NEAR-implicit or ETH-implicit.
+pub fn classify_account_id(account_id: &AccountId) -> AccountIdType {
+    let id = account_id.as_str();
+    let is_hex = |s: &str| s.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b));
+    if id.len() == 64 && is_hex(id) {
+        return AccountIdType::NearImplicit;
+    }
+    if id.len() == 42
+        && let Some(address) = id.strip_prefix("0x")
+        && is_hex(address)
+    {
+        return AccountIdType::EthImplicit;
+    }
+    AccountIdType::Named
+}
+
+/// An access key attached to an account: a nonce against replays plus what
+/// the key is allowed to do.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AccessKey {
+    /// Nonce of the last transaction signed with this key.
+    pub nonce: Nonce,
+    pub permission: AccessKeyPermission,
+}
+
+impl AccessKey {
+    /// A fresh key with full access to the account.
+    pub fn full_access() -> Self {
+        Self { nonce: 0, permission: AccessKeyPermission::FullAccess }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum AccessKeyPermission {
+    /// The key may only call the given methods on one receiver, spending at
+    /// most the allowance on gas.
+    FunctionCall(FunctionCallPermission),
+    /// The key may sign any transaction on behalf of the account.
+    FullAccess,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FunctionCallPermission {
+    /// Tokens the key may spend on gas, across all its calls; `None` means
+    /// unlimited.
+    pub allowance: Option<Balance>,
+    /// The only account the key may send function calls to.
+    pub receiver_id: String,
+    /// The methods the key may call; empty means any method.
+    pub method_names: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify(id: &str) -> AccountIdType {
+        classify_account_id(&id.parse().unwrap())
+    }
+
+    #[test]
+    fn test_v1_serializes_in_the_legacy_layout() {
+        let account = Account::new(1000, 250, crate::hash::hash(b"contract"), 4096);
+        let bytes = borsh::to_vec(&account).unwrap();
+        // Exactly the bare V1 fields: pre-versioning readers still work.
+        let Account::V1(inner) = &account else { panic!("expected a V1 account") };
+        assert_eq!(bytes, borsh::to_vec(inner).unwrap());
+        assert_eq!(Account::try_from_slice(&bytes).unwrap(), account);
+    }
+
+    #[test]
+    fn test_v2_round_trips_through_the_sentinel_layout() {
+        let account = Account::builder()
+            .amount(1000)
+            .locked(250)
+            .code_hash(crate::hash::hash(b"contract"))
+            .storage_usage(4096)
+            .permanent_storage_bytes(512)
+            .protocol_version(ACCOUNT_V2_PROTOCOL_VERSION)
+            .build();
+        let bytes = borsh::to_vec(&account).unwrap();
+        // The sentinel fills the slot a V1 amount would occupy, followed by
+        // the version byte.
+        assert_eq!(bytes[..16], [0xff; 16]);
+        assert_eq!(bytes[16], 2);
+        let decoded = Account::try_from_slice(&bytes).unwrap();
+        assert_eq!(decoded, account);
+        assert_eq!(decoded.permanent_storage_bytes(), 512);
+
+        // A version byte from the future is an error, not a guess.
+        let mut tampered = bytes;
+        tampered[16] = 3;
+        assert!(Account::try_from_slice(&tampered).is_err());
+    }
+
+    #[test]
+    fn test_builder_matches_the_positional_constructor() {
+        let code_hash = crate::hash::hash(b"contract");
+        let built = Account::builder()
+            .amount(1000)
+            .locked(250)
+            .code_hash(code_hash)
+            .storage_usage(4096)
+            .build();
+        let constructed = Account::new(1000, 250, code_hash, 4096);
+        assert_eq!(built, constructed);
+        assert_eq!(borsh::to_vec(&built).unwrap(), borsh::to_vec(&constructed).unwrap());
+    }
+
+    #[test]
+    fn test_builder_selects_the_version_by_protocol_version() {
+        let v2 = Account::builder()
+            .amount(1000)
+            .permanent_storage_bytes(512)
+            .protocol_version(ACCOUNT_V2_PROTOCOL_VERSION)
+            .build();
+        assert!(matches!(v2, Account::V2(_)));
+        assert_eq!(v2.permanent_storage_bytes(), 512);
+
+        // Before the V2 version the builder stays on V1, and defaults build
+        // an empty V1 account.
+        let v1 = Account::builder().protocol_version(ACCOUNT_V2_PROTOCOL_VERSION - 1).build();
+        assert!(matches!(v1, Account::V1(_)));
+        assert_eq!(v1, Account::new(0, 0, CryptoHash::default(), 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "a V1 account cannot carry permanent storage bytes")]
+    fn test_builder_rejects_permanent_storage_on_v1() {
+        Account::builder().permanent_storage_bytes(512).build();
+    }
+
+    #[cfg(feature = "protocol_feature_nonrefundable_transfer_nep491")]
+    #[test]
+    fn test_upgrade_to_v2_changes_the_serialized_form() {
+        let mut account = Account::new(1000, 250, crate::hash::hash(b"contract"), 4096);
+        assert!(account.is_legacy());
+        let legacy_hash = CryptoHash::hash_borsh(&account);
+
+        account.upgrade_to_v2(512);
+        assert!(!account.is_legacy());
+        assert_eq!(account.permanent_storage_bytes(), 512);
+        // The scalar fields survive the migration unchanged.
+        assert_eq!(account.amount(), 1000);
+        assert_eq!(account.locked(), 250);
+        assert_eq!(account.storage_usage(), 4096);
+        // The layout switched to the sentinel-prefixed one.
+        assert_ne!(CryptoHash::hash_borsh(&account), legacy_hash);
+
+        let bytes = borsh::to_vec(&account).unwrap();
+        let decoded = Account::try_from_slice(&bytes).unwrap();
+        assert!(matches!(decoded, Account::V2(_)));
+        assert_eq!(decoded, account);
+    }
+
+    #[cfg(feature = "protocol_feature_nonrefundable_transfer_nep491")]
+    #[test]
+    #[should_panic(expected = "only a V1 account can be upgraded to V2")]
+    fn test_upgrade_to_v2_rejects_an_already_upgraded_account() {
+        let mut account =
+            Account::builder().protocol_version(ACCOUNT_V2_PROTOCOL_VERSION).build();
+        account.upgrade_to_v2(512);
+    }
+
+    #[test]
+    fn test_classify_account_id() {
+        assert_eq!(classify("alice.near"), AccountIdType::Named);
+        assert_eq!(classify(&"ab".repeat(32)), AccountIdType::NearImplicit);
+        assert_eq!(classify(&format!("0x{}", "cd".repeat(20))), AccountIdType::EthImplicit);
+
+        // 63 hex characters are one short of implicit: a plain name.
+        assert_eq!(classify(&"a".repeat(63)), AccountIdType::Named);
+        // 64 characters with a non-hex letter are a (long) name too.
+        assert_eq!(classify(&format!("g{}", "a".repeat(63))), AccountIdType::Named);
+        // The 0x prefix alone is not enough; the length must match exactly.
+        assert_eq!(classify(&format!("0x{}", "cd".repeat(19))), AccountIdType::Named);
+        assert_eq!(classify("0xgg"), AccountIdType::Named);
+    }
+}
@@ -0,0 +1,110 @@
+use crate::serialize::{base64_format, dec_format};
+use crate::types::{AccountId, Balance, Gas};
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_crypto::PublicKey;
+use serde::{Deserialize, Serialize};
+
+/// An action an account asks the runtime to perform on its behalf.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    CreateAccount(CreateAccountAction),
+    FunctionCall(Box<FunctionCallAction>),
+    Transfer(TransferAction),
+    Stake(Box<StakeAction>),
+    DeleteAccount(DeleteAccountAction),
+}
+
+/// Creates the (receiver) account.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CreateAccountAction {}
+
+/// Calls a method of the receiver contract.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FunctionCallAction {
+    pub method_name: String,
+    #[serde(with = "base64_format")]
+    pub args: Vec<u8>,
+    pub gas: Gas,
+    #[serde(with = "dec_format")]
+    pub deposit: Balance,
+}
+
+/// Transfers the deposit to the receiver account.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TransferAction {
+    #[serde(with = "dec_format")]
+    pub deposit: Balance,
+}
+
+/// Stakes the given amount with the given validator key.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct StakeAction {
+    #[serde(with = "dec_format")]
+    pub stake: Balance,
+    pub public_key: PublicKey,
+}
+
+/// Deletes the account and transfers the remaining balance to the beneficiary.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DeleteAccountAction {
+    pub beneficiary_id: AccountId,
+}
+
+impl From<CreateAccountAction> for Action {
+    fn from(action: CreateAccountAction) -> Self {
+        Self::CreateAccount(action)
+    }
+}
+
+impl From<FunctionCallAction> for Action {
+    fn from(action: FunctionCallAction) -> Self {
+        Self::FunctionCall(Box::new(action))
+    }
+}
+
+impl From<TransferAction> for Action {
+    fn from(action: TransferAction) -> Self {
+        Self::Transfer(action)
+    }
+}
+
+impl From<StakeAction> for Action {
+    fn from(action: StakeAction) -> Self {
+        Self::Stake(Box::new(action))
+    }
+}
+
+impl From<DeleteAccountAction> for Action {
+    fn from(action: DeleteAccountAction) -> Self {
+        Self::DeleteAccount(action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_function_call_json_round_trip() {
+        let action: Action = FunctionCallAction {
+            method_name: "transfer".to_string(),
+            args: vec![0, 159, 146, 150],
+            gas: 300_000_000_000_000,
+            deposit: u128::MAX,
+        }
+        .into();
+        let json = serde_json::to_value(&action).unwrap();
+        // Binary args travel as base64, the deposit as a decimal string.
+        assert_eq!(json["FunctionCall"]["args"], "AJ+Slg==");
+        assert_eq!(json["FunctionCall"]["deposit"], u128::MAX.to_string());
+        assert_eq!(serde_json::from_value::<Action>(json).unwrap(), action);
+    }
+
+    #[test]
+    fn test_transfer_json_round_trip() {
+        let action: Action = TransferAction { deposit: 100 }.into();
+        let json = serde_json::to_value(&action).unwrap();
+        assert_eq!(json["Transfer"]["deposit"], "100");
+        assert_eq!(serde_json::from_value::<Action>(json).unwrap(), action);
+    }
+}
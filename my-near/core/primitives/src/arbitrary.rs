@@ -0,0 +1,394 @@
+//! Proptest [`Arbitrary`] impls for the core domain types, so fuzz-style
+//! tests -- here and downstream behind the `arbitrary` feature -- can
+//! generate arbitrary instances instead of hand-picking cases.
+//!
+//! Each impl respects the type's invariants: account ids are valid, an
+//! account's version is consistent with its field set, and anything whose
+//! hash or signature must survive a round trip is built through the real
+//! constructors so the derived values are genuine. There is no `Receipt`
+//! type in this tree yet; it should join this module when one lands.
+
+use crate::account::{
+    ACCOUNT_V2_PROTOCOL_VERSION, AccessKey, AccessKeyPermission, Account, FunctionCallPermission,
+};
+use crate::action::{
+    Action, CreateAccountAction, DeleteAccountAction, FunctionCallAction, StakeAction,
+    TransferAction,
+};
+use crate::block_header::{BlockHeader, BlockHeaderInnerLite, BlockHeaderInnerRestV5};
+use crate::congestion_info::{CongestionInfo, CongestionInfoV1, CongestionInfoV2};
+use crate::hash::CryptoHash;
+use crate::shard_layout::ShardUId;
+use crate::transaction::{SignedTransaction, Transaction};
+use crate::types::{AccountId, EpochId, SlashedValidator, ValidatorStake};
+use near_crypto::{KeyType, PublicKey, SecretKey, Signature};
+use proptest::prelude::*;
+
+/// A valid account id: alphanumeric runs joined by single separators,
+/// within the [`AccountId::MIN_LEN`]..=[`AccountId::MAX_LEN`] bounds.
+fn account_id() -> impl Strategy<Value = AccountId> {
+    "[a-z0-9]{2,12}([._-][a-z0-9]{1,8}){0,2}"
+        .prop_map(|id| id.parse().expect("the pattern only emits valid account ids"))
+}
+
+/// A seed for a deterministic ed25519 key pair; generating the seed instead
+/// of raw key bytes keeps every generated key usable for real signing.
+fn key_seed() -> impl Strategy<Value = String> {
+    "[a-z]{1,16}"
+}
+
+fn public_key() -> impl Strategy<Value = PublicKey> {
+    key_seed().prop_map(|seed| SecretKey::from_seed(KeyType::ED25519, &seed).public_key())
+}
+
+/// A real signature over arbitrary bytes -- never the all-zero placeholder,
+/// which types like the header's approvals reject.
+fn signature() -> impl Strategy<Value = Signature> {
+    (key_seed(), proptest::collection::vec(any::<u8>(), 1..32))
+        .prop_map(|(seed, data)| SecretKey::from_seed(KeyType::ED25519, &seed).sign(&data))
+}
+
+fn validator_stake() -> impl Strategy<Value = ValidatorStake> {
+    (account_id(), public_key(), any::<u128>())
+        .prop_map(|(account_id, public_key, stake)| ValidatorStake::new(account_id, public_key, stake))
+}
+
+impl Arbitrary for EpochId {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        any::<[u8; 32]>().prop_map(|bytes| EpochId(CryptoHash(bytes))).boxed()
+    }
+}
+
+impl Arbitrary for ShardUId {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (any::<u32>(), any::<u32>())
+            .prop_map(|(version, shard_id)| ShardUId { version, shard_id })
+            .boxed()
+    }
+}
+
+impl Arbitrary for CongestionInfo {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        prop_oneof![
+            (any::<u128>(), any::<u128>(), any::<u64>(), any::<u16>()).prop_map(
+                |(delayed_receipts_gas, buffered_receipts_gas, receipt_bytes, allowed_shard)| {
+                    CongestionInfo::V1(CongestionInfoV1 {
+                        delayed_receipts_gas,
+                        buffered_receipts_gas,
+                        receipt_bytes,
+                        allowed_shard,
+                    })
+                }
+            ),
+            (any::<u128>(), any::<u128>(), any::<u64>(), any::<u64>(), any::<u16>()).prop_map(
+                |(
+                    delayed_receipts_gas,
+                    buffered_receipts_gas,
+                    receipt_bytes,
+                    buffered_receipt_bytes,
+                    allowed_shard,
+                )| {
+                    CongestionInfo::V2(CongestionInfoV2 {
+                        delayed_receipts_gas,
+                        buffered_receipts_gas,
+                        receipt_bytes,
+                        buffered_receipt_bytes,
+                        allowed_shard,
+                    })
+                }
+            ),
+        ]
+        .boxed()
+    }
+}
+
+impl Arbitrary for Account {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        // A V2 account always carries nonzero permanent storage: a V2
+        // without any is indistinguishable from a V1 in the flat view, so
+        // generating one would fuzz an equivalence the views fold away on
+        // purpose.
+        (any::<u128>(), any::<u128>(), any::<[u8; 32]>(), any::<u64>(), any::<Option<u64>>())
+            .prop_map(|(amount, locked, code_hash, storage_usage, permanent_storage_bytes)| {
+                let builder = Account::builder()
+                    .amount(amount)
+                    .locked(locked)
+                    .code_hash(CryptoHash(code_hash))
+                    .storage_usage(storage_usage);
+                match permanent_storage_bytes {
+                    Some(bytes) => builder
+                        .permanent_storage_bytes(bytes.max(1))
+                        .protocol_version(ACCOUNT_V2_PROTOCOL_VERSION)
+                        .build(),
+                    None => builder.build(),
+                }
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for AccessKey {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        let function_call = (
+            any::<Option<u128>>(),
+            account_id(),
+            proptest::collection::vec("[a-z_]{1,12}", 0..4),
+        )
+            .prop_map(|(allowance, receiver_id, method_names)| {
+                AccessKeyPermission::FunctionCall(FunctionCallPermission {
+                    allowance,
+                    receiver_id: receiver_id.to_string(),
+                    method_names,
+                })
+            });
+        let permission = prop_oneof![Just(AccessKeyPermission::FullAccess), function_call];
+        (any::<u64>(), permission)
+            .prop_map(|(nonce, permission)| AccessKey { nonce, permission })
+            .boxed()
+    }
+}
+
+fn action() -> impl Strategy<Value = Action> {
+    prop_oneof![
+        Just(Action::CreateAccount(CreateAccountAction {})),
+        any::<u128>().prop_map(|deposit| Action::Transfer(TransferAction { deposit })),
+        ("[a-z_]{1,12}", proptest::collection::vec(any::<u8>(), 0..32), any::<u64>(), any::<u128>())
+            .prop_map(|(method_name, args, gas, deposit)| {
+                Action::FunctionCall(Box::new(FunctionCallAction {
+                    method_name,
+                    args,
+                    gas,
+                    deposit,
+                }))
+            }),
+        (any::<u128>(), public_key())
+            .prop_map(|(stake, public_key)| Action::Stake(Box::new(StakeAction {
+                stake,
+                public_key,
+            }))),
+        account_id().prop_map(|beneficiary_id| Action::DeleteAccount(DeleteAccountAction {
+            beneficiary_id,
+        })),
+    ]
+}
+
+impl Arbitrary for SignedTransaction {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        // Signed with the real key whose public half the transaction names,
+        // so the signature verifies against the derived hash.
+        (
+            account_id(),
+            key_seed(),
+            any::<u64>(),
+            account_id(),
+            any::<[u8; 32]>(),
+            proptest::collection::vec(action(), 0..4),
+            any::<u64>(),
+        )
+            .prop_map(
+                |(signer_id, seed, nonce, receiver_id, block_hash, actions, priority_fee)| {
+                    let secret_key = SecretKey::from_seed(KeyType::ED25519, &seed);
+                    Transaction {
+                        signer_id,
+                        public_key: secret_key.public_key(),
+                        nonce,
+                        receiver_id,
+                        block_hash: CryptoHash(block_hash),
+                        actions,
+                        priority_fee,
+                    }
+                    .sign(&secret_key)
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for BlockHeader {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        let inner_lite = (
+            any::<u64>(),
+            any::<EpochId>(),
+            any::<EpochId>(),
+            any::<[u8; 32]>(),
+            any::<[u8; 32]>(),
+            any::<u64>(),
+        )
+            .prop_map(
+                |(height, epoch_id, next_epoch_id, prev_state_root, block_merkle_root, timestamp)| {
+                    BlockHeaderInnerLite {
+                        height,
+                        epoch_id,
+                        next_epoch_id,
+                        prev_state_root: CryptoHash(prev_state_root),
+                        block_merkle_root: CryptoHash(block_merkle_root),
+                        timestamp,
+                        ..Default::default()
+                    }
+                },
+            );
+        // A present approval must be a real signature: the header
+        // constructor rejects the all-zero placeholder.
+        let approvals = proptest::collection::vec(
+            proptest::option::of(signature().prop_map(Box::new)),
+            0..4,
+        );
+        let slashes = proptest::collection::vec(
+            (account_id(), any::<bool>()).prop_map(|(account_id, is_double_sign)| {
+                SlashedValidator::new(account_id, is_double_sign)
+            }),
+            0..2,
+        );
+        let inner_rest = (
+            any::<[u8; 32]>(),
+            proptest::collection::vec(validator_stake(), 0..3),
+            proptest::collection::vec(any::<bool>(), 0..4),
+            slashes,
+            (any::<u128>(), any::<u128>(), any::<u32>()),
+            approvals,
+        )
+            .prop_map(
+                |(
+                    random_value,
+                    prev_validator_proposals,
+                    chunk_mask,
+                    challenges_result,
+                    (gas_price, total_supply, latest_protocol_version),
+                    approvals,
+                )| {
+                    BlockHeaderInnerRestV5 {
+                        random_value: CryptoHash(random_value),
+                        prev_validator_proposals,
+                        chunk_mask,
+                        challenges_result,
+                        gas_price,
+                        total_supply,
+                        approvals,
+                        latest_protocol_version,
+                        ..Default::default()
+                    }
+                },
+            );
+        (any::<[u8; 32]>(), inner_lite, inner_rest, signature())
+            .prop_map(|(prev_hash, inner_lite, inner_rest, signature)| {
+                BlockHeader::new(CryptoHash(prev_hash), inner_lite, inner_rest, signature)
+            })
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::views::{AccessKeyView, AccountView};
+    use borsh::BorshDeserialize;
+
+    /// JSON round trip through the flat view types: asymmetric serde
+    /// attributes -- a field serialized one way and parsed another -- and
+    /// lossy view folds fail here.
+    mod view_round_trips {
+        use super::*;
+
+        proptest! {
+            #[test]
+            fn account_survives_the_view(account in any::<Account>()) {
+                let view = AccountView::from(&account);
+                let json = serde_json::to_string(&view).unwrap();
+                let parsed: AccountView = serde_json::from_str(&json).unwrap();
+                prop_assert_eq!(&parsed, &view);
+                prop_assert_eq!(Account::from(&parsed), account);
+            }
+
+            #[test]
+            fn access_key_survives_the_view(access_key in any::<AccessKey>()) {
+                let view = AccessKeyView::from(&access_key);
+                let json = serde_json::to_string(&view).unwrap();
+                let parsed: AccessKeyView = serde_json::from_str(&json).unwrap();
+                prop_assert_eq!(&parsed, &view);
+                prop_assert_eq!(AccessKey::from(&parsed), access_key);
+            }
+
+            #[test]
+            fn congestion_info_survives_the_view(info in any::<CongestionInfo>()) {
+                let view = info.to_view();
+                let json = serde_json::to_string(&view).unwrap();
+                let parsed = serde_json::from_str::<crate::views::CongestionInfoView>(&json).unwrap();
+                prop_assert_eq!(&parsed, &view);
+                // The decimal strings carry the full u128 values.
+                prop_assert_eq!(parsed.delayed_receipts_gas.parse::<u128>().unwrap(), info.delayed_receipts_gas());
+                prop_assert_eq!(parsed.buffered_receipts_gas.parse::<u128>().unwrap(), info.buffered_receipts_gas());
+                prop_assert_eq!(parsed.receipt_bytes, info.receipt_bytes());
+                prop_assert_eq!(parsed.allowed_shard, info.allowed_shard());
+            }
+        }
+    }
+
+    /// Direct round trips for the types serialized natively, asserting the
+    /// derived hashes survive where the type caches one.
+    mod native_round_trips {
+        use super::*;
+
+        proptest! {
+            #[test]
+            fn epoch_id_borsh(epoch_id in any::<EpochId>()) {
+                let bytes = borsh::to_vec(&epoch_id).unwrap();
+                prop_assert_eq!(EpochId::try_from_slice(&bytes).unwrap(), epoch_id);
+            }
+
+            #[test]
+            fn shard_uid_json(shard_uid in any::<ShardUId>()) {
+                let json = serde_json::to_string(&shard_uid).unwrap();
+                prop_assert_eq!(serde_json::from_str::<ShardUId>(&json).unwrap(), shard_uid);
+            }
+
+            #[test]
+            fn signed_transaction_json_and_borsh(signed_tx in any::<SignedTransaction>()) {
+                // The cached hash is skipped by both formats and recomputed
+                // on the way in; it must come back identical.
+                let json = serde_json::to_string(&signed_tx).unwrap();
+                let parsed: SignedTransaction = serde_json::from_str(&json).unwrap();
+                prop_assert_eq!(&parsed, &signed_tx);
+                prop_assert_eq!(parsed.get_hash(), signed_tx.get_hash());
+
+                let bytes = borsh::to_vec(&signed_tx).unwrap();
+                let mut decoded = SignedTransaction::try_from_slice(&bytes).unwrap();
+                decoded.init();
+                prop_assert_eq!(decoded.get_hash(), signed_tx.get_hash());
+                prop_assert!(signed_tx.signature.verify(
+                    signed_tx.get_hash().as_bytes(),
+                    &signed_tx.transaction.public_key,
+                ));
+            }
+
+            #[test]
+            fn block_header_borsh(header in any::<BlockHeader>()) {
+                let bytes = borsh::to_vec(&header).unwrap();
+                let mut decoded = BlockHeader::try_from_slice(&bytes).unwrap();
+                decoded.init();
+                prop_assert_eq!(&decoded, &header);
+                prop_assert_eq!(decoded.hash(), header.hash());
+            }
+        }
+    }
+}
@@ -0,0 +1,344 @@
+use crate::block_body::{BlockBody, ChunkEndorsementsBitmap};
+use crate::block_header::BlockHeader;
+use crate::hash::CryptoHash;
+use crate::merkle::merklize;
+use crate::sharding::ShardChunkHeader;
+use crate::types::{ShardId, StateRoot};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// The head of a chain: everything needed to anchor the next block without
+/// holding the full head block.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Tip {
+    pub height: crate::types::BlockHeight,
+    pub last_block_hash: CryptoHash,
+    pub prev_block_hash: CryptoHash,
+    pub epoch_id: crate::types::EpochId,
+    pub next_epoch_id: crate::types::EpochId,
+}
+
+impl Tip {
+    /// The tip a chain sits at after processing the block with this header.
+    pub fn from_header(header: &BlockHeader) -> Self {
+        Self {
+            height: header.height(),
+            last_block_hash: *header.hash(),
+            prev_block_hash: *header.prev_hash(),
+            epoch_id: *header.epoch_id(),
+            next_epoch_id: *header.next_epoch_id(),
+        }
+    }
+}
+
+/// `#H <short hash> (prev: <short hash>, epoch: E)`: the same short-hash form
+/// the other consensus types use, so head updates correlate with block logs.
+impl std::fmt::Display for Tip {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "#{} {} (prev: {}, epoch: {})",
+            self.height,
+            self.last_block_hash.short(),
+            self.prev_block_hash.short(),
+            self.epoch_id,
+        )
+    }
+}
+
+/// Ways a block's header can be inconsistent with the body it carries.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum BlockValidationError {
+    #[error("header chunk endorsements bitmap does not match the body's signatures")]
+    ChunkEndorsementsMismatch,
+}
+
+/// A block of the chain: the signed header plus the body it commits to.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum Block {
+    V4(Box<BlockV4>),
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BlockV4 {
+    pub header: BlockHeader,
+    pub body: BlockBody,
+}
+
+impl Block {
+    pub fn new(header: BlockHeader, body: BlockBody) -> Self {
+        Self::V4(Box::new(BlockV4 { header, body }))
+    }
+
+    #[inline]
+    pub fn header(&self) -> &BlockHeader {
+        match self {
+            Self::V4(block) => &block.header,
+        }
+    }
+
+    #[inline]
+    pub fn body(&self) -> &BlockBody {
+        match self {
+            Self::V4(block) => &block.body,
+        }
+    }
+
+    #[inline]
+    pub fn chunks(&self) -> &[ShardChunkHeader] {
+        self.body().chunks()
+    }
+
+    #[inline]
+    pub fn hash(&self) -> &CryptoHash {
+        self.header().hash()
+    }
+
+    pub fn compute_block_body_hash(&self) -> CryptoHash {
+        CryptoHash::hash_borsh(self.body())
+    }
+
+    /// Checks that the header commits to this body.
+    pub fn check_block_body_hash(&self) -> bool {
+        self.header().block_body_hash() == &self.compute_block_body_hash()
+    }
+
+    /// The per-shard state roots the block's chunks commit to, ordered by
+    /// shard index.
+    pub fn prev_state_roots(&self) -> Vec<StateRoot> {
+        self.chunks().iter().map(|chunk| *chunk.prev_state_root()).collect()
+    }
+
+    /// The chunk header of the given shard, if the block carries one.
+    pub fn chunk_for_shard(&self, shard_id: ShardId) -> Option<&ShardChunkHeader> {
+        self.chunks().iter().find(|chunk| chunk.shard_id() == shard_id)
+    }
+
+    /// The shards whose chunk in this block is freshly produced rather than
+    /// carried over from the previous block, per the header's chunk mask.
+    pub fn shards_with_new_chunks(&self) -> Vec<ShardId> {
+        self.header()
+            .chunk_mask()
+            .iter()
+            .enumerate()
+            .filter(|(_, new_chunk)| **new_chunk)
+            .map(|(shard_id, _)| shard_id as ShardId)
+            .collect()
+    }
+
+    /// The merklized combination of the chunks' per-shard state roots; the
+    /// value the header's `prev_state_root` commits to.
+    pub fn compute_state_root(chunks: &[ShardChunkHeader]) -> StateRoot {
+        merklize(&chunks.iter().map(|chunk| *chunk.prev_state_root()).collect::<Vec<_>>()).0
+    }
+
+    /// Checks that the header's endorsement bitmap matches the body's
+    /// endorsement signatures bit for bit. A header claiming endorsements
+    /// the body does not carry -- or hiding ones it does -- inflates or
+    /// deflates the endorsement count approvals logic relies on.
+    pub fn validate_chunk_endorsements(&self) -> Result<(), BlockValidationError> {
+        let derived =
+            ChunkEndorsementsBitmap::from_endorsement_signatures(self.body().chunk_endorsements());
+        if self.header().chunk_endorsements() != &derived {
+            return Err(BlockValidationError::ChunkEndorsementsMismatch);
+        }
+        Ok(())
+    }
+
+    /// Checks that the header commits to this body and that its state root
+    /// matches the chunk headers the body carries. The second check catches
+    /// a chunk header swapped after the header roots were computed, even
+    /// when the body hash was recomputed to cover the swap.
+    pub fn check_validity(&self) -> bool {
+        self.check_block_body_hash()
+            && self.header().prev_state_root() == &Self::compute_state_root(self.chunks())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_header::{BlockHeaderInnerLite, BlockHeaderInnerRestV5};
+    use crate::congestion_info::CongestionInfo;
+    use crate::hash::hash;
+    use crate::sharding::{ShardChunkHeaderInnerV3, ShardChunkHeaderV3};
+    use near_crypto::{KeyType, SecretKey, Signature};
+
+    /// A chunk header for the given shard whose state root differs per
+    /// shard, so that swapping two headers changes the combined state root.
+    fn chunk_header(shard_id: ShardId) -> ShardChunkHeader {
+        let inner = ShardChunkHeaderInnerV3 {
+            prev_block_hash: hash(b"prev block"),
+            prev_state_root: hash(format!("state root {shard_id}").as_bytes()),
+            prev_outcome_root: hash(b"outcome root"),
+            encoded_merkle_root: hash(b"encoded merkle root"),
+            encoded_length: 100,
+            height_created: 1,
+            shard_id,
+            prev_gas_used: 10,
+            gas_limit: 1000,
+            prev_balance_burnt: 0,
+            prev_outgoing_receipts_root: hash(b"receipts root"),
+            tx_root: hash(b"tx root"),
+            prev_validator_proposals: vec![],
+            congestion_info: CongestionInfo::default(),
+        };
+        ShardChunkHeader::V3(ShardChunkHeaderV3::new(inner, Signature::default()))
+    }
+
+    /// A block over the given chunks whose header state root and body hash
+    /// are both consistent with them.
+    fn test_block(chunks: Vec<ShardChunkHeader>, chunk_mask: Vec<bool>) -> Block {
+        let secret_key = SecretKey::from_seed(KeyType::ED25519, "producer");
+        let (vrf_value, vrf_proof) = secret_key.compute_vrf_with_proof(b"prev random value");
+        let endorsements = vec![vec![]; chunks.len()];
+        let inner_lite = BlockHeaderInnerLite {
+            height: 2,
+            prev_state_root: Block::compute_state_root(&chunks),
+            ..Default::default()
+        };
+        let body = BlockBody::new(chunks, vrf_value, vrf_proof, endorsements);
+        let inner_rest = BlockHeaderInnerRestV5 {
+            block_body_hash: CryptoHash::hash_borsh(&body),
+            chunk_mask,
+            chunk_endorsements: ChunkEndorsementsBitmap::from_endorsement_signatures(
+                body.chunk_endorsements(),
+            ),
+            ..Default::default()
+        };
+        let header =
+            BlockHeader::new(hash(b"prev block"), inner_lite, inner_rest, Signature::default());
+        Block::new(header, body)
+    }
+
+    #[test]
+    fn test_per_shard_accessors() {
+        let chunks: Vec<_> = (0..3).map(chunk_header).collect();
+        let block = test_block(chunks.clone(), vec![true, false, true]);
+
+        assert_eq!(
+            block.prev_state_roots(),
+            vec![
+                hash(b"state root 0"),
+                hash(b"state root 1"),
+                hash(b"state root 2"),
+            ]
+        );
+        assert_eq!(block.chunk_for_shard(1), Some(&chunks[1]));
+        assert_eq!(block.chunk_for_shard(9), None);
+        assert_eq!(block.shards_with_new_chunks(), vec![0, 2]);
+        assert!(block.check_validity());
+    }
+
+    #[test]
+    fn test_tip_display_format() {
+        use crate::types::EpochId;
+
+        let inner_lite = BlockHeaderInnerLite {
+            height: 42,
+            epoch_id: EpochId(hash(b"epoch")),
+            next_epoch_id: EpochId(hash(b"next epoch")),
+            ..Default::default()
+        };
+        let header = BlockHeader::new(
+            hash(b"prev block"),
+            inner_lite,
+            BlockHeaderInnerRestV5::default(),
+            Signature::default(),
+        );
+        let tip = Tip::from_header(&header);
+        assert_eq!(tip.height, 42);
+        assert_eq!(tip.last_block_hash, *header.hash());
+        assert_eq!(tip.prev_block_hash, hash(b"prev block"));
+        assert_eq!(tip.next_epoch_id, EpochId(hash(b"next epoch")));
+
+        // Pinned format: log-parsing tooling relies on it.
+        let short = |hash: &CryptoHash| hash.to_string()[..8].to_string();
+        assert_eq!(
+            tip.to_string(),
+            format!(
+                "#42 {} (prev: {}, epoch: {})",
+                short(header.hash()),
+                short(&hash(b"prev block")),
+                short(&hash(b"epoch")),
+            )
+        );
+    }
+
+    #[test]
+    fn test_validate_chunk_endorsements() {
+        let secret_key = SecretKey::from_seed(KeyType::ED25519, "producer");
+        let (vrf_value, vrf_proof) = secret_key.compute_vrf_with_proof(b"prev random value");
+        let endorsement = || {
+            Some(Box::new(SecretKey::from_seed(KeyType::ED25519, "validator").sign(b"endorse")))
+        };
+        let body = BlockBody::new(
+            vec![chunk_header(0)],
+            vrf_value,
+            vrf_proof,
+            vec![vec![endorsement(), None, endorsement()]],
+        );
+        let header_with_bitmap = |bitmap| {
+            BlockHeader::new(
+                hash(b"prev block"),
+                BlockHeaderInnerLite::default(),
+                BlockHeaderInnerRestV5 { chunk_endorsements: bitmap, ..Default::default() },
+                Signature::default(),
+            )
+        };
+
+        let matching = Block::new(
+            header_with_bitmap(ChunkEndorsementsBitmap::from_endorsement_signatures(
+                body.chunk_endorsements(),
+            )),
+            body.clone(),
+        );
+        assert_eq!(matching.validate_chunk_endorsements(), Ok(()));
+
+        // A header claiming an endorsement the body lacks is rejected.
+        let inflated = Block::new(
+            header_with_bitmap(ChunkEndorsementsBitmap::from_endorsements(vec![vec![
+                true, true, true,
+            ]])),
+            body,
+        );
+        assert_eq!(
+            inflated.validate_chunk_endorsements(),
+            Err(BlockValidationError::ChunkEndorsementsMismatch)
+        );
+
+        // The helper-built blocks stay self-consistent.
+        assert_eq!(
+            test_block(vec![chunk_header(0)], vec![true]).validate_chunk_endorsements(),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_check_validity_detects_swapped_chunk_header() {
+        let block = test_block((0..2).map(chunk_header).collect(), vec![true, true]);
+        assert!(block.check_validity());
+
+        // Swap the shards' chunk headers and recompute the body hash to
+        // cover the swap: the body commitment passes, the state root
+        // cross-check does not.
+        let mut chunks = block.chunks().to_vec();
+        chunks.swap(0, 1);
+        let swapped_body = test_block(chunks, vec![true, true]).body().clone();
+        let header = BlockHeader::new(
+            *block.header().prev_hash(),
+            BlockHeaderInnerLite {
+                prev_state_root: *block.header().prev_state_root(),
+                ..Default::default()
+            },
+            BlockHeaderInnerRestV5 {
+                block_body_hash: CryptoHash::hash_borsh(&swapped_body),
+                chunk_mask: vec![true, true],
+                ..Default::default()
+            },
+            Signature::default(),
+        );
+        let tampered = Block::new(header, swapped_body);
+        assert!(tampered.check_block_body_hash());
+        assert!(!tampered.check_validity());
+    }
+}
@@ -0,0 +1,477 @@
+use crate::hash::CryptoHash;
+use crate::sharding::ShardChunkHeader;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_crypto::Signature;
+use near_crypto::vrf::{Proof, Value};
+
+/// Signatures of chunk validators endorsing one chunk; `None` for validators
+/// that did not endorse.
+pub type ChunkEndorsementSignatures = Vec<Option<Box<Signature>>>;
+
+/// The chunk endorsements carried in a block body, one list per chunk.
+///
+/// Versioned with explicit borsh discriminants so the layout can grow --
+/// e.g. attach the endorsing accounts -- without breaking serialized
+/// blocks. V1 is exactly the bare `Vec<ChunkEndorsementSignatures>` layout
+/// behind a one-byte version tag.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+#[borsh(use_discriminant = true)]
+#[repr(u8)]
+pub enum ChunkEndorsementsInBlock {
+    V1(Vec<ChunkEndorsementSignatures>) = 0,
+}
+
+impl ChunkEndorsementsInBlock {
+    #[inline]
+    pub fn signatures(&self) -> &[ChunkEndorsementSignatures] {
+        match self {
+            Self::V1(signatures) => signatures,
+        }
+    }
+
+    /// The endorsement of the given validator on the given chunk, if both
+    /// indices are in range and the validator endorsed.
+    pub fn signature_for(
+        &self,
+        shard_index: usize,
+        validator_index: usize,
+    ) -> Option<&Signature> {
+        self.signatures().get(shard_index)?.get(validator_index)?.as_deref()
+    }
+
+    /// How many validators endorsed the given chunk; zero for an
+    /// out-of-range index.
+    pub fn count_endorsements(&self, shard_index: usize) -> usize {
+        self.signatures()
+            .get(shard_index)
+            .map(|signatures| signatures.iter().flatten().count())
+            .unwrap_or(0)
+    }
+}
+
+impl From<Vec<ChunkEndorsementSignatures>> for ChunkEndorsementsInBlock {
+    fn from(signatures: Vec<ChunkEndorsementSignatures>) -> Self {
+        Self::V1(signatures)
+    }
+}
+
+impl From<ChunkEndorsementsInBlock> for Vec<ChunkEndorsementSignatures> {
+    fn from(endorsements: ChunkEndorsementsInBlock) -> Self {
+        match endorsements {
+            ChunkEndorsementsInBlock::V1(signatures) => signatures,
+        }
+    }
+}
+
+/// A compact record of which validators endorsed each chunk: one bit per
+/// validator, packed little-endian into bytes, one byte vector per shard.
+/// Cheaper to carry around for metrics than the signature lists in
+/// [`ChunkEndorsementsInBlock`], which it is derived from.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq, Default)]
+pub struct ChunkEndorsementsBitmap {
+    bitmaps: Vec<Vec<u8>>,
+}
+
+impl ChunkEndorsementsBitmap {
+    /// Packs per-shard endorsement flags -- `true` for validators that
+    /// endorsed -- into the bitmap.
+    pub fn from_endorsements(endorsements: Vec<Vec<bool>>) -> Self {
+        let bitmaps = endorsements
+            .into_iter()
+            .map(|flags| {
+                let mut bytes = vec![0u8; flags.len().div_ceil(8)];
+                for (index, endorsed) in flags.into_iter().enumerate() {
+                    if endorsed {
+                        bytes[index / 8] |= 1 << (index % 8);
+                    }
+                }
+                bytes
+            })
+            .collect();
+        Self { bitmaps }
+    }
+
+    pub fn num_shards(&self) -> usize {
+        self.bitmaps.len()
+    }
+
+    /// Whether the validator endorsed the given chunk; `false` for indices
+    /// out of range.
+    pub fn get_bit(&self, shard_index: usize, validator_index: usize) -> bool {
+        self.bitmaps
+            .get(shard_index)
+            .and_then(|bytes| bytes.get(validator_index / 8))
+            .is_some_and(|byte| byte & (1 << (validator_index % 8)) != 0)
+    }
+
+    /// How many validators endorsed the given chunk; zero for an
+    /// out-of-range index.
+    pub fn num_endorsements(&self, shard_index: usize) -> usize {
+        self.bitmaps
+            .get(shard_index)
+            .map(|bytes| bytes.iter().map(|byte| byte.count_ones() as usize).sum())
+            .unwrap_or(0)
+    }
+
+    /// How many endorsements the bitmap records across all shards.
+    pub fn total_endorsements(&self) -> usize {
+        (0..self.bitmaps.len()).map(|shard_index| self.num_endorsements(shard_index)).sum()
+    }
+
+    /// Checks a received bitmap against the expected chunk validator counts:
+    /// it must cover exactly the epoch's shards and set no bit past the
+    /// shard's validator count. A bitmap failing this claims endorsements
+    /// from validators that do not exist and must be rejected.
+    pub fn validate(&self, validators_per_shard: &[usize]) -> Result<(), BitmapValidationError> {
+        if self.bitmaps.len() != validators_per_shard.len() {
+            return Err(BitmapValidationError::WrongShardCount {
+                expected: validators_per_shard.len(),
+                got: self.bitmaps.len(),
+            });
+        }
+        for (shard_index, (bytes, &num_validators)) in
+            self.bitmaps.iter().zip(validators_per_shard).enumerate()
+        {
+            for (byte_index, byte) in bytes.iter().enumerate() {
+                for bit in 0..8 {
+                    let validator_index = byte_index * 8 + bit;
+                    if byte & (1 << bit) != 0 && validator_index >= num_validators {
+                        return Err(BitmapValidationError::BitBeyondValidatorCount {
+                            shard_index,
+                            validator_index,
+                            num_validators,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Why a received [`ChunkEndorsementsBitmap`] is inconsistent with the
+/// chunk validator assignment it claims to describe.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum BitmapValidationError {
+    #[error("bitmap covers {got} shards, the epoch has {expected}")]
+    WrongShardCount { expected: usize, got: usize },
+    #[error(
+        "bitmap for shard {shard_index} sets bit {validator_index}, \
+         but the shard only has {num_validators} chunk validators"
+    )]
+    BitBeyondValidatorCount {
+        shard_index: usize,
+        validator_index: usize,
+        num_validators: usize,
+    },
+}
+
+impl ChunkEndorsementsBitmap {
+    /// Derives the bitmap from the per-chunk signature lists of a block
+    /// body: a bit is set exactly where a signature is present.
+    pub fn from_endorsement_signatures(signatures: &[ChunkEndorsementSignatures]) -> Self {
+        Self::from_endorsements(
+            signatures
+                .iter()
+                .map(|shard| shard.iter().map(|signature| signature.is_some()).collect())
+                .collect(),
+        )
+    }
+}
+
+impl From<&ChunkEndorsementsInBlock> for ChunkEndorsementsBitmap {
+    fn from(endorsements: &ChunkEndorsementsInBlock) -> Self {
+        Self::from_endorsement_signatures(endorsements.signatures())
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum BlockBody {
+    V2(BlockBodyV2),
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BlockBodyV2 {
+    pub chunks: Vec<ShardChunkHeader>,
+    /// The output of the block producer's VRF evaluated on the previous
+    /// block's random value.
+    pub vrf_value: Value,
+    pub vrf_proof: Proof,
+    /// Chunk endorsements, one list per chunk.
+    pub chunk_endorsements: ChunkEndorsementsInBlock,
+}
+
+impl BlockBody {
+    pub fn new(
+        chunks: Vec<ShardChunkHeader>,
+        vrf_value: Value,
+        vrf_proof: Proof,
+        chunk_endorsements: Vec<ChunkEndorsementSignatures>,
+    ) -> Self {
+        BlockBody::V2(BlockBodyV2 {
+            chunks,
+            vrf_value,
+            vrf_proof,
+            chunk_endorsements: chunk_endorsements.into(),
+        })
+    }
+
+    #[inline]
+    pub fn chunks(&self) -> &[ShardChunkHeader] {
+        match self {
+            BlockBody::V2(body) => &body.chunks,
+        }
+    }
+
+    #[inline]
+    pub fn vrf_value(&self) -> &Value {
+        match self {
+            BlockBody::V2(body) => &body.vrf_value,
+        }
+    }
+
+    #[inline]
+    pub fn vrf_proof(&self) -> &Proof {
+        match self {
+            BlockBody::V2(body) => &body.vrf_proof,
+        }
+    }
+
+    #[inline]
+    pub fn chunk_endorsements(&self) -> &[ChunkEndorsementSignatures] {
+        match self {
+            BlockBody::V2(body) => body.chunk_endorsements.signatures(),
+        }
+    }
+
+    /// Whether two bodies carry the same content: the same chunks by hash,
+    /// the same endorsements and the same VRF output.
+    ///
+    /// `PartialEq` compares the full chunk headers including
+    /// `height_included`, which is bookkeeping set when a chunk lands in a
+    /// block rather than content, so bodies referencing the same chunks at
+    /// different inclusion heights compare unequal there but equal here.
+    pub fn content_eq(&self, other: &BlockBody) -> bool {
+        self.chunks().len() == other.chunks().len()
+            && self
+                .chunks()
+                .iter()
+                .zip(other.chunks())
+                .all(|(chunk, other_chunk)| chunk.chunk_hash() == other_chunk.chunk_hash())
+            && self.chunk_endorsements() == other.chunk_endorsements()
+            && self.vrf_value() == other.vrf_value()
+            && self.vrf_proof() == other.vrf_proof()
+    }
+
+    /// Derives the `random_value` the block header should carry from the VRF
+    /// output stored in this body.
+    pub fn compute_randomness(&self) -> CryptoHash {
+        CryptoHash::hash_bytes(&self.vrf_value().0)
+    }
+
+    /// Checks that the VRF output stored in this body is the block producer's
+    /// valid VRF evaluation on `input`, which must be the previous block's
+    /// random value.
+    pub fn verify_vrf(&self, public_key: &near_crypto::PublicKey, input: &[u8]) -> bool {
+        public_key.is_vrf_valid(input, self.vrf_value(), self.vrf_proof())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::hash;
+    use near_crypto::{KeyType, SecretKey};
+
+    fn endorsement(seed: &str) -> Option<Box<Signature>> {
+        Some(Box::new(SecretKey::from_seed(KeyType::ED25519, seed).sign(b"endorsement")))
+    }
+
+    #[test]
+    fn test_chunk_endorsements_accessors() {
+        // Two chunks: the first endorsed by validators 0 and 2, the second
+        // by nobody.
+        let endorsements = ChunkEndorsementsInBlock::V1(vec![
+            vec![endorsement("alice"), None, endorsement("carol")],
+            vec![None, None],
+        ]);
+        assert_eq!(endorsements.count_endorsements(0), 2);
+        assert_eq!(endorsements.count_endorsements(1), 0);
+        assert_eq!(endorsements.count_endorsements(5), 0);
+
+        assert_eq!(
+            endorsements.signature_for(0, 2),
+            endorsement("carol").as_deref(),
+        );
+        assert_eq!(endorsements.signature_for(0, 1), None);
+        // Out-of-range indices are a missing endorsement, not a panic.
+        assert_eq!(endorsements.signature_for(0, 9), None);
+        assert_eq!(endorsements.signature_for(9, 0), None);
+    }
+
+    #[test]
+    fn test_endorsements_bitmap_counts() {
+        // Ten validators: shard 0 endorsed by 0, 7 and 9 (crossing a byte
+        // boundary), shard 1 by nobody, shard 2 by validators 1..=3.
+        let mut flags = vec![vec![false; 10], vec![false; 10], vec![false; 10]];
+        for index in [0, 7, 9] {
+            flags[0][index] = true;
+        }
+        for index in [1, 2, 3] {
+            flags[2][index] = true;
+        }
+        let bitmap = ChunkEndorsementsBitmap::from_endorsements(flags);
+
+        assert_eq!(bitmap.num_shards(), 3);
+        assert_eq!(bitmap.num_endorsements(0), 3);
+        assert_eq!(bitmap.num_endorsements(1), 0);
+        assert_eq!(bitmap.num_endorsements(2), 3);
+        assert_eq!(bitmap.num_endorsements(9), 0);
+        assert_eq!(bitmap.total_endorsements(), 6);
+
+        assert!(bitmap.get_bit(0, 9));
+        assert!(!bitmap.get_bit(0, 8));
+        assert!(bitmap.get_bit(2, 3));
+        // Out-of-range indices read as not endorsed.
+        assert!(!bitmap.get_bit(0, 16));
+        assert!(!bitmap.get_bit(9, 0));
+
+        // The bitmap derived from block endorsements counts the same way
+        // the signature lists do.
+        let endorsements = ChunkEndorsementsInBlock::V1(vec![
+            vec![endorsement("alice"), None, endorsement("carol")],
+            vec![None, None],
+        ]);
+        let derived = ChunkEndorsementsBitmap::from(&endorsements);
+        assert_eq!(derived.num_endorsements(0), endorsements.count_endorsements(0));
+        assert_eq!(derived.num_endorsements(1), 0);
+        assert_eq!(derived.total_endorsements(), 2);
+        assert!(derived.get_bit(0, 0) && !derived.get_bit(0, 1) && derived.get_bit(0, 2));
+    }
+
+    #[test]
+    fn test_endorsements_bitmap_validation() {
+        let mut flags = vec![vec![false; 10], vec![false; 3]];
+        flags[0][9] = true;
+        flags[1][2] = true;
+        let bitmap = ChunkEndorsementsBitmap::from_endorsements(flags);
+        // All set bits are within the validator counts, even though the
+        // packed bytes have padding bits past them.
+        assert_eq!(bitmap.validate(&[10, 3]), Ok(()));
+        // Larger counts are fine too; the bitmap just has trailing zeros.
+        assert_eq!(bitmap.validate(&[12, 5]), Ok(()));
+
+        // A bit past the shard's validator count is rejected.
+        assert_eq!(
+            bitmap.validate(&[10, 2]),
+            Err(BitmapValidationError::BitBeyondValidatorCount {
+                shard_index: 1,
+                validator_index: 2,
+                num_validators: 2,
+            })
+        );
+        // So is a bitmap covering the wrong number of shards.
+        assert_eq!(
+            bitmap.validate(&[10, 3, 4]),
+            Err(BitmapValidationError::WrongShardCount { expected: 3, got: 2 })
+        );
+    }
+
+    #[test]
+    fn test_chunk_endorsements_round_trip_and_layout() {
+        let signatures = vec![vec![endorsement("alice"), None]];
+        let endorsements = ChunkEndorsementsInBlock::from(signatures.clone());
+
+        let bytes = borsh::to_vec(&endorsements).unwrap();
+        assert_eq!(
+            ChunkEndorsementsInBlock::try_from_slice(&bytes).unwrap(),
+            endorsements,
+        );
+        assert_eq!(Vec::<ChunkEndorsementSignatures>::from(endorsements), signatures);
+
+        // Layout snapshot: V1 is the bare alias layout behind the explicit
+        // discriminant byte. Changing this breaks every serialized block.
+        assert_eq!(bytes[0], 0);
+        assert_eq!(bytes[1..], borsh::to_vec(&signatures).unwrap());
+    }
+
+    #[test]
+    fn test_content_eq_ignores_height_included() {
+        use crate::congestion_info::CongestionInfo;
+        use crate::sharding::{ShardChunkHeaderInnerV3, ShardChunkHeaderV3};
+
+        let chunk_header = || {
+            let inner = ShardChunkHeaderInnerV3 {
+                prev_block_hash: hash(b"prev block"),
+                prev_state_root: hash(b"state root"),
+                prev_outcome_root: hash(b"outcome root"),
+                encoded_merkle_root: hash(b"encoded merkle root"),
+                encoded_length: 100,
+                height_created: 1,
+                shard_id: 0,
+                prev_gas_used: 10,
+                gas_limit: 1000,
+                prev_balance_burnt: 0,
+                prev_outgoing_receipts_root: hash(b"receipts root"),
+                tx_root: hash(b"tx root"),
+                prev_validator_proposals: vec![],
+                congestion_info: CongestionInfo::default(),
+            };
+            ShardChunkHeader::V3(ShardChunkHeaderV3::new(inner, Signature::default()))
+        };
+        let secret_key = SecretKey::from_seed(KeyType::ED25519, "producer");
+        let (vrf_value, vrf_proof) = secret_key.compute_vrf_with_proof(b"prev random value");
+
+        let body_at = |height_included| {
+            let mut chunk = chunk_header();
+            chunk.set_height_included(height_included).unwrap();
+            BlockBody::new(vec![chunk], vrf_value, vrf_proof, vec![vec![]])
+        };
+        let body = body_at(5);
+        let reincluded = body_at(7);
+        // Same chunks at different inclusion heights: different by
+        // `PartialEq`, the same by content.
+        assert_ne!(body, reincluded);
+        assert!(body.content_eq(&reincluded));
+
+        // A different VRF output is different content.
+        let (other_value, other_proof) = secret_key.compute_vrf_with_proof(b"other input");
+        let other_vrf =
+            BlockBody::new(vec![chunk_header()], other_value, other_proof, vec![vec![]]);
+        assert!(!body.content_eq(&other_vrf));
+        // So is a different chunk set.
+        let no_chunks = BlockBody::new(vec![], vrf_value, vrf_proof, vec![]);
+        assert!(!body.content_eq(&no_chunks));
+    }
+
+    #[test]
+    fn test_compute_randomness_is_deterministic() {
+        let secret_key = SecretKey::from_seed(KeyType::ED25519, "producer");
+        let (vrf_value, vrf_proof) = secret_key.compute_vrf_with_proof(b"prev random value");
+        let body = BlockBody::new(vec![], vrf_value, vrf_proof, vec![]);
+        assert_eq!(body.compute_randomness(), body.compute_randomness());
+        assert_eq!(body.compute_randomness(), hash(&vrf_value.0));
+        assert_eq!(body.vrf_value(), &vrf_value);
+        assert_eq!(body.vrf_proof(), &vrf_proof);
+    }
+
+    #[test]
+    fn test_verify_vrf() {
+        let secret_key = SecretKey::from_seed(KeyType::ED25519, "producer");
+        let input = hash(b"prev random value");
+        let (vrf_value, vrf_proof) = secret_key.compute_vrf_with_proof(input.as_ref());
+        let body = BlockBody::new(vec![], vrf_value, vrf_proof, vec![]);
+
+        assert!(body.verify_vrf(&secret_key.public_key(), input.as_ref()));
+        // Wrong input or wrong producer key must fail.
+        assert!(!body.verify_vrf(&secret_key.public_key(), hash(b"other").as_ref()));
+        let other_key = SecretKey::from_seed(KeyType::ED25519, "other").public_key();
+        assert!(!body.verify_vrf(&other_key, input.as_ref()));
+
+        // A tampered VRF value must fail even with a valid proof.
+        let mut tampered_value = vrf_value;
+        tampered_value.0[0] ^= 1;
+        let tampered = BlockBody::new(vec![], tampered_value, vrf_proof, vec![]);
+        assert!(!tampered.verify_vrf(&secret_key.public_key(), input.as_ref()));
+    }
+}
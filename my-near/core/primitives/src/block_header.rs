@@ -0,0 +1,432 @@
+use crate::block_body::ChunkEndorsementsBitmap;
+use crate::hash::CryptoHash;
+use crate::types::{
+    Balance, BlockHeight, ChallengesResult, EpochId, ProtocolVersion, ValidatorStake,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_crypto::{PublicKey, Signature};
+
+/// Protocol version that activated challenge-based slashing. Headers from
+/// earlier protocol versions must not carry a challenges result.
+pub const CHALLENGES_RESULT_VERSION: ProtocolVersion = 2;
+
+/// Ways a header can be inconsistent with the protocol version of its epoch.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum HeaderValidationError {
+    #[error(
+        "field {field} requires protocol version {feature_version}, \
+         the epoch runs version {protocol_version}"
+    )]
+    FieldBeforeFeature {
+        field: &'static str,
+        feature_version: ProtocolVersion,
+        protocol_version: ProtocolVersion,
+    },
+}
+
+/// Part of the block header that is included into the light client block.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlockHeaderInnerLite {
+    /// Height of this block.
+    pub height: BlockHeight,
+    /// Epoch this block belongs to.
+    pub epoch_id: EpochId,
+    /// Epoch that immediately follows.
+    pub next_epoch_id: EpochId,
+    /// Root of the state before this block is applied.
+    pub prev_state_root: CryptoHash,
+    /// Root of the outcomes of transactions and receipts of the previous
+    /// chunks.
+    pub prev_outcome_root: CryptoHash,
+    /// Timestamp at which the block was built, in nanoseconds since epoch.
+    pub timestamp: u64,
+    /// Hash of the next epoch's block producer set.
+    pub next_bp_hash: CryptoHash,
+    /// Merkle root of all block hashes up to this block.
+    pub block_merkle_root: CryptoHash,
+}
+
+/// The rest of the block header fields.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlockHeaderInnerRestV5 {
+    /// Hash of the block body.
+    pub block_body_hash: CryptoHash,
+    /// Root of the outgoing receipts of the previous chunks.
+    pub prev_chunk_outgoing_receipts_root: CryptoHash,
+    /// Root of the chunk headers included in this block.
+    pub chunk_headers_root: CryptoHash,
+    /// Root of the chunk transactions included in this block.
+    pub chunk_tx_root: CryptoHash,
+    /// The output of the randomness beacon, derived from the body VRF value.
+    pub random_value: CryptoHash,
+    /// Validator proposals from the previous chunks.
+    pub prev_validator_proposals: Vec<ValidatorStake>,
+    /// Mask of which chunks are present in this block.
+    pub chunk_mask: Vec<bool>,
+    /// Validators slashed by the challenges included in this block.
+    pub challenges_result: ChallengesResult,
+    /// Gas price of this block.
+    pub gas_price: Balance,
+    /// Total supply of tokens after this block.
+    pub total_supply: Balance,
+    /// Approvals of block producers for this block, ordered by the block
+    /// producer settlement; `None` stands for a missing approval. A present
+    /// approval must never be the all-zero placeholder signature.
+    pub approvals: Vec<Option<Box<Signature>>>,
+    /// Latest protocol version the block producer runs.
+    pub latest_protocol_version: ProtocolVersion,
+    /// Compact commitment to the body's chunk endorsement signatures: one
+    /// bit per chunk validator and shard, set where the body carries a
+    /// signature.
+    pub chunk_endorsements: ChunkEndorsementsBitmap,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BlockHeaderV5 {
+    pub prev_hash: CryptoHash,
+    pub inner_lite: BlockHeaderInnerLite,
+    pub inner_rest: BlockHeaderInnerRestV5,
+    /// Signature of the block producer over the header hash.
+    pub signature: Signature,
+    #[borsh(skip)]
+    pub hash: CryptoHash,
+}
+
+/// Versioned block header.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum BlockHeader {
+    V5(Box<BlockHeaderV5>),
+}
+
+impl BlockHeader {
+    /// The header hash commits to everything except the signature, which is
+    /// computed over the hash itself.
+    pub fn compute_hash(
+        prev_hash: &CryptoHash,
+        inner_lite: &BlockHeaderInnerLite,
+        inner_rest: &BlockHeaderInnerRestV5,
+    ) -> CryptoHash {
+        CryptoHash::hash_borsh(&(prev_hash, inner_lite, inner_rest))
+    }
+
+    pub fn new(
+        prev_hash: CryptoHash,
+        inner_lite: BlockHeaderInnerLite,
+        inner_rest: BlockHeaderInnerRestV5,
+        signature: Signature,
+    ) -> Self {
+        debug_assert!(
+            inner_rest.approvals.iter().flatten().all(|approval| !approval.is_empty()),
+            "a present approval must be a real signature, not the empty placeholder"
+        );
+        let hash = Self::compute_hash(&prev_hash, &inner_lite, &inner_rest);
+        Self::V5(Box::new(BlockHeaderV5 { prev_hash, inner_lite, inner_rest, signature, hash }))
+    }
+
+    /// Recomputes the cached header hash; must be called after
+    /// deserialization, which skips the hash field.
+    pub fn init(&mut self) {
+        match self {
+            Self::V5(header) => {
+                header.hash =
+                    Self::compute_hash(&header.prev_hash, &header.inner_lite, &header.inner_rest);
+            }
+        }
+    }
+
+    fn v5(&self) -> &BlockHeaderV5 {
+        match self {
+            Self::V5(header) => header,
+        }
+    }
+
+    #[inline]
+    pub fn hash(&self) -> &CryptoHash {
+        &self.v5().hash
+    }
+
+    #[inline]
+    pub fn prev_hash(&self) -> &CryptoHash {
+        &self.v5().prev_hash
+    }
+
+    #[inline]
+    pub fn height(&self) -> BlockHeight {
+        self.v5().inner_lite.height
+    }
+
+    #[inline]
+    pub fn epoch_id(&self) -> &EpochId {
+        &self.v5().inner_lite.epoch_id
+    }
+
+    #[inline]
+    pub fn next_epoch_id(&self) -> &EpochId {
+        &self.v5().inner_lite.next_epoch_id
+    }
+
+    #[inline]
+    pub fn prev_state_root(&self) -> &CryptoHash {
+        &self.v5().inner_lite.prev_state_root
+    }
+
+    #[inline]
+    pub fn prev_outcome_root(&self) -> &CryptoHash {
+        &self.v5().inner_lite.prev_outcome_root
+    }
+
+    #[inline]
+    pub fn timestamp(&self) -> u64 {
+        self.v5().inner_lite.timestamp
+    }
+
+    #[inline]
+    pub fn next_bp_hash(&self) -> &CryptoHash {
+        &self.v5().inner_lite.next_bp_hash
+    }
+
+    #[inline]
+    pub fn block_merkle_root(&self) -> &CryptoHash {
+        &self.v5().inner_lite.block_merkle_root
+    }
+
+    #[inline]
+    pub fn block_body_hash(&self) -> &CryptoHash {
+        &self.v5().inner_rest.block_body_hash
+    }
+
+    #[inline]
+    pub fn chunk_headers_root(&self) -> &CryptoHash {
+        &self.v5().inner_rest.chunk_headers_root
+    }
+
+    #[inline]
+    pub fn chunk_tx_root(&self) -> &CryptoHash {
+        &self.v5().inner_rest.chunk_tx_root
+    }
+
+    #[inline]
+    pub fn random_value(&self) -> &CryptoHash {
+        &self.v5().inner_rest.random_value
+    }
+
+    #[inline]
+    pub fn prev_validator_proposals(&self) -> &[ValidatorStake] {
+        &self.v5().inner_rest.prev_validator_proposals
+    }
+
+    #[inline]
+    pub fn chunk_mask(&self) -> &[bool] {
+        &self.v5().inner_rest.chunk_mask
+    }
+
+    pub fn challenges_result(&self) -> &ChallengesResult {
+        &self.v5().inner_rest.challenges_result
+    }
+
+    #[inline]
+    pub fn gas_price(&self) -> Balance {
+        self.v5().inner_rest.gas_price
+    }
+
+    #[inline]
+    pub fn total_supply(&self) -> Balance {
+        self.v5().inner_rest.total_supply
+    }
+
+    #[inline]
+    pub fn approvals(&self) -> &[Option<Box<Signature>>] {
+        &self.v5().inner_rest.approvals
+    }
+
+    #[inline]
+    pub fn latest_protocol_version(&self) -> ProtocolVersion {
+        self.v5().inner_rest.latest_protocol_version
+    }
+
+    #[inline]
+    pub fn chunk_endorsements(&self) -> &ChunkEndorsementsBitmap {
+        &self.v5().inner_rest.chunk_endorsements
+    }
+
+    #[inline]
+    pub fn signature(&self) -> &Signature {
+        &self.v5().signature
+    }
+
+    /// Verifies the producer signature over the header hash.
+    pub fn verify_block_producer(&self, public_key: &PublicKey) -> bool {
+        self.signature().verify(self.hash().as_ref(), public_key)
+    }
+
+    /// Checks that no field of the header is populated before the protocol
+    /// version that activates it.
+    ///
+    /// The borsh layout carries every field regardless of the epoch's
+    /// protocol version, so a header from an older protocol version arrives
+    /// with feature fields at their defaults; anything else means the
+    /// producer ran a feature ahead of its activation.
+    pub fn validate_for_protocol_version(
+        &self,
+        protocol_version: ProtocolVersion,
+    ) -> Result<(), HeaderValidationError> {
+        if protocol_version < CHALLENGES_RESULT_VERSION && !self.challenges_result().is_empty() {
+            return Err(HeaderValidationError::FieldBeforeFeature {
+                field: "challenges_result",
+                feature_version: CHALLENGES_RESULT_VERSION,
+                protocol_version,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that the carried hash matches the header contents.
+    pub fn check_hash(&self) -> bool {
+        let header = self.v5();
+        header.hash
+            == Self::compute_hash(&header.prev_hash, &header.inner_lite, &header.inner_rest)
+    }
+
+    /// Key-value pairs for structured log events about this header. The keys
+    /// are stable; dashboards filter on them.
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("height", self.height().to_string()),
+            ("hash", self.hash().short().to_string()),
+            ("prev_hash", self.prev_hash().short().to_string()),
+            ("epoch_id", self.epoch_id().to_string()),
+            ("chunks", self.chunk_mask().iter().filter(|mask| **mask).count().to_string()),
+            ("approvals", self.approvals().iter().filter(|a| a.is_some()).count().to_string()),
+        ]
+    }
+}
+
+/// `#H <short hash> (epoch: E, chunks: present/total, approvals: present/total)`:
+/// what a human scanning block production logs needs, nothing more.
+impl std::fmt::Display for BlockHeader {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "#{} {} (epoch: {}, chunks: {}/{}, approvals: {}/{})",
+            self.height(),
+            self.hash().short(),
+            self.epoch_id(),
+            self.chunk_mask().iter().filter(|mask| **mask).count(),
+            self.chunk_mask().len(),
+            self.approvals().iter().filter(|approval| approval.is_some()).count(),
+            self.approvals().len(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_crypto::{KeyType, SecretKey};
+
+    #[test]
+    #[should_panic(expected = "a present approval must be a real signature")]
+    fn test_header_construction_rejects_present_empty_approval() {
+        let inner_rest = BlockHeaderInnerRestV5 {
+            approvals: vec![None, Some(Box::new(Signature::default()))],
+            ..Default::default()
+        };
+        BlockHeader::new(
+            CryptoHash::default(),
+            BlockHeaderInnerLite::default(),
+            inner_rest,
+            Signature::default(),
+        );
+    }
+
+    #[test]
+    fn test_validate_for_protocol_version_gates_challenges_result() {
+        let inner_rest = BlockHeaderInnerRestV5 {
+            challenges_result: vec![crate::types::SlashedValidator::new(
+                "alice".parse().unwrap(),
+                true,
+            )],
+            ..Default::default()
+        };
+        let header = BlockHeader::new(
+            CryptoHash::default(),
+            BlockHeaderInnerLite::default(),
+            inner_rest,
+            Signature::default(),
+        );
+
+        // Valid at and after the activation version.
+        assert_eq!(header.validate_for_protocol_version(CHALLENGES_RESULT_VERSION), Ok(()));
+        // A challenges result before its feature version is rejected.
+        assert_eq!(
+            header.validate_for_protocol_version(CHALLENGES_RESULT_VERSION - 1),
+            Err(HeaderValidationError::FieldBeforeFeature {
+                field: "challenges_result",
+                feature_version: CHALLENGES_RESULT_VERSION,
+                protocol_version: CHALLENGES_RESULT_VERSION - 1,
+            })
+        );
+
+        // A header without the field is valid at any version.
+        let plain = BlockHeader::new(
+            CryptoHash::default(),
+            BlockHeaderInnerLite::default(),
+            BlockHeaderInnerRestV5::default(),
+            Signature::default(),
+        );
+        assert_eq!(plain.validate_for_protocol_version(0), Ok(()));
+    }
+
+    #[test]
+    fn test_display_and_fields() {
+        let signature = SecretKey::from_seed(KeyType::ED25519, "approver").sign(b"approval");
+        let inner_lite = BlockHeaderInnerLite {
+            height: 42,
+            epoch_id: EpochId(crate::hash::hash(b"epoch")),
+            ..Default::default()
+        };
+        let inner_rest = BlockHeaderInnerRestV5 {
+            chunk_mask: vec![true, false, true, true],
+            approvals: vec![Some(Box::new(signature)), None, None],
+            ..Default::default()
+        };
+        let header = BlockHeader::new(
+            CryptoHash::default(),
+            inner_lite,
+            inner_rest,
+            Signature::default(),
+        );
+
+        let short_hash = &header.hash().to_string()[..8];
+        let short_epoch = &header.epoch_id().0.to_string()[..8];
+        assert_eq!(
+            header.to_string(),
+            format!("#42 {short_hash} (epoch: {short_epoch}, chunks: 3/4, approvals: 1/3)"),
+        );
+
+        let fields = header.fields();
+        let get = |key| &fields.iter().find(|(k, _)| *k == key).unwrap().1;
+        assert_eq!(get("height"), "42");
+        assert_eq!(get("hash"), short_hash);
+        assert_eq!(get("epoch_id"), short_epoch);
+        assert_eq!(get("chunks"), "3");
+        assert_eq!(get("approvals"), "1");
+    }
+
+    #[test]
+    fn test_header_construction_accepts_real_and_missing_approvals() {
+        let signature = SecretKey::from_seed(KeyType::ED25519, "approver").sign(b"approval");
+        let inner_rest = BlockHeaderInnerRestV5 {
+            approvals: vec![None, Some(Box::new(signature))],
+            ..Default::default()
+        };
+        let header = BlockHeader::new(
+            CryptoHash::default(),
+            BlockHeaderInnerLite::default(),
+            inner_rest,
+            Signature::default(),
+        );
+        assert_eq!(header.approvals().len(), 2);
+        assert!(header.approvals()[0].is_none());
+    }
+}
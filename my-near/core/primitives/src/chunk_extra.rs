@@ -0,0 +1,36 @@
+use crate::hash::CryptoHash;
+use crate::types::Gas;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Information the chain keeps about an applied chunk: the outcome of
+/// applying the chunk to its shard, keyed by `(block hash, shard id)`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ChunkExtra {
+    V1(ChunkExtraV1),
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ChunkExtraV1 {
+    /// Root of the shard state after applying the chunk.
+    pub state_root: CryptoHash,
+    /// Gas burnt applying the chunk.
+    pub gas_used: Gas,
+}
+
+impl ChunkExtra {
+    pub fn new(state_root: CryptoHash, gas_used: Gas) -> Self {
+        Self::V1(ChunkExtraV1 { state_root, gas_used })
+    }
+
+    pub fn state_root(&self) -> &CryptoHash {
+        match self {
+            Self::V1(v1) => &v1.state_root,
+        }
+    }
+
+    pub fn gas_used(&self) -> Gas {
+        match self {
+            Self::V1(v1) => v1.gas_used,
+        }
+    }
+}
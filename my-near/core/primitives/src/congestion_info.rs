@@ -0,0 +1,835 @@
+use crate::types::{Gas, ShardId};
+use crate::views::CongestionInfoView;
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Stores the congestion level of a shard, carried in the chunk header and
+/// used by other shards to throttle traffic towards it.
+#[derive(
+    BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq,
+)]
+pub enum CongestionInfo {
+    V1(CongestionInfoV1),
+    V2(CongestionInfoV2),
+}
+
+impl Default for CongestionInfo {
+    fn default() -> Self {
+        Self::V1(CongestionInfoV1::default())
+    }
+}
+
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+)]
+pub struct CongestionInfoV1 {
+    /// Gas in the delayed receipts queue of this shard.
+    pub delayed_receipts_gas: u128,
+    /// Gas in the outgoing buffers of this shard, waiting for congested
+    /// receivers to accept them.
+    pub buffered_receipts_gas: u128,
+    /// Size of borsh serialized receipts stored in the state of this shard.
+    pub receipt_bytes: u64,
+    /// If fully congested, only this shard can forward receipts to us.
+    pub allowed_shard: u16,
+}
+
+/// Like [`CongestionInfoV1`], but with the bytes of the outgoing buffers
+/// tracked apart from the delayed receipt bytes, so outgoing memory pressure
+/// can be attributed to the congested receivers causing it.
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+)]
+pub struct CongestionInfoV2 {
+    /// Gas in the delayed receipts queue of this shard.
+    pub delayed_receipts_gas: u128,
+    /// Gas in the outgoing buffers of this shard, waiting for congested
+    /// receivers to accept them.
+    pub buffered_receipts_gas: u128,
+    /// Size of borsh serialized receipts stored in the state of this shard,
+    /// excluding the outgoing buffers.
+    pub receipt_bytes: u64,
+    /// Size of borsh serialized receipts in the outgoing buffers of this
+    /// shard.
+    pub buffered_receipt_bytes: u64,
+    /// If fully congested, only this shard can forward receipts to us.
+    pub allowed_shard: u16,
+}
+
+impl CongestionInfo {
+    /// A zeroed info of the newest version, for chains past the protocol
+    /// upgrade that splits the buffered bytes out.
+    pub fn new_v2() -> Self {
+        Self::V2(CongestionInfoV2::default())
+    }
+
+    pub fn delayed_receipts_gas(&self) -> u128 {
+        match self {
+            CongestionInfo::V1(inner) => inner.delayed_receipts_gas,
+            CongestionInfo::V2(inner) => inner.delayed_receipts_gas,
+        }
+    }
+
+    pub fn buffered_receipts_gas(&self) -> u128 {
+        match self {
+            CongestionInfo::V1(inner) => inner.buffered_receipts_gas,
+            CongestionInfo::V2(inner) => inner.buffered_receipts_gas,
+        }
+    }
+
+    /// All receipt bytes stored in the state of this shard; for V2 that is
+    /// the delayed and buffered bytes combined.
+    pub fn receipt_bytes(&self) -> u64 {
+        match self {
+            CongestionInfo::V1(inner) => inner.receipt_bytes,
+            CongestionInfo::V2(inner) => {
+                inner.receipt_bytes.saturating_add(inner.buffered_receipt_bytes)
+            }
+        }
+    }
+
+    /// The bytes of the outgoing buffers alone; V1 lumps them into
+    /// [`Self::receipt_bytes`] and reports zero here.
+    pub fn buffered_receipt_bytes(&self) -> u64 {
+        match self {
+            CongestionInfo::V1(_) => 0,
+            CongestionInfo::V2(inner) => inner.buffered_receipt_bytes,
+        }
+    }
+
+    pub fn allowed_shard(&self) -> u16 {
+        match self {
+            CongestionInfo::V1(inner) => inner.allowed_shard,
+            CongestionInfo::V2(inner) => inner.allowed_shard,
+        }
+    }
+
+    pub fn add_delayed_receipt_gas(&mut self, gas: Gas) {
+        match self {
+            CongestionInfo::V1(inner) => {
+                inner.delayed_receipts_gas =
+                    inner.delayed_receipts_gas.saturating_add(gas as u128);
+            }
+            CongestionInfo::V2(inner) => {
+                inner.delayed_receipts_gas =
+                    inner.delayed_receipts_gas.saturating_add(gas as u128);
+            }
+        }
+    }
+
+    pub fn add_buffered_receipt_gas(&mut self, gas: Gas) {
+        match self {
+            CongestionInfo::V1(inner) => {
+                inner.buffered_receipts_gas =
+                    inner.buffered_receipts_gas.saturating_add(gas as u128);
+            }
+            CongestionInfo::V2(inner) => {
+                inner.buffered_receipts_gas =
+                    inner.buffered_receipts_gas.saturating_add(gas as u128);
+            }
+        }
+    }
+
+    pub fn remove_delayed_receipt_gas(&mut self, gas: Gas) {
+        match self {
+            CongestionInfo::V1(inner) => {
+                inner.delayed_receipts_gas =
+                    inner.delayed_receipts_gas.saturating_sub(gas as u128);
+            }
+            CongestionInfo::V2(inner) => {
+                inner.delayed_receipts_gas =
+                    inner.delayed_receipts_gas.saturating_sub(gas as u128);
+            }
+        }
+    }
+
+    pub fn remove_buffered_receipt_gas(&mut self, gas: Gas) {
+        match self {
+            CongestionInfo::V1(inner) => {
+                inner.buffered_receipts_gas =
+                    inner.buffered_receipts_gas.saturating_sub(gas as u128);
+            }
+            CongestionInfo::V2(inner) => {
+                inner.buffered_receipts_gas =
+                    inner.buffered_receipts_gas.saturating_sub(gas as u128);
+            }
+        }
+    }
+
+    /// Accounts bytes added to the delayed receipts; V1 keeps a single lump
+    /// for all receipt bytes.
+    pub fn add_receipt_bytes(&mut self, bytes: u64) {
+        match self {
+            CongestionInfo::V1(inner) => {
+                inner.receipt_bytes = inner.receipt_bytes.saturating_add(bytes);
+            }
+            CongestionInfo::V2(inner) => {
+                inner.receipt_bytes = inner.receipt_bytes.saturating_add(bytes);
+            }
+        }
+    }
+
+    pub fn remove_receipt_bytes(&mut self, bytes: u64) {
+        match self {
+            CongestionInfo::V1(inner) => {
+                inner.receipt_bytes = inner.receipt_bytes.saturating_sub(bytes);
+            }
+            CongestionInfo::V2(inner) => {
+                inner.receipt_bytes = inner.receipt_bytes.saturating_sub(bytes);
+            }
+        }
+    }
+
+    /// Accounts bytes added to the outgoing buffers; V1 cannot tell them
+    /// apart and folds them into its single byte counter.
+    pub fn add_buffered_receipt_bytes(&mut self, bytes: u64) {
+        match self {
+            CongestionInfo::V1(inner) => {
+                inner.receipt_bytes = inner.receipt_bytes.saturating_add(bytes);
+            }
+            CongestionInfo::V2(inner) => {
+                inner.buffered_receipt_bytes =
+                    inner.buffered_receipt_bytes.saturating_add(bytes);
+            }
+        }
+    }
+
+    pub fn remove_buffered_receipt_bytes(&mut self, bytes: u64) {
+        match self {
+            CongestionInfo::V1(inner) => {
+                inner.receipt_bytes = inner.receipt_bytes.saturating_sub(bytes);
+            }
+            CongestionInfo::V2(inner) => {
+                inner.buffered_receipt_bytes =
+                    inner.buffered_receipt_bytes.saturating_sub(bytes);
+            }
+        }
+    }
+
+    pub fn set_allowed_shard(&mut self, shard_id: ShardId) {
+        match self {
+            CongestionInfo::V1(inner) => inner.allowed_shard = shard_id as u16,
+            CongestionInfo::V2(inner) => inner.allowed_shard = shard_id as u16,
+        }
+    }
+
+    /// Flattens this info into the RPC view; the byte counters are reported
+    /// combined, as in [`Self::receipt_bytes`].
+    pub fn to_view(&self) -> CongestionInfoView {
+        let version = match self {
+            CongestionInfo::V1(_) => 1,
+            CongestionInfo::V2(_) => 2,
+        };
+        CongestionInfoView {
+            version,
+            delayed_receipts_gas: self.delayed_receipts_gas().to_string(),
+            buffered_receipts_gas: self.buffered_receipts_gas().to_string(),
+            receipt_bytes: self.receipt_bytes(),
+            allowed_shard: self.allowed_shard(),
+        }
+    }
+}
+
+/// [`CongestionInfo`] of a shard together with bookkeeping the chain tracks
+/// on top of it.
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+)]
+pub struct ExtendedCongestionInfo {
+    pub congestion_info: CongestionInfo,
+    /// How many consecutive chunks this shard has missed; a missing chunk is
+    /// itself a sign of congestion.
+    pub missed_chunks_count: u64,
+}
+
+impl ExtendedCongestionInfo {
+    pub fn new(congestion_info: CongestionInfo, missed_chunks_count: u64) -> Self {
+        Self { congestion_info, missed_chunks_count }
+    }
+
+    /// A copy with `n` more missed chunks, for what-if analysis: schedulers
+    /// can probe how the congestion level would react to further misses
+    /// without touching the recorded state.
+    pub fn with_additional_missed_chunks(&self, n: u64) -> Self {
+        Self {
+            congestion_info: self.congestion_info,
+            missed_chunks_count: self.missed_chunks_count.saturating_add(n),
+        }
+    }
+}
+
+/// The congestion information of every shard at one block.
+#[derive(
+    BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq,
+)]
+#[serde(transparent)]
+pub struct BlockCongestionInfo {
+    shards_congestion_info: BTreeMap<ShardId, ExtendedCongestionInfo>,
+}
+
+impl BlockCongestionInfo {
+    pub fn new(shards_congestion_info: BTreeMap<ShardId, ExtendedCongestionInfo>) -> Self {
+        Self { shards_congestion_info }
+    }
+
+    pub fn get(&self, shard_id: &ShardId) -> Option<&ExtendedCongestionInfo> {
+        self.shards_congestion_info.get(shard_id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&ShardId, &ExtendedCongestionInfo)> {
+        self.shards_congestion_info.iter()
+    }
+
+    /// Pairs every shard's congestion information with the config, yielding
+    /// the [`CongestionControl`] of each shard in one pass.
+    pub fn congestion_controls(
+        &self,
+        config: &CongestionControlConfig,
+    ) -> BTreeMap<ShardId, CongestionControl> {
+        self.shards_congestion_info
+            .iter()
+            .map(|(shard_id, extended)| {
+                (
+                    *shard_id,
+                    CongestionControl::new(
+                        *config,
+                        extended.congestion_info,
+                        extended.missed_chunks_count,
+                    ),
+                )
+            })
+            .collect()
+    }
+}
+
+/// The limits at which a shard counts as fully congested.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CongestionControlConfig {
+    /// Delayed receipts gas at which incoming congestion reaches 1.
+    pub max_congestion_incoming_gas: Gas,
+    /// Buffered receipts gas at which outgoing congestion reaches 1.
+    pub max_congestion_outgoing_gas: Gas,
+    /// Stored receipt bytes at which memory congestion reaches 1.
+    pub max_congestion_memory_consumption: u64,
+    /// Missed chunk streak at which missed-chunk congestion reaches 1.
+    pub max_congestion_missed_chunks: u64,
+}
+
+impl Default for CongestionControlConfig {
+    fn default() -> Self {
+        Self {
+            max_congestion_incoming_gas: 20_000_000_000_000_000,
+            max_congestion_outgoing_gas: 10_000_000_000_000_000,
+            max_congestion_memory_consumption: 1000 * 1024 * 1024,
+            max_congestion_missed_chunks: 5,
+        }
+    }
+}
+
+/// A shard's congestion information interpreted under a config: answers how
+/// congested the shard is on a scale from 0 to 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CongestionControl {
+    config: CongestionControlConfig,
+    info: CongestionInfo,
+    missed_chunks_count: u64,
+}
+
+impl CongestionControl {
+    pub fn new(
+        config: CongestionControlConfig,
+        info: CongestionInfo,
+        missed_chunks_count: u64,
+    ) -> Self {
+        Self { config, info, missed_chunks_count }
+    }
+
+    pub fn congestion_info(&self) -> &CongestionInfo {
+        &self.info
+    }
+
+    /// The congestion level of the shard in `0.0..=1.0`: the worst of the
+    /// incoming, outgoing, memory and missed-chunk dimensions.
+    pub fn congestion_level(&self) -> f64 {
+        self.breakdown().overall
+    }
+
+    /// Whether the shard is in the red-light state: some dimension has hit
+    /// its limit and the shard stops accepting new transactions.
+    pub fn is_fully_congested(&self) -> bool {
+        self.congestion_level() >= 1.0
+    }
+
+    fn incoming_congestion(&self) -> f64 {
+        clamped_ratio(
+            self.info.delayed_receipts_gas(),
+            self.config.max_congestion_incoming_gas as u128,
+        )
+    }
+
+    fn outgoing_congestion(&self) -> f64 {
+        clamped_ratio(
+            self.info.buffered_receipts_gas(),
+            self.config.max_congestion_outgoing_gas as u128,
+        )
+    }
+
+    fn memory_congestion(&self) -> f64 {
+        clamped_ratio(
+            self.info.receipt_bytes() as u128,
+            self.config.max_congestion_memory_consumption as u128,
+        )
+    }
+
+    fn missed_chunks_congestion(&self) -> f64 {
+        clamped_ratio(
+            self.missed_chunks_count as u128,
+            self.config.max_congestion_missed_chunks as u128,
+        )
+    }
+
+    /// All four congestion components at once, computed in a single pass;
+    /// [`Self::congestion_level`] and
+    /// [`Self::shard_accepts_transactions`] both read the overall level off
+    /// this snapshot instead of re-deriving the components.
+    pub fn breakdown(&self) -> CongestionBreakdown {
+        let incoming = self.incoming_congestion();
+        let outgoing = self.outgoing_congestion();
+        let memory = self.memory_congestion();
+        let missed_chunks = self.missed_chunks_congestion();
+        let overall = incoming.max(outgoing).max(memory).max(missed_chunks);
+        CongestionBreakdown { incoming, outgoing, memory, missed_chunks, overall }
+    }
+
+    /// Whether the shard still accepts new transactions: it does until it is
+    /// fully congested, and the `No` answer provides the reason for extra
+    /// debugging information -- the dimension that hit its limit, ties
+    /// broken in incoming, outgoing, memory, missed-chunks order.
+    pub fn shard_accepts_transactions(&self) -> ShardAcceptsTransactions {
+        let breakdown = self.breakdown();
+        if breakdown.overall < 1.0 {
+            return ShardAcceptsTransactions::Yes;
+        }
+        let reason = if breakdown.incoming >= breakdown.overall {
+            RejectTransactionReason::IncomingCongestion { congestion_level: breakdown.incoming }
+        } else if breakdown.outgoing >= breakdown.overall {
+            RejectTransactionReason::OutgoingCongestion { congestion_level: breakdown.outgoing }
+        } else if breakdown.memory >= breakdown.overall {
+            RejectTransactionReason::MemoryCongestion { congestion_level: breakdown.memory }
+        } else {
+            RejectTransactionReason::MissedChunks { missed_chunks: self.missed_chunks_count }
+        };
+        ShardAcceptsTransactions::No(reason)
+    }
+}
+
+/// Whether a shard accepts new transactions; see
+/// [`CongestionControl::shard_accepts_transactions`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShardAcceptsTransactions {
+    Yes,
+    No(RejectTransactionReason),
+}
+
+/// Why a fully congested shard rejects new transactions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RejectTransactionReason {
+    IncomingCongestion { congestion_level: f64 },
+    OutgoingCongestion { congestion_level: f64 },
+    MemoryCongestion { congestion_level: f64 },
+    MissedChunks { missed_chunks: u64 },
+}
+
+impl RejectTransactionReason {
+    /// The congestion level behind the rejection; `None` for missed chunks,
+    /// which are counted rather than measured.
+    pub fn as_congestion_level(&self) -> Option<f64> {
+        match self {
+            Self::IncomingCongestion { congestion_level }
+            | Self::OutgoingCongestion { congestion_level }
+            | Self::MemoryCongestion { congestion_level } => Some(*congestion_level),
+            Self::MissedChunks { .. } => None,
+        }
+    }
+}
+
+impl std::fmt::Display for RejectTransactionReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IncomingCongestion { congestion_level } => {
+                write!(f, "incoming congestion {congestion_level:.2}")
+            }
+            Self::OutgoingCongestion { congestion_level } => {
+                write!(f, "outgoing congestion {congestion_level:.2}")
+            }
+            Self::MemoryCongestion { congestion_level } => {
+                write!(f, "memory congestion {congestion_level:.2}")
+            }
+            Self::MissedChunks { missed_chunks } => {
+                write!(f, "{missed_chunks} missed chunks")
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ShardAcceptsTransactions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Yes => write!(f, "accepts transactions"),
+            Self::No(reason) => write!(f, "rejected: {reason}"),
+        }
+    }
+}
+
+/// The per-dimension congestion levels behind a shard's overall level; see
+/// [`CongestionControl::breakdown`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct CongestionBreakdown {
+    pub incoming: f64,
+    pub outgoing: f64,
+    pub memory: f64,
+    pub missed_chunks: f64,
+    /// The maximum of the four components, i.e.
+    /// [`CongestionControl::congestion_level`].
+    pub overall: f64,
+}
+
+/// `value / max` clamped to `0.0..=1.0`; a zero `max` counts as fully
+/// congested as soon as there is any value at all.
+fn clamped_ratio(value: u128, max: u128) -> f64 {
+    if value == 0 {
+        return 0.0;
+    }
+    if value >= max {
+        return 1.0;
+    }
+    value as f64 / max as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_view_json_field_types() {
+        let info = CongestionInfo::V1(CongestionInfoV1 {
+            delayed_receipts_gas: u128::MAX,
+            buffered_receipts_gas: 42,
+            receipt_bytes: 4096,
+            allowed_shard: 3,
+        });
+        let json = serde_json::to_value(info.to_view()).unwrap();
+        assert!(json["delayed_receipts_gas"].is_string());
+        assert!(json["buffered_receipts_gas"].is_string());
+        assert!(json["receipt_bytes"].is_u64());
+        assert!(json["allowed_shard"].is_u64());
+        assert_eq!(json["version"], 1);
+        assert_eq!(json["delayed_receipts_gas"], u128::MAX.to_string());
+        assert_eq!(json["buffered_receipts_gas"], "42");
+    }
+
+    #[test]
+    fn test_v2_tracks_buffered_bytes_separately() {
+        let mut info = CongestionInfo::new_v2();
+        info.add_receipt_bytes(1000);
+        info.add_buffered_receipt_bytes(300);
+        assert_eq!(info.buffered_receipt_bytes(), 300);
+        // The combined accessor keeps feeding memory congestion the total.
+        assert_eq!(info.receipt_bytes(), 1300);
+
+        info.remove_buffered_receipt_bytes(100);
+        info.remove_receipt_bytes(500);
+        assert_eq!(info.buffered_receipt_bytes(), 200);
+        assert_eq!(info.receipt_bytes(), 700);
+
+        // The mutators saturate instead of wrapping.
+        info.add_buffered_receipt_bytes(u64::MAX);
+        assert_eq!(info.buffered_receipt_bytes(), u64::MAX);
+        info.remove_receipt_bytes(u64::MAX);
+        info.remove_receipt_bytes(1);
+        assert_eq!(info.receipt_bytes(), u64::MAX);
+
+        assert_eq!(info.to_view().version, 2);
+    }
+
+    #[test]
+    fn test_v1_folds_buffered_bytes_into_the_lump() {
+        let mut info = CongestionInfo::default();
+        info.add_receipt_bytes(1000);
+        info.add_buffered_receipt_bytes(300);
+        // V1 cannot attribute the buffered bytes, it only knows the total.
+        assert_eq!(info.buffered_receipt_bytes(), 0);
+        assert_eq!(info.receipt_bytes(), 1300);
+        info.remove_buffered_receipt_bytes(300);
+        assert_eq!(info.receipt_bytes(), 1000);
+    }
+
+    #[test]
+    fn test_simulated_missed_chunks_raise_congestion_monotonically() {
+        let config = CongestionControlConfig { max_congestion_missed_chunks: 8, ..Default::default() };
+        let extended = ExtendedCongestionInfo::new(CongestionInfo::default(), 1);
+
+        let level_of = |extended: ExtendedCongestionInfo| {
+            CongestionControl::new(config, extended.congestion_info, extended.missed_chunks_count)
+                .congestion_level()
+        };
+        let mut previous = level_of(extended);
+        for n in 1..=10 {
+            let level = level_of(extended.with_additional_missed_chunks(n));
+            assert!(level >= previous, "level must not drop as misses increase");
+            previous = level;
+        }
+        // The simulation is a copy; the original is untouched.
+        assert_eq!(extended.missed_chunks_count, 1);
+        assert_eq!(level_of(extended.with_additional_missed_chunks(3)), 0.5);
+        assert_eq!(level_of(extended.with_additional_missed_chunks(u64::MAX)), 1.0);
+    }
+
+    #[test]
+    fn test_congestion_controls_per_shard() {
+        let config = CongestionControlConfig {
+            max_congestion_incoming_gas: 1000,
+            max_congestion_outgoing_gas: 1000,
+            max_congestion_memory_consumption: 1000,
+            max_congestion_missed_chunks: 4,
+        };
+        let idle = ExtendedCongestionInfo::default();
+        let half_incoming = ExtendedCongestionInfo::new(
+            CongestionInfo::V1(CongestionInfoV1 { delayed_receipts_gas: 500, ..Default::default() }),
+            0,
+        );
+        let saturated_outgoing = ExtendedCongestionInfo::new(
+            CongestionInfo::V1(CongestionInfoV1 {
+                buffered_receipts_gas: 5000,
+                ..Default::default()
+            }),
+            0,
+        );
+        let missing_chunks = ExtendedCongestionInfo::new(CongestionInfo::default(), 1);
+        let block_info = BlockCongestionInfo::new(BTreeMap::from([
+            (0, idle),
+            (1, half_incoming),
+            (2, saturated_outgoing),
+            (3, missing_chunks),
+        ]));
+
+        let controls = block_info.congestion_controls(&config);
+        assert_eq!(controls.len(), 4);
+        assert_eq!(controls[&0].congestion_level(), 0.0);
+        assert_eq!(controls[&1].congestion_level(), 0.5);
+        // Saturation clamps at 1 rather than extrapolating beyond it.
+        assert_eq!(controls[&2].congestion_level(), 1.0);
+        assert_eq!(controls[&3].congestion_level(), 0.25);
+    }
+
+    #[test]
+    fn test_breakdown_exposes_every_component() {
+        let config = CongestionControlConfig {
+            max_congestion_incoming_gas: 1000,
+            max_congestion_outgoing_gas: 1000,
+            max_congestion_memory_consumption: 1000,
+            max_congestion_missed_chunks: 4,
+        };
+        let info = CongestionInfo::V1(CongestionInfoV1 {
+            delayed_receipts_gas: 250,
+            buffered_receipts_gas: 500,
+            receipt_bytes: 750,
+            allowed_shard: 0,
+        });
+        let control = CongestionControl::new(config, info, 1);
+
+        let breakdown = control.breakdown();
+        assert_eq!(
+            breakdown,
+            CongestionBreakdown {
+                incoming: 0.25,
+                outgoing: 0.5,
+                memory: 0.75,
+                missed_chunks: 0.25,
+                overall: 0.75,
+            }
+        );
+        // The overall level is the worst component, exactly as
+        // `congestion_level` reports it.
+        let components =
+            [breakdown.incoming, breakdown.outgoing, breakdown.memory, breakdown.missed_chunks];
+        assert_eq!(breakdown.overall, components.into_iter().fold(0.0, f64::max));
+        assert_eq!(breakdown.overall, control.congestion_level());
+
+        // Serializes to plain numbers for log and RPC consumers.
+        assert_eq!(
+            serde_json::to_value(breakdown).unwrap(),
+            serde_json::json!({
+                "incoming": 0.25,
+                "outgoing": 0.5,
+                "memory": 0.75,
+                "missed_chunks": 0.25,
+                "overall": 0.75,
+            })
+        );
+    }
+
+    #[test]
+    fn test_is_fully_congested_matches_the_snapshot_maximum() {
+        let config = CongestionControlConfig {
+            max_congestion_incoming_gas: 100,
+            max_congestion_outgoing_gas: 100,
+            max_congestion_memory_consumption: 100,
+            max_congestion_missed_chunks: 4,
+        };
+        for (gas, missed) in [(0, 0), (50, 1), (99, 3), (100, 0), (0, 4), (500, 10)] {
+            let mut info = CongestionInfo::default();
+            info.add_delayed_receipt_gas(gas);
+            let control = CongestionControl::new(config, info, missed);
+            let breakdown = control.breakdown();
+            let components = [
+                breakdown.incoming,
+                breakdown.outgoing,
+                breakdown.memory,
+                breakdown.missed_chunks,
+            ];
+            assert_eq!(breakdown.overall, components.into_iter().fold(0.0, f64::max));
+            assert_eq!(breakdown.overall, control.congestion_level());
+            assert_eq!(
+                control.is_fully_congested(),
+                control.congestion_level() == 1.0,
+                "gas {gas}, missed {missed}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_reject_transaction_reason_messages() {
+        let incoming = RejectTransactionReason::IncomingCongestion { congestion_level: 0.83 };
+        assert_eq!(incoming.to_string(), "incoming congestion 0.83");
+        assert_eq!(incoming.as_congestion_level(), Some(0.83));
+
+        let outgoing = RejectTransactionReason::OutgoingCongestion { congestion_level: 1.0 };
+        assert_eq!(outgoing.to_string(), "outgoing congestion 1.00");
+        assert_eq!(outgoing.as_congestion_level(), Some(1.0));
+
+        let memory = RejectTransactionReason::MemoryCongestion { congestion_level: 0.5 };
+        assert_eq!(memory.to_string(), "memory congestion 0.50");
+        assert_eq!(memory.as_congestion_level(), Some(0.5));
+
+        // Missed chunks are a count, not a level.
+        let missed = RejectTransactionReason::MissedChunks { missed_chunks: 7 };
+        assert_eq!(missed.to_string(), "7 missed chunks");
+        assert_eq!(missed.as_congestion_level(), None);
+
+        assert_eq!(ShardAcceptsTransactions::Yes.to_string(), "accepts transactions");
+        assert_eq!(
+            ShardAcceptsTransactions::No(incoming).to_string(),
+            "rejected: incoming congestion 0.83"
+        );
+    }
+
+    #[test]
+    fn test_shard_accepts_transactions_until_fully_congested() {
+        let config = CongestionControlConfig {
+            max_congestion_incoming_gas: 100,
+            max_congestion_outgoing_gas: 100,
+            max_congestion_memory_consumption: 100,
+            max_congestion_missed_chunks: 4,
+        };
+        let mut info = CongestionInfo::default();
+        info.add_delayed_receipt_gas(99);
+
+        // Below every limit the shard accepts transactions.
+        let control = CongestionControl::new(config, info, 0);
+        assert_eq!(control.shard_accepts_transactions(), ShardAcceptsTransactions::Yes);
+
+        // The dimension that hit its limit names the rejection.
+        info.add_delayed_receipt_gas(1);
+        let control = CongestionControl::new(config, info, 0);
+        assert_eq!(
+            control.shard_accepts_transactions(),
+            ShardAcceptsTransactions::No(RejectTransactionReason::IncomingCongestion {
+                congestion_level: 1.0
+            })
+        );
+
+        let control = CongestionControl::new(config, CongestionInfo::default(), 4);
+        assert_eq!(
+            control.shard_accepts_transactions(),
+            ShardAcceptsTransactions::No(RejectTransactionReason::MissedChunks {
+                missed_chunks: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_block_congestion_info_json_round_trip() {
+        let shards_congestion_info = BTreeMap::from([
+            (
+                0,
+                ExtendedCongestionInfo {
+                    congestion_info: CongestionInfo::V1(CongestionInfoV1 {
+                        delayed_receipts_gas: 100,
+                        buffered_receipts_gas: 200,
+                        receipt_bytes: 300,
+                        allowed_shard: 1,
+                    }),
+                    missed_chunks_count: 0,
+                },
+            ),
+            (
+                1,
+                ExtendedCongestionInfo {
+                    congestion_info: CongestionInfo::V2(CongestionInfoV2 {
+                        delayed_receipts_gas: 400,
+                        buffered_receipts_gas: 500,
+                        receipt_bytes: 600,
+                        buffered_receipt_bytes: 70,
+                        allowed_shard: 0,
+                    }),
+                    missed_chunks_count: 3,
+                },
+            ),
+            (2, ExtendedCongestionInfo::default()),
+        ]);
+        let info = BlockCongestionInfo::new(shards_congestion_info);
+
+        let json = serde_json::to_value(&info).unwrap();
+        // `BlockCongestionInfo` is transparent: the JSON top level is the
+        // shard map itself, keyed by shard id, not a wrapper object.
+        let map = json.as_object().unwrap();
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["0", "1", "2"]);
+        assert!(map["1"]["congestion_info"]["V2"].is_object());
+        assert_eq!(map["1"]["missed_chunks_count"], 3);
+
+        let deserialized: BlockCongestionInfo = serde_json::from_value(json).unwrap();
+        assert_eq!(deserialized, info);
+        for shard_id in [0, 1, 2] {
+            assert_eq!(deserialized.get(&shard_id), info.get(&shard_id));
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use crate::hash::CryptoHash;
+use crate::block_header::BlockHeader;
+use crate::types::{Balance, BlockHeight, EpochId, ProtocolVersion, SlashedValidator, ValidatorStake};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Information per block that the epoch manager needs to do its bookkeeping.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum BlockInfo {
+    V1(Box<BlockInfoV1>),
+}
+
+impl Default for BlockInfo {
+    fn default() -> Self {
+        Self::V1(Box::default())
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlockInfoV1 {
+    pub hash: CryptoHash,
+    pub height: BlockHeight,
+    pub last_finalized_height: BlockHeight,
+    pub last_final_block_hash: CryptoHash,
+    pub prev_hash: CryptoHash,
+    /// Hash of the first block of the epoch this block belongs to.
+    pub epoch_first_block: CryptoHash,
+    pub epoch_id: EpochId,
+    /// Validator proposals from this block.
+    pub proposals: Vec<ValidatorStake>,
+    /// Mask of which chunks were present in this block.
+    pub chunk_mask: Vec<bool>,
+    /// Validators slashed by the challenges included in this block.
+    pub slashed: Vec<SlashedValidator>,
+    /// Latest protocol version this validator observes.
+    pub latest_protocol_version: ProtocolVersion,
+    pub total_supply: Balance,
+    pub timestamp_nanosec: u64,
+}
+
+impl BlockInfo {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        hash: CryptoHash,
+        height: BlockHeight,
+        last_finalized_height: BlockHeight,
+        last_final_block_hash: CryptoHash,
+        prev_hash: CryptoHash,
+        proposals: Vec<ValidatorStake>,
+        chunk_mask: Vec<bool>,
+        slashed: Vec<SlashedValidator>,
+        latest_protocol_version: ProtocolVersion,
+        total_supply: Balance,
+        timestamp_nanosec: u64,
+    ) -> Self {
+        Self::V1(Box::new(BlockInfoV1 {
+            hash,
+            height,
+            last_finalized_height,
+            last_final_block_hash,
+            prev_hash,
+            epoch_first_block: CryptoHash::default(),
+            epoch_id: EpochId::default(),
+            proposals,
+            chunk_mask,
+            slashed,
+            latest_protocol_version,
+            total_supply,
+            timestamp_nanosec,
+        }))
+    }
+
+    /// Captures the bookkeeping the epoch manager needs from a processed
+    /// block header, including the validators its challenges slashed.
+    pub fn from_header(header: &BlockHeader, last_finalized_height: BlockHeight) -> Self {
+        let mut block_info = Self::new(
+            *header.hash(),
+            header.height(),
+            last_finalized_height,
+            CryptoHash::default(),
+            *header.prev_hash(),
+            header.prev_validator_proposals().to_vec(),
+            header.chunk_mask().to_vec(),
+            header.challenges_result().clone(),
+            header.latest_protocol_version(),
+            header.total_supply(),
+            header.timestamp(),
+        );
+        *block_info.epoch_id_mut() = *header.epoch_id();
+        block_info
+    }
+
+    fn v1(&self) -> &BlockInfoV1 {
+        match self {
+            Self::V1(v1) => v1,
+        }
+    }
+
+    fn v1_mut(&mut self) -> &mut BlockInfoV1 {
+        match self {
+            Self::V1(v1) => v1,
+        }
+    }
+
+    #[inline]
+    pub fn hash(&self) -> &CryptoHash {
+        &self.v1().hash
+    }
+
+    #[inline]
+    pub fn height(&self) -> BlockHeight {
+        self.v1().height
+    }
+
+    #[inline]
+    pub fn last_finalized_height(&self) -> BlockHeight {
+        self.v1().last_finalized_height
+    }
+
+    #[inline]
+    pub fn last_final_block_hash(&self) -> &CryptoHash {
+        &self.v1().last_final_block_hash
+    }
+
+    #[inline]
+    pub fn prev_hash(&self) -> &CryptoHash {
+        &self.v1().prev_hash
+    }
+
+    #[inline]
+    pub fn epoch_first_block(&self) -> &CryptoHash {
+        &self.v1().epoch_first_block
+    }
+
+    #[inline]
+    pub fn epoch_first_block_mut(&mut self) -> &mut CryptoHash {
+        &mut self.v1_mut().epoch_first_block
+    }
+
+    #[inline]
+    pub fn epoch_id(&self) -> &EpochId {
+        &self.v1().epoch_id
+    }
+
+    #[inline]
+    pub fn epoch_id_mut(&mut self) -> &mut EpochId {
+        &mut self.v1_mut().epoch_id
+    }
+
+    #[inline]
+    pub fn proposals(&self) -> &[ValidatorStake] {
+        &self.v1().proposals
+    }
+
+    #[inline]
+    pub fn chunk_mask(&self) -> &[bool] {
+        &self.v1().chunk_mask
+    }
+
+    #[inline]
+    pub fn slashed(&self) -> &[SlashedValidator] {
+        &self.v1().slashed
+    }
+
+    #[inline]
+    pub fn latest_protocol_version(&self) -> ProtocolVersion {
+        self.v1().latest_protocol_version
+    }
+
+    #[inline]
+    pub fn total_supply(&self) -> Balance {
+        self.v1().total_supply
+    }
+
+    #[inline]
+    pub fn timestamp_nanosec(&self) -> u64 {
+        self.v1().timestamp_nanosec
+    }
+
+    /// Whether this block starts a new epoch, i.e. it is its own epoch's
+    /// first block.
+    pub fn is_epoch_start(&self) -> bool {
+        self.v1().epoch_first_block == self.v1().hash
+    }
+}
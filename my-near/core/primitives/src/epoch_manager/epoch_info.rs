@@ -0,0 +1,394 @@
+use crate::types::{
+    AccountId, Balance, EpochHeight, ProtocolVersion, RngSeed, ValidatorId,
+    ValidatorKickoutReason, ValidatorStake,
+};
+use crate::hash::CryptoHash;
+use crate::views::{EpochInfoView, ValidatorInfoView, ValidatorKickoutView};
+use borsh::{BorshDeserialize, BorshSerialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Information per epoch.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum EpochInfo {
+    V1(EpochInfoV1),
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EpochInfoV1 {
+    /// Ordinal of given epoch from genesis.
+    pub epoch_height: EpochHeight,
+    /// List of current validators.
+    pub validators: Vec<ValidatorStake>,
+    /// Validator account id to index in the `validators` list.
+    pub validator_to_index: HashMap<AccountId, ValidatorId>,
+    /// Settlement of validators responsible for block production.
+    pub block_producers_settlement: Vec<ValidatorId>,
+    /// Per-shard settlement of validators responsible for chunk production.
+    pub chunk_producers_settlement: Vec<Vec<ValidatorId>>,
+    /// Expected stake change at the end of the epoch.
+    pub stake_change: BTreeMap<AccountId, Balance>,
+    /// Total minted tokens in the epoch.
+    pub minted_amount: Balance,
+    /// Seat price of this epoch.
+    pub seat_price: Balance,
+    /// Validators kicked out during the epoch transition that produced this
+    /// epoch, with the reason.
+    pub validator_kickout: BTreeMap<AccountId, ValidatorKickoutReason>,
+    /// Protocol version this epoch runs.
+    pub protocol_version: ProtocolVersion,
+    /// Seed for the validator samplers.
+    pub rng_seed: RngSeed,
+}
+
+impl EpochInfo {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        epoch_height: EpochHeight,
+        validators: Vec<ValidatorStake>,
+        validator_to_index: HashMap<AccountId, ValidatorId>,
+        block_producers_settlement: Vec<ValidatorId>,
+        chunk_producers_settlement: Vec<Vec<ValidatorId>>,
+        stake_change: BTreeMap<AccountId, Balance>,
+        minted_amount: Balance,
+        seat_price: Balance,
+        validator_kickout: BTreeMap<AccountId, ValidatorKickoutReason>,
+        protocol_version: ProtocolVersion,
+        rng_seed: RngSeed,
+    ) -> Self {
+        Self::V1(EpochInfoV1 {
+            epoch_height,
+            validators,
+            validator_to_index,
+            block_producers_settlement,
+            chunk_producers_settlement,
+            stake_change,
+            minted_amount,
+            seat_price,
+            validator_kickout,
+            protocol_version,
+            rng_seed,
+        })
+    }
+
+    /// Equality on the source fields only: the inputs an epoch is built
+    /// from, not caches derived from them.
+    ///
+    /// Derived structures -- e.g. stake-weighted samplers, which are pure
+    /// functions of the stakes -- may differ in internal representation
+    /// between otherwise identical epochs, so migration and diff tests
+    /// should compare with this instead of `PartialEq`.
+    pub fn semantic_eq(&self, other: &EpochInfo) -> bool {
+        let (Self::V1(this), Self::V1(other)) = (self, other);
+        this.epoch_height == other.epoch_height
+            && this.validators == other.validators
+            && this.validator_to_index == other.validator_to_index
+            && this.block_producers_settlement == other.block_producers_settlement
+            && this.chunk_producers_settlement == other.chunk_producers_settlement
+            && this.stake_change == other.stake_change
+            && this.minted_amount == other.minted_amount
+            && this.seat_price == other.seat_price
+            && this.validator_kickout == other.validator_kickout
+            && this.protocol_version == other.protocol_version
+            && this.rng_seed == other.rng_seed
+    }
+
+    #[inline]
+    pub fn epoch_height(&self) -> EpochHeight {
+        match self {
+            Self::V1(v1) => v1.epoch_height,
+        }
+    }
+
+    #[inline]
+    pub fn protocol_version(&self) -> ProtocolVersion {
+        match self {
+            Self::V1(v1) => v1.protocol_version,
+        }
+    }
+
+    #[inline]
+    pub fn validators(&self) -> &[ValidatorStake] {
+        match self {
+            Self::V1(v1) => &v1.validators,
+        }
+    }
+
+    pub fn get_validator(&self, validator_id: ValidatorId) -> Option<&ValidatorStake> {
+        self.validators().get(validator_id as usize)
+    }
+
+    pub fn get_validator_id(&self, account_id: &AccountId) -> Option<ValidatorId> {
+        match self {
+            Self::V1(v1) => v1.validator_to_index.get(account_id).copied(),
+        }
+    }
+
+    pub fn get_validator_by_account(&self, account_id: &AccountId) -> Option<&ValidatorStake> {
+        self.get_validator_id(account_id).and_then(|id| self.get_validator(id))
+    }
+
+    pub fn account_is_validator(&self, account_id: &AccountId) -> bool {
+        self.get_validator_id(account_id).is_some()
+    }
+
+    #[inline]
+    pub fn block_producers_settlement(&self) -> &[ValidatorId] {
+        match self {
+            Self::V1(v1) => &v1.block_producers_settlement,
+        }
+    }
+
+    #[inline]
+    pub fn chunk_producers_settlement(&self) -> &[Vec<ValidatorId>] {
+        match self {
+            Self::V1(v1) => &v1.chunk_producers_settlement,
+        }
+    }
+
+    #[inline]
+    pub fn stake_change(&self) -> &BTreeMap<AccountId, Balance> {
+        match self {
+            Self::V1(v1) => &v1.stake_change,
+        }
+    }
+
+    #[inline]
+    pub fn minted_amount(&self) -> Balance {
+        match self {
+            Self::V1(v1) => v1.minted_amount,
+        }
+    }
+
+    #[inline]
+    pub fn seat_price(&self) -> Balance {
+        match self {
+            Self::V1(v1) => v1.seat_price,
+        }
+    }
+
+    #[inline]
+    pub fn validator_kickout(&self) -> &BTreeMap<AccountId, ValidatorKickoutReason> {
+        match self {
+            Self::V1(v1) => &v1.validator_kickout,
+        }
+    }
+
+    #[inline]
+    pub fn rng_seed(&self) -> RngSeed {
+        match self {
+            Self::V1(v1) => v1.rng_seed,
+        }
+    }
+
+    /// Canonical hash of this epoch's transition data, for the
+    /// `epoch_sync_data_hash` field of block headers. Light clients compare
+    /// it against the hash of the sync data they received, so every node
+    /// must serialize the same layout: the borsh serialization of the tuple
+    ///
+    /// `(epoch_height, validators, block_producers_settlement,
+    /// chunk_producers_settlement, protocol_version, rng_seed,
+    /// prev_epoch_first_block)`
+    ///
+    /// in that order, where `prev_epoch_first_block` is the hash of the
+    /// first block of the previous epoch. Local bookkeeping such as
+    /// `stake_change` and `validator_kickout` is deliberately excluded: it
+    /// is derivable and not needed to validate the new validator set.
+    pub fn sync_data_hash(&self, prev_epoch_first_block: &CryptoHash) -> CryptoHash {
+        CryptoHash::hash_borsh(&(
+            self.epoch_height(),
+            self.validators(),
+            self.block_producers_settlement(),
+            self.chunk_producers_settlement(),
+            self.protocol_version(),
+            self.rng_seed(),
+            prev_epoch_first_block,
+        ))
+    }
+
+    /// Flattens this info into the RPC view, omitting sampling internals like
+    /// `rng_seed` and the settlements.
+    pub fn to_view(&self) -> EpochInfoView {
+        EpochInfoView {
+            epoch_height: self.epoch_height(),
+            validators: self
+                .validators()
+                .iter()
+                .map(|validator| ValidatorInfoView {
+                    account_id: validator.account_id().to_string(),
+                    stake: validator.stake().to_string(),
+                })
+                .collect(),
+            seat_price: self.seat_price().to_string(),
+            minted_amount: self.minted_amount().to_string(),
+            protocol_version: self.protocol_version(),
+            kickouts: self
+                .validator_kickout()
+                .iter()
+                .map(|(account_id, reason)| ValidatorKickoutView {
+                    account_id: account_id.to_string(),
+                    reason: reason.to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Ways a stored [`EpochInfo`] can be internally inconsistent.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum EpochInfoInconsistency {
+    #[error("expected {expected} per-shard chunk producer settlements, found {found}")]
+    WrongShardCount { expected: u64, found: u64 },
+    #[error("shard {shard_id} has no chunk producers")]
+    EmptyShardSettlement { shard_id: u64 },
+    #[error("validator id {id} is out of range, only {num_validators} validators")]
+    ValidatorIdOutOfRange { id: ValidatorId, num_validators: usize },
+    #[error("block producer settlement is empty")]
+    EmptyBlockProducerSettlement,
+}
+
+impl EpochInfo {
+    /// Checks that the settlements of this epoch info are consistent with the
+    /// validator set and the expected shard count.
+    ///
+    /// A corrupted or hand-edited store can carry settlements referencing
+    /// out-of-range validator ids or missing shards; catching that when the
+    /// info is loaded turns a panic deep in sampling into a descriptive
+    /// error.
+    pub fn validate_settlements(
+        &self,
+        num_shards: crate::types::NumShards,
+    ) -> Result<(), EpochInfoInconsistency> {
+        let num_validators = self.validators().len();
+        if self.block_producers_settlement().is_empty() {
+            return Err(EpochInfoInconsistency::EmptyBlockProducerSettlement);
+        }
+        for id in self.block_producers_settlement() {
+            if *id as usize >= num_validators {
+                return Err(EpochInfoInconsistency::ValidatorIdOutOfRange {
+                    id: *id,
+                    num_validators,
+                });
+            }
+        }
+        let settlements = self.chunk_producers_settlement();
+        if settlements.len() as u64 != num_shards {
+            return Err(EpochInfoInconsistency::WrongShardCount {
+                expected: num_shards,
+                found: settlements.len() as u64,
+            });
+        }
+        for (shard_id, settlement) in settlements.iter().enumerate() {
+            if settlement.is_empty() {
+                return Err(EpochInfoInconsistency::EmptyShardSettlement {
+                    shard_id: shard_id as u64,
+                });
+            }
+            for id in settlement {
+                if *id as usize >= num_validators {
+                    return Err(EpochInfoInconsistency::ValidatorIdOutOfRange {
+                        id: *id,
+                        num_validators,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_crypto::{KeyType, SecretKey};
+
+    #[test]
+    fn test_to_view_omits_sampling_internals() {
+        let account_id: AccountId = "alice".parse().unwrap();
+        let public_key = SecretKey::from_seed(KeyType::ED25519, account_id.as_str()).public_key();
+        let kicked: AccountId = "bob".parse().unwrap();
+        let epoch_info = EpochInfo::new(
+            7,
+            vec![ValidatorStake::new(account_id.clone(), public_key, u128::MAX)],
+            [(account_id, 0)].into_iter().collect(),
+            vec![0],
+            vec![vec![0]],
+            BTreeMap::new(),
+            100,
+            50,
+            [(kicked, ValidatorKickoutReason::Unstaked)].into_iter().collect(),
+            1,
+            [1; 32],
+        );
+        let json = serde_json::to_value(epoch_info.to_view()).unwrap();
+        assert_eq!(json["epoch_height"], 7);
+        assert_eq!(json["protocol_version"], 1);
+        assert_eq!(json["seat_price"], "50");
+        assert_eq!(json["minted_amount"], "100");
+        assert_eq!(json["validators"][0]["account_id"], "alice");
+        assert_eq!(json["validators"][0]["stake"], u128::MAX.to_string());
+        assert_eq!(json["kickouts"][0]["account_id"], "bob");
+        assert_eq!(json["kickouts"][0]["reason"], "unstaked");
+        for omitted in [
+            "rng_seed",
+            "block_producers_settlement",
+            "chunk_producers_settlement",
+            "validator_to_index",
+            "stake_change",
+        ] {
+            assert!(json.get(omitted).is_none(), "{omitted} must not be exposed");
+        }
+    }
+
+    #[test]
+    fn test_semantic_eq_compares_source_fields() {
+        let build = |rng_seed| {
+            let account_id: AccountId = "alice".parse().unwrap();
+            let public_key =
+                SecretKey::from_seed(KeyType::ED25519, account_id.as_str()).public_key();
+            EpochInfo::new(
+                7,
+                vec![ValidatorStake::new(account_id.clone(), public_key, 100)],
+                [(account_id, 0)].into_iter().collect(),
+                vec![0],
+                vec![vec![0]],
+                BTreeMap::new(),
+                100,
+                50,
+                BTreeMap::new(),
+                1,
+                rng_seed,
+            )
+        };
+        // Two epochs built from the same inputs are semantically equal.
+        assert!(build([1; 32]).semantic_eq(&build([1; 32])));
+        // A changed source field is not.
+        assert!(!build([1; 32]).semantic_eq(&build([2; 32])));
+    }
+
+    #[test]
+    fn test_sync_data_hash_is_stable() {
+        let account_id: AccountId = "alice".parse().unwrap();
+        let public_key = SecretKey::from_seed(KeyType::ED25519, account_id.as_str()).public_key();
+        let epoch_info = EpochInfo::new(
+            7,
+            vec![ValidatorStake::new(account_id.clone(), public_key, 100)],
+            [(account_id, 0)].into_iter().collect(),
+            vec![0],
+            vec![vec![0]],
+            BTreeMap::new(),
+            100,
+            50,
+            BTreeMap::new(),
+            1,
+            [1; 32],
+        );
+        let boundary = crate::hash::hash(b"first block of previous epoch");
+        let sync_data_hash = epoch_info.sync_data_hash(&boundary);
+        // Pinned: nodes across the network must agree on this hash, so a
+        // change to the serialized layout must be deliberate and versioned.
+        assert_eq!(sync_data_hash.to_string(), "5FbsBDXAA5nkZKBQJ65WdJ2sZnGx8o4LLtvNHsNKcV3Q");
+        // The boundary block is part of the hashed data.
+        let other_boundary = crate::hash::hash(b"other block");
+        assert_ne!(sync_data_hash, epoch_info.sync_data_hash(&other_boundary));
+    }
+}
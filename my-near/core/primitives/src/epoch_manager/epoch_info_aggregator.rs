@@ -0,0 +1,84 @@
+use crate::epoch_manager::block_info::BlockInfo;
+use crate::epoch_manager::epoch_info::EpochInfo;
+use crate::hash::CryptoHash;
+use crate::types::{AccountId, BlockHeight, EpochId, ProtocolVersion, ValidatorId, ValidatorStake};
+use borsh::{BorshDeserialize, BorshSerialize};
+use std::collections::BTreeMap;
+
+/// How often one validator produced a block against how often it was
+/// expected to, within one epoch.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ValidatorProductionTally {
+    pub produced: u64,
+    pub expected: u64,
+}
+
+/// Running statistics of one epoch, accumulated block by block instead of
+/// re-walking the whole epoch on every query: who produced how many blocks,
+/// what everyone proposed to stake and which protocol versions the
+/// producers announced.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct EpochInfoAggregator {
+    /// Per-validator block production in the epoch so far.
+    pub block_tracker: BTreeMap<ValidatorId, ValidatorProductionTally>,
+    /// The latest stake proposal of each account in the epoch so far.
+    pub all_proposals: BTreeMap<AccountId, ValidatorStake>,
+    /// The protocol version each block producer last announced.
+    pub version_tracker: BTreeMap<ValidatorId, ProtocolVersion>,
+    /// The epoch the statistics belong to.
+    pub epoch_id: EpochId,
+    /// The last block whose statistics are merged in.
+    pub last_block_hash: CryptoHash,
+}
+
+impl EpochInfoAggregator {
+    pub fn new(epoch_id: EpochId, last_block_hash: CryptoHash) -> Self {
+        Self { epoch_id, last_block_hash, ..Self::default() }
+    }
+
+    /// Merges one block's contribution: production statistics for every
+    /// height since the previous block -- the skipped heights count against
+    /// the producers expected at them -- plus the block's stake proposals
+    /// and its producer's protocol version vote. `prev_block_height` is
+    /// `None` when the block has no recorded predecessor, in which case only
+    /// the block's own height is tallied.
+    pub fn update_tally(
+        &mut self,
+        epoch_info: &EpochInfo,
+        block_info: &BlockInfo,
+        prev_block_height: Option<BlockHeight>,
+    ) {
+        let settlement = epoch_info.block_producers_settlement();
+        let start_height = match prev_block_height {
+            Some(height) => height + 1,
+            None => block_info.height(),
+        };
+        for height in start_height..=block_info.height() {
+            let producer_id = settlement[(height % settlement.len() as u64) as usize];
+            let tally = self.block_tracker.entry(producer_id).or_default();
+            tally.expected += 1;
+            if height == block_info.height() {
+                tally.produced += 1;
+                self.version_tracker.insert(producer_id, block_info.latest_protocol_version());
+            }
+        }
+        for proposal in block_info.proposals() {
+            self.all_proposals.insert(proposal.account_id().clone(), proposal.clone());
+        }
+        self.last_block_hash = *block_info.hash();
+    }
+
+    /// Merges a newer aggregator of the same epoch into this one; the
+    /// newer's proposals and version votes win where they overlap.
+    pub fn merge(&mut self, newer: EpochInfoAggregator) {
+        debug_assert_eq!(self.epoch_id, newer.epoch_id);
+        for (producer_id, tally) in newer.block_tracker {
+            let merged = self.block_tracker.entry(producer_id).or_default();
+            merged.produced += tally.produced;
+            merged.expected += tally.expected;
+        }
+        self.all_proposals.extend(newer.all_proposals);
+        self.version_tracker.extend(newer.version_tracker);
+        self.last_block_hash = newer.last_block_hash;
+    }
+}
@@ -0,0 +1,3 @@
+pub mod block_info;
+pub mod epoch_info;
+pub mod epoch_info_aggregator;
@@ -0,0 +1,52 @@
+use crate::hash::CryptoHash;
+use crate::types::{AccountId, EpochId};
+
+/// Errors raised when working with epochs and the epoch manager.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum EpochError {
+    /// The requested epoch is not in the range of epochs this node knows
+    /// about.
+    #[error("epoch {0:?} is out of bounds")]
+    EpochOutOfBounds(EpochId),
+    /// The information for the requested epoch existed once but has been
+    /// garbage collected.
+    #[error("information for epoch {0:?} has been garbage collected")]
+    EpochGarbageCollected(EpochId),
+    /// A block required to answer the query is missing.
+    #[error("block {0} is missing")]
+    MissingBlock(CryptoHash),
+    /// The account is not a validator in the given epoch.
+    #[error("{0} is not a validator in epoch {1:?}")]
+    NotAValidator(AccountId, EpochId),
+    /// A shard id does not exist in the epoch's shard layout.
+    #[error("sharding error: {0}")]
+    ShardingError(String),
+    /// No chunk producer could be selected for the shard at the height.
+    #[error("no chunk producer for shard {shard_id} at height {height}")]
+    ChunkProducerSelectionError {
+        shard_id: crate::types::ShardId,
+        height: crate::types::BlockHeight,
+    },
+    /// The protocol version tally picked a version below the current one;
+    /// protocol versions are monotonic per chain, so this points at a
+    /// majority of validators running an outdated binary.
+    #[error(
+        "protocol version tally proposed {proposed}, below the current version {current}"
+    )]
+    ProtocolVersionRegression {
+        current: crate::types::ProtocolVersion,
+        proposed: crate::types::ProtocolVersion,
+    },
+    /// The stored information for the epoch is internally inconsistent.
+    #[error("epoch info for epoch {0:?} is corrupted: {1}")]
+    CorruptedEpochInfo(EpochId, String),
+    /// Error from the underlying storage.
+    #[error("IO error: {0}")]
+    IOErr(String),
+}
+
+impl From<std::io::Error> for EpochError {
+    fn from(error: std::io::Error) -> Self {
+        EpochError::IOErr(error.to_string())
+    }
+}
@@ -0,0 +1,266 @@
+//! Pre-submission cost estimation for wallets and RPC fee previews.
+//!
+//! The estimates mirror how the runtime charges transactions -- a send fee
+//! burnt on the sender's shard and an execution fee burnt on the receiver's
+//! shard per action -- but against a local fee table, so they are previews:
+//! the actual cost is determined by the runtime config of the block the
+//! transaction lands in.
+
+use crate::action::Action;
+use crate::transaction::Transaction;
+use crate::types::{Balance, Gas};
+
+/// The send and execution gas the runtime charges for one action type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ActionFee {
+    /// Charged on the sender's shard when the action is converted into a
+    /// receipt.
+    pub send: Gas,
+    /// Charged on the receiver's shard when the action is applied.
+    pub exec: Gas,
+}
+
+/// A minimal runtime fee table, one [`ActionFee`] per action type plus the
+/// per-byte fee of function call payloads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeConfig {
+    pub create_account: ActionFee,
+    pub function_call: ActionFee,
+    /// Charged per byte of a function call's method name and arguments, on
+    /// top of the base [`Self::function_call`] fee.
+    pub function_call_per_byte: ActionFee,
+    pub transfer: ActionFee,
+    pub stake: ActionFee,
+    pub delete_account: ActionFee,
+}
+
+impl Default for FeeConfig {
+    /// Indicative fees in the right orders of magnitude; real previews
+    /// should load the fee table of the chain they target.
+    fn default() -> Self {
+        Self {
+            create_account: ActionFee { send: 100_000_000_000, exec: 100_000_000_000 },
+            function_call: ActionFee { send: 2_300_000_000_000, exec: 2_300_000_000_000 },
+            function_call_per_byte: ActionFee { send: 2_000_000, exec: 2_000_000 },
+            transfer: ActionFee { send: 115_000_000_000, exec: 115_000_000_000 },
+            stake: ActionFee { send: 140_000_000_000, exec: 140_000_000_000 },
+            delete_account: ActionFee { send: 150_000_000_000, exec: 150_000_000_000 },
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum FeeEstimationError {
+    #[error("gas overflow while estimating the transaction cost")]
+    GasOverflow,
+    #[error("balance overflow while estimating the token cost")]
+    BalanceOverflow,
+}
+
+/// The estimated cost of a transaction, split the way the runtime charges
+/// it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CostBreakdown {
+    /// Gas burnt on the sender's shard for converting and sending.
+    pub send_gas: Gas,
+    /// Gas burnt or attached on the receiver's shard, including the gas
+    /// attached to function calls.
+    pub exec_gas: Gas,
+    pub total_gas: Gas,
+    /// Tokens the actions themselves carry: transfer and function call
+    /// deposits.
+    pub total_deposit: Balance,
+    /// Tokens paying for `total_gas` at the given gas price.
+    pub tokens_for_gas: Balance,
+}
+
+/// Estimates what submitting `tx` at `gas_price` costs the signer.
+pub fn estimate_tx_cost(
+    tx: &Transaction,
+    fees: &FeeConfig,
+    gas_price: Balance,
+) -> Result<CostBreakdown, FeeEstimationError> {
+    let mut send_gas: Gas = 0;
+    let mut exec_gas: Gas = 0;
+    let mut total_deposit: Balance = 0;
+    for action in &tx.actions {
+        let (action_send, action_exec) = action_gas(action, fees)?;
+        send_gas = add_gas(send_gas, action_send)?;
+        exec_gas = add_gas(exec_gas, action_exec)?;
+        total_deposit = total_deposit
+            .checked_add(action_deposit(action))
+            .ok_or(FeeEstimationError::BalanceOverflow)?;
+    }
+    let total_gas = add_gas(send_gas, exec_gas)?;
+    let tokens_for_gas = (total_gas as Balance)
+        .checked_mul(gas_price)
+        .ok_or(FeeEstimationError::BalanceOverflow)?;
+    Ok(CostBreakdown { send_gas, exec_gas, total_gas, total_deposit, tokens_for_gas })
+}
+
+/// Estimates the gas executing the given receipt actions burns on the
+/// receiver's shard: the execution half of the fees plus attached gas.
+pub fn estimate_receipt_exec_cost(
+    actions: &[Action],
+    fees: &FeeConfig,
+) -> Result<Gas, FeeEstimationError> {
+    let mut exec_gas: Gas = 0;
+    for action in actions {
+        let (_, action_exec) = action_gas(action, fees)?;
+        exec_gas = add_gas(exec_gas, action_exec)?;
+    }
+    Ok(exec_gas)
+}
+
+/// The send and exec gas of one action, with the function call payload
+/// charged per byte and its attached gas counted as execution gas.
+fn action_gas(action: &Action, fees: &FeeConfig) -> Result<(Gas, Gas), FeeEstimationError> {
+    Ok(match action {
+        Action::CreateAccount(_) => (fees.create_account.send, fees.create_account.exec),
+        Action::FunctionCall(function_call) => {
+            let num_bytes = (function_call.method_name.len() + function_call.args.len()) as u64;
+            let per_byte = &fees.function_call_per_byte;
+            let send = add_gas(
+                fees.function_call.send,
+                per_byte.send.checked_mul(num_bytes).ok_or(FeeEstimationError::GasOverflow)?,
+            )?;
+            let exec = add_gas(
+                fees.function_call.exec,
+                per_byte.exec.checked_mul(num_bytes).ok_or(FeeEstimationError::GasOverflow)?,
+            )?;
+            (send, add_gas(exec, function_call.gas)?)
+        }
+        Action::Transfer(_) => (fees.transfer.send, fees.transfer.exec),
+        Action::Stake(_) => (fees.stake.send, fees.stake.exec),
+        Action::DeleteAccount(_) => (fees.delete_account.send, fees.delete_account.exec),
+    })
+}
+
+/// The tokens the action carries on top of its gas cost.
+fn action_deposit(action: &Action) -> Balance {
+    match action {
+        Action::FunctionCall(function_call) => function_call.deposit,
+        Action::Transfer(transfer) => transfer.deposit,
+        Action::CreateAccount(_) | Action::Stake(_) | Action::DeleteAccount(_) => 0,
+    }
+}
+
+fn add_gas(a: Gas, b: Gas) -> Result<Gas, FeeEstimationError> {
+    a.checked_add(b).ok_or(FeeEstimationError::GasOverflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::{CreateAccountAction, FunctionCallAction, TransferAction};
+    use crate::hash::CryptoHash;
+    use near_crypto::{KeyType, SecretKey};
+
+    fn flat_fees() -> FeeConfig {
+        let fee = |send, exec| ActionFee { send, exec };
+        FeeConfig {
+            create_account: fee(10, 20),
+            function_call: fee(100, 200),
+            function_call_per_byte: fee(1, 2),
+            transfer: fee(30, 40),
+            stake: fee(50, 60),
+            delete_account: fee(70, 80),
+        }
+    }
+
+    fn tx(actions: Vec<Action>) -> Transaction {
+        let signer_id = "alice".parse().unwrap();
+        let public_key = SecretKey::from_seed(KeyType::ED25519, "alice").public_key();
+        Transaction {
+            signer_id,
+            public_key,
+            nonce: 1,
+            receiver_id: "bob".parse().unwrap(),
+            block_hash: CryptoHash::default(),
+            actions,
+            priority_fee: 0,
+        }
+    }
+
+    #[test]
+    fn test_transfer_cost() {
+        let tx = tx(vec![TransferAction { deposit: 1000 }.into()]);
+        let cost = estimate_tx_cost(&tx, &flat_fees(), 5).unwrap();
+        assert_eq!(
+            cost,
+            CostBreakdown {
+                send_gas: 30,
+                exec_gas: 40,
+                total_gas: 70,
+                total_deposit: 1000,
+                tokens_for_gas: 350,
+            }
+        );
+    }
+
+    #[test]
+    fn test_function_call_cost_includes_payload_and_attached_gas() {
+        let tx = tx(vec![
+            FunctionCallAction {
+                method_name: "set".to_string(), // 3 bytes
+                args: vec![0; 7],
+                gas: 5000,
+                deposit: 1,
+            }
+            .into(),
+        ]);
+        let cost = estimate_tx_cost(&tx, &flat_fees(), 1).unwrap();
+        // 10 payload bytes: send 100 + 10 * 1, exec 200 + 10 * 2 + 5000
+        // attached.
+        assert_eq!(cost.send_gas, 110);
+        assert_eq!(cost.exec_gas, 5220);
+        assert_eq!(cost.total_gas, 5330);
+        assert_eq!(cost.total_deposit, 1);
+        assert_eq!(cost.tokens_for_gas, 5330);
+    }
+
+    #[test]
+    fn test_multi_action_cost_sums_per_action() {
+        let actions: Vec<Action> = vec![
+            CreateAccountAction {}.into(),
+            TransferAction { deposit: 500 }.into(),
+            FunctionCallAction {
+                method_name: "new".to_string(),
+                args: vec![],
+                gas: 1000,
+                deposit: 25,
+            }
+            .into(),
+        ];
+        let cost = estimate_tx_cost(&tx(actions.clone()), &flat_fees(), 2).unwrap();
+        assert_eq!(cost.send_gas, 10 + 30 + 103);
+        assert_eq!(cost.exec_gas, 20 + 40 + 206 + 1000);
+        assert_eq!(cost.total_deposit, 525);
+        assert_eq!(cost.tokens_for_gas, 2 * cost.total_gas as u128);
+        // The receipt-side estimate is exactly the execution half.
+        assert_eq!(estimate_receipt_exec_cost(&actions, &flat_fees()), Ok(cost.exec_gas));
+    }
+
+    #[test]
+    fn test_absurd_attached_gas_overflows_cleanly() {
+        let tx = tx(vec![
+            FunctionCallAction {
+                method_name: "burn".to_string(),
+                args: vec![],
+                gas: u64::MAX,
+                deposit: 0,
+            }
+            .into(),
+        ]);
+        assert_eq!(
+            estimate_tx_cost(&tx, &flat_fees(), 1),
+            Err(FeeEstimationError::GasOverflow)
+        );
+        // A sane gas amount at an absurd gas price overflows the token side.
+        let tx = self::tx(vec![TransferAction { deposit: 0 }.into()]);
+        assert_eq!(
+            estimate_tx_cost(&tx, &flat_fees(), u128::MAX),
+            Err(FeeEstimationError::BalanceOverflow)
+        );
+    }
+}
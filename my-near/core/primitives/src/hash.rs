@@ -0,0 +1,212 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use sha2::Digest;
+use std::fmt;
+use std::str::FromStr;
+
+/// A sha256 digest, the hash type used throughout the chain.
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+)]
+pub struct CryptoHash(pub [u8; 32]);
+
+impl CryptoHash {
+    pub const fn new() -> Self {
+        Self([0; 32])
+    }
+
+    /// Calculates the hash of the given bytes.
+    pub fn hash_bytes(bytes: &[u8]) -> CryptoHash {
+        CryptoHash(sha2::Sha256::digest(bytes).into())
+    }
+
+    /// Calculates the hash of the borsh serialization of the value.
+    ///
+    /// Borsh serialization of in-memory values cannot fail, so unlike the
+    /// manual `hash(&borsh::to_vec(value).unwrap())` pattern this keeps the
+    /// unwrap in one place.
+    pub fn hash_borsh<T: BorshSerialize>(value: &T) -> CryptoHash {
+        let mut hasher = sha2::Sha256::default();
+        value.serialize(&mut hasher).unwrap();
+        CryptoHash(hasher.finalize().into())
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// The number of leading bytes the two hashes agree on.
+    pub fn common_prefix_len(a: &CryptoHash, b: &CryptoHash) -> usize {
+        a.0.iter().zip(b.0.iter()).take_while(|(a, b)| a == b).count()
+    }
+
+    /// The short 8-character display form of this hash, for log lines.
+    pub fn short(&self) -> ShortHash<'_> {
+        ShortHash(self)
+    }
+}
+
+/// Displays the first [`ShortHash::LEN`] base58 characters of a hash: long
+/// enough to correlate log lines across nodes, short enough to keep them
+/// readable. Obtained via [`CryptoHash::short`].
+pub struct ShortHash<'a>(pub &'a CryptoHash);
+
+impl ShortHash<'_> {
+    pub const LEN: usize = 8;
+}
+
+impl fmt::Display for ShortHash<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Base58 of 32 bytes is at most 44 characters; encode onto the
+        // stack instead of allocating a string for every log line.
+        let mut buf = [0u8; 44];
+        let len = bs58::encode(&self.0.0).onto(&mut buf[..]).map_err(|_| fmt::Error)?;
+        let encoded = std::str::from_utf8(&buf[..len]).map_err(|_| fmt::Error)?;
+        f.write_str(&encoded[..Self::LEN.min(encoded.len())])
+    }
+}
+
+/// The key range covered by a byte prefix, for database range scans: the
+/// inclusive start and the exclusive end of all keys starting with `prefix`.
+///
+/// The end is the prefix with its last non-`0xFF` byte incremented and the
+/// bytes after it stripped; an all-`0xFF` prefix has no upper bound in byte
+/// order, so the end is `None` (scan to the end of the column).
+pub fn prefix_range(prefix: &[u8]) -> (Vec<u8>, Option<Vec<u8>>) {
+    let start = prefix.to_vec();
+    let mut end = prefix.to_vec();
+    while let Some(last) = end.pop() {
+        if last < u8::MAX {
+            end.push(last + 1);
+            return (start, Some(end));
+        }
+    }
+    (start, None)
+}
+
+/// Calculates the hash of the given bytes.
+pub fn hash(bytes: &[u8]) -> CryptoHash {
+    CryptoHash::hash_bytes(bytes)
+}
+
+impl AsRef<[u8]> for CryptoHash {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<CryptoHash> for [u8; 32] {
+    fn from(hash: CryptoHash) -> Self {
+        hash.0
+    }
+}
+
+impl fmt::Display for CryptoHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", bs58::encode(&self.0).into_string())
+    }
+}
+
+impl fmt::Debug for CryptoHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl serde::Serialize for CryptoHash {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for CryptoHash {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <String as serde::Deserialize>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl FromStr for CryptoHash {
+    type Err = Box<dyn std::error::Error + Send + Sync>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = bs58::decode(s).into_vec()?;
+        let bytes: [u8; 32] =
+            bytes.try_into().map_err(|_| "incorrect hash length, expected 32 bytes")?;
+        Ok(CryptoHash(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(BorshSerialize)]
+    struct SampleStruct {
+        id: u64,
+        name: String,
+    }
+
+    #[test]
+    fn test_hash_borsh_matches_manual_hashing() {
+        let value = SampleStruct { id: 42, name: "chunk".to_string() };
+        assert_eq!(
+            CryptoHash::hash_borsh(&value),
+            hash(&borsh::to_vec(&value).unwrap()),
+        );
+    }
+
+    #[test]
+    fn test_common_prefix_len() {
+        let a = CryptoHash([7; 32]);
+        assert_eq!(CryptoHash::common_prefix_len(&a, &a), 32);
+        let mut b = a;
+        b.0[3] = 8;
+        assert_eq!(CryptoHash::common_prefix_len(&a, &b), 3);
+        assert_eq!(CryptoHash::common_prefix_len(&b, &a), 3);
+        assert_eq!(CryptoHash::common_prefix_len(&CryptoHash([0; 32]), &CryptoHash([1; 32])), 0);
+    }
+
+    #[test]
+    fn test_prefix_range_increments_last_byte() {
+        assert_eq!(prefix_range(&[1, 2, 3]), (vec![1, 2, 3], Some(vec![1, 2, 4])));
+        // A trailing 0xFF is stripped and the byte before it incremented.
+        assert_eq!(prefix_range(&[1, 2, 0xff]), (vec![1, 2, 0xff], Some(vec![1, 3])));
+        // Every key with the prefix is inside the range, the incremented
+        // prefix itself is not.
+        let (start, end) = prefix_range(&[1, 2, 3]);
+        let end = end.unwrap();
+        assert!(start.as_slice() < [1, 2, 3, 0].as_slice());
+        assert!([1, 2, 3, 0xff, 0xff].as_slice() < end.as_slice());
+        assert!(end.as_slice() <= [1, 2, 4].as_slice());
+    }
+
+    #[test]
+    fn test_prefix_range_all_ff_has_no_end() {
+        assert_eq!(prefix_range(&[0xff, 0xff]), (vec![0xff, 0xff], None));
+        assert_eq!(prefix_range(&[]), (vec![], None));
+    }
+
+    #[test]
+    fn test_display_from_str_round_trip() {
+        let value = hash(b"some data");
+        assert_eq!(CryptoHash::from_str(&value.to_string()).unwrap(), value);
+    }
+
+    #[test]
+    fn test_short_hash_is_prefix_of_full_display() {
+        let value = hash(b"some data");
+        assert_eq!(value.short().to_string(), value.to_string()[..ShortHash::LEN]);
+        assert_eq!(value.short().to_string().len(), 8);
+        // The format machinery works for padded/embedded uses too.
+        assert_eq!(format!("block {}", value.short()), format!("block {}", &value.to_string()[..8]));
+    }
+}
@@ -0,0 +1,170 @@
+//! Epoch boundary arithmetic over block heights.
+//!
+//! The conventions, in one place instead of re-derived per call site:
+//!
+//! * The genesis block sits in epoch 0; epoch `i` covers heights
+//!   `[genesis_height + i * epoch_length, genesis_height + (i + 1) * epoch_length)`.
+//! * The boundary block is the *last* block of the old epoch; the height
+//!   right after it is the first height of the new epoch.
+//!
+//! All functions use checked arithmetic: heights below genesis, a zero
+//! epoch length and overflowing indices yield `None` (or `false`) instead
+//! of wrapping.
+
+use crate::types::{BlockHeight, EpochHeight};
+use std::ops::Range;
+
+/// Whether a block at `height` is the boundary block -- the last block --
+/// of the epoch starting at `epoch_start`.
+pub fn is_epoch_boundary(
+    height: BlockHeight,
+    epoch_start: BlockHeight,
+    epoch_length: BlockHeight,
+) -> bool {
+    if epoch_length == 0 {
+        return false;
+    }
+    epoch_start
+        .checked_add(epoch_length)
+        .is_some_and(|next_epoch_start| height.checked_add(1) == Some(next_epoch_start))
+}
+
+/// Index of the epoch that contains blocks at the given height, or `None`
+/// if the height precedes genesis or the epoch length is zero.
+pub fn epoch_index_for_height(
+    height: BlockHeight,
+    genesis_height: BlockHeight,
+    epoch_length: BlockHeight,
+) -> Option<EpochHeight> {
+    if epoch_length == 0 {
+        return None;
+    }
+    Some(height.checked_sub(genesis_height)? / epoch_length)
+}
+
+/// First height of the epoch with the given index, or `None` on overflow
+/// or a zero epoch length.
+pub fn first_height_of_epoch(
+    index: EpochHeight,
+    genesis_height: BlockHeight,
+    epoch_length: BlockHeight,
+) -> Option<BlockHeight> {
+    if epoch_length == 0 {
+        return None;
+    }
+    genesis_height.checked_add(index.checked_mul(epoch_length)?)
+}
+
+/// All heights of the epoch with the given index, as a half-open range.
+pub fn heights_in_epoch(
+    index: EpochHeight,
+    genesis_height: BlockHeight,
+    epoch_length: BlockHeight,
+) -> Option<Range<BlockHeight>> {
+    let start = first_height_of_epoch(index, genesis_height, epoch_length)?;
+    Some(start..start.checked_add(epoch_length)?)
+}
+
+/// The height right below the given one, or `None` at (or below) genesis.
+pub fn prev_height(height: BlockHeight, genesis_height: BlockHeight) -> Option<BlockHeight> {
+    if height <= genesis_height {
+        return None;
+    }
+    height.checked_sub(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_epoch_index_for_height_zero_genesis() {
+        // Epoch length 5 from genesis 0: epoch 0 is heights 0..5.
+        for height in 0..5 {
+            assert_eq!(epoch_index_for_height(height, 0, 5), Some(0));
+        }
+        assert_eq!(epoch_index_for_height(5, 0, 5), Some(1));
+        assert_eq!(epoch_index_for_height(9, 0, 5), Some(1));
+        assert_eq!(epoch_index_for_height(10, 0, 5), Some(2));
+    }
+
+    #[test]
+    fn test_epoch_index_for_height_nonzero_genesis() {
+        // Genesis at 100: heights below it have no epoch.
+        assert_eq!(epoch_index_for_height(99, 100, 5), None);
+        for height in 100..105 {
+            assert_eq!(epoch_index_for_height(height, 100, 5), Some(0));
+        }
+        assert_eq!(epoch_index_for_height(105, 100, 5), Some(1));
+    }
+
+    #[test]
+    fn test_zero_epoch_length_yields_nothing() {
+        assert_eq!(epoch_index_for_height(7, 0, 0), None);
+        assert_eq!(first_height_of_epoch(1, 0, 0), None);
+        assert_eq!(heights_in_epoch(1, 0, 0), None);
+        assert!(!is_epoch_boundary(4, 0, 0));
+    }
+
+    #[test]
+    fn test_first_height_of_epoch() {
+        assert_eq!(first_height_of_epoch(0, 0, 5), Some(0));
+        assert_eq!(first_height_of_epoch(3, 0, 5), Some(15));
+        assert_eq!(first_height_of_epoch(3, 100, 5), Some(115));
+        // Overflow does not wrap around.
+        assert_eq!(first_height_of_epoch(u64::MAX, 0, 2), None);
+        assert_eq!(first_height_of_epoch(1, u64::MAX, 5), None);
+    }
+
+    #[test]
+    fn test_heights_in_epoch_partitions_the_chain() {
+        // Consecutive epochs tile the height space without gaps or overlap.
+        let mut expected_next = 100;
+        for index in 0..4 {
+            let range = heights_in_epoch(index, 100, 5).unwrap();
+            assert_eq!(range.start, expected_next);
+            assert_eq!(range.end - range.start, 5);
+            expected_next = range.end;
+        }
+    }
+
+    #[test]
+    fn test_is_epoch_boundary_marks_last_height_of_epoch() {
+        // Epoch starting at 10 with length 5: only height 14 is its boundary;
+        // height 15 already belongs to the next epoch.
+        for height in 10..14 {
+            assert!(!is_epoch_boundary(height, 10, 5));
+        }
+        assert!(is_epoch_boundary(14, 10, 5));
+        assert!(!is_epoch_boundary(15, 10, 5));
+        // Near the top of the height space nothing overflows.
+        assert!(!is_epoch_boundary(u64::MAX, u64::MAX - 1, 5));
+    }
+
+    #[test]
+    fn test_boundary_agrees_with_epoch_indices() {
+        // A height is a boundary exactly when the next height has a larger
+        // epoch index.
+        for height in 0..30u64 {
+            let epoch_start = first_height_of_epoch(
+                epoch_index_for_height(height, 0, 7).unwrap(),
+                0,
+                7,
+            )
+            .unwrap();
+            let crosses = epoch_index_for_height(height + 1, 0, 7)
+                > epoch_index_for_height(height, 0, 7);
+            assert_eq!(is_epoch_boundary(height, epoch_start, 7), crosses, "{height}");
+        }
+    }
+
+    #[test]
+    fn test_prev_height() {
+        assert_eq!(prev_height(1, 0), Some(0));
+        assert_eq!(prev_height(101, 100), Some(100));
+        // Genesis has no previous height, and neither does anything below it.
+        assert_eq!(prev_height(0, 0), None);
+        assert_eq!(prev_height(100, 100), None);
+        assert_eq!(prev_height(99, 100), None);
+    }
+}
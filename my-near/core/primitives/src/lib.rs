@@ -0,0 +1,25 @@
+pub mod account;
+pub mod action;
+#[cfg(any(test, feature = "arbitrary"))]
+pub mod arbitrary;
+pub mod block;
+pub mod block_body;
+pub mod block_header;
+pub mod chunk_extra;
+pub mod congestion_info;
+pub mod epoch_manager;
+pub mod errors;
+pub mod fee_estimation;
+pub mod hash;
+pub mod height_math;
+pub mod merkle;
+pub mod reed_solomon;
+pub mod serialize;
+pub mod shard_layout;
+pub mod sharding;
+pub mod transaction;
+pub mod types;
+pub mod upgrade_schedule;
+pub mod validator_mandates;
+pub mod validator_signer;
+pub mod views;
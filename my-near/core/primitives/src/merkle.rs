@@ -0,0 +1,134 @@
+use crate::hash::CryptoHash;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+pub type MerkleHash = CryptoHash;
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MerklePathItem {
+    pub hash: MerkleHash,
+    pub direction: Direction,
+}
+
+pub type MerklePath = Vec<MerklePathItem>;
+
+pub fn combine_hash(hash1: &MerkleHash, hash2: &MerkleHash) -> MerkleHash {
+    CryptoHash::hash_borsh(&(hash1, hash2))
+}
+
+/// Merklizes an array of items, returning the root and one inclusion path
+/// per item. For an empty array the root is the zero hash.
+pub fn merklize<T: BorshSerialize>(arr: &[T]) -> (MerkleHash, Vec<MerklePath>) {
+    if arr.is_empty() {
+        return (MerkleHash::default(), vec![]);
+    }
+    let mut len = arr.len().next_power_of_two();
+    let mut hashes: Vec<_> = arr.iter().map(|item| CryptoHash::hash_borsh(item)).collect();
+    let mut level_len = arr.len();
+    let mut paths: Vec<MerklePath> = (0..level_len)
+        .map(|i| {
+            if i % 2 == 0 {
+                if i + 1 < level_len {
+                    vec![MerklePathItem { hash: hashes[i + 1], direction: Direction::Right }]
+                } else {
+                    vec![]
+                }
+            } else {
+                vec![MerklePathItem { hash: hashes[i - 1], direction: Direction::Left }]
+            }
+        })
+        .collect();
+
+    // Walk up the tree level by level; `counter` is the number of leaves
+    // under each node of the current level, used to find which leaves get
+    // the node's sibling hash appended to their path.
+    let mut counter = 1;
+    while len > 1 {
+        len /= 2;
+        counter *= 2;
+        for i in 0..len {
+            let hash = if 2 * i >= level_len {
+                continue;
+            } else if 2 * i + 1 >= level_len {
+                hashes[2 * i]
+            } else {
+                combine_hash(&hashes[2 * i], &hashes[2 * i + 1])
+            };
+            hashes[i] = hash;
+            if len > 1 {
+                let (sibling_of, direction) = if i % 2 == 0 {
+                    (i + 1, Direction::Left)
+                } else {
+                    (i - 1, Direction::Right)
+                };
+                for j in 0..counter {
+                    let index = sibling_of * counter + j;
+                    if index < arr.len() {
+                        paths[index].push(MerklePathItem { hash, direction });
+                    }
+                }
+            }
+        }
+        level_len = level_len.div_ceil(2);
+    }
+    (hashes[0], paths)
+}
+
+/// Recomputes the root implied by an item and its inclusion path.
+pub fn compute_root_from_path(path: &MerklePath, item_hash: MerkleHash) -> MerkleHash {
+    let mut res = item_hash;
+    for item in path {
+        match item.direction {
+            Direction::Left => res = combine_hash(&item.hash, &res),
+            Direction::Right => res = combine_hash(&res, &item.hash),
+        }
+    }
+    res
+}
+
+/// Verifies that `item` is in the tree with the given root via `path`.
+pub fn verify_path<T: BorshSerialize>(root: MerkleHash, path: &MerklePath, item: &T) -> bool {
+    verify_hash(root, path, CryptoHash::hash_borsh(item))
+}
+
+pub fn verify_hash(root: MerkleHash, path: &MerklePath, item_hash: MerkleHash) -> bool {
+    compute_root_from_path(path, item_hash) == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merklize_all_paths_verify() {
+        for size in 1..=10usize {
+            let items: Vec<u64> = (0..size as u64).collect();
+            let (root, paths) = merklize(&items);
+            assert_eq!(paths.len(), size);
+            for (item, path) in items.iter().zip(&paths) {
+                assert!(verify_path(root, path, item), "size {size} item {item}");
+            }
+            // A wrong item must not verify.
+            assert!(!verify_path(root, &paths[0], &1000u64));
+        }
+    }
+
+    #[test]
+    fn test_merklize_empty() {
+        let (root, paths) = merklize::<u64>(&[]);
+        assert_eq!(root, MerkleHash::default());
+        assert!(paths.is_empty());
+    }
+
+    #[test]
+    fn test_order_changes_root() {
+        let (root1, _) = merklize(&[1u64, 2, 3]);
+        let (root2, _) = merklize(&[2u64, 1, 3]);
+        assert_ne!(root1, root2);
+    }
+}
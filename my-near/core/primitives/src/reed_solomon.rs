@@ -0,0 +1,126 @@
+//! Reed-Solomon erasure coding of chunk bodies.
+//!
+//! A chunk body is split into data shards and extended with parity shards so
+//! that any `data_shards` of the total suffice to recover the body. The
+//! wrapper owns the codec configuration so callers cannot mix shard counts.
+
+use reed_solomon_erasure::galois_8::ReedSolomon;
+
+pub use reed_solomon_erasure::Error as ReedSolomonError;
+
+/// A Reed-Solomon codec for a fixed data/parity shard split.
+pub struct ReedSolomonWrapper {
+    rs: ReedSolomon,
+}
+
+impl ReedSolomonWrapper {
+    pub fn new(data_shards: usize, parity_shards: usize) -> Self {
+        Self {
+            rs: ReedSolomon::new(data_shards, parity_shards)
+                .expect("shard counts are validated by the caller"),
+        }
+    }
+
+    pub fn data_shard_count(&self) -> usize {
+        self.rs.data_shard_count()
+    }
+
+    pub fn total_shard_count(&self) -> usize {
+        self.rs.total_shard_count()
+    }
+
+    /// Encodes the bytes into `total_shard_count` equally-sized parts: the
+    /// data shards carry the (padded) bytes, the parity shards the erasure
+    /// code over them. Returns the parts and the unpadded length.
+    pub fn encode(&self, bytes: &[u8]) -> (Vec<Option<Box<[u8]>>>, usize) {
+        let data_shards = self.data_shard_count();
+        let shard_length = bytes.len().div_ceil(data_shards).max(1);
+        let mut parts: Vec<Box<[u8]>> = (0..data_shards)
+            .map(|i| {
+                let start = (i * shard_length).min(bytes.len());
+                let end = ((i + 1) * shard_length).min(bytes.len());
+                let mut shard = bytes[start..end].to_vec();
+                shard.resize(shard_length, 0);
+                shard.into_boxed_slice()
+            })
+            .collect();
+        parts.resize(self.total_shard_count(), vec![0; shard_length].into_boxed_slice());
+        self.rs.encode(&mut parts).expect("shards are equally sized by construction");
+        (parts.into_iter().map(Some).collect(), bytes.len())
+    }
+
+    /// Reconstructs every missing shard in place, data and parity alike.
+    pub fn reconstruct(
+        &self,
+        parts: &mut [Option<Box<[u8]>>],
+    ) -> Result<(), ReedSolomonError> {
+        self.rs.reconstruct(parts)
+    }
+
+    /// Reconstructs only the missing data shards in place, which is cheaper
+    /// than [`Self::reconstruct`] when the caller just wants to decode the
+    /// body and does not need the parity shards back.
+    pub fn reconstruct_data_shards(
+        &self,
+        parts: &mut [Option<Box<[u8]>>],
+    ) -> Result<(), ReedSolomonError> {
+        self.rs.reconstruct_data(parts)
+    }
+
+    /// Concatenates the data shards back into the original bytes; all data
+    /// shards must be present, reconstructed if need be.
+    pub fn decode(&self, parts: &[Option<Box<[u8]>>], encoded_length: usize) -> Vec<u8> {
+        let mut bytes: Vec<u8> = parts[..self.data_shard_count()]
+            .iter()
+            .flat_map(|part| {
+                part.as_deref().expect("data shards must be reconstructed before decoding")
+            })
+            .copied()
+            .collect();
+        bytes.truncate(encoded_length);
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reconstruct_data_shards_recovers_dropped_parts() {
+        let codec = ReedSolomonWrapper::new(4, 2);
+        let bytes: Vec<u8> = (0u8..=255).cycle().take(1000).collect();
+        let (mut parts, encoded_length) = codec.encode(&bytes);
+        assert_eq!(parts.len(), 6);
+        let original = parts.clone();
+
+        // Drop one data shard and one parity shard.
+        parts[1] = None;
+        parts[5] = None;
+        codec.reconstruct_data_shards(&mut parts).unwrap();
+
+        // The data shards are back and decode to the original bytes; the
+        // parity shard was deliberately not recomputed.
+        assert_eq!(parts[..4], original[..4]);
+        assert_eq!(parts[5], None);
+        assert_eq!(codec.decode(&parts, encoded_length), bytes);
+
+        // Full reconstruction also restores the parity shard.
+        codec.reconstruct(&mut parts).unwrap();
+        assert_eq!(parts, original);
+    }
+
+    #[test]
+    fn test_reconstruct_fails_with_too_few_shards() {
+        let codec = ReedSolomonWrapper::new(4, 2);
+        let (mut parts, _) = codec.encode(b"some chunk body");
+        // Three missing shards exceed the two parity shards.
+        parts[0] = None;
+        parts[1] = None;
+        parts[2] = None;
+        assert_eq!(
+            codec.reconstruct_data_shards(&mut parts),
+            Err(ReedSolomonError::TooFewShardsPresent)
+        );
+    }
+}
@@ -0,0 +1,99 @@
+//! Serde helper modules for field formats used on the RPC JSON paths.
+
+/// Serializes a number as a decimal string, for balances that exceed what
+/// JSON numbers can carry losslessly. Use with `#[serde(with = "dec_format")]`.
+pub mod dec_format {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    pub fn serialize<T: Display, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+
+    /// The same decimal-string format for optional fields: `None` is JSON
+    /// `null`. Use with `#[serde(with = "dec_format::option")]`.
+    pub mod option {
+        use serde::{Deserialize, Deserializer, Serializer};
+        use std::fmt::Display;
+        use std::str::FromStr;
+
+        pub fn serialize<T: Display, S: Serializer>(
+            value: &Option<T>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match value {
+                Some(value) => serializer.collect_str(value),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+        where
+            T: FromStr,
+            T::Err: Display,
+            D: Deserializer<'de>,
+        {
+            match Option::<String>::deserialize(deserializer)? {
+                Some(s) => s.parse().map(Some).map_err(serde::de::Error::custom),
+                None => Ok(None),
+            }
+        }
+    }
+}
+
+/// Serializes binary data as a standard base64 string. Use with
+/// `#[serde(with = "base64_format")]`.
+pub mod base64_format {
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&STANDARD.encode(value))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        STANDARD.decode(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq)]
+    struct Sample {
+        #[serde(with = "super::dec_format")]
+        balance: u128,
+        #[serde(with = "super::base64_format")]
+        data: Vec<u8>,
+    }
+
+    #[test]
+    fn test_formats_round_trip() {
+        let sample = Sample { balance: u128::MAX, data: vec![0, 1, 254, 255] };
+        let json = serde_json::to_value(&sample).unwrap();
+        assert_eq!(json["balance"], u128::MAX.to_string());
+        assert_eq!(json["data"], "AAH+/w==");
+        assert_eq!(serde_json::from_value::<Sample>(json).unwrap(), sample);
+    }
+
+    #[test]
+    fn test_rejects_malformed_strings() {
+        assert!(serde_json::from_str::<Sample>(r#"{"balance":"12x","data":""}"#).is_err());
+        assert!(serde_json::from_str::<Sample>(r#"{"balance":"1","data":"!"}"#).is_err());
+    }
+}
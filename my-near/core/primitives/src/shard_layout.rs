@@ -0,0 +1,484 @@
+use crate::hash::hash;
+use crate::types::{AccountId, NumShards, ShardId};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+/// Layout version, bumped on every resharding.
+pub type ShardVersion = u32;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ShardLayoutError {
+    #[error("shard id {shard_id} does not exist in the layout")]
+    InvalidShardId { shard_id: ShardId },
+    #[error("shard uid has version {got}, the layout has version {expected}")]
+    InvalidShardVersion { got: ShardVersion, expected: ShardVersion },
+    #[error("the layout has no split map, so shard {shard_id} has no parent")]
+    NoParent { shard_id: ShardId },
+}
+
+/// A shard id made unique across reshardings by pairing it with the version
+/// of the layout it belongs to.
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+)]
+pub struct ShardUId {
+    pub version: ShardVersion,
+    pub shard_id: u32,
+}
+
+impl ShardUId {
+    pub fn new(version: ShardVersion, shard_id: ShardId) -> Self {
+        Self { version, shard_id: shard_id as u32 }
+    }
+
+    pub fn shard_id(&self) -> ShardId {
+        self.shard_id as ShardId
+    }
+
+    /// The byte representation used as a store key prefix: the version
+    /// followed by the shard id, both little endian.
+    pub fn to_bytes(&self) -> [u8; 8] {
+        let mut bytes = [0u8; 8];
+        bytes[..4].copy_from_slice(&self.version.to_le_bytes());
+        bytes[4..].copy_from_slice(&self.shard_id.to_le_bytes());
+        bytes
+    }
+
+    /// The smallest byte string of the same length above every key starting
+    /// with the given prefix, for ending store range scans. `None` when the
+    /// prefix is all `0xFF`, which no string of the same length exceeds.
+    pub fn try_next_shard_prefix(shard_uid_bytes: &[u8; 8]) -> Option<[u8; 8]> {
+        let mut next = *shard_uid_bytes;
+        for byte in next.iter_mut().rev() {
+            match byte.checked_add(1) {
+                Some(incremented) => {
+                    *byte = incremented;
+                    return Some(next);
+                }
+                None => *byte = 0,
+            }
+        }
+        None
+    }
+
+    /// Like [`Self::try_next_shard_prefix`], for callers that know the
+    /// prefix is not the maximum; panics when it is.
+    pub fn next_shard_prefix(shard_uid_bytes: &[u8; 8]) -> [u8; 8] {
+        Self::try_next_shard_prefix(shard_uid_bytes)
+            .unwrap_or_else(|| panic!("Next shard prefix for {shard_uid_bytes:?} does not exist"))
+    }
+}
+
+/// Ways a shard uid string can fail to parse.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ShardUIdParseError {
+    #[error("shard uid \"{0}\" must look like `s<shard>.v<version>`")]
+    InvalidFormat(String),
+    #[error("invalid shard id in shard uid \"{0}\"")]
+    InvalidShardId(String),
+    #[error("invalid version in shard uid \"{0}\"")]
+    InvalidVersion(String),
+}
+
+/// The human-readable form `s<shard>.v<version>`, used in logs and metric
+/// labels; [`ShardUId::from_str`] parses it back.
+impl std::fmt::Display for ShardUId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "s{}.v{}", self.shard_id, self.version)
+    }
+}
+
+impl std::str::FromStr for ShardUId {
+    type Err = ShardUIdParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid_format = || ShardUIdParseError::InvalidFormat(s.to_string());
+        let (shard_part, version_part) = s.split_once('.').ok_or_else(invalid_format)?;
+        let shard_id = shard_part
+            .strip_prefix('s')
+            .ok_or_else(invalid_format)?
+            .parse()
+            .map_err(|_| ShardUIdParseError::InvalidShardId(s.to_string()))?;
+        let version = version_part
+            .strip_prefix('v')
+            .ok_or_else(invalid_format)?
+            .parse()
+            .map_err(|_| ShardUIdParseError::InvalidVersion(s.to_string()))?;
+        Ok(Self { version, shard_id })
+    }
+}
+
+/// Describes how accounts map to shards.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ShardLayout {
+    V0(ShardLayoutV0),
+    V1(ShardLayoutV1),
+}
+
+/// The layout the chain launched with: no boundary accounts, every account
+/// lands in the shard its account id hashes to.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ShardLayoutV0 {
+    /// Number of shards; accounts are assigned by account id hash modulo
+    /// this.
+    num_shards: NumShards,
+    /// Version of the shard layout.
+    version: ShardVersion,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ShardLayoutV1 {
+    /// The boundary accounts are the accounts on boundaries between shards.
+    /// Each shard contains a range of accounts from one boundary account to
+    /// another -- shard i contains account ids between
+    /// `boundary_accounts[i - 1]` (inclusive) and `boundary_accounts[i]`
+    /// (exclusive).
+    boundary_accounts: Vec<AccountId>,
+    /// Maps shards of the parent layout to the shards that they split to in
+    /// this layout.
+    shards_split_map: Option<Vec<Vec<ShardId>>>,
+    /// Version of the shard layout.
+    version: ShardVersion,
+}
+
+impl ShardLayout {
+    pub fn v0(num_shards: NumShards, version: ShardVersion) -> Self {
+        Self::V0(ShardLayoutV0 { num_shards, version })
+    }
+
+    pub fn v1(
+        boundary_accounts: Vec<AccountId>,
+        shards_split_map: Option<Vec<Vec<ShardId>>>,
+        version: ShardVersion,
+    ) -> Self {
+        Self::V1(ShardLayoutV1 { boundary_accounts, shards_split_map, version })
+    }
+
+    /// A layout with a single shard and no boundaries.
+    pub fn single_shard() -> Self {
+        Self::v1(vec![], None, 0)
+    }
+
+    pub fn num_shards(&self) -> NumShards {
+        match self {
+            Self::V0(v0) => v0.num_shards,
+            Self::V1(v1) => v1.boundary_accounts.len() as NumShards + 1,
+        }
+    }
+
+    pub fn version(&self) -> ShardVersion {
+        match self {
+            Self::V0(v0) => v0.version,
+            Self::V1(v1) => v1.version,
+        }
+    }
+
+    pub fn boundary_accounts(&self) -> &[AccountId] {
+        match self {
+            Self::V0(_) => &[],
+            Self::V1(v1) => &v1.boundary_accounts,
+        }
+    }
+
+    pub fn shards_split_map(&self) -> Option<&Vec<Vec<ShardId>>> {
+        match self {
+            Self::V0(_) => None,
+            Self::V1(v1) => v1.shards_split_map.as_ref(),
+        }
+    }
+
+    pub fn shard_ids(&self) -> impl Iterator<Item = ShardId> + use<> {
+        0..self.num_shards()
+    }
+
+    /// All shard ids of the layout as a `Vec`.
+    ///
+    /// Convenience over `shard_ids().collect()` for the congestion functions
+    /// that take `&[ShardId]`.
+    pub fn shard_ids_vec(&self) -> Vec<ShardId> {
+        self.shard_ids().collect()
+    }
+
+    /// The [`ShardUId`] of a shard of this layout.
+    pub fn shard_uid(&self, shard_id: ShardId) -> Result<ShardUId, ShardLayoutError> {
+        if shard_id >= self.num_shards() {
+            return Err(ShardLayoutError::InvalidShardId { shard_id });
+        }
+        Ok(ShardUId::new(self.version(), shard_id))
+    }
+
+    /// The shard of the previous layout that the given shard of this layout
+    /// split from, per the layout's split map.
+    pub fn get_parent_shard_id(&self, shard_id: ShardId) -> Result<ShardId, ShardLayoutError> {
+        if shard_id >= self.num_shards() {
+            return Err(ShardLayoutError::InvalidShardId { shard_id });
+        }
+        let split_map =
+            self.shards_split_map().ok_or(ShardLayoutError::NoParent { shard_id })?;
+        split_map
+            .iter()
+            .position(|children| children.contains(&shard_id))
+            .map(|parent| parent as ShardId)
+            .ok_or(ShardLayoutError::NoParent { shard_id })
+    }
+
+    /// The [`ShardUId`] in `parent_layout` that the given child shard of
+    /// this layout split from, for state migration across a resharding.
+    pub fn get_parent_shard_uid(
+        &self,
+        child: ShardUId,
+        parent_layout: &ShardLayout,
+    ) -> Result<ShardUId, ShardLayoutError> {
+        if child.version != self.version() {
+            return Err(ShardLayoutError::InvalidShardVersion {
+                got: child.version,
+                expected: self.version(),
+            });
+        }
+        let parent_shard_id = self.get_parent_shard_id(child.shard_id())?;
+        parent_layout.shard_uid(parent_shard_id)
+    }
+
+    /// Maps an account to the shard that contains it. For V0, maps according
+    /// to the hash of the account id; for V1, by its position among the
+    /// boundary accounts.
+    pub fn account_id_to_shard_id(&self, account_id: &AccountId) -> ShardId {
+        match self {
+            Self::V0(v0) => {
+                let hash = hash(account_id.as_str().as_bytes());
+                let prefix = u64::from_le_bytes(
+                    hash.as_bytes()[..8].try_into().expect("hash is 32 bytes"),
+                );
+                prefix % v0.num_shards
+            }
+            Self::V1(v1) => {
+                // The boundary accounts are sorted; an account equal to a
+                // boundary lands in the shard above it.
+                v1.boundary_accounts
+                    .partition_point(|boundary| boundary.as_str() <= account_id.as_str())
+                    as ShardId
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn two_boundary_layout() -> ShardLayout {
+        ShardLayout::v1(
+            vec!["keeper".parse().unwrap(), "ripe".parse().unwrap()],
+            None,
+            1,
+        )
+    }
+
+    #[test]
+    fn test_shard_ids_vec_matches_iterator() {
+        for layout in [ShardLayout::single_shard(), two_boundary_layout()] {
+            assert_eq!(layout.shard_ids_vec(), layout.shard_ids().collect::<Vec<_>>());
+        }
+        assert_eq!(two_boundary_layout().shard_ids_vec(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_account_id_to_shard_id() {
+        let layout = two_boundary_layout();
+        assert_eq!(layout.account_id_to_shard_id(&"aurora".parse().unwrap()), 0);
+        assert_eq!(layout.account_id_to_shard_id(&"keeper".parse().unwrap()), 1);
+        assert_eq!(layout.account_id_to_shard_id(&"pineapple".parse().unwrap()), 1);
+        assert_eq!(layout.account_id_to_shard_id(&"ripe".parse().unwrap()), 2);
+        assert_eq!(layout.account_id_to_shard_id(&"zebra".parse().unwrap()), 2);
+    }
+
+    #[test]
+    fn test_account_id_to_shard_id_matches_linear_scan() {
+        // Hundreds of sorted boundaries; `aa000`, `aa003`, `aa006`, ...
+        let boundary_accounts: Vec<AccountId> =
+            (0..300).map(|i| format!("aa{:03}", i * 3).parse().unwrap()).collect();
+        let layout = ShardLayout::v1(boundary_accounts.clone(), None, 1);
+
+        let linear_scan = |account_id: &AccountId| -> ShardId {
+            let mut shard_id = 0;
+            for boundary_account in &boundary_accounts {
+                if account_id.as_str() < boundary_account.as_str() {
+                    break;
+                }
+                shard_id += 1;
+            }
+            shard_id
+        };
+
+        // Every boundary itself, its neighbours, and ids off both ends.
+        let mut samples: Vec<AccountId> = vec!["a0".parse().unwrap(), "zz".parse().unwrap()];
+        for i in 0..900 {
+            samples.push(format!("aa{i:03}").parse().unwrap());
+            samples.push(format!("aa{i:03}x").parse().unwrap());
+        }
+        for account_id in &samples {
+            assert_eq!(
+                layout.account_id_to_shard_id(account_id),
+                linear_scan(account_id),
+                "mismatch for {account_id}",
+            );
+        }
+        // An account equal to a boundary lands in the higher shard.
+        assert_eq!(layout.account_id_to_shard_id(&"aa000".parse().unwrap()), 1);
+    }
+
+    #[test]
+    fn test_v0_assigns_by_account_hash() {
+        let layout = ShardLayout::v0(4, 0);
+        assert_eq!(layout.num_shards(), 4);
+        assert_eq!(layout.version(), 0);
+        assert_eq!(layout.shard_ids_vec(), vec![0, 1, 2, 3]);
+        assert!(layout.boundary_accounts().is_empty());
+
+        // Deterministic and in range for any account.
+        for name in ["alice", "bob", "contract.near", "a-very.long_account.id"] {
+            let account: AccountId = name.parse().unwrap();
+            let shard_id = layout.account_id_to_shard_id(&account);
+            assert!(shard_id < 4);
+            assert_eq!(layout.account_id_to_shard_id(&account), shard_id);
+            assert_eq!(layout.shard_uid(shard_id), Ok(ShardUId::new(0, shard_id)));
+        }
+        // A single-shard V0 layout maps everything to shard 0.
+        let single = ShardLayout::v0(1, 0);
+        assert_eq!(single.account_id_to_shard_id(&"alice".parse().unwrap()), 0);
+
+        // V0 predates split maps, so no shard has a parent.
+        assert_eq!(
+            layout.get_parent_shard_id(0),
+            Err(ShardLayoutError::NoParent { shard_id: 0 })
+        );
+    }
+
+    #[test]
+    fn test_layout_serde_round_trips() {
+        for layout in [ShardLayout::v0(4, 0), two_boundary_layout(), ShardLayout::single_shard()]
+        {
+            let json = serde_json::to_string(&layout).unwrap();
+            assert_eq!(serde_json::from_str::<ShardLayout>(&json).unwrap(), layout);
+            let bytes = borsh::to_vec(&layout).unwrap();
+            assert_eq!(ShardLayout::try_from_slice(&bytes).unwrap(), layout);
+        }
+    }
+
+    #[test]
+    fn test_get_parent_shard_uid_stamps_parent_version() {
+        // v2 has two shards; v3 splits the second one into shards 1 and 2.
+        let parent_layout = ShardLayout::v1(vec!["keeper".parse().unwrap()], None, 2);
+        let child_layout = ShardLayout::v1(
+            vec!["keeper".parse().unwrap(), "ripe".parse().unwrap()],
+            Some(vec![vec![0], vec![1, 2]]),
+            3,
+        );
+
+        let child = ShardUId::new(3, 2);
+        assert_eq!(
+            child_layout.get_parent_shard_uid(child, &parent_layout),
+            Ok(ShardUId::new(2, 1))
+        );
+        assert_eq!(
+            child_layout.get_parent_shard_uid(ShardUId::new(3, 0), &parent_layout),
+            Ok(ShardUId::new(2, 0))
+        );
+    }
+
+    #[test]
+    fn test_get_parent_shard_uid_errors() {
+        let parent_layout = ShardLayout::v1(vec!["keeper".parse().unwrap()], None, 2);
+        let child_layout = ShardLayout::v1(
+            vec!["keeper".parse().unwrap(), "ripe".parse().unwrap()],
+            Some(vec![vec![0], vec![1, 2]]),
+            3,
+        );
+
+        // A uid stamped with the wrong version does not belong to the layout.
+        assert_eq!(
+            child_layout.get_parent_shard_uid(ShardUId::new(2, 1), &parent_layout),
+            Err(ShardLayoutError::InvalidShardVersion { got: 2, expected: 3 })
+        );
+        // A shard id the layout does not have.
+        assert_eq!(
+            child_layout.get_parent_shard_uid(ShardUId::new(3, 7), &parent_layout),
+            Err(ShardLayoutError::InvalidShardId { shard_id: 7 })
+        );
+        // A layout without a split map has no parents at all.
+        assert_eq!(
+            parent_layout.get_parent_shard_id(0),
+            Err(ShardLayoutError::NoParent { shard_id: 0 })
+        );
+    }
+
+    #[test]
+    fn test_next_shard_prefix() {
+        // A normal increment bumps the last byte.
+        let bytes = ShardUId::new(1, 2).to_bytes();
+        assert_eq!(bytes, [1, 0, 0, 0, 2, 0, 0, 0]);
+        assert_eq!(
+            ShardUId::try_next_shard_prefix(&bytes),
+            Some([1, 0, 0, 0, 2, 0, 0, 1])
+        );
+        assert_eq!(ShardUId::next_shard_prefix(&bytes), [1, 0, 0, 0, 2, 0, 0, 1]);
+
+        // The carry ripples across byte boundaries.
+        assert_eq!(
+            ShardUId::try_next_shard_prefix(&[0, 0, 0, 0, 0, 0, 0, 255]),
+            Some([0, 0, 0, 0, 0, 0, 1, 0])
+        );
+        assert_eq!(
+            ShardUId::try_next_shard_prefix(&[0, 255, 255, 255, 255, 255, 255, 255]),
+            Some([1, 0, 0, 0, 0, 0, 0, 0])
+        );
+
+        // The maximum prefix has no successor of the same length.
+        assert_eq!(ShardUId::try_next_shard_prefix(&[255; 8]), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "does not exist")]
+    fn test_next_shard_prefix_panics_at_the_maximum() {
+        ShardUId::next_shard_prefix(&[255; 8]);
+    }
+
+    #[test]
+    fn test_shard_uid_string_round_trip() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+        use std::str::FromStr;
+
+        assert_eq!(ShardUId::new(3, 2).to_string(), "s2.v3");
+        let mut rng = StdRng::seed_from_u64(0x5eed);
+        for _ in 0..1000 {
+            let shard_uid =
+                ShardUId { version: rng.r#gen(), shard_id: rng.r#gen() };
+            assert_eq!(ShardUId::from_str(&shard_uid.to_string()), Ok(shard_uid));
+        }
+    }
+
+    #[test]
+    fn test_shard_uid_parse_errors() {
+        for (input, expected) in [
+            ("s0", "shard uid \"s0\" must look like `s<shard>.v<version>`"),
+            ("v3.s0", "shard uid \"v3.s0\" must look like `s<shard>.v<version>`"),
+            ("sx.v1", "invalid shard id in shard uid \"sx.v1\""),
+            ("s0.vx", "invalid version in shard uid \"s0.vx\""),
+        ] {
+            let err = input.parse::<ShardUId>().unwrap_err();
+            assert_eq!(err.to_string(), expected, "for input {input:?}");
+        }
+    }
+}
@@ -0,0 +1,670 @@
+use crate::congestion_info::CongestionInfo;
+use crate::hash::CryptoHash;
+use crate::merkle::{MerklePath, merklize, verify_path};
+use crate::transaction::SignedTransaction;
+use crate::types::{AccountId, Balance, BlockHeight, EpochId, Gas, ShardId, ValidatorStake};
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_crypto::{PublicKey, Signature};
+use std::fmt;
+
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+)]
+pub struct ChunkHash(pub CryptoHash);
+
+impl ChunkHash {
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        self.0.as_bytes()
+    }
+}
+
+impl From<ChunkHash> for CryptoHash {
+    fn from(chunk_hash: ChunkHash) -> Self {
+        chunk_hash.0
+    }
+}
+
+/// The short 8-character form, matching how log lines abbreviate hashes.
+impl fmt::Display for ChunkHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0.short())
+    }
+}
+
+/// Identifies one expected chunk: the chunk of a shard at a height of an
+/// epoch. Use this instead of ad-hoc `(BlockHeight, ShardId)` tuples so the
+/// epoch is never dropped from the key.
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+)]
+pub struct ChunkProductionKey {
+    pub epoch_id: EpochId,
+    pub height_created: BlockHeight,
+    pub shard_id: ShardId,
+}
+
+impl ChunkProductionKey {
+    pub const ENCODED_LEN: usize = 48;
+
+    /// Compact big-endian encoding for store keys; keys of one epoch sort
+    /// by height, then by shard.
+    pub fn to_bytes(self) -> [u8; Self::ENCODED_LEN] {
+        let mut bytes = [0; Self::ENCODED_LEN];
+        bytes[..32].copy_from_slice(self.epoch_id.0.as_bytes());
+        bytes[32..40].copy_from_slice(&self.height_created.to_be_bytes());
+        bytes[40..].copy_from_slice(&self.shard_id.to_be_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; Self::ENCODED_LEN]) -> Self {
+        Self {
+            epoch_id: EpochId(CryptoHash(bytes[..32].try_into().unwrap())),
+            height_created: BlockHeight::from_be_bytes(bytes[32..40].try_into().unwrap()),
+            shard_id: ShardId::from_be_bytes(bytes[40..].try_into().unwrap()),
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ChunkHeaderError {
+    #[error(
+        "chunk already included at height {previous}, cannot re-include at height {new}"
+    )]
+    DoubleInclusion { previous: BlockHeight, new: BlockHeight },
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ShardChunkHeaderInner {
+    V3(ShardChunkHeaderInnerV3),
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ShardChunkHeaderInnerV3 {
+    /// Previous block hash.
+    pub prev_block_hash: CryptoHash,
+    /// Root of the state of the previous chunk.
+    pub prev_state_root: CryptoHash,
+    /// Root of the outcomes from execution transactions and results of the
+    /// previous chunk.
+    pub prev_outcome_root: CryptoHash,
+    /// The merkle root of the reed solomon encoded parts of this chunk.
+    pub encoded_merkle_root: CryptoHash,
+    /// Size of the encoded chunk in bytes.
+    pub encoded_length: u64,
+    /// Height at which the chunk was created.
+    pub height_created: BlockHeight,
+    /// Shard index.
+    pub shard_id: ShardId,
+    /// Gas used in the previous chunk.
+    pub prev_gas_used: Gas,
+    /// Gas limit voted by validators.
+    pub gas_limit: Gas,
+    /// Total balance burnt in the previous chunk.
+    pub prev_balance_burnt: Balance,
+    /// Root of the outgoing receipts generated by the previous chunk.
+    pub prev_outgoing_receipts_root: CryptoHash,
+    /// Root of the transactions included in this chunk.
+    pub tx_root: CryptoHash,
+    /// Validator proposals from the previous chunk.
+    pub prev_validator_proposals: Vec<ValidatorStake>,
+    /// Congestion info about this shard after the previous chunk was applied.
+    pub congestion_info: CongestionInfo,
+}
+
+impl ShardChunkHeaderInner {
+    /// How much of the gas limit the previous chunk used, in `0.0..=1.0`;
+    /// zero when there is no limit to utilize.
+    pub fn gas_utilization(&self) -> f64 {
+        let (prev_gas_used, gas_limit) = match self {
+            Self::V3(inner) => (inner.prev_gas_used, inner.gas_limit),
+        };
+        if gas_limit == 0 {
+            return 0.0;
+        }
+        prev_gas_used as f64 / gas_limit as f64
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ShardChunkHeaderV3 {
+    pub inner: ShardChunkHeaderInner,
+    /// The height at which the chunk was included in a block; zero until the
+    /// chunk is included. Only mutable through
+    /// [`ShardChunkHeader::set_height_included`].
+    height_included: BlockHeight,
+    /// Signature of the chunk producer over the inner hash.
+    pub signature: Signature,
+    #[borsh(skip)]
+    pub hash: ChunkHash,
+}
+
+impl ShardChunkHeaderV3 {
+    pub fn new(inner: ShardChunkHeaderInnerV3, signature: Signature) -> Self {
+        let inner = ShardChunkHeaderInner::V3(inner);
+        let hash = Self::compute_hash(&inner);
+        Self { inner, height_included: 0, signature, hash }
+    }
+
+    pub fn compute_hash(inner: &ShardChunkHeaderInner) -> ChunkHash {
+        ChunkHash(CryptoHash::hash_borsh(inner))
+    }
+}
+
+/// Versioned chunk header. Use the accessors below instead of matching on the
+/// version to reach inner fields.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ShardChunkHeader {
+    V3(ShardChunkHeaderV3),
+}
+
+impl ShardChunkHeader {
+    #[inline]
+    pub fn inner(&self) -> &ShardChunkHeaderInner {
+        match self {
+            Self::V3(header) => &header.inner,
+        }
+    }
+
+    pub fn chunk_hash(&self) -> &ChunkHash {
+        match self {
+            Self::V3(header) => &header.hash,
+        }
+    }
+
+    pub fn signature(&self) -> &Signature {
+        match self {
+            Self::V3(header) => &header.signature,
+        }
+    }
+
+    pub fn height_included(&self) -> BlockHeight {
+        match self {
+            Self::V3(header) => header.height_included,
+        }
+    }
+
+    /// Records the height at which the chunk got included in a block.
+    ///
+    /// Returns an error if the header already carries a different nonzero
+    /// inclusion height, which would indicate a double-inclusion bug.
+    pub fn set_height_included(&mut self, height: BlockHeight) -> Result<(), ChunkHeaderError> {
+        let height_included = match self {
+            Self::V3(header) => &mut header.height_included,
+        };
+        if *height_included != 0 && *height_included != height {
+            return Err(ChunkHeaderError::DoubleInclusion {
+                previous: *height_included,
+                new: height,
+            });
+        }
+        *height_included = height;
+        Ok(())
+    }
+
+    /// Whether the chunk is a new chunk in the block at the given height, as
+    /// opposed to a copy of the header carried over from an older block.
+    pub fn is_new_chunk(&self, block_height: BlockHeight) -> bool {
+        self.height_included() == block_height
+    }
+
+    pub fn shard_id(&self) -> ShardId {
+        match self.inner() {
+            ShardChunkHeaderInner::V3(inner) => inner.shard_id,
+        }
+    }
+
+    pub fn gas_limit(&self) -> Gas {
+        match self.inner() {
+            ShardChunkHeaderInner::V3(inner) => inner.gas_limit,
+        }
+    }
+
+    pub fn prev_gas_used(&self) -> Gas {
+        match self.inner() {
+            ShardChunkHeaderInner::V3(inner) => inner.prev_gas_used,
+        }
+    }
+
+    pub fn prev_balance_burnt(&self) -> Balance {
+        match self.inner() {
+            ShardChunkHeaderInner::V3(inner) => inner.prev_balance_burnt,
+        }
+    }
+
+    pub fn encoded_length(&self) -> u64 {
+        match self.inner() {
+            ShardChunkHeaderInner::V3(inner) => inner.encoded_length,
+        }
+    }
+
+    pub fn encoded_merkle_root(&self) -> &CryptoHash {
+        match self.inner() {
+            ShardChunkHeaderInner::V3(inner) => &inner.encoded_merkle_root,
+        }
+    }
+
+    pub fn prev_block_hash(&self) -> &CryptoHash {
+        match self.inner() {
+            ShardChunkHeaderInner::V3(inner) => &inner.prev_block_hash,
+        }
+    }
+
+    pub fn prev_state_root(&self) -> &CryptoHash {
+        match self.inner() {
+            ShardChunkHeaderInner::V3(inner) => &inner.prev_state_root,
+        }
+    }
+
+    pub fn prev_outcome_root(&self) -> &CryptoHash {
+        match self.inner() {
+            ShardChunkHeaderInner::V3(inner) => &inner.prev_outcome_root,
+        }
+    }
+
+    pub fn prev_outgoing_receipts_root(&self) -> &CryptoHash {
+        match self.inner() {
+            ShardChunkHeaderInner::V3(inner) => &inner.prev_outgoing_receipts_root,
+        }
+    }
+
+    pub fn tx_root(&self) -> &CryptoHash {
+        match self.inner() {
+            ShardChunkHeaderInner::V3(inner) => &inner.tx_root,
+        }
+    }
+
+    pub fn height_created(&self) -> BlockHeight {
+        match self.inner() {
+            ShardChunkHeaderInner::V3(inner) => inner.height_created,
+        }
+    }
+
+    pub fn prev_validator_proposals(&self) -> &[ValidatorStake] {
+        match self.inner() {
+            ShardChunkHeaderInner::V3(inner) => &inner.prev_validator_proposals,
+        }
+    }
+
+    pub fn congestion_info(&self) -> &CongestionInfo {
+        match self.inner() {
+            ShardChunkHeaderInner::V3(inner) => &inner.congestion_info,
+        }
+    }
+
+    /// See [`ShardChunkHeaderInner::gas_utilization`].
+    pub fn gas_utilization(&self) -> f64 {
+        self.inner().gas_utilization()
+    }
+
+    /// Verifies the producer signature over the chunk hash.
+    pub fn verify(&self, public_key: &PublicKey) -> bool {
+        self.signature().verify(self.chunk_hash().as_bytes(), public_key)
+    }
+}
+
+/// `shard N height H <short hash>`: the fields chunk tracking log lines key
+/// on, in one deterministic form.
+impl fmt::Display for ShardChunkHeader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "shard {} height {} {}",
+            self.shard_id(),
+            self.height_created(),
+            self.chunk_hash(),
+        )
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ChunkValidationError {
+    #[error("chunk hash {got:?} does not match its header hash {expected:?}")]
+    InvalidChunkHash { got: ChunkHash, expected: ChunkHash },
+    #[error("transaction root {got} does not match the header tx_root {expected}")]
+    InvalidTxRoot { got: CryptoHash, expected: CryptoHash },
+    #[error("failed to decode chunk body: {0}")]
+    InvalidChunkBody(String),
+}
+
+/// Versioned chunk body: the header together with the transactions it commits
+/// to via `tx_root`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ShardChunk {
+    V2(ShardChunkV2),
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ShardChunkV2 {
+    pub chunk_hash: ChunkHash,
+    pub header: ShardChunkHeader,
+    pub transactions: Vec<SignedTransaction>,
+}
+
+impl ShardChunk {
+    pub fn new(header: ShardChunkHeader, transactions: Vec<SignedTransaction>) -> Self {
+        let chunk_hash = header.chunk_hash().clone();
+        Self::V2(ShardChunkV2 { chunk_hash, header, transactions })
+    }
+
+    pub fn chunk_hash(&self) -> &ChunkHash {
+        match self {
+            Self::V2(chunk) => &chunk.chunk_hash,
+        }
+    }
+
+    pub fn header(&self) -> &ShardChunkHeader {
+        match self {
+            Self::V2(chunk) => &chunk.header,
+        }
+    }
+
+    pub fn transactions(&self) -> &[SignedTransaction] {
+        match self {
+            Self::V2(chunk) => &chunk.transactions,
+        }
+    }
+
+    /// Merkle root of the transaction hashes, in chunk order. This is what
+    /// `ShardChunkHeaderInner::tx_root` commits to.
+    pub fn compute_tx_root(transactions: &[SignedTransaction]) -> CryptoHash {
+        let tx_hashes: Vec<_> = transactions.iter().map(|tx| tx.get_hash()).collect();
+        merklize(&tx_hashes).0
+    }
+
+    /// Inclusion paths for each transaction of the chunk, in chunk order,
+    /// against [`Self::compute_tx_root`].
+    pub fn compute_tx_proofs(transactions: &[SignedTransaction]) -> Vec<MerklePath> {
+        let tx_hashes: Vec<_> = transactions.iter().map(|tx| tx.get_hash()).collect();
+        merklize(&tx_hashes).1
+    }
+
+    /// Checks that the body matches what the header commits to: the cached
+    /// chunk hash is the header hash and the transactions hash to `tx_root`.
+    pub fn validate_consistency(&self) -> Result<(), ChunkValidationError> {
+        if self.chunk_hash() != self.header().chunk_hash() {
+            return Err(ChunkValidationError::InvalidChunkHash {
+                got: self.chunk_hash().clone(),
+                expected: self.header().chunk_hash().clone(),
+            });
+        }
+        let tx_root = Self::compute_tx_root(self.transactions());
+        if &tx_root != self.header().tx_root() {
+            return Err(ChunkValidationError::InvalidTxRoot {
+                got: tx_root,
+                expected: *self.header().tx_root(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// A chunk validator's endorsement of a chunk, gossiped to the block producer
+/// so it can assemble the endorsement signatures of the block body.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ChunkEndorsement {
+    pub chunk_hash: ChunkHash,
+    /// The endorsing chunk validator.
+    pub account_id: AccountId,
+    /// Signature over the chunk hash.
+    pub signature: Signature,
+}
+
+/// Verifies a per-transaction inclusion proof against the chunk header's
+/// `tx_root`, e.g. for a light client that only has the header.
+pub fn verify_tx_in_chunk(tx_hash: CryptoHash, chunk: &ShardChunk, path: &MerklePath) -> bool {
+    verify_path(*chunk.header().tx_root(), path, &tx_hash)
+}
+
+/// A chunk body encoded into equally-sized parts for distribution; parts are
+/// borsh bytes of the transactions. Decoding reassembles the body and rejects
+/// it if it does not match the header.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EncodedShardChunk {
+    pub header: ShardChunkHeader,
+    pub parts: Vec<Vec<u8>>,
+    /// Length of the encoded body in bytes; the last part is padded past it.
+    pub encoded_length: u64,
+}
+
+impl EncodedShardChunk {
+    pub fn new(
+        header: ShardChunkHeader,
+        transactions: &[SignedTransaction],
+        num_parts: usize,
+    ) -> Self {
+        assert!(num_parts > 0, "chunk must be encoded into at least one part");
+        let bytes = borsh::to_vec(transactions).expect("failed to serialize transactions");
+        let encoded_length = bytes.len() as u64;
+        let part_length = (bytes.len()).div_ceil(num_parts).max(1);
+        let parts = (0..num_parts)
+            .map(|i| {
+                let start = (i * part_length).min(bytes.len());
+                let end = ((i + 1) * part_length).min(bytes.len());
+                let mut part = bytes[start..end].to_vec();
+                part.resize(part_length, 0);
+                part
+            })
+            .collect();
+        Self { header, parts, encoded_length }
+    }
+
+    /// Reassembles and decodes the chunk body, then validates it against the
+    /// header so a mismatching body is rejected at decode time.
+    pub fn decode_chunk(&self) -> Result<ShardChunk, ChunkValidationError> {
+        let mut bytes: Vec<u8> = self.parts.concat();
+        bytes.truncate(self.encoded_length as usize);
+        let mut transactions = Vec::<SignedTransaction>::try_from_slice(&bytes)
+            .map_err(|err| ChunkValidationError::InvalidChunkBody(err.to_string()))?;
+        for tx in &mut transactions {
+            tx.init();
+        }
+        let chunk = ShardChunk::new(self.header.clone(), transactions);
+        chunk.validate_consistency()?;
+        Ok(chunk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::hash;
+
+    pub(crate) fn test_chunk_header(shard_id: ShardId, height_created: BlockHeight) -> ShardChunkHeader {
+        let inner = ShardChunkHeaderInnerV3 {
+            prev_block_hash: hash(b"prev block"),
+            prev_state_root: hash(b"state root"),
+            prev_outcome_root: hash(b"outcome root"),
+            encoded_merkle_root: hash(b"encoded merkle root"),
+            encoded_length: 100,
+            height_created,
+            shard_id,
+            prev_gas_used: 10,
+            gas_limit: 1000,
+            prev_balance_burnt: 7,
+            prev_outgoing_receipts_root: hash(b"receipts root"),
+            tx_root: hash(b"tx root"),
+            prev_validator_proposals: vec![],
+            congestion_info: CongestionInfo::default(),
+        };
+        ShardChunkHeader::V3(ShardChunkHeaderV3::new(inner, Signature::default()))
+    }
+
+    #[test]
+    fn test_gas_utilization() {
+        let header_with_gas = |prev_gas_used, gas_limit| {
+            let inner = ShardChunkHeaderInnerV3 {
+                prev_block_hash: hash(b"prev block"),
+                prev_state_root: hash(b"state root"),
+                prev_outcome_root: hash(b"outcome root"),
+                encoded_merkle_root: hash(b"encoded merkle root"),
+                encoded_length: 100,
+                height_created: 1,
+                shard_id: 0,
+                prev_gas_used,
+                gas_limit,
+                prev_balance_burnt: 0,
+                prev_outgoing_receipts_root: hash(b"receipts root"),
+                tx_root: hash(b"tx root"),
+                prev_validator_proposals: vec![],
+                congestion_info: CongestionInfo::default(),
+            };
+            ShardChunkHeader::V3(ShardChunkHeaderV3::new(inner, Signature::default()))
+        };
+
+        assert_eq!(header_with_gas(0, 1000).gas_utilization(), 0.0);
+        assert_eq!(header_with_gas(500, 1000).gas_utilization(), 0.5);
+        assert_eq!(header_with_gas(1000, 1000).gas_utilization(), 1.0);
+        // A zero gas limit reads as no utilization, not a division by zero.
+        assert_eq!(header_with_gas(10, 0).gas_utilization(), 0.0);
+        // The header accessor forwards to the inner one.
+        let header = header_with_gas(500, 1000);
+        assert_eq!(header.gas_utilization(), header.inner().gas_utilization());
+    }
+
+    #[test]
+    fn test_display_formats() {
+        let header = test_chunk_header(3, 100);
+        let short = &header.chunk_hash().0.to_string()[..8];
+        assert_eq!(header.chunk_hash().to_string(), short);
+        assert_eq!(header.to_string(), format!("shard 3 height 100 {short}"));
+    }
+
+    #[test]
+    fn test_chunk_production_key_encoding_round_trips() {
+        let key = ChunkProductionKey {
+            epoch_id: EpochId(hash(b"epoch")),
+            height_created: 12345,
+            shard_id: 3,
+        };
+        assert_eq!(ChunkProductionKey::from_bytes(&key.to_bytes()), key);
+
+        // Byte order of the encoding matches the derived `Ord`.
+        let later = ChunkProductionKey { height_created: 12346, shard_id: 0, ..key };
+        assert!(key < later);
+        assert!(key.to_bytes() < later.to_bytes());
+    }
+
+    #[test]
+    fn test_accessors_match_inner_fields() {
+        let header = test_chunk_header(3, 10);
+        let ShardChunkHeaderInner::V3(inner) = header.inner().clone();
+        assert_eq!(header.shard_id(), inner.shard_id);
+        assert_eq!(header.gas_limit(), inner.gas_limit);
+        assert_eq!(header.prev_gas_used(), inner.prev_gas_used);
+        assert_eq!(header.prev_balance_burnt(), inner.prev_balance_burnt);
+        assert_eq!(header.encoded_length(), inner.encoded_length);
+        assert_eq!(header.encoded_merkle_root(), &inner.encoded_merkle_root);
+        assert_eq!(header.prev_block_hash(), &inner.prev_block_hash);
+        assert_eq!(header.height_created(), inner.height_created);
+        assert_eq!(header.prev_validator_proposals(), &inner.prev_validator_proposals[..]);
+        assert_eq!(header.congestion_info(), &inner.congestion_info);
+        assert_eq!(header.signature(), &Signature::default());
+        assert_eq!(header.chunk_hash(), &ShardChunkHeaderV3::compute_hash(header.inner()));
+    }
+
+    fn test_chunk_with_transactions(num_txs: u64) -> ShardChunk {
+        let transactions: Vec<_> =
+            (0..num_txs).map(crate::transaction::tests::test_transaction).collect();
+        let inner = ShardChunkHeaderInnerV3 {
+            prev_block_hash: hash(b"prev block"),
+            prev_state_root: hash(b"state root"),
+            prev_outcome_root: hash(b"outcome root"),
+            encoded_merkle_root: hash(b"encoded merkle root"),
+            encoded_length: 100,
+            height_created: 10,
+            shard_id: 0,
+            prev_gas_used: 10,
+            gas_limit: 1000,
+            prev_balance_burnt: 7,
+            prev_outgoing_receipts_root: hash(b"receipts root"),
+            tx_root: ShardChunk::compute_tx_root(&transactions),
+            prev_validator_proposals: vec![],
+            congestion_info: CongestionInfo::default(),
+        };
+        let header = ShardChunkHeader::V3(ShardChunkHeaderV3::new(inner, Signature::default()));
+        ShardChunk::new(header, transactions)
+    }
+
+    #[test]
+    fn test_decode_chunk_roundtrip() {
+        let chunk = test_chunk_with_transactions(5);
+        chunk.validate_consistency().unwrap();
+        for num_parts in [1, 3, 16] {
+            let encoded =
+                EncodedShardChunk::new(chunk.header().clone(), chunk.transactions(), num_parts);
+            assert_eq!(encoded.decode_chunk().unwrap(), chunk);
+        }
+    }
+
+    #[test]
+    fn test_decode_chunk_rejects_swapped_transactions() {
+        let chunk = test_chunk_with_transactions(5);
+        let mut transactions = chunk.transactions().to_vec();
+        transactions.swap(0, 1);
+        let encoded = EncodedShardChunk::new(chunk.header().clone(), &transactions, 3);
+        assert!(matches!(
+            encoded.decode_chunk(),
+            Err(ChunkValidationError::InvalidTxRoot { .. })
+        ));
+    }
+
+    #[test]
+    fn test_decode_chunk_rejects_garbage_body() {
+        let chunk = test_chunk_with_transactions(2);
+        let mut encoded = EncodedShardChunk::new(chunk.header().clone(), chunk.transactions(), 3);
+        encoded.parts[0][0] ^= 0xff;
+        assert!(encoded.decode_chunk().is_err());
+    }
+
+    #[test]
+    fn test_tx_inclusion_proofs() {
+        let chunk = test_chunk_with_transactions(7);
+        let proofs = ShardChunk::compute_tx_proofs(chunk.transactions());
+        assert_eq!(proofs.len(), chunk.transactions().len());
+        for (tx, path) in chunk.transactions().iter().zip(&proofs) {
+            assert!(verify_tx_in_chunk(tx.get_hash(), &chunk, path));
+            // A proof for one transaction must not verify another's hash.
+            assert!(!verify_tx_in_chunk(hash(b"other tx"), &chunk, path));
+        }
+    }
+
+    #[test]
+    fn test_set_height_included_guard() {
+        let mut header = test_chunk_header(0, 10);
+        assert_eq!(header.height_included(), 0);
+        assert!(!header.is_new_chunk(11));
+
+        header.set_height_included(11).unwrap();
+        assert_eq!(header.height_included(), 11);
+        assert!(header.is_new_chunk(11));
+        assert!(!header.is_new_chunk(12));
+
+        // Setting the same height again is idempotent.
+        header.set_height_included(11).unwrap();
+
+        // Setting a different height is a double-inclusion bug.
+        assert_eq!(
+            header.set_height_included(12),
+            Err(ChunkHeaderError::DoubleInclusion { previous: 11, new: 12 })
+        );
+        assert_eq!(header.height_included(), 11);
+    }
+}
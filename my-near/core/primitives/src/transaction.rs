@@ -0,0 +1,232 @@
+use crate::action::Action;
+use crate::hash::CryptoHash;
+use crate::types::{AccountId, Gas, Nonce};
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_crypto::{PublicKey, SecretKey, Signature};
+use serde::{Deserialize, Serialize};
+
+/// An unsigned transaction: a batch of actions from a signer to a receiver.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Transaction {
+    /// Account that signs and pays for the transaction.
+    pub signer_id: AccountId,
+    /// Access key used to sign the transaction.
+    pub public_key: PublicKey,
+    /// Nonce of the access key, to order transactions and prevent replays.
+    pub nonce: Nonce,
+    /// Account the actions are applied to.
+    pub receiver_id: AccountId,
+    /// A recent block hash, to expire stale transactions.
+    pub block_hash: CryptoHash,
+    pub actions: Vec<Action>,
+    /// Fee the signer attaches to outbid other transactions for inclusion
+    /// when the receiving shard is congested; zero asks for no priority.
+    #[serde(default)]
+    pub priority_fee: u64,
+}
+
+impl Transaction {
+    /// Hash of the borsh-serialized transaction, and its serialized size.
+    pub fn get_hash_and_size(&self) -> (CryptoHash, u64) {
+        let bytes = borsh::to_vec(self).expect("failed to serialize transaction");
+        (CryptoHash::hash_bytes(&bytes), bytes.len() as u64)
+    }
+
+    pub fn sign(self, secret_key: &SecretKey) -> SignedTransaction {
+        let signature = secret_key.sign(self.get_hash_and_size().0.as_bytes());
+        SignedTransaction::new(signature, self)
+    }
+}
+
+/// A transaction together with the signature over its hash.
+///
+/// The hash and size are computed on construction and cached; they are not
+/// part of the serialized form, neither the borsh nor the JSON one.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct SignedTransaction {
+    pub transaction: Transaction,
+    pub signature: Signature,
+    #[borsh(skip)]
+    #[serde(skip)]
+    hash: CryptoHash,
+    #[borsh(skip)]
+    #[serde(skip)]
+    size: u64,
+}
+
+// Deserialized by hand rather than derived so the cached hash and size are
+// recomputed via `init` instead of coming back as defaults.
+impl<'de> Deserialize<'de> for SignedTransaction {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct SignedTransactionFields {
+            transaction: Transaction,
+            signature: Signature,
+        }
+        let fields = SignedTransactionFields::deserialize(deserializer)?;
+        Ok(Self::new(fields.signature, fields.transaction))
+    }
+}
+
+impl SignedTransaction {
+    pub fn new(signature: Signature, transaction: Transaction) -> Self {
+        let mut signed_tx = Self { transaction, signature, hash: CryptoHash::default(), size: 0 };
+        signed_tx.init();
+        signed_tx
+    }
+
+    /// Recomputes the cached hash and size; must be called after
+    /// deserialization, which skips both fields.
+    pub fn init(&mut self) {
+        let (hash, size) = self.transaction.get_hash_and_size();
+        self.hash = hash;
+        self.size = size;
+    }
+
+    pub fn get_hash(&self) -> CryptoHash {
+        self.hash
+    }
+
+    pub fn get_size(&self) -> u64 {
+        self.size
+    }
+
+    /// The key congestion-aware transaction pools order by, highest first:
+    /// the declared priority fee plus one unit per teragas of attached
+    /// function-call gas, since attached gas is purchased up front and
+    /// burning a queue slot on it wastes more the longer it waits. When the
+    /// receiving shard stops accepting transactions -- see
+    /// [`crate::congestion_info::CongestionControl::shard_accepts_transactions`]
+    /// -- pools should keep the highest effective priorities and shed from
+    /// the bottom.
+    pub fn effective_priority(&self) -> u64 {
+        const TERAGAS: Gas = 1_000_000_000_000;
+        let attached_gas = self.transaction.actions.iter().fold(0u64, |total, action| {
+            match action {
+                Action::FunctionCall(call) => total.saturating_add(call.gas),
+                _ => total,
+            }
+        });
+        self.transaction.priority_fee.saturating_add(attached_gas / TERAGAS)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::action::TransferAction;
+    use near_crypto::KeyType;
+
+    pub(crate) fn test_transaction(nonce: Nonce) -> SignedTransaction {
+        let signer_id: AccountId = "alice".parse().unwrap();
+        let secret_key = SecretKey::from_seed(KeyType::ED25519, signer_id.as_str());
+        Transaction {
+            signer_id,
+            public_key: secret_key.public_key(),
+            nonce,
+            receiver_id: "bob".parse().unwrap(),
+            block_hash: CryptoHash::default(),
+            actions: vec![TransferAction { deposit: 100 }.into()],
+            priority_fee: 0,
+        }
+        .sign(&secret_key)
+    }
+
+    #[test]
+    fn test_effective_priority_orders_the_pool() {
+        let tx = |priority_fee, gas_teras: u64, nonce| {
+            let signer_id: AccountId = "alice".parse().unwrap();
+            let secret_key = SecretKey::from_seed(KeyType::ED25519, signer_id.as_str());
+            let mut actions = vec![TransferAction { deposit: 1 }.into()];
+            if gas_teras > 0 {
+                actions.push(
+                    crate::action::FunctionCallAction {
+                        method_name: "run".to_string(),
+                        args: vec![],
+                        gas: gas_teras * 1_000_000_000_000,
+                        deposit: 0,
+                    }
+                    .into(),
+                );
+            }
+            Transaction {
+                signer_id,
+                public_key: secret_key.public_key(),
+                nonce,
+                receiver_id: "bob".parse().unwrap(),
+                block_hash: CryptoHash::default(),
+                actions,
+                priority_fee,
+            }
+            .sign(&secret_key)
+        };
+
+        // A fee of 5 with no attached gas, no priority at all, gas only,
+        // and gas on top of a fee.
+        let fee_only = tx(5, 0, 1);
+        let plain = tx(0, 0, 2);
+        let gas_only = tx(0, 3, 3);
+        let fee_and_gas = tx(4, 2, 4);
+        assert_eq!(fee_only.effective_priority(), 5);
+        assert_eq!(plain.effective_priority(), 0);
+        assert_eq!(gas_only.effective_priority(), 3);
+        assert_eq!(fee_and_gas.effective_priority(), 6);
+
+        let mut pool = [&plain, &fee_only, &gas_only, &fee_and_gas];
+        pool.sort_by_key(|signed_tx| std::cmp::Reverse(signed_tx.effective_priority()));
+        let nonces: Vec<_> = pool.iter().map(|signed_tx| signed_tx.transaction.nonce).collect();
+        assert_eq!(nonces, [4, 1, 3, 2]);
+    }
+
+    #[test]
+    fn test_signature_verifies_against_hash() {
+        let signed_tx = test_transaction(1);
+        assert!(signed_tx.signature.verify(
+            signed_tx.get_hash().as_bytes(),
+            &signed_tx.transaction.public_key
+        ));
+    }
+
+    #[test]
+    fn test_json_round_trip_recomputes_hash() {
+        let signed_tx = test_transaction(7);
+        let json = serde_json::to_value(&signed_tx).unwrap();
+        // Keys and signatures travel as their display strings, the cached
+        // fields do not travel at all.
+        assert!(json["transaction"]["public_key"].as_str().unwrap().starts_with("ed25519:"));
+        assert!(json["signature"].as_str().unwrap().starts_with("ed25519:"));
+        assert!(json.get("hash").is_none());
+        assert!(json.get("size").is_none());
+
+        let decoded: SignedTransaction = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded, signed_tx);
+        // The hash is recomputed on deserialize and matches the borsh hash.
+        assert_eq!(decoded.get_hash(), signed_tx.get_hash());
+        assert_eq!(decoded.get_size(), signed_tx.get_size());
+    }
+
+    #[test]
+    fn test_json_rejects_invalid_account_id() {
+        let json = serde_json::json!({
+            "signer_id": "x",
+            "public_key": "ed25519:11111111111111111111111111111111",
+            "nonce": 1,
+            "receiver_id": "bob",
+            "block_hash": CryptoHash::default().to_string(),
+            "actions": [],
+        });
+        assert!(serde_json::from_value::<Transaction>(json).is_err());
+    }
+
+    #[test]
+    fn test_roundtrip_restores_hash_and_size() {
+        let signed_tx = test_transaction(7);
+        let bytes = borsh::to_vec(&signed_tx).unwrap();
+        let mut decoded = SignedTransaction::try_from_slice(&bytes).unwrap();
+        assert_eq!(decoded.get_hash(), CryptoHash::default());
+        decoded.init();
+        assert_eq!(decoded, signed_tx);
+        assert_eq!(decoded.get_hash(), signed_tx.get_hash());
+        assert_eq!(decoded.get_size(), signed_tx.get_size());
+    }
+}
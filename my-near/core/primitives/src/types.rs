@@ -0,0 +1,345 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_crypto::PublicKey;
+use std::fmt;
+use std::str::FromStr;
+
+/// Protocol version type.
+pub type ProtocolVersion = u32;
+
+/// Height of the block.
+pub type BlockHeight = u64;
+
+/// Height of the epoch.
+pub type EpochHeight = u64;
+
+/// Balance is type for storing amounts of tokens.
+pub type Balance = u128;
+
+/// Gas is a type for storing amount of gas.
+pub type Gas = u64;
+
+/// Shard index, from 0 up to `NumShards - 1`.
+pub type ShardId = u64;
+
+/// Number of shards in the chain.
+pub type NumShards = u64;
+
+/// Nonce for transactions.
+pub type Nonce = u64;
+
+/// Bytes of state an account occupies, for storage staking.
+pub type StorageUsage = u64;
+
+/// Hash of the state trie root of a shard.
+pub type StateRoot = crate::hash::CryptoHash;
+
+/// Number of seats of validators in a given shard.
+pub type NumSeats = u64;
+
+/// Validator identifier in the current epoch.
+pub type ValidatorId = u64;
+
+/// Seed for the pseudo random number generators used for validator sampling.
+pub type RngSeed = [u8; 32];
+
+/// Epoch identifier -- wrapped hash, to make it easier to distinguish.
+/// `EpochId` of epoch T is the hash of last block in T-2.
+/// `EpochId` of first two epochs is 0.
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+)]
+pub struct EpochId(pub crate::hash::CryptoHash);
+
+/// The short 8-character form; epoch ids recur across many log lines, the
+/// full hash adds nothing a reader would use.
+impl fmt::Display for EpochId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0.short())
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("invalid account id '{0}'")]
+pub struct ParseAccountError(pub String);
+
+/// A human readable account identifier.
+///
+/// Between 2 and 64 characters, consisting of lowercase alphanumeric segments
+/// separated by single `.`, `-` or `_` characters.
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    serde::Serialize,
+    serde::Deserialize,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+)]
+#[serde(try_from = "String")]
+pub struct AccountId(String);
+
+impl AccountId {
+    pub const MIN_LEN: usize = 2;
+    pub const MAX_LEN: usize = 64;
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    fn validate(value: &str) -> Result<(), ParseAccountError> {
+        if value.len() < Self::MIN_LEN || value.len() > Self::MAX_LEN {
+            return Err(ParseAccountError(value.to_string()));
+        }
+        let mut prev_was_separator = true;
+        for c in value.chars() {
+            match c {
+                'a'..='z' | '0'..='9' => prev_was_separator = false,
+                '.' | '-' | '_' => {
+                    if prev_was_separator {
+                        return Err(ParseAccountError(value.to_string()));
+                    }
+                    prev_was_separator = true;
+                }
+                _ => return Err(ParseAccountError(value.to_string())),
+            }
+        }
+        if prev_was_separator {
+            return Err(ParseAccountError(value.to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<String> for AccountId {
+    type Error = ParseAccountError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::validate(&value)?;
+        Ok(Self(value))
+    }
+}
+
+impl FromStr for AccountId {
+    type Err = ParseAccountError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::validate(s)?;
+        Ok(Self(s.to_string()))
+    }
+}
+
+impl AsRef<str> for AccountId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for AccountId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Reason a validator was kicked out of the validator set.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ValidatorKickoutReason {
+    /// The validator unstaked.
+    Unstaked,
+    /// The stake fell below the seat price.
+    NotEnoughStake { stake: Balance, threshold: Balance },
+    /// The validator produced too few blocks.
+    NotEnoughBlocks { produced: u64, expected: u64 },
+    /// The validator produced too few chunks.
+    NotEnoughChunks { produced: u64, expected: u64 },
+}
+
+impl fmt::Display for ValidatorKickoutReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unstaked => write!(f, "unstaked"),
+            Self::NotEnoughStake { stake, threshold } => {
+                write!(f, "stake {stake} below the seat price {threshold}")
+            }
+            Self::NotEnoughBlocks { produced, expected } => {
+                write!(f, "produced {produced} blocks out of {expected} expected")
+            }
+            Self::NotEnoughChunks { produced, expected } => {
+                write!(f, "produced {produced} chunks out of {expected} expected")
+            }
+        }
+    }
+}
+
+/// How many blocks or chunks a validator produced against how many it was
+/// expected to.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ValidatorStats {
+    pub produced: u64,
+    pub expected: u64,
+}
+
+/// A validator's block and chunk production statistics over an epoch,
+/// driving kickouts and the uptime scaling of rewards.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlockChunkValidatorStats {
+    pub block_stats: ValidatorStats,
+    pub chunk_stats: ValidatorStats,
+}
+
+/// A validator slashed by a challenge included in a block.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SlashedValidator {
+    pub account_id: AccountId,
+    /// Whether the validator was caught signing two blocks at the same
+    /// height, the strongest offence.
+    pub is_double_sign: bool,
+}
+
+impl SlashedValidator {
+    pub fn new(account_id: AccountId, is_double_sign: bool) -> Self {
+        Self { account_id, is_double_sign }
+    }
+}
+
+/// The validators slashed by the challenges included in a block.
+pub type ChallengesResult = Vec<SlashedValidator>;
+
+/// Where a slashed validator stands in the slashing pipeline.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlashState {
+    /// Slashed for double signing; the strongest penalty.
+    DoubleSign,
+    /// Slashed for a challenge other than double signing.
+    Other,
+    /// Slashed in an earlier epoch; the penalty has already been applied to
+    /// the validator set.
+    AlreadySlashed,
+}
+
+/// Epoch queries runtime components need, abstracted from the concrete
+/// epoch manager so they can be served by anything that knows the epochs --
+/// the epoch manager handle, a view client, or a test stub.
+pub trait EpochInfoProvider: Send + Sync {
+    /// Stake of the given account in the given epoch; `None` when the
+    /// account is not one of the epoch's validators.
+    fn validator_stake(
+        &self,
+        epoch_id: &EpochId,
+        account_id: &AccountId,
+    ) -> Result<Option<Balance>, crate::errors::EpochError>;
+
+    /// Combined stake of all the epoch's validators.
+    fn validator_total_stake(
+        &self,
+        epoch_id: &EpochId,
+    ) -> Result<Balance, crate::errors::EpochError>;
+
+    /// The minimum stake a validator seat costs in the epoch of the block
+    /// built on top of `prev_block_hash`.
+    fn minimum_stake(
+        &self,
+        prev_block_hash: &crate::hash::CryptoHash,
+    ) -> Result<Balance, crate::errors::EpochError>;
+}
+
+/// Stores a validator and its stake.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ValidatorStake {
+    V1(ValidatorStakeV1),
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ValidatorStakeV1 {
+    /// Account that stakes money.
+    pub account_id: AccountId,
+    /// Public key of the proposed validator.
+    pub public_key: PublicKey,
+    /// Stake / weight of the validator.
+    pub stake: Balance,
+}
+
+impl ValidatorStake {
+    pub fn new(account_id: AccountId, public_key: PublicKey, stake: Balance) -> Self {
+        Self::V1(ValidatorStakeV1 { account_id, public_key, stake })
+    }
+
+    pub fn account_id(&self) -> &AccountId {
+        match self {
+            Self::V1(v1) => &v1.account_id,
+        }
+    }
+
+    pub fn public_key(&self) -> &PublicKey {
+        match self {
+            Self::V1(v1) => &v1.public_key,
+        }
+    }
+
+    pub fn stake(&self) -> Balance {
+        match self {
+            Self::V1(v1) => v1.stake,
+        }
+    }
+
+    pub fn stake_mut(&mut self) -> &mut Balance {
+        match self {
+            Self::V1(v1) => &mut v1.stake,
+        }
+    }
+
+    pub fn take_account_id(self) -> AccountId {
+        match self {
+            Self::V1(v1) => v1.account_id,
+        }
+    }
+}
+
+/// `account@stake`, the form log lines print validators in.
+impl fmt::Display for ValidatorStake {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}@{}", self.account_id(), self.stake())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_account_id_validation() {
+        for valid in ["alice", "alice.near", "app-1.alice_near", "00"] {
+            assert!(AccountId::from_str(valid).is_ok(), "{valid} should be valid");
+        }
+        for invalid in ["a", "Alice", "alice..near", ".alice", "alice.", "al/ice"] {
+            assert!(AccountId::from_str(invalid).is_err(), "{invalid} should be invalid");
+        }
+        assert!(AccountId::from_str(&"a".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn test_display_formats() {
+        let hash = crate::hash::hash(b"epoch");
+        assert_eq!(EpochId(hash).to_string(), hash.to_string()[..8]);
+
+        let stake = ValidatorStake::new(
+            "alice".parse().unwrap(),
+            near_crypto::SecretKey::from_seed(near_crypto::KeyType::ED25519, "alice").public_key(),
+            1_000_000,
+        );
+        assert_eq!(stake.to_string(), "alice@1000000");
+    }
+}
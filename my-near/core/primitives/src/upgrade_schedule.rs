@@ -0,0 +1,191 @@
+use crate::types::ProtocolVersion;
+use chrono::{DateTime, Utc};
+
+#[derive(thiserror::Error, Clone, Debug)]
+pub enum ProtocolUpgradeVotingScheduleError {
+    #[error("The voting schedule must be sorted by datetime")]
+    InvalidDateTimeOrder,
+    #[error("The voting schedule must be sorted by version")]
+    InvalidVersionOrder,
+    #[error("The last version in the schedule must be the client protocol version")]
+    InvalidClientProtocolVersion,
+}
+
+/// Defines a schedule for validators to vote for the new protocol version.
+///
+/// The schedule is a list of `(datetime, version)` entries, each meaning that
+/// starting from `datetime` the node is allowed to vote for `version`. The
+/// schedule only bounds the vote from above; the protocol version that the next
+/// epoch actually runs is decided by stake-weighted votes. The epoch manager
+/// must therefore take the minimum of the version this schedule allows and the
+/// version that the voting outcome makes safe to adopt.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProtocolUpgradeVotingSchedule {
+    /// The highest protocol version supported by this client.
+    client_protocol_version: ProtocolVersion,
+    /// List of (datetime, version) tuples, sorted by datetime in increasing
+    /// order. An empty schedule means voting for the client protocol version
+    /// immediately.
+    schedule: Vec<(DateTime<Utc>, ProtocolVersion)>,
+}
+
+impl ProtocolUpgradeVotingSchedule {
+    /// A schedule that votes for the client protocol version right away.
+    pub fn new_immediate(client_protocol_version: ProtocolVersion) -> Self {
+        Self { client_protocol_version, schedule: vec![] }
+    }
+
+    /// Creates a schedule from a list of (datetime, version) entries.
+    ///
+    /// The entries must be sorted in strictly increasing order both by
+    /// datetime and by version, and the last entry must carry the client
+    /// protocol version.
+    pub fn new_from_schedule(
+        client_protocol_version: ProtocolVersion,
+        schedule: Vec<(DateTime<Utc>, ProtocolVersion)>,
+    ) -> Result<Self, ProtocolUpgradeVotingScheduleError> {
+        for i in 1..schedule.len() {
+            if schedule[i - 1].0 >= schedule[i].0 {
+                return Err(ProtocolUpgradeVotingScheduleError::InvalidDateTimeOrder);
+            }
+            if schedule[i - 1].1 >= schedule[i].1 {
+                return Err(ProtocolUpgradeVotingScheduleError::InvalidVersionOrder);
+            }
+        }
+        if let Some((_, last_version)) = schedule.last()
+            && *last_version != client_protocol_version
+        {
+            return Err(ProtocolUpgradeVotingScheduleError::InvalidClientProtocolVersion);
+        }
+        Ok(Self { client_protocol_version, schedule })
+    }
+
+    /// The highest protocol version that the schedule allows voting for at the
+    /// given time.
+    ///
+    /// This is the version of the latest schedule entry whose datetime has
+    /// passed. Before the first entry's datetime the schedule allows at most
+    /// the version just below the first scheduled one; with an empty schedule
+    /// there is no restriction and the client protocol version is returned.
+    pub fn max_scheduled_version_at(&self, now: DateTime<Utc>) -> ProtocolVersion {
+        let Some((_, first_version)) = self.schedule.first() else {
+            return self.client_protocol_version;
+        };
+        let mut result = first_version.saturating_sub(1);
+        for (datetime, version) in &self.schedule {
+            if *datetime <= now {
+                result = *version;
+            } else {
+                break;
+            }
+        }
+        result
+    }
+
+    /// This method returns the protocol version that the node should vote for,
+    /// given the time `now` and the protocol version of the next epoch.
+    ///
+    /// The vote never goes below what the network has already adopted and
+    /// never above what the schedule currently allows or what this client
+    /// supports.
+    pub fn get_protocol_version(
+        &self,
+        now: DateTime<Utc>,
+        next_epoch_protocol_version: ProtocolVersion,
+    ) -> ProtocolVersion {
+        let scheduled = self.max_scheduled_version_at(now).min(self.client_protocol_version);
+        scheduled.max(next_epoch_protocol_version.min(self.client_protocol_version))
+    }
+
+    /// The highest protocol version supported by this client.
+    pub fn client_protocol_version(&self) -> ProtocolVersion {
+        self.client_protocol_version
+    }
+
+    /// The raw schedule entries.
+    pub fn schedule(&self) -> &[(DateTime<Utc>, ProtocolVersion)] {
+        &self.schedule
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const CLIENT_PROTOCOL_VERSION: ProtocolVersion = 100;
+
+    fn datetime(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2000, 1, 1, hour, 0, 0).unwrap()
+    }
+
+    fn two_step_schedule() -> ProtocolUpgradeVotingSchedule {
+        ProtocolUpgradeVotingSchedule::new_from_schedule(
+            CLIENT_PROTOCOL_VERSION,
+            vec![(datetime(6), 99), (datetime(12), 100)],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_empty_schedule_votes_immediately() {
+        let schedule = ProtocolUpgradeVotingSchedule::new_immediate(CLIENT_PROTOCOL_VERSION);
+        assert_eq!(schedule.max_scheduled_version_at(datetime(0)), CLIENT_PROTOCOL_VERSION);
+        assert_eq!(schedule.get_protocol_version(datetime(0), 98), CLIENT_PROTOCOL_VERSION);
+    }
+
+    #[test]
+    fn test_max_scheduled_version_across_boundaries() {
+        let schedule = two_step_schedule();
+        // Before the first entry only the pre-upgrade version is allowed.
+        assert_eq!(schedule.max_scheduled_version_at(datetime(5)), 98);
+        // The entry datetime itself already allows the new version.
+        assert_eq!(schedule.max_scheduled_version_at(datetime(6)), 99);
+        assert_eq!(schedule.max_scheduled_version_at(datetime(11)), 99);
+        assert_eq!(schedule.max_scheduled_version_at(datetime(12)), 100);
+        assert_eq!(schedule.max_scheduled_version_at(datetime(23)), 100);
+    }
+
+    #[test]
+    fn test_vote_bounded_by_schedule() {
+        let schedule = two_step_schedule();
+        assert_eq!(schedule.get_protocol_version(datetime(5), 98), 98);
+        assert_eq!(schedule.get_protocol_version(datetime(6), 98), 99);
+        assert_eq!(schedule.get_protocol_version(datetime(12), 99), 100);
+    }
+
+    #[test]
+    fn test_vote_never_below_network_version() {
+        let schedule = two_step_schedule();
+        // The network already runs a higher version than the schedule allows
+        // at this time; the node must not vote for a downgrade.
+        assert_eq!(schedule.get_protocol_version(datetime(5), 99), 99);
+        // ...but it also never votes above the client protocol version.
+        assert_eq!(schedule.get_protocol_version(datetime(5), 101), CLIENT_PROTOCOL_VERSION);
+    }
+
+    #[test]
+    fn test_invalid_schedules_rejected() {
+        assert!(matches!(
+            ProtocolUpgradeVotingSchedule::new_from_schedule(
+                CLIENT_PROTOCOL_VERSION,
+                vec![(datetime(12), 99), (datetime(6), 100)],
+            ),
+            Err(ProtocolUpgradeVotingScheduleError::InvalidDateTimeOrder)
+        ));
+        assert!(matches!(
+            ProtocolUpgradeVotingSchedule::new_from_schedule(
+                CLIENT_PROTOCOL_VERSION,
+                vec![(datetime(6), 100), (datetime(12), 100)],
+            ),
+            Err(ProtocolUpgradeVotingScheduleError::InvalidVersionOrder)
+        ));
+        assert!(matches!(
+            ProtocolUpgradeVotingSchedule::new_from_schedule(
+                CLIENT_PROTOCOL_VERSION,
+                vec![(datetime(6), 99)],
+            ),
+            Err(ProtocolUpgradeVotingScheduleError::InvalidClientProtocolVersion)
+        ));
+    }
+}
@@ -0,0 +1,395 @@
+//! Validator mandates: validator stake split into equally sized mandates
+//! that can be assigned to shards for stateless chunk validation.
+
+use crate::types::{AccountId, Balance, RngSeed, ValidatorId, ValidatorStake};
+use num_rational::Rational32;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+/// Per shard, the validators assigned to it and how much of their stake
+/// backs that shard.
+pub type ChunkValidatorStakeAssignment = Vec<HashMap<ValidatorId, Balance>>;
+
+/// Parameters that determine how stake is split into mandates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ValidatorMandatesConfig {
+    /// Stake one whole mandate represents.
+    pub stake_per_mandate: Balance,
+    /// How many mandates each shard should ideally be backed by.
+    pub target_mandates_per_shard: usize,
+    /// Number of shards the mandates are distributed over.
+    pub num_shards: usize,
+}
+
+/// Ways a [`ValidatorMandatesConfig`] can be invalid.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ValidatorMandatesConfigError {
+    #[error("mandates must represent a positive stake")]
+    ZeroStakePerMandate,
+    #[error("there must be at least one shard")]
+    ZeroShards,
+}
+
+impl ValidatorMandatesConfig {
+    pub fn new(
+        stake_per_mandate: Balance,
+        target_mandates_per_shard: usize,
+        num_shards: usize,
+    ) -> Self {
+        Self::try_new(stake_per_mandate, target_mandates_per_shard, num_shards)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// The checked counterpart of [`Self::new`], for configuration read
+    /// from untrusted input -- e.g. a genesis file -- where an invalid
+    /// value should surface as an error rather than a panic.
+    pub fn try_new(
+        stake_per_mandate: Balance,
+        target_mandates_per_shard: usize,
+        num_shards: usize,
+    ) -> Result<Self, ValidatorMandatesConfigError> {
+        if stake_per_mandate == 0 {
+            return Err(ValidatorMandatesConfigError::ZeroStakePerMandate);
+        }
+        if num_shards == 0 {
+            return Err(ValidatorMandatesConfigError::ZeroShards);
+        }
+        Ok(Self { stake_per_mandate, target_mandates_per_shard, num_shards })
+    }
+
+    /// Clamps the mandate price to within `max_change_ratio` of the previous
+    /// epoch's price.
+    ///
+    /// A config change (e.g. of `target_mandates_per_shard`) can step the
+    /// computed price abruptly, ejecting small validators from chunk
+    /// validation in one epoch. Smoothing limits each epoch's move, so a
+    /// large target change converges over several epochs instead. The
+    /// clamping is deterministic integer arithmetic on the previous price,
+    /// and the clamped price ends up in the epoch's mandates config, so
+    /// external verifiers reproduce it from the previous epoch's data alone.
+    pub fn with_transition(mut self, prev_price: Balance, max_change_ratio: Rational32) -> Self {
+        assert!(
+            *max_change_ratio.numer() > 0 && *max_change_ratio.denom() > 0,
+            "the maximum change ratio must be positive"
+        );
+        let max_step = prev_price * (*max_change_ratio.numer() as Balance)
+            / (*max_change_ratio.denom() as Balance);
+        let upper = prev_price.saturating_add(max_step);
+        // The price must stay positive even when the ratio allows a move
+        // down to zero.
+        let lower = prev_price.saturating_sub(max_step).max(1);
+        self.stake_per_mandate = self.stake_per_mandate.clamp(lower, upper);
+        self
+    }
+}
+
+/// The mandates of an epoch's validators: each validator holds one whole
+/// mandate per `stake_per_mandate` of its stake plus at most one partial
+/// mandate for the remainder.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidatorMandates {
+    config: ValidatorMandatesConfig,
+    /// Each element stands for one whole mandate of the given validator.
+    mandates: Vec<ValidatorId>,
+    /// Stake remainders below `stake_per_mandate`, one per validator that
+    /// has one.
+    partials: Vec<(ValidatorId, Balance)>,
+}
+
+impl ValidatorMandates {
+    /// Splits the stake of the given validators into mandates; validator ids
+    /// are the indices into `validators`.
+    pub fn new(config: ValidatorMandatesConfig, validators: &[ValidatorStake]) -> Self {
+        let mut mandates = Vec::new();
+        let mut partials = Vec::new();
+        for (validator_id, validator) in validators.iter().enumerate() {
+            let validator_id = validator_id as ValidatorId;
+            let num_whole = validator.stake() / config.stake_per_mandate;
+            mandates.extend(std::iter::repeat_n(validator_id, num_whole as usize));
+            let remainder = validator.stake() % config.stake_per_mandate;
+            if remainder > 0 {
+                partials.push((validator_id, remainder));
+            }
+        }
+        Self { config, mandates, partials }
+    }
+
+    pub fn config(&self) -> &ValidatorMandatesConfig {
+        &self.config
+    }
+
+    pub fn num_whole_mandates(&self) -> usize {
+        self.mandates.len()
+    }
+
+    /// The stake one whole mandate represents, as recorded in the config.
+    pub fn stake_per_mandate(&self) -> Balance {
+        self.config.stake_per_mandate
+    }
+
+    /// All mandates that take part in sampling: the whole ones plus one per
+    /// partial remainder.
+    pub fn num_total_mandates(&self) -> usize {
+        self.mandates.len() + self.partials.len()
+    }
+
+    /// How many whole mandates the given validator's stake was split into:
+    /// its stake divided by [`Self::stake_per_mandate`], rounded down.
+    pub fn mandates_for_validator(&self, validator_id: ValidatorId) -> usize {
+        self.mandates.iter().filter(|&&id| id == validator_id).count()
+    }
+
+    /// Distributes the mandates over the configured shards, each whole and
+    /// partial mandate independently drawing its shard from `rng`.
+    ///
+    /// The result is entirely determined by the mandates and the state of
+    /// `rng`; callers that need nodes to agree on an assignment must seed
+    /// the rng deterministically from consensus data.
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> ChunkValidatorStakeAssignment {
+        let mut assignment = vec![HashMap::new(); self.config.num_shards];
+        for &validator_id in &self.mandates {
+            let shard_id = rng.gen_range(0..self.config.num_shards);
+            *assignment[shard_id].entry(validator_id).or_default() +=
+                self.config.stake_per_mandate;
+        }
+        for &(validator_id, stake) in &self.partials {
+            let shard_id = rng.gen_range(0..self.config.num_shards);
+            *assignment[shard_id].entry(validator_id).or_default() += stake;
+        }
+        assignment
+    }
+
+    /// Samples an assignment from a 32-byte seed, as stored in e.g.
+    /// `EpochInfo::rng_seed`, sparing callers the rng construction. The
+    /// same seed always yields the same assignment.
+    pub fn sample_with_seed(&self, seed: RngSeed) -> ChunkValidatorStakeAssignment {
+        self.sample(&mut StdRng::from_seed(seed))
+    }
+
+    /// Summary statistics of a produced assignment, for judging how evenly
+    /// stake and validators are spread over the shards.
+    pub fn assignment_stats(assignment: &ChunkValidatorStakeAssignment) -> AssignmentStats {
+        let validators_per_shard: Vec<usize> =
+            assignment.iter().map(|shard| shard.len()).collect();
+        let stake_per_shard: Vec<Balance> =
+            assignment.iter().map(|shard| shard.values().sum()).collect();
+        AssignmentStats {
+            min_validators_per_shard: validators_per_shard.iter().copied().min().unwrap_or(0),
+            max_validators_per_shard: validators_per_shard.iter().copied().max().unwrap_or(0),
+            min_stake_per_shard: stake_per_shard.iter().copied().min().unwrap_or(0),
+            max_stake_per_shard: stake_per_shard.iter().copied().max().unwrap_or(0),
+            total_stake: stake_per_shard.iter().sum(),
+        }
+    }
+}
+
+/// The chunk validators of one `(shard, height)` slot, with the stake that
+/// backs each of them there.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChunkValidatorAssignments {
+    /// The assigned validators ordered by account id.
+    assignments: Vec<(AccountId, Balance)>,
+    assignments_by_account: HashMap<AccountId, Balance>,
+}
+
+impl ChunkValidatorAssignments {
+    pub fn new(mut assignments: Vec<(AccountId, Balance)>) -> Self {
+        assignments.sort();
+        let assignments_by_account = assignments.iter().cloned().collect();
+        Self { assignments, assignments_by_account }
+    }
+
+    pub fn assignments(&self) -> &[(AccountId, Balance)] {
+        &self.assignments
+    }
+
+    pub fn contains(&self, account_id: &AccountId) -> bool {
+        self.assignments_by_account.contains_key(account_id)
+    }
+
+    /// The stake backing the shard through this validator, if assigned.
+    pub fn assigned_stake(&self, account_id: &AccountId) -> Option<Balance> {
+        self.assignments_by_account.get(account_id).copied()
+    }
+}
+
+/// How balanced a [`ChunkValidatorStakeAssignment`] is across shards.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AssignmentStats {
+    pub min_validators_per_shard: usize,
+    pub max_validators_per_shard: usize,
+    pub min_stake_per_shard: Balance,
+    pub max_stake_per_shard: Balance,
+    pub total_stake: Balance,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_crypto::{KeyType, SecretKey};
+
+    fn validators(stakes: &[Balance]) -> Vec<ValidatorStake> {
+        stakes
+            .iter()
+            .enumerate()
+            .map(|(i, stake)| {
+                let name = format!("validator{i}");
+                let public_key = SecretKey::from_seed(KeyType::ED25519, &name).public_key();
+                ValidatorStake::new(name.parse().unwrap(), public_key, *stake)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_stake_splits_into_whole_and_partial_mandates() {
+        let config = ValidatorMandatesConfig::new(10, 2, 2);
+        // 25 = 2 whole + partial 5; 10 = 1 whole; 7 = partial only.
+        let mandates = ValidatorMandates::new(config, &validators(&[25, 10, 7]));
+        assert_eq!(mandates.mandates, vec![0, 0, 1]);
+        assert_eq!(mandates.partials, vec![(0, 5), (2, 7)]);
+        assert_eq!(mandates.num_whole_mandates(), 3);
+    }
+
+    #[test]
+    fn test_try_new_rejects_invalid_configs() {
+        assert_eq!(
+            ValidatorMandatesConfig::try_new(10, 10, 0),
+            Err(ValidatorMandatesConfigError::ZeroShards)
+        );
+        assert_eq!(
+            ValidatorMandatesConfig::try_new(0, 10, 4),
+            Err(ValidatorMandatesConfigError::ZeroStakePerMandate)
+        );
+        assert_eq!(
+            ValidatorMandatesConfig::try_new(10, 10, 4),
+            Ok(ValidatorMandatesConfig::new(10, 10, 4))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "there must be at least one shard")]
+    fn test_new_panics_on_zero_shards() {
+        ValidatorMandatesConfig::new(10, 10, 0);
+    }
+
+    #[test]
+    fn test_mandate_inspection_getters() {
+        let config = ValidatorMandatesConfig::new(10, 2, 2);
+        // 25 = 2 whole + a partial; 10 = 1 whole; 7 = a partial only.
+        let mandates = ValidatorMandates::new(config, &validators(&[25, 10, 7]));
+
+        assert_eq!(mandates.stake_per_mandate(), 10);
+        assert_eq!(mandates.config(), &config);
+        assert_eq!(mandates.mandates_for_validator(0), 2);
+        assert_eq!(mandates.mandates_for_validator(1), 1);
+        assert_eq!(mandates.mandates_for_validator(2), 0);
+        // A validator id outside the set simply holds no mandates.
+        assert_eq!(mandates.mandates_for_validator(9), 0);
+        // 3 whole mandates plus the two partials of validators 0 and 2.
+        assert_eq!(mandates.num_whole_mandates(), 3);
+        assert_eq!(mandates.num_total_mandates(), 5);
+    }
+
+    #[test]
+    fn test_with_transition_limits_price_step() {
+        // The config would double the price; a 25% ratio only allows 125.
+        let config =
+            ValidatorMandatesConfig::new(200, 2, 2).with_transition(100, Rational32::new(1, 4));
+        assert_eq!(config.stake_per_mandate, 125);
+
+        // Moves within the ratio pass through unclamped, both directions.
+        let config =
+            ValidatorMandatesConfig::new(110, 2, 2).with_transition(100, Rational32::new(1, 4));
+        assert_eq!(config.stake_per_mandate, 110);
+        let config =
+            ValidatorMandatesConfig::new(10, 2, 2).with_transition(100, Rational32::new(1, 4));
+        assert_eq!(config.stake_per_mandate, 75);
+    }
+
+    #[test]
+    fn test_with_transition_converges_to_target() {
+        let ratio = Rational32::new(1, 4);
+        let mut price = 100;
+        let mut epochs = 0;
+        while price != 200 {
+            price = ValidatorMandatesConfig::new(200, 2, 2)
+                .with_transition(price, ratio)
+                .stake_per_mandate;
+            epochs += 1;
+            assert!(epochs < 100, "the price must converge to the target");
+        }
+        // 100 -> 125 -> 156 -> 195 -> 200.
+        assert_eq!(epochs, 4);
+    }
+
+    #[test]
+    fn test_assignment_stats() {
+        let assignment: ChunkValidatorStakeAssignment = vec![
+            HashMap::from([(0, 30), (1, 10)]),
+            HashMap::from([(2, 25)]),
+            HashMap::from([(0, 5), (1, 10), (2, 20)]),
+        ];
+        let stats = ValidatorMandates::assignment_stats(&assignment);
+        assert_eq!(
+            stats,
+            AssignmentStats {
+                min_validators_per_shard: 1,
+                max_validators_per_shard: 3,
+                min_stake_per_shard: 25,
+                max_stake_per_shard: 40,
+                total_stake: 100,
+            }
+        );
+    }
+
+    #[test]
+    fn test_assignment_stats_empty() {
+        let stats = ValidatorMandates::assignment_stats(&Vec::new());
+        assert_eq!(stats, AssignmentStats::default());
+    }
+
+    #[test]
+    fn test_sample_is_a_function_of_the_rng_seed() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        let config = ValidatorMandatesConfig::new(10, 2, 3);
+        let mandates = ValidatorMandates::new(config, &validators(&[25, 10, 7]));
+        let assignment = mandates.sample(&mut StdRng::seed_from_u64(42));
+        assert_eq!(assignment, mandates.sample(&mut StdRng::seed_from_u64(42)));
+        // Sampling moves mandates between shards but never mints or loses
+        // stake.
+        assert_eq!(ValidatorMandates::assignment_stats(&assignment).total_stake, 42);
+    }
+
+    #[test]
+    fn test_sample_with_seed_is_deterministic() {
+        let config = ValidatorMandatesConfig::new(10, 2, 3);
+        let mandates = ValidatorMandates::new(config, &validators(&[25, 10, 7]));
+
+        let assignment = mandates.sample_with_seed([7; 32]);
+        assert_eq!(assignment, mandates.sample_with_seed([7; 32]));
+        // The seed feeds the same rng `sample` uses, so both entry points
+        // agree on the assignment.
+        assert_eq!(assignment, mandates.sample(&mut StdRng::from_seed([7; 32])));
+
+        // With 3 shards and 5 draws, distinct seeds overwhelmingly place
+        // mandates differently; these two do.
+        assert_ne!(assignment, mandates.sample_with_seed([8; 32]));
+    }
+
+    #[test]
+    fn test_chunk_validator_assignments_lookup() {
+        let assignments = ChunkValidatorAssignments::new(vec![
+            ("bob".parse().unwrap(), 10),
+            ("alice".parse().unwrap(), 30),
+        ]);
+        // The assignment list is ordered by account id regardless of input
+        // order.
+        assert_eq!(assignments.assignments()[0].0.as_str(), "alice");
+        assert!(assignments.contains(&"bob".parse().unwrap()));
+        assert_eq!(assignments.assigned_stake(&"alice".parse().unwrap()), Some(30));
+        assert_eq!(assignments.assigned_stake(&"carol".parse().unwrap()), None);
+    }
+}
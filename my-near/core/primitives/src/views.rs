@@ -0,0 +1,398 @@
+//! Flat, serde-friendly representations of core types for RPC responses.
+
+use crate::account::{
+    AccessKey, AccessKeyPermission, Account, AccountV1, AccountV2, FunctionCallPermission,
+};
+use crate::hash::CryptoHash;
+use crate::serialize::dec_format;
+use crate::types::{Balance, BlockHeight, Nonce, StorageUsage};
+use serde::{Deserialize, Serialize};
+
+/// The block a query was answered at, wrapped around the queried value. The
+/// value's fields are flattened into the response, per the RPC conventions.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct QueryResponse<T> {
+    pub block_height: BlockHeight,
+    pub block_hash: CryptoHash,
+    #[serde(flatten)]
+    pub value: T,
+}
+
+/// Flat view of [`Account`].
+///
+/// The account version does not leak into the JSON: the V1/V2 field sets are
+/// folded together, with `permanent_storage_bytes` zero for V1 accounts.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AccountView {
+    /// Spendable balance as a decimal string.
+    #[serde(with = "dec_format")]
+    pub amount: Balance,
+    /// Balance locked for staking as a decimal string.
+    #[serde(with = "dec_format")]
+    pub locked: Balance,
+    pub code_hash: CryptoHash,
+    pub storage_usage: StorageUsage,
+    #[serde(default)]
+    pub permanent_storage_bytes: StorageUsage,
+}
+
+impl From<&Account> for AccountView {
+    fn from(account: &Account) -> Self {
+        Self {
+            amount: account.amount(),
+            locked: account.locked(),
+            code_hash: *account.code_hash(),
+            storage_usage: account.storage_usage(),
+            permanent_storage_bytes: account.permanent_storage_bytes(),
+        }
+    }
+}
+
+/// The version is recovered from the field set: any permanent storage means
+/// V2, none means V1 -- the same fold the view applies on the way out.
+impl From<&AccountView> for Account {
+    fn from(view: &AccountView) -> Self {
+        if view.permanent_storage_bytes == 0 {
+            Account::V1(AccountV1 {
+                amount: view.amount,
+                locked: view.locked,
+                code_hash: view.code_hash,
+                storage_usage: view.storage_usage,
+            })
+        } else {
+            Account::V2(AccountV2 {
+                amount: view.amount,
+                locked: view.locked,
+                code_hash: view.code_hash,
+                storage_usage: view.storage_usage,
+                permanent_storage_bytes: view.permanent_storage_bytes,
+            })
+        }
+    }
+}
+
+/// Flat view of [`AccessKey`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AccessKeyView {
+    pub nonce: Nonce,
+    pub permission: AccessKeyPermissionView,
+}
+
+/// [`AccessKeyPermission`] in the RPC shape: `"FullAccess"` as a bare string,
+/// function call permissions as a tagged object.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum AccessKeyPermissionView {
+    FunctionCall {
+        /// Remaining gas allowance as a decimal string; `null` is unlimited.
+        #[serde(with = "dec_format::option")]
+        allowance: Option<Balance>,
+        receiver_id: String,
+        method_names: Vec<String>,
+    },
+    FullAccess,
+}
+
+impl From<&AccessKey> for AccessKeyView {
+    fn from(access_key: &AccessKey) -> Self {
+        let permission = match &access_key.permission {
+            AccessKeyPermission::FunctionCall(permission) => {
+                AccessKeyPermissionView::FunctionCall {
+                    allowance: permission.allowance,
+                    receiver_id: permission.receiver_id.clone(),
+                    method_names: permission.method_names.clone(),
+                }
+            }
+            AccessKeyPermission::FullAccess => AccessKeyPermissionView::FullAccess,
+        };
+        Self { nonce: access_key.nonce, permission }
+    }
+}
+
+impl From<&AccessKeyView> for AccessKey {
+    fn from(view: &AccessKeyView) -> Self {
+        let permission = match &view.permission {
+            AccessKeyPermissionView::FunctionCall { allowance, receiver_id, method_names } => {
+                AccessKeyPermission::FunctionCall(FunctionCallPermission {
+                    allowance: *allowance,
+                    receiver_id: receiver_id.clone(),
+                    method_names: method_names.clone(),
+                })
+            }
+            AccessKeyPermissionView::FullAccess => AccessKeyPermission::FullAccess,
+        };
+        Self { nonce: view.nonce, permission }
+    }
+}
+
+/// One entry of a `view_access_key_list` response: the key and what it may do.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AccessKeyInfoView {
+    pub public_key: near_crypto::PublicKey,
+    pub access_key: AccessKeyView,
+}
+
+/// Flat view of [`crate::congestion_info::CongestionInfo`].
+///
+/// The u128 gas fields are decimal strings so they survive JSON number
+/// limits; `version` carries the enum version without leaking its shape.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CongestionInfoView {
+    pub version: u8,
+    pub delayed_receipts_gas: String,
+    pub buffered_receipts_gas: String,
+    pub receipt_bytes: u64,
+    pub allowed_shard: u16,
+}
+
+/// A validator of an epoch, for [`EpochInfoView`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ValidatorInfoView {
+    pub account_id: String,
+    /// Stake as a decimal string.
+    pub stake: String,
+}
+
+/// A validator kicked out at the epoch transition, for [`EpochInfoView`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ValidatorKickoutView {
+    pub account_id: String,
+    /// Human readable kickout reason.
+    pub reason: String,
+}
+
+/// Flat view of [`crate::epoch_manager::epoch_info::EpochInfo`].
+///
+/// Deliberately omits sampling internals such as `rng_seed`, the settlements
+/// and the validator index map; balances are decimal strings.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EpochInfoView {
+    pub epoch_height: u64,
+    pub validators: Vec<ValidatorInfoView>,
+    pub seat_price: String,
+    pub minted_amount: String,
+    pub protocol_version: u32,
+    pub kickouts: Vec<ValidatorKickoutView>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::congestion_info::{CongestionInfo, CongestionInfoV1};
+    use crate::epoch_manager::epoch_info::EpochInfo;
+    use crate::types::{Balance, ValidatorKickoutReason, ValidatorStake};
+    use near_crypto::{KeyType, SecretKey};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use serde::de::DeserializeOwned;
+    use std::collections::BTreeMap;
+    use std::fmt::Debug;
+
+    /// Fuzz-style coverage: enough random instances to hit field extremes
+    /// without noticeable test runtime.
+    const ITERATIONS: usize = 100;
+
+    /// Deterministic seed so a failure reproduces; bump to explore.
+    fn rng() -> StdRng {
+        StdRng::seed_from_u64(0x5eed)
+    }
+
+    /// Serializes to JSON and back, asserting nothing is lost or reshaped.
+    /// Asymmetric serde attributes -- a field that serializes one way and
+    /// parses another -- fail here.
+    fn assert_json_round_trip<T>(value: &T)
+    where
+        T: Serialize + DeserializeOwned + PartialEq + Debug,
+    {
+        let json = serde_json::to_string(value).unwrap();
+        let parsed: T = serde_json::from_str(&json).unwrap();
+        assert_eq!(&parsed, value, "round trip changed the value; json: {json}");
+    }
+
+    #[test]
+    fn test_account_view_json_shape_and_round_trip() {
+        let v1 = Account::new(1_000_000, 50, crate::hash::hash(b"code"), 182);
+        let view = AccountView::from(&v1);
+        let json = serde_json::to_value(&view).unwrap();
+        // Balances are decimal strings, storage is plain bytes, and the
+        // account version is nowhere to be seen.
+        assert_eq!(json["amount"], "1000000");
+        assert_eq!(json["locked"], "50");
+        assert_eq!(json["code_hash"], crate::hash::hash(b"code").to_string());
+        assert_eq!(json["storage_usage"], 182);
+        assert_eq!(json["permanent_storage_bytes"], 0);
+        assert_json_round_trip(&view);
+        assert_eq!(Account::from(&view), v1);
+
+        // A pre-permanent-storage response parses with the field defaulted.
+        let mut legacy = json;
+        legacy.as_object_mut().unwrap().remove("permanent_storage_bytes");
+        let parsed: AccountView = serde_json::from_value(legacy).unwrap();
+        assert_eq!(parsed, view);
+
+        let v2 = Account::V2(AccountV2 {
+            amount: u128::MAX,
+            locked: 0,
+            code_hash: CryptoHash::default(),
+            storage_usage: 100,
+            permanent_storage_bytes: 64,
+        });
+        let view = AccountView::from(&v2);
+        assert_eq!(view.permanent_storage_bytes, 64);
+        assert_json_round_trip(&view);
+        assert_eq!(Account::from(&view), v2);
+    }
+
+    #[test]
+    fn test_access_key_view_json_shape_and_round_trip() {
+        let full_access = AccessKey::full_access();
+        let view = AccessKeyView::from(&full_access);
+        let json = serde_json::to_value(&view).unwrap();
+        assert_eq!(json["nonce"], 0);
+        // Full access is the bare string, per the RPC conventions.
+        assert_eq!(json["permission"], "FullAccess");
+        assert_json_round_trip(&view);
+        assert_eq!(AccessKey::from(&view), full_access);
+
+        let function_call = AccessKey {
+            nonce: 7,
+            permission: AccessKeyPermission::FunctionCall(FunctionCallPermission {
+                allowance: Some(250_000_000),
+                receiver_id: "contract.near".to_string(),
+                method_names: vec!["get".to_string(), "set".to_string()],
+            }),
+        };
+        let view = AccessKeyView::from(&function_call);
+        let json = serde_json::to_value(&view).unwrap();
+        let permission = &json["permission"]["FunctionCall"];
+        assert_eq!(permission["allowance"], "250000000");
+        assert_eq!(permission["receiver_id"], "contract.near");
+        assert_eq!(permission["method_names"], serde_json::json!(["get", "set"]));
+        assert_json_round_trip(&view);
+        assert_eq!(AccessKey::from(&view), function_call);
+
+        // An unlimited allowance is null, not "0".
+        let unlimited = AccessKey {
+            nonce: 0,
+            permission: AccessKeyPermission::FunctionCall(FunctionCallPermission {
+                allowance: None,
+                receiver_id: "contract.near".to_string(),
+                method_names: vec![],
+            }),
+        };
+        let view = AccessKeyView::from(&unlimited);
+        let json = serde_json::to_value(&view).unwrap();
+        assert!(json["permission"]["FunctionCall"]["allowance"].is_null());
+        assert_json_round_trip(&view);
+        assert_eq!(AccessKey::from(&view), unlimited);
+    }
+
+    #[test]
+    fn test_query_response_flattens_block_context() {
+        let account = Account::new(10, 0, CryptoHash::default(), 100);
+        let response = QueryResponse {
+            block_height: 42,
+            block_hash: crate::hash::hash(b"block"),
+            value: AccountView::from(&account),
+        };
+        let json = serde_json::to_value(&response).unwrap();
+        // The block context sits next to the value's own fields.
+        assert_eq!(json["block_height"], 42);
+        assert_eq!(json["block_hash"], crate::hash::hash(b"block").to_string());
+        assert_eq!(json["amount"], "10");
+        assert!(json.get("value").is_none());
+        assert_json_round_trip(&response);
+
+        let info = AccessKeyInfoView {
+            public_key: SecretKey::from_seed(KeyType::ED25519, "key").public_key(),
+            access_key: AccessKeyView::from(&AccessKey::full_access()),
+        };
+        let json = serde_json::to_value(&info).unwrap();
+        assert!(json["public_key"].as_str().unwrap().starts_with("ed25519:"));
+        assert_json_round_trip(&info);
+    }
+
+    #[test]
+    fn test_congestion_info_view_round_trip() {
+        let mut rng = rng();
+        for _ in 0..ITERATIONS {
+            let info = CongestionInfo::V1(CongestionInfoV1 {
+                delayed_receipts_gas: rng.r#gen(),
+                buffered_receipts_gas: rng.r#gen(),
+                receipt_bytes: rng.r#gen(),
+                allowed_shard: rng.r#gen(),
+            });
+            let view = info.to_view();
+            assert_json_round_trip(&view);
+            // The decimal strings must carry the full u128 values.
+            assert_eq!(view.delayed_receipts_gas.parse::<u128>().unwrap(), info.delayed_receipts_gas());
+            assert_eq!(view.buffered_receipts_gas.parse::<u128>().unwrap(), info.buffered_receipts_gas());
+            assert_eq!(view.receipt_bytes, info.receipt_bytes());
+            assert_eq!(view.allowed_shard, info.allowed_shard());
+        }
+    }
+
+    #[test]
+    fn test_epoch_info_view_round_trip() {
+        let mut rng = rng();
+        for _ in 0..ITERATIONS {
+            let num_validators = rng.gen_range(1..=5usize);
+            let validators: Vec<ValidatorStake> = (0..num_validators)
+                .map(|i| {
+                    let name = format!("validator{i}");
+                    let public_key = SecretKey::from_seed(KeyType::ED25519, &name).public_key();
+                    ValidatorStake::new(name.parse().unwrap(), public_key, rng.r#gen())
+                })
+                .collect();
+            let validator_to_index = validators
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (v.account_id().clone(), i as u64))
+                .collect();
+            let settlement: Vec<u64> = (0..num_validators as u64).collect();
+            let kickouts: BTreeMap<_, _> = (0..rng.gen_range(0..=3))
+                .map(|i| {
+                    let reason = match rng.gen_range(0..4) {
+                        0 => ValidatorKickoutReason::Unstaked,
+                        1 => ValidatorKickoutReason::NotEnoughStake {
+                            stake: rng.r#gen(),
+                            threshold: rng.r#gen(),
+                        },
+                        2 => ValidatorKickoutReason::NotEnoughBlocks {
+                            produced: rng.r#gen(),
+                            expected: rng.r#gen(),
+                        },
+                        _ => ValidatorKickoutReason::NotEnoughChunks {
+                            produced: rng.r#gen(),
+                            expected: rng.r#gen(),
+                        },
+                    };
+                    (format!("kicked{i}").parse().unwrap(), reason)
+                })
+                .collect();
+            let epoch_info = EpochInfo::new(
+                rng.r#gen(),
+                validators.clone(),
+                validator_to_index,
+                settlement.clone(),
+                vec![settlement],
+                BTreeMap::new(),
+                rng.r#gen(),
+                rng.r#gen(),
+                kickouts.clone(),
+                rng.r#gen(),
+                [0; 32],
+            );
+
+            let view = epoch_info.to_view();
+            assert_json_round_trip(&view);
+            // Balances survive the decimal string detour.
+            assert_eq!(view.seat_price.parse::<Balance>().unwrap(), epoch_info.seat_price());
+            assert_eq!(view.minted_amount.parse::<Balance>().unwrap(), epoch_info.minted_amount());
+            for (validator, validator_view) in validators.iter().zip(&view.validators) {
+                assert_eq!(validator_view.account_id, validator.account_id().to_string());
+                assert_eq!(validator_view.stake.parse::<Balance>().unwrap(), validator.stake());
+            }
+            assert_eq!(view.kickouts.len(), kickouts.len());
+        }
+    }
+}
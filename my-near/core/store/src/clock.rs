@@ -0,0 +1,49 @@
+//! A minimal clock abstraction so time-dependent store behavior is testable.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A source of "now", either the system clock or a test-controlled fake.
+#[derive(Clone)]
+pub enum Clock {
+    Real,
+    Fake(Arc<Mutex<Duration>>),
+}
+
+impl Clock {
+    pub fn real() -> Self {
+        Self::Real
+    }
+
+    /// Current time as a duration since the unix epoch.
+    pub fn now(&self) -> Duration {
+        match self {
+            Self::Real => {
+                SystemTime::now().duration_since(UNIX_EPOCH).expect("system time before unix epoch")
+            }
+            Self::Fake(now) => *now.lock().expect("clock lock poisoned"),
+        }
+    }
+}
+
+/// A fake clock for tests; starts at the unix epoch and only moves when
+/// [`FakeClock::advance`] is called.
+#[derive(Clone, Default)]
+pub struct FakeClock {
+    now: Arc<Mutex<Duration>>,
+}
+
+impl FakeClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A [`Clock`] handle reading this fake's time.
+    pub fn clock(&self) -> Clock {
+        Clock::Fake(self.now.clone())
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().expect("clock lock poisoned") += duration;
+    }
+}
@@ -0,0 +1,265 @@
+//! Archival of finalized chain data into a cold database.
+//!
+//! An archival node keeps two databases: a hot one serving the recent chain
+//! and a cold one accumulating everything final. The [`ColdStoreArchiver`]
+//! copies finalized canonical heights from hot to cold and records how far
+//! it got in a `cold_head` watermark, so hot data past the watermark can be
+//! garbage collected. Reads go through a [`SplitStore`] that prefers hot and
+//! falls back to cold transparently, so callers never notice the move.
+
+use crate::{DBCol, Store};
+use borsh::BorshDeserialize;
+use std::io;
+
+/// Columns copied to the cold store for every archived block, in the
+/// deterministic order the archiver writes them.
+///
+/// All of them key their entries by the block hash (or by a key prefixed
+/// with it, like [`DBCol::ChunkExtra`]), except [`DBCol::BlockHeight`] which
+/// the archiver copies separately to carry the canonical index over.
+pub const ARCHIVE_COLUMNS: &[DBCol] = &[
+    DBCol::BlockHeader,
+    DBCol::Block,
+    DBCol::Chunk,
+    DBCol::ChunkExtra,
+    DBCol::TransactionOutcome,
+];
+
+/// Key of the cold store watermark in [`DBCol::BlockMisc`]: the highest
+/// height whose data is fully copied.
+pub const COLD_HEAD_KEY: &[u8] = b"COLD_HEAD";
+
+/// What one [`ColdStoreArchiver::archive_up_to`] run did.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ArchiveStats {
+    /// Canonical heights whose data was copied in this run.
+    pub heights_archived: u64,
+    /// Individual `(key, value)` entries copied in this run.
+    pub entries_copied: u64,
+    /// The `cold_head` watermark the run started from, if any.
+    pub resumed_from: Option<u64>,
+}
+
+/// Copies finalized canonical chain data from the hot store to the cold one.
+pub struct ColdStoreArchiver {
+    hot: Store,
+    cold: Store,
+}
+
+impl ColdStoreArchiver {
+    pub fn new(hot: Store, cold: Store) -> Self {
+        Self { hot, cold }
+    }
+
+    /// The highest height fully copied to the cold store, if any.
+    pub fn cold_head(&self) -> io::Result<Option<u64>> {
+        self.cold.get_ser(DBCol::BlockMisc, COLD_HEAD_KEY)
+    }
+
+    /// Copies every canonical height up to and including `final_height` into
+    /// the cold store.
+    ///
+    /// Each height is committed together with the advanced `cold_head`
+    /// watermark, so a crash mid-archive loses at most the height being
+    /// copied; the next run resumes from the watermark. Re-running over an
+    /// already-archived range is a no-op. Heights absent from the canonical
+    /// index are skipped: the chain may skip heights.
+    pub fn archive_up_to(&self, final_height: u64) -> io::Result<ArchiveStats> {
+        let cold_head = self.cold_head()?;
+        let mut stats = ArchiveStats { resumed_from: cold_head, ..Default::default() };
+        let start = match cold_head {
+            Some(cold_head) if cold_head >= final_height => return Ok(stats),
+            Some(cold_head) => cold_head + 1,
+            None => 0,
+        };
+        for height in start..=final_height {
+            let mut update = self.cold.store_update();
+            let height_key = height.to_le_bytes();
+            if let Some(block_hash) = self.hot.get(DBCol::BlockHeight, &height_key)? {
+                update.set(DBCol::BlockHeight, &height_key, block_hash.clone());
+                stats.entries_copied += 1;
+                for &column in ARCHIVE_COLUMNS {
+                    for (key, value) in self.hot.iter_prefix(column, &block_hash) {
+                        update.set(column, &key, value);
+                        stats.entries_copied += 1;
+                    }
+                }
+                stats.heights_archived += 1;
+            }
+            // The watermark advances in the same commit as the height's
+            // data, covering skipped heights as well.
+            update.set_ser(DBCol::BlockMisc, COLD_HEAD_KEY, &height)?;
+            update.commit()?;
+        }
+        Ok(stats)
+    }
+}
+
+/// A read view over the hot and cold stores together: hot wins, cold fills
+/// in whatever the hot store has already garbage collected.
+#[derive(Clone)]
+pub struct SplitStore {
+    hot: Store,
+    cold: Store,
+}
+
+impl SplitStore {
+    pub fn new(hot: Store, cold: Store) -> Self {
+        Self { hot, cold }
+    }
+
+    pub fn get(&self, column: DBCol, key: &[u8]) -> io::Result<Option<Vec<u8>>> {
+        match self.hot.get(column, key)? {
+            Some(value) => Ok(Some(value)),
+            None => self.cold.get(column, key),
+        }
+    }
+
+    pub fn get_ser<T: BorshDeserialize>(
+        &self,
+        column: DBCol,
+        key: &[u8],
+    ) -> io::Result<Option<T>> {
+        match self.get(column, key)? {
+            Some(bytes) => Ok(Some(T::try_from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn exists(&self, column: DBCol, key: &[u8]) -> io::Result<bool> {
+        Ok(self.get(column, key)?.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A made-up block hash for the given height.
+    fn block_hash(height: u64) -> Vec<u8> {
+        let mut hash = vec![0xab; 32];
+        hash[..8].copy_from_slice(&height.to_le_bytes());
+        hash
+    }
+
+    /// Fills the hot store with canonical blocks at the given heights: the
+    /// index entry plus one entry per archive column, chunk extras with a
+    /// per-shard key suffix.
+    fn populate_hot(hot: &Store, heights: &[u64]) {
+        let mut update = hot.store_update();
+        for &height in heights {
+            let hash = block_hash(height);
+            update.set(DBCol::BlockHeight, &height.to_le_bytes(), hash.clone());
+            update.set(DBCol::BlockHeader, &hash, format!("header {height}").into_bytes());
+            update.set(DBCol::Block, &hash, format!("body {height}").into_bytes());
+            update.set(DBCol::Chunk, &hash, format!("chunk {height}").into_bytes());
+            for shard_id in [0u64, 1] {
+                let mut key = hash.clone();
+                key.extend_from_slice(&shard_id.to_le_bytes());
+                update.set(DBCol::ChunkExtra, &key, format!("extra {height} {shard_id}").into_bytes());
+            }
+            update.set(DBCol::TransactionOutcome, &hash, format!("outcome {height}").into_bytes());
+        }
+        update.commit().unwrap();
+    }
+
+    /// Deletes everything the hot store holds for the given heights, the way
+    /// GC would after archiving.
+    fn gc_hot(hot: &Store, heights: &[u64]) {
+        let mut update = hot.store_update();
+        for &height in heights {
+            let hash = block_hash(height);
+            update.delete(DBCol::BlockHeight, &height.to_le_bytes());
+            for &column in ARCHIVE_COLUMNS {
+                for (key, _) in hot.iter_prefix(column, &hash) {
+                    update.delete(column, &key);
+                }
+            }
+        }
+        update.commit().unwrap();
+    }
+
+    #[test]
+    fn test_archived_blocks_survive_hot_gc_via_split_store() {
+        let hot = Store::new();
+        let cold = Store::new();
+        // Height 2 is skipped by the chain.
+        populate_hot(&hot, &[0, 1, 3, 4]);
+
+        let archiver = ColdStoreArchiver::new(hot.clone(), cold.clone());
+        let stats = archiver.archive_up_to(3).unwrap();
+        assert_eq!(stats.heights_archived, 3);
+        assert_eq!(stats.resumed_from, None);
+        assert_eq!(archiver.cold_head().unwrap(), Some(3));
+
+        gc_hot(&hot, &[0, 1, 3]);
+        let split = SplitStore::new(hot.clone(), cold);
+        for height in [0, 1, 3] {
+            let hash = block_hash(height);
+            assert_eq!(
+                split.get(DBCol::BlockHeight, &height.to_le_bytes()).unwrap(),
+                Some(hash.clone())
+            );
+            assert_eq!(
+                split.get(DBCol::BlockHeader, &hash).unwrap(),
+                Some(format!("header {height}").into_bytes())
+            );
+            assert_eq!(
+                split.get(DBCol::Block, &hash).unwrap(),
+                Some(format!("body {height}").into_bytes())
+            );
+            for shard_id in [0u64, 1] {
+                let mut key = hash.clone();
+                key.extend_from_slice(&shard_id.to_le_bytes());
+                assert_eq!(
+                    split.get(DBCol::ChunkExtra, &key).unwrap(),
+                    Some(format!("extra {height} {shard_id}").into_bytes())
+                );
+            }
+        }
+        // Height 4 was not archived yet and is still served from hot.
+        assert!(split.exists(DBCol::BlockHeader, &block_hash(4)).unwrap());
+        // The split store does not invent data that exists nowhere.
+        assert!(!split.exists(DBCol::BlockHeader, &block_hash(9)).unwrap());
+    }
+
+    #[test]
+    fn test_archiving_is_idempotent() {
+        let hot = Store::new();
+        let cold = Store::new();
+        populate_hot(&hot, &[0, 1, 2]);
+
+        let archiver = ColdStoreArchiver::new(hot, cold);
+        let first = archiver.archive_up_to(2).unwrap();
+        assert_eq!(first.heights_archived, 3);
+
+        // Re-running over the archived range copies nothing.
+        let again = archiver.archive_up_to(2).unwrap();
+        assert_eq!(again, ArchiveStats { resumed_from: Some(2), ..Default::default() });
+        assert_eq!(archiver.cold_head().unwrap(), Some(2));
+    }
+
+    #[test]
+    fn test_crash_mid_archive_resumes_from_watermark() {
+        let hot = Store::new();
+        let cold = Store::new();
+        populate_hot(&hot, &[0, 1, 2, 3]);
+
+        let archiver = ColdStoreArchiver::new(hot.clone(), cold.clone());
+        // "Crash" after archiving up to height 1: each height commits with
+        // its watermark, so stopping there is exactly what a crash leaves.
+        archiver.archive_up_to(1).unwrap();
+        assert_eq!(archiver.cold_head().unwrap(), Some(1));
+
+        let resumed = archiver.archive_up_to(3).unwrap();
+        assert_eq!(resumed.resumed_from, Some(1));
+        assert_eq!(resumed.heights_archived, 2);
+        assert_eq!(archiver.cold_head().unwrap(), Some(3));
+
+        gc_hot(&hot, &[0, 1, 2, 3]);
+        let split = SplitStore::new(hot, cold);
+        for height in [0, 1, 2, 3] {
+            assert!(split.exists(DBCol::Block, &block_hash(height)).unwrap());
+        }
+    }
+}
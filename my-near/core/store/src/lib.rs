@@ -0,0 +1,354 @@
+//! A column-oriented key-value store with borsh-serialized values.
+//!
+//! Reads go through [`Store`]; writes are batched in a [`StoreUpdate`] and
+//! applied atomically with [`StoreUpdate::commit`].
+
+pub mod clock;
+pub mod cold_storage;
+
+use crate::clock::Clock;
+use borsh::{BorshDeserialize, BorshSerialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// Columns of the store, each an independent key space.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DBCol {
+    /// Epoch information: `EpochId -> EpochInfo`.
+    EpochInfo,
+    /// Block bookkeeping of the epoch manager: `CryptoHash -> BlockInfo`.
+    BlockInfo,
+    /// Block headers: `CryptoHash -> BlockHeader`.
+    BlockHeader,
+    /// Block bodies: `CryptoHash -> BlockBody`.
+    Block,
+    /// Chunks: `ChunkHash -> ShardChunk`.
+    Chunk,
+    /// Outcome of applying a chunk: `(CryptoHash, ShardId) -> ChunkExtra`.
+    ChunkExtra,
+    /// Execution outcomes: `CryptoHash -> ExecutionOutcome`.
+    TransactionOutcome,
+    /// Canonical chain index: `BlockHeight -> CryptoHash`.
+    BlockHeight,
+    /// Singleton bookkeeping entries such as the cold store watermark.
+    BlockMisc,
+}
+
+type ColumnData = BTreeMap<Vec<u8>, Vec<u8>>;
+type ColumnExpiries = BTreeMap<Vec<u8>, Duration>;
+
+/// The database handle. Cheap to clone; clones share the same underlying
+/// storage.
+#[derive(Clone)]
+pub struct Store {
+    data: Arc<RwLock<HashMap<DBCol, ColumnData>>>,
+    /// Expiry timestamps (since the unix epoch) of entries written with
+    /// [`StoreUpdate::set_with_ttl`]; entries without one never expire.
+    expiries: Arc<RwLock<HashMap<DBCol, ColumnExpiries>>>,
+    clock: Clock,
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Self::with_clock(Clock::real())
+    }
+}
+
+impl Store {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A store reading "now" from the given clock, for tests that need to
+    /// control entry expiry.
+    pub fn with_clock(clock: Clock) -> Self {
+        Self { data: Arc::new(Default::default()), expiries: Arc::new(Default::default()), clock }
+    }
+
+    pub fn get(&self, column: DBCol, key: &[u8]) -> io::Result<Option<Vec<u8>>> {
+        if self.is_expired(column, key) {
+            // Lazy deletion: drop the entry the first time it is read past
+            // its expiry.
+            let mut data = self.data.write().expect("store lock poisoned");
+            let mut expiries = self.expiries.write().expect("store lock poisoned");
+            data.entry(column).or_default().remove(key);
+            expiries.entry(column).or_default().remove(key);
+            return Ok(None);
+        }
+        let data = self.data.read().expect("store lock poisoned");
+        Ok(data.get(&column).and_then(|column| column.get(key)).cloned())
+    }
+
+    fn is_expired(&self, column: DBCol, key: &[u8]) -> bool {
+        let expiries = self.expiries.read().expect("store lock poisoned");
+        match expiries.get(&column).and_then(|column| column.get(key)) {
+            Some(expires_at) => self.clock.now() >= *expires_at,
+            None => false,
+        }
+    }
+
+    pub fn get_ser<T: BorshDeserialize>(
+        &self,
+        column: DBCol,
+        key: &[u8],
+    ) -> io::Result<Option<T>> {
+        match self.get(column, key)? {
+            Some(bytes) => Ok(Some(T::try_from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn exists(&self, column: DBCol, key: &[u8]) -> io::Result<bool> {
+        Ok(self.get(column, key)?.is_some())
+    }
+
+    /// Iterates over all `(key, value)` pairs of a column, ordered by key.
+    pub fn iter(&self, column: DBCol) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let data = self.data.read().expect("store lock poisoned");
+        data.get(&column)
+            .map(|column| column.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+            .unwrap_or_default()
+    }
+
+    /// Iterates over all `(key, value)` pairs of a column whose key starts
+    /// with `prefix`, ordered by key.
+    pub fn iter_prefix(&self, column: DBCol, prefix: &[u8]) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let data = self.data.read().expect("store lock poisoned");
+        data.get(&column)
+            .map(|column| {
+                column
+                    .iter()
+                    .filter(|(key, _)| key.starts_with(prefix))
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn store_update(&self) -> StoreUpdate {
+        StoreUpdate { store: self.clone(), ops: vec![] }
+    }
+}
+
+/// A read-only view of a [`Store`], for components that must never write --
+/// e.g. RPC replicas sharing a store written by another process.
+///
+/// There is deliberately no way to obtain a [`StoreUpdate`] from this type,
+/// so an accidental write path is a compile error rather than a runtime
+/// surprise.
+#[derive(Clone)]
+pub struct ReadOnlyStore {
+    store: Store,
+}
+
+impl Store {
+    /// A read-only view sharing this store's underlying storage.
+    pub fn read_only(&self) -> ReadOnlyStore {
+        ReadOnlyStore { store: self.clone() }
+    }
+}
+
+impl ReadOnlyStore {
+    pub fn get(&self, column: DBCol, key: &[u8]) -> io::Result<Option<Vec<u8>>> {
+        self.store.get(column, key)
+    }
+
+    pub fn get_ser<T: BorshDeserialize>(
+        &self,
+        column: DBCol,
+        key: &[u8],
+    ) -> io::Result<Option<T>> {
+        self.store.get_ser(column, key)
+    }
+
+    pub fn exists(&self, column: DBCol, key: &[u8]) -> io::Result<bool> {
+        self.store.exists(column, key)
+    }
+
+    /// See [`Store::iter`].
+    pub fn iter(&self, column: DBCol) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.store.iter(column)
+    }
+
+    /// See [`Store::iter_prefix`].
+    pub fn iter_prefix(&self, column: DBCol, prefix: &[u8]) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.store.iter_prefix(column, prefix)
+    }
+}
+
+enum DBOp {
+    Set { column: DBCol, key: Vec<u8>, value: Vec<u8>, expires_at: Option<Duration> },
+    Delete { column: DBCol, key: Vec<u8> },
+    DeleteAll { column: DBCol },
+}
+
+/// A batch of writes to be committed atomically.
+pub struct StoreUpdate {
+    store: Store,
+    ops: Vec<DBOp>,
+}
+
+impl StoreUpdate {
+    pub fn set(&mut self, column: DBCol, key: &[u8], value: Vec<u8>) {
+        self.ops.push(DBOp::Set { column, key: key.to_vec(), value, expires_at: None });
+    }
+
+    /// Like [`Self::set`], but the entry silently disappears once `ttl` has
+    /// elapsed on `clock`. Meant for ephemeral caches such as transient peer
+    /// data.
+    pub fn set_with_ttl(
+        &mut self,
+        column: DBCol,
+        key: &[u8],
+        value: Vec<u8>,
+        ttl: Duration,
+        clock: &Clock,
+    ) {
+        self.ops.push(DBOp::Set {
+            column,
+            key: key.to_vec(),
+            value,
+            expires_at: Some(clock.now() + ttl),
+        });
+    }
+
+    pub fn set_ser<T: BorshSerialize>(
+        &mut self,
+        column: DBCol,
+        key: &[u8],
+        value: &T,
+    ) -> io::Result<()> {
+        self.set(column, key, borsh::to_vec(value)?);
+        Ok(())
+    }
+
+    pub fn delete(&mut self, column: DBCol, key: &[u8]) {
+        self.ops.push(DBOp::Delete { column, key: key.to_vec() });
+    }
+
+    /// Removes every entry of the given column on commit, leaving the other
+    /// columns untouched. Meant for test teardown and for dropping derived
+    /// data wholesale, e.g. an index rebuilt after a reorg.
+    pub fn delete_all(&mut self, column: DBCol) {
+        self.ops.push(DBOp::DeleteAll { column });
+    }
+
+    pub fn commit(self) -> io::Result<()> {
+        let mut data = self.store.data.write().expect("store lock poisoned");
+        let mut expiries = self.store.expiries.write().expect("store lock poisoned");
+        for op in self.ops {
+            match op {
+                DBOp::Set { column, key, value, expires_at } => {
+                    match expires_at {
+                        Some(expires_at) => {
+                            expiries.entry(column).or_default().insert(key.clone(), expires_at);
+                        }
+                        // A plain overwrite makes the entry permanent again.
+                        None => {
+                            expiries.entry(column).or_default().remove(&key);
+                        }
+                    }
+                    data.entry(column).or_default().insert(key, value);
+                }
+                DBOp::Delete { column, key } => {
+                    data.entry(column).or_default().remove(&key);
+                    expiries.entry(column).or_default().remove(&key);
+                }
+                DBOp::DeleteAll { column } => {
+                    data.entry(column).or_default().clear();
+                    expiries.entry(column).or_default().clear();
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_get_delete_round_trip() {
+        let store = Store::new();
+        let mut update = store.store_update();
+        update.set_ser(DBCol::EpochInfo, b"key", &42u64).unwrap();
+        update.commit().unwrap();
+        assert_eq!(store.get_ser::<u64>(DBCol::EpochInfo, b"key").unwrap(), Some(42));
+        // Columns are independent key spaces.
+        assert_eq!(store.get_ser::<u64>(DBCol::BlockInfo, b"key").unwrap(), None);
+
+        let mut update = store.store_update();
+        update.delete(DBCol::EpochInfo, b"key");
+        update.commit().unwrap();
+        assert_eq!(store.get(DBCol::EpochInfo, b"key").unwrap(), None);
+    }
+
+    #[test]
+    fn test_ttl_entry_expires() {
+        let fake_clock = crate::clock::FakeClock::new();
+        let clock = fake_clock.clock();
+        let store = Store::with_clock(clock.clone());
+
+        let mut update = store.store_update();
+        update.set_with_ttl(DBCol::EpochInfo, b"peer", b"data".to_vec(), Duration::from_secs(60), &clock);
+        update.commit().unwrap();
+
+        // Visible before the TTL elapses.
+        fake_clock.advance(Duration::from_secs(59));
+        assert_eq!(store.get(DBCol::EpochInfo, b"peer").unwrap(), Some(b"data".to_vec()));
+
+        // Gone once the clock passes the expiry, including the raw entry.
+        fake_clock.advance(Duration::from_secs(1));
+        assert_eq!(store.get(DBCol::EpochInfo, b"peer").unwrap(), None);
+        assert!(!store.exists(DBCol::EpochInfo, b"peer").unwrap());
+    }
+
+    #[test]
+    fn test_plain_set_clears_ttl() {
+        let fake_clock = crate::clock::FakeClock::new();
+        let clock = fake_clock.clock();
+        let store = Store::with_clock(clock.clone());
+
+        let mut update = store.store_update();
+        update.set_with_ttl(DBCol::EpochInfo, b"key", b"old".to_vec(), Duration::from_secs(1), &clock);
+        update.commit().unwrap();
+
+        let mut update = store.store_update();
+        update.set(DBCol::EpochInfo, b"key", b"new".to_vec());
+        update.commit().unwrap();
+
+        fake_clock.advance(Duration::from_secs(100));
+        assert_eq!(store.get(DBCol::EpochInfo, b"key").unwrap(), Some(b"new".to_vec()));
+    }
+
+    #[test]
+    fn test_delete_all_clears_one_column_only() {
+        let store = Store::new();
+        let mut update = store.store_update();
+        update.set(DBCol::EpochInfo, b"a", b"1".to_vec());
+        update.set(DBCol::EpochInfo, b"b", b"2".to_vec());
+        update.set(DBCol::BlockInfo, b"a", b"3".to_vec());
+        update.commit().unwrap();
+
+        let mut update = store.store_update();
+        update.delete_all(DBCol::EpochInfo);
+        update.commit().unwrap();
+
+        // The cleared column is empty; the other column survives intact.
+        assert_eq!(store.iter(DBCol::EpochInfo), vec![]);
+        assert_eq!(store.get(DBCol::BlockInfo, b"a").unwrap(), Some(b"3".to_vec()));
+    }
+
+    #[test]
+    fn test_uncommitted_update_is_not_visible() {
+        let store = Store::new();
+        let mut update = store.store_update();
+        update.set_ser(DBCol::EpochInfo, b"key", &1u64).unwrap();
+        assert_eq!(store.get(DBCol::EpochInfo, b"key").unwrap(), None);
+        update.commit().unwrap();
+        assert!(store.exists(DBCol::EpochInfo, b"key").unwrap());
+    }
+}
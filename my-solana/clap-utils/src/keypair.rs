@@ -0,0 +1,227 @@
+//! Prompt-driven key recovery helpers.
+//!
+//! Prompts are abstracted behind [`PromptSource`] so commands can run
+//! unattended: CI provides responses through environment variables or a
+//! response file instead of a TTY, and missing input surfaces as an error
+//! the caller maps to an exit code rather than a hard `exit(1)`.
+
+use solana_sdk::signer::keypair::{
+    Keypair, SeedDerivationConfig, keypair_from_seed_phrase_and_passphrase_with_config,
+};
+use solana_sdk::signer::{SeedDerivable, Signer};
+use std::collections::HashMap;
+use std::error;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+#[derive(thiserror::Error, Debug)]
+pub enum PromptError {
+    #[error("input '{0}' is not available from a non-interactive source")]
+    InputUnavailable(String),
+    #[error("recovered pubkey {0} was not confirmed")]
+    ConfirmationDeclined(String),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Where prompt responses come from.
+pub trait PromptSource {
+    /// Returns the response for the prompt identified by `name` (e.g.
+    /// "seed-phrase"), displaying `prompt` where interaction is possible.
+    fn read(&self, name: &str, prompt: &str) -> Result<String, PromptError>;
+}
+
+/// Reads responses interactively from stdin, echoing the prompt to stderr.
+pub struct TtyPromptSource;
+
+impl PromptSource for TtyPromptSource {
+    fn read(&self, _name: &str, prompt: &str) -> Result<String, PromptError> {
+        eprint!("{prompt}");
+        std::io::stderr().flush()?;
+        let mut line = String::new();
+        std::io::stdin().lock().read_line(&mut line)?;
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
+    }
+}
+
+/// Reads responses from environment variables named `{prefix}_{NAME}`, with
+/// the prompt name uppercased and dashes replaced by underscores, e.g.
+/// `SOLANA_SEED_PHRASE`.
+pub struct EnvPromptSource {
+    prefix: String,
+}
+
+impl EnvPromptSource {
+    pub fn new(prefix: &str) -> Self {
+        Self { prefix: prefix.to_string() }
+    }
+}
+
+impl Default for EnvPromptSource {
+    fn default() -> Self {
+        Self::new("SOLANA")
+    }
+}
+
+impl PromptSource for EnvPromptSource {
+    fn read(&self, name: &str, _prompt: &str) -> Result<String, PromptError> {
+        let var = format!("{}_{}", self.prefix, name.to_uppercase().replace('-', "_"));
+        std::env::var(&var).map_err(|_| PromptError::InputUnavailable(name.to_string()))
+    }
+}
+
+/// Reads responses from a file of `name=value` lines; empty lines and lines
+/// starting with `#` are skipped.
+pub struct FilePromptSource {
+    responses: HashMap<String, String>,
+}
+
+impl FilePromptSource {
+    pub fn load<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        let responses = contents
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                line.split_once('=').map(|(name, value)| (name.to_string(), value.to_string()))
+            })
+            .collect();
+        Ok(Self { responses })
+    }
+}
+
+impl PromptSource for FilePromptSource {
+    fn read(&self, name: &str, _prompt: &str) -> Result<String, PromptError> {
+        self.responses
+            .get(name)
+            .cloned()
+            .ok_or_else(|| PromptError::InputUnavailable(name.to_string()))
+    }
+}
+
+/// The prompt source implied by a `--no-interactive` flag: environment
+/// variables when set, the TTY otherwise.
+pub fn prompt_source(no_interactive: bool) -> Box<dyn PromptSource> {
+    if no_interactive { Box::new(EnvPromptSource::default()) } else { Box::new(TtyPromptSource) }
+}
+
+pub fn prompt_passphrase(source: &dyn PromptSource) -> Result<String, PromptError> {
+    source.read("passphrase", "Enter passphrase (empty for none): ")
+}
+
+/// Recovers a key from a prompted seed phrase and passphrase.
+pub fn encodable_key_from_seed_phrase<K: SeedDerivable>(
+    key_name: &str,
+    source: &dyn PromptSource,
+) -> Result<K, Box<dyn error::Error>> {
+    let seed_phrase =
+        source.read("seed-phrase", &format!("[{key_name}] recover from seed phrase: "))?;
+    let passphrase = prompt_passphrase(source)?;
+    K::from_seed_phrase_and_passphrase(seed_phrase.trim(), &passphrase)
+}
+
+/// Asks for confirmation that the recovered pubkey is the expected one;
+/// declining or not being able to answer is an error, not an exit.
+pub fn confirm_encodable_keypair_pubkey(
+    keypair: &Keypair,
+    source: &dyn PromptSource,
+) -> Result<(), PromptError> {
+    let pubkey = keypair.pubkey();
+    let answer = source
+        .read("confirm-pubkey", &format!("Recovered pubkey `{pubkey}`. Continue? (y/n): "))?;
+    match answer.trim().to_lowercase().as_str() {
+        "y" | "yes" => Ok(()),
+        _ => Err(PromptError::ConfirmationDeclined(pubkey.to_string())),
+    }
+}
+
+/// Recovers a [`Keypair`] from a prompted seed phrase, optionally confirming
+/// the recovered pubkey through the same source.
+pub fn keypair_from_seed_phrase(
+    key_name: &str,
+    confirm_pubkey: bool,
+    source: &dyn PromptSource,
+) -> Result<Keypair, Box<dyn error::Error>> {
+    keypair_from_seed_phrase_with_config(
+        key_name,
+        confirm_pubkey,
+        source,
+        &SeedDerivationConfig::default(),
+    )
+}
+
+/// Like [`keypair_from_seed_phrase`], deriving under a caller-chosen
+/// [`SeedDerivationConfig`] instead of the BIP-39 default.
+pub fn keypair_from_seed_phrase_with_config(
+    key_name: &str,
+    confirm_pubkey: bool,
+    source: &dyn PromptSource,
+    config: &SeedDerivationConfig,
+) -> Result<Keypair, Box<dyn error::Error>> {
+    let seed_phrase =
+        source.read("seed-phrase", &format!("[{key_name}] recover from seed phrase: "))?;
+    let passphrase = prompt_passphrase(source)?;
+    let keypair =
+        keypair_from_seed_phrase_and_passphrase_with_config(seed_phrase.trim(), &passphrase, config)?;
+    if confirm_pubkey {
+        confirm_encodable_keypair_pubkey(&keypair, source)?;
+    }
+    Ok(keypair)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signer::keypair::keypair_from_seed_phrase_and_passphrase;
+
+    const SEED_PHRASE: &str =
+        "lend ability amused industry toe sweet plastic vacuum cereal mobile sad drill";
+
+    fn set_var(name: &str, value: &str) {
+        // SAFETY: tests touching the environment use unique variable names.
+        unsafe { std::env::set_var(name, value) };
+    }
+
+    #[test]
+    fn test_recovery_from_env_without_tty() {
+        set_var("TEST_RECOVERY_SEED_PHRASE", SEED_PHRASE);
+        set_var("TEST_RECOVERY_PASSPHRASE", "secret");
+        let source = EnvPromptSource::new("TEST_RECOVERY");
+        let keypair = keypair_from_seed_phrase("keypair", false, &source).unwrap();
+        let expected = keypair_from_seed_phrase_and_passphrase(SEED_PHRASE, "secret").unwrap();
+        assert_eq!(keypair.pubkey(), expected.pubkey());
+    }
+
+    #[test]
+    fn test_missing_env_input_is_a_clean_error() {
+        let source = EnvPromptSource::new("TEST_MISSING");
+        let err = keypair_from_seed_phrase("keypair", false, &source).err().unwrap();
+        assert_eq!(err.to_string(), "input 'seed-phrase' is not available from a non-interactive source");
+    }
+
+    #[test]
+    fn test_recovery_from_file() {
+        let dir = std::env::temp_dir();
+        let path = dir.join("prompt_responses_test.txt");
+        std::fs::write(
+            &path,
+            format!("# recovery inputs\nseed-phrase={SEED_PHRASE}\npassphrase=secret\nconfirm-pubkey=y\n"),
+        )
+        .unwrap();
+        let source = FilePromptSource::load(&path).unwrap();
+        let keypair = keypair_from_seed_phrase("keypair", true, &source).unwrap();
+        let expected = keypair_from_seed_phrase_and_passphrase(SEED_PHRASE, "secret").unwrap();
+        assert_eq!(keypair.pubkey(), expected.pubkey());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_declined_confirmation_is_an_error() {
+        set_var("TEST_DECLINED_SEED_PHRASE", SEED_PHRASE);
+        set_var("TEST_DECLINED_PASSPHRASE", "");
+        set_var("TEST_DECLINED_CONFIRM_PUBKEY", "n");
+        let source = EnvPromptSource::new("TEST_DECLINED");
+        let err = keypair_from_seed_phrase("keypair", true, &source).err().unwrap();
+        assert!(err.to_string().contains("was not confirmed"), "{err}");
+    }
+}
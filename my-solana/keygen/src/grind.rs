@@ -0,0 +1,135 @@
+//! Vanity keypair grinding: generate keypairs until their base58 pubkeys
+//! start with the requested prefixes.
+
+use solana_sdk::signer::keypair::Keypair;
+use solana_sdk::signer::{EncodableKey, Signer};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum GrindError {
+    #[error("expected PREFIX:COUNT, got '{0}'")]
+    MalformedSpec(String),
+    #[error("'{0}' contains characters that cannot appear in a base58 pubkey")]
+    NotBase58(String),
+    #[error("count must be at least 1")]
+    ZeroCount,
+}
+
+/// A single `--starts-with PREFIX:COUNT` specification: grind until `count`
+/// keypairs with a base58 pubkey starting with `prefix` have been found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GrindSpec {
+    pub prefix: String,
+    pub count: u64,
+}
+
+impl FromStr for GrindSpec {
+    type Err = GrindError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (prefix, count) =
+            s.split_once(':').ok_or_else(|| GrindError::MalformedSpec(s.to_string()))?;
+        if prefix.is_empty() || !prefix.chars().all(|c| BASE58_ALPHABET.contains(c)) {
+            return Err(GrindError::NotBase58(prefix.to_string()));
+        }
+        let count =
+            count.parse::<u64>().map_err(|_| GrindError::MalformedSpec(s.to_string()))?;
+        if count == 0 {
+            return Err(GrindError::ZeroCount);
+        }
+        Ok(Self { prefix: prefix.to_string(), count })
+    }
+}
+
+/// Grinds keypairs across `num_threads` threads until every spec has its
+/// count of matches, writing each match to `<output_dir>/<pubkey>.json`.
+/// Returns the paths of the written keypair files.
+pub fn grind(
+    specs: &[GrindSpec],
+    output_dir: &Path,
+    num_threads: usize,
+) -> std::io::Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(output_dir)?;
+    let remaining: Vec<(&GrindSpec, AtomicU64)> =
+        specs.iter().map(|spec| (spec, AtomicU64::new(spec.count))).collect();
+    let written: Mutex<std::io::Result<Vec<PathBuf>>> = Mutex::new(Ok(Vec::new()));
+
+    std::thread::scope(|scope| {
+        for _ in 0..num_threads.max(1) {
+            scope.spawn(|| {
+                loop {
+                    if remaining.iter().all(|(_, left)| left.load(Ordering::Relaxed) == 0) {
+                        return;
+                    }
+                    let keypair = Keypair::new();
+                    let pubkey = keypair.pubkey().to_string();
+                    for (spec, left) in &remaining {
+                        if !pubkey.starts_with(&spec.prefix) {
+                            continue;
+                        }
+                        // Claim one of the remaining slots for this spec.
+                        let claimed = left
+                            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| {
+                                left.checked_sub(1)
+                            })
+                            .is_ok();
+                        if !claimed {
+                            continue;
+                        }
+                        let path = output_dir.join(format!("{pubkey}.json"));
+                        let result = keypair.write_to_file(&path).map(|_| path).map_err(|err| {
+                            std::io::Error::other(err.to_string())
+                        });
+                        let mut written = written.lock().expect("grind lock poisoned");
+                        match (&mut *written, result) {
+                            (Ok(paths), Ok(path)) => paths.push(path),
+                            (Ok(_), Err(err)) => *written = Err(err),
+                            (Err(_), _) => {}
+                        }
+                    }
+                }
+            });
+        }
+    });
+    written.into_inner().expect("grind lock poisoned")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_parsing() {
+        assert_eq!(
+            "ab:3".parse::<GrindSpec>().unwrap(),
+            GrindSpec { prefix: "ab".to_string(), count: 3 }
+        );
+        assert_eq!("ab".parse::<GrindSpec>(), Err(GrindError::MalformedSpec("ab".to_string())));
+        assert_eq!("ab:x".parse::<GrindSpec>(), Err(GrindError::MalformedSpec("ab:x".to_string())));
+        assert_eq!("ab:0".parse::<GrindSpec>(), Err(GrindError::ZeroCount));
+        // '0', 'O', 'I' and 'l' never appear in base58.
+        assert_eq!("O0:1".parse::<GrindSpec>(), Err(GrindError::NotBase58("O0".to_string())));
+        assert_eq!(":1".parse::<GrindSpec>(), Err(GrindError::NotBase58(String::new())));
+    }
+
+    #[test]
+    fn test_grind_finds_one_char_prefix() {
+        let output_dir = std::env::temp_dir().join("keygen_grind_test");
+        let _ = std::fs::remove_dir_all(&output_dir);
+        let spec: GrindSpec = "a:1".parse().unwrap();
+        let written = grind(std::slice::from_ref(&spec), &output_dir, 2).unwrap();
+        assert_eq!(written.len(), 1);
+
+        let path = &written[0];
+        let pubkey_from_name = path.file_stem().unwrap().to_str().unwrap();
+        assert!(pubkey_from_name.starts_with("a"), "{pubkey_from_name}");
+        let keypair = Keypair::read_from_file(path).unwrap();
+        assert_eq!(keypair.pubkey().to_string(), pubkey_from_name);
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+}
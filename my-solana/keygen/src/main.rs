@@ -0,0 +1,106 @@
+use clap::{Parser, Subcommand};
+use solana_clap_utils::keypair::{keypair_from_seed_phrase_with_config, prompt_source};
+use solana_keygen::grind::{GrindSpec, grind};
+use solana_sdk::signer::keypair::SeedDerivationConfig;
+use solana_sdk::signer::{EncodableKey, Signer};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(name = "solana-keygen", about = "Keypair utilities")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Grind for vanity keypairs whose base58 pubkeys start with the given
+    /// prefixes.
+    Grind {
+        /// Prefix to search for and how many matches to find, as
+        /// PREFIX:COUNT; may be given multiple times.
+        #[arg(long = "starts-with", value_name = "PREFIX:COUNT", required = true)]
+        starts_with: Vec<GrindSpec>,
+        /// Directory the matching keypair files are written to.
+        #[arg(long, default_value = ".")]
+        output_dir: PathBuf,
+        /// Number of grinding threads; defaults to the number of cpus.
+        #[arg(long)]
+        num_threads: Option<usize>,
+    },
+    /// Recover a keypair from a seed phrase and optional passphrase.
+    Recover {
+        /// Path the recovered keypair file is written to.
+        outfile: PathBuf,
+        /// Read prompt responses from the environment instead of a TTY.
+        #[arg(long)]
+        no_interactive: bool,
+        /// Skip confirmation of the recovered pubkey.
+        #[arg(long)]
+        skip_seed_phrase_validation: bool,
+        /// Number of PBKDF2 rounds to stretch the seed phrase with.
+        /// Anything other than the BIP-39 standard 2048 produces a key no
+        /// other BIP-39 tool can recover.
+        #[arg(long, value_name = "ROUNDS")]
+        pbkdf2_rounds: Option<u32>,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match cli.command {
+        Command::Grind { starts_with, output_dir, num_threads } => {
+            let num_threads = num_threads.unwrap_or_else(|| {
+                std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+            });
+            match grind(&starts_with, &output_dir, num_threads) {
+                Ok(written) => {
+                    for path in written {
+                        println!("Wrote keypair to {}", path.display());
+                    }
+                    ExitCode::SUCCESS
+                }
+                Err(err) => {
+                    eprintln!("error: {err}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Command::Recover { outfile, no_interactive, skip_seed_phrase_validation, pbkdf2_rounds } => {
+            let config = match pbkdf2_rounds {
+                Some(pbkdf2_rounds) => SeedDerivationConfig { pbkdf2_rounds },
+                None => SeedDerivationConfig::default(),
+            };
+            if !config.is_bip39_compatible() {
+                eprintln!(
+                    "warning: {} PBKDF2 rounds is not the BIP-39 standard; \
+                     the key will not be recoverable by other BIP-39 tools",
+                    config.pbkdf2_rounds
+                );
+            }
+            let source = prompt_source(no_interactive);
+            let result = keypair_from_seed_phrase_with_config(
+                "recover",
+                !skip_seed_phrase_validation,
+                source.as_ref(),
+                &config,
+            )
+            .and_then(|keypair| {
+                keypair.write_to_file(&outfile)?;
+                Ok(keypair)
+            });
+            match result {
+                Ok(keypair) => {
+                    println!("Wrote recovered keypair to {}", outfile.display());
+                    println!("pubkey: {}", keypair.pubkey());
+                    ExitCode::SUCCESS
+                }
+                Err(err) => {
+                    eprintln!("error: {err}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
+    }
+}
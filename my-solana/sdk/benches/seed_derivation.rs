@@ -0,0 +1,32 @@
+//! How long stretching a seed phrase into a keypair takes at the BIP-39
+//! standard round count versus a hardened one.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use solana_sdk::signer::keypair::{SeedDerivationConfig, generate_seed_with_rounds};
+use std::hint::black_box;
+
+const SEED_PHRASE: &str =
+    "lend ability amused industry toe sweet plastic vacuum cereal mobile sad drill";
+
+fn bench_seed_derivation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("seed_derivation");
+    group.sample_size(10);
+    group.bench_function("bip39_2048_rounds", |b| {
+        b.iter(|| {
+            generate_seed_with_rounds(
+                black_box(SEED_PHRASE),
+                black_box("secret"),
+                SeedDerivationConfig::BIP39.pbkdf2_rounds,
+            )
+        })
+    });
+    group.bench_function("hardened_131072_rounds", |b| {
+        b.iter(|| {
+            generate_seed_with_rounds(black_box(SEED_PHRASE), black_box("secret"), 1 << 17)
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_seed_derivation);
+criterion_main!(benches);
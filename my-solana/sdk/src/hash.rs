@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+pub const HASH_BYTES: usize = 32;
+
+/// A recent blockhash placed into a transaction message, proving the
+/// transaction was built recently and making its signatures unique.
+///
+/// For offline signing the blockhash is obtained out of band and supplied to
+/// the transaction builder explicitly.
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+pub struct RecentBlockhash(pub(crate) [u8; HASH_BYTES]);
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseHashError {
+    #[error("String is the wrong size")]
+    WrongSize,
+    #[error("Invalid Base58 string")]
+    Invalid,
+}
+
+impl RecentBlockhash {
+    pub const fn new_from_array(bytes: [u8; HASH_BYTES]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn to_bytes(self) -> [u8; HASH_BYTES] {
+        self.0
+    }
+}
+
+impl AsRef<[u8]> for RecentBlockhash {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Display for RecentBlockhash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", bs58::encode(&self.0).into_string())
+    }
+}
+
+impl fmt::Debug for RecentBlockhash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", bs58::encode(&self.0).into_string())
+    }
+}
+
+impl FromStr for RecentBlockhash {
+    type Err = ParseHashError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = bs58::decode(s).into_vec().map_err(|_| ParseHashError::Invalid)?;
+        let bytes: [u8; HASH_BYTES] = bytes.try_into().map_err(|_| ParseHashError::WrongSize)?;
+        Ok(Self(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_from_str_round_trip() {
+        let blockhash = RecentBlockhash::new_from_array([42; 32]);
+        assert_eq!(RecentBlockhash::from_str(&blockhash.to_string()).unwrap(), blockhash);
+    }
+}
@@ -0,0 +1,19 @@
+use crate::pubkey::Pubkey;
+use serde::{Deserialize, Serialize};
+
+/// A directive for a program to run against a set of accounts.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Instruction {
+    /// The program that executes this instruction.
+    pub program_id: Pubkey,
+    /// Accounts the instruction touches.
+    pub accounts: Vec<Pubkey>,
+    /// Opaque program input.
+    pub data: Vec<u8>,
+}
+
+impl Instruction {
+    pub fn new(program_id: Pubkey, accounts: Vec<Pubkey>, data: Vec<u8>) -> Self {
+        Self { program_id, accounts, data }
+    }
+}
@@ -0,0 +1,8 @@
+pub mod hash;
+pub mod instruction;
+pub mod message;
+pub mod offline;
+pub mod pubkey;
+pub mod signature;
+pub mod signer;
+pub mod transaction;
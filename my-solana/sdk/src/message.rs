@@ -0,0 +1,50 @@
+use crate::hash::RecentBlockhash;
+use crate::instruction::Instruction;
+use crate::pubkey::Pubkey;
+use serde::{Deserialize, Serialize};
+
+/// The content of a transaction that gets signed: the signers it expects, the
+/// blockhash anchoring it in time, and the instructions to run.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Message {
+    /// Pubkeys that must sign this message, in signature order.
+    pub signer_pubkeys: Vec<Pubkey>,
+    /// The blockhash of a recent block.
+    pub recent_blockhash: RecentBlockhash,
+    /// Instructions to execute, in order.
+    pub instructions: Vec<Instruction>,
+}
+
+impl Message {
+    pub fn new(instructions: Vec<Instruction>, signer_pubkeys: Vec<Pubkey>) -> Self {
+        Self { signer_pubkeys, recent_blockhash: RecentBlockhash::default(), instructions }
+    }
+
+    /// The bytes that signers sign.
+    pub fn serialize(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("message serialization cannot fail")
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, bincode::Error> {
+        bincode::deserialize(bytes)
+    }
+
+    /// The position of `pubkey` in the expected signer list, if present.
+    pub fn signer_position(&self, pubkey: &Pubkey) -> Option<usize> {
+        self.signer_pubkeys.iter().position(|signer| signer == pubkey)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize_round_trip() {
+        let message = Message::new(
+            vec![Instruction::new(Pubkey::new_from_array([1; 32]), vec![], vec![1, 2, 3])],
+            vec![Pubkey::new_from_array([2; 32])],
+        );
+        assert_eq!(Message::deserialize(&message.serialize()).unwrap(), message);
+    }
+}
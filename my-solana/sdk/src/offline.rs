@@ -0,0 +1,184 @@
+//! Helpers for signing a transaction on a machine with no network access.
+//!
+//! The online machine builds an unsigned [`Message`] with an explicit recent
+//! blockhash and encodes it with [`OfflineTransaction::encode_message`]. The
+//! base64 string is transported to the air-gapped machine, which checks it,
+//! signs with [`OfflineTransaction::sign_message`] and sends back the
+//! detached `(pubkey, signature)` pairs. The online machine reassembles a
+//! fully signed [`Transaction`] with [`OfflineTransaction::combine`].
+
+use crate::message::Message;
+use crate::pubkey::Pubkey;
+use crate::signature::Signature;
+use crate::signer::{Signer, SignerError};
+use crate::transaction::Transaction;
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum OfflineError {
+    #[error("transported message is not valid base64 or does not deserialize")]
+    InvalidMessage,
+    #[error("signature provided for {0}, which the message does not expect")]
+    UnknownSigner(Pubkey),
+    #[error("signature for {0} does not verify over the message")]
+    InvalidSignature(Pubkey),
+    #[error("no signature provided for expected signer {0}")]
+    MissingSignature(Pubkey),
+    #[error(transparent)]
+    SignerError(#[from] SignerError),
+}
+
+pub struct OfflineTransaction;
+
+impl OfflineTransaction {
+    /// Serializes an unsigned message to base64 for transport.
+    pub fn encode_message(message: &Message) -> String {
+        BASE64.encode(message.serialize())
+    }
+
+    /// Decodes a transported message.
+    pub fn decode_message(message_b64: &str) -> Result<Message, OfflineError> {
+        let bytes = BASE64.decode(message_b64).map_err(|_| OfflineError::InvalidMessage)?;
+        Message::deserialize(&bytes).map_err(|_| OfflineError::InvalidMessage)
+    }
+
+    /// Produces a detached signature for a transported message. Run on the
+    /// air-gapped machine.
+    pub fn sign_message(
+        message_b64: &str,
+        signer: &dyn Signer,
+    ) -> Result<(Pubkey, Signature), OfflineError> {
+        let message = Self::decode_message(message_b64)?;
+        let pubkey = signer.try_pubkey()?;
+        if message.signer_position(&pubkey).is_none() {
+            return Err(OfflineError::UnknownSigner(pubkey));
+        }
+        let signature = signer.try_sign_message(&message.serialize())?;
+        Ok((pubkey, signature))
+    }
+
+    /// Reassembles a fully signed transaction from the transported message
+    /// and the detached signatures collected from the signing machines.
+    ///
+    /// Every signature must belong to a signer the message expects and must
+    /// verify over the message; every expected signer must be covered.
+    pub fn combine(
+        message_b64: &str,
+        signatures: &[(Pubkey, Signature)],
+    ) -> Result<Transaction, OfflineError> {
+        let message = Self::decode_message(message_b64)?;
+        let serialized = message.serialize();
+        let mut transaction = Transaction::new_unsigned(message);
+        for (pubkey, signature) in signatures {
+            let position = transaction
+                .message
+                .signer_position(pubkey)
+                .ok_or(OfflineError::UnknownSigner(*pubkey))?;
+            if !signature.verify(pubkey, &serialized) {
+                return Err(OfflineError::InvalidSignature(*pubkey));
+            }
+            transaction.signatures[position] = *signature;
+        }
+        for (pubkey, signature) in
+            transaction.message.signer_pubkeys.iter().zip(&transaction.signatures)
+        {
+            if *signature == Signature::default() {
+                return Err(OfflineError::MissingSignature(*pubkey));
+            }
+        }
+        Ok(transaction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::RecentBlockhash;
+    use crate::instruction::Instruction;
+    use crate::signer::keypair::Keypair;
+    use crate::signer::presigner::Presigner;
+
+    fn unsigned_message(signers: Vec<Pubkey>) -> Message {
+        let mut message = Message::new(
+            vec![Instruction::new(Pubkey::new_from_array([9; 32]), signers.clone(), vec![7])],
+            signers,
+        );
+        message.recent_blockhash = RecentBlockhash::new_from_array([1; 32]);
+        message
+    }
+
+    #[test]
+    fn test_offline_round_trip_with_two_signers() {
+        // Online machine: build the message and transport it.
+        let machine_a = Keypair::new();
+        let machine_b = Keypair::new();
+        let message = unsigned_message(vec![machine_a.pubkey(), machine_b.pubkey()]);
+        let transported = OfflineTransaction::encode_message(&message);
+
+        // Each air-gapped machine signs independently.
+        let sig_a = OfflineTransaction::sign_message(&transported, &machine_a).unwrap();
+        let sig_b = OfflineTransaction::sign_message(&transported, &machine_b).unwrap();
+
+        // Online machine: reassemble and verify, exercising Presigner too.
+        let presigner = Presigner::new(&sig_b.0, &sig_b.1);
+        let sig_b = (presigner.pubkey(), presigner.sign_message(&message.serialize()));
+        let transaction = OfflineTransaction::combine(&transported, &[sig_a, sig_b]).unwrap();
+        assert!(transaction.is_signed());
+        assert_eq!(transaction.message, message);
+    }
+
+    #[test]
+    fn test_tampered_message_detected() {
+        let signer = Keypair::new();
+        let message = unsigned_message(vec![signer.pubkey()]);
+        let transported = OfflineTransaction::encode_message(&message);
+        let signature = OfflineTransaction::sign_message(&transported, &signer).unwrap();
+
+        // An attacker swaps the message in transit after signing.
+        let mut tampered = message.clone();
+        tampered.instructions[0].data = vec![66];
+        let tampered_b64 = OfflineTransaction::encode_message(&tampered);
+        assert_eq!(
+            OfflineTransaction::combine(&tampered_b64, &[signature]),
+            Err(OfflineError::InvalidSignature(signer.pubkey()))
+        );
+    }
+
+    #[test]
+    fn test_unknown_signer_distinguished_from_invalid_signature() {
+        let signer = Keypair::new();
+        let stranger = Keypair::new();
+        let message = unsigned_message(vec![signer.pubkey()]);
+        let transported = OfflineTransaction::encode_message(&message);
+
+        let stranger_sig = stranger.sign_message(&message.serialize());
+        assert_eq!(
+            OfflineTransaction::combine(&transported, &[(stranger.pubkey(), stranger_sig)]),
+            Err(OfflineError::UnknownSigner(stranger.pubkey()))
+        );
+        assert_eq!(
+            OfflineTransaction::sign_message(&transported, &stranger),
+            Err(OfflineError::UnknownSigner(stranger.pubkey()))
+        );
+
+        let bogus = Signature::new_from_array([8; 64]);
+        assert_eq!(
+            OfflineTransaction::combine(&transported, &[(signer.pubkey(), bogus)]),
+            Err(OfflineError::InvalidSignature(signer.pubkey()))
+        );
+    }
+
+    #[test]
+    fn test_missing_signature_reported() {
+        let machine_a = Keypair::new();
+        let machine_b = Keypair::new();
+        let message = unsigned_message(vec![machine_a.pubkey(), machine_b.pubkey()]);
+        let transported = OfflineTransaction::encode_message(&message);
+        let sig_a = OfflineTransaction::sign_message(&transported, &machine_a).unwrap();
+        assert_eq!(
+            OfflineTransaction::combine(&transported, &[sig_a]),
+            Err(OfflineError::MissingSignature(machine_b.pubkey()))
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+pub const PUBKEY_BYTES: usize = 32;
+
+/// The address of an account, the ed25519 public key of its owner.
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+pub struct Pubkey(pub(crate) [u8; PUBKEY_BYTES]);
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParsePubkeyError {
+    #[error("String is the wrong size")]
+    WrongSize,
+    #[error("Invalid Base58 string")]
+    Invalid,
+}
+
+impl Pubkey {
+    pub const fn new_from_array(bytes: [u8; PUBKEY_BYTES]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn to_bytes(self) -> [u8; PUBKEY_BYTES] {
+        self.0
+    }
+}
+
+impl AsRef<[u8]> for Pubkey {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Display for Pubkey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", bs58::encode(&self.0).into_string())
+    }
+}
+
+impl fmt::Debug for Pubkey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", bs58::encode(&self.0).into_string())
+    }
+}
+
+impl FromStr for Pubkey {
+    type Err = ParsePubkeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = bs58::decode(s).into_vec().map_err(|_| ParsePubkeyError::Invalid)?;
+        let bytes: [u8; PUBKEY_BYTES] =
+            bytes.try_into().map_err(|_| ParsePubkeyError::WrongSize)?;
+        Ok(Self(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_from_str_round_trip() {
+        let pubkey = Pubkey::new_from_array([7; 32]);
+        assert_eq!(Pubkey::from_str(&pubkey.to_string()).unwrap(), pubkey);
+    }
+
+    #[test]
+    fn test_from_str_errors() {
+        assert_eq!(Pubkey::from_str("abc"), Err(ParsePubkeyError::WrongSize));
+        assert_eq!(Pubkey::from_str("I0I0I0"), Err(ParsePubkeyError::Invalid));
+    }
+}
@@ -0,0 +1,90 @@
+use crate::pubkey::Pubkey;
+use ed25519_dalek::Verifier;
+use std::fmt;
+use std::str::FromStr;
+
+pub const SIGNATURE_BYTES: usize = 64;
+
+/// An ed25519 signature over a serialized transaction message.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Signature([u8; SIGNATURE_BYTES]);
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseSignatureError {
+    #[error("String is the wrong size")]
+    WrongSize,
+    #[error("Invalid Base58 string")]
+    Invalid,
+}
+
+impl Signature {
+    pub const fn new_from_array(bytes: [u8; SIGNATURE_BYTES]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn to_bytes(self) -> [u8; SIGNATURE_BYTES] {
+        self.0
+    }
+
+    /// Verifies the signature over `message` against the given public key.
+    pub fn verify(&self, pubkey: &Pubkey, message: &[u8]) -> bool {
+        let Ok(verifying_key) = ed25519_dalek::VerifyingKey::from_bytes(&pubkey.to_bytes())
+        else {
+            return false;
+        };
+        let signature = ed25519_dalek::Signature::from_bytes(&self.0);
+        verifying_key.verify(message, &signature).is_ok()
+    }
+}
+
+impl Default for Signature {
+    fn default() -> Self {
+        Self([0; SIGNATURE_BYTES])
+    }
+}
+
+impl AsRef<[u8]> for Signature {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<ed25519_dalek::Signature> for Signature {
+    fn from(signature: ed25519_dalek::Signature) -> Self {
+        Self(signature.to_bytes())
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", bs58::encode(&self.0).into_string())
+    }
+}
+
+impl fmt::Debug for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", bs58::encode(&self.0).into_string())
+    }
+}
+
+impl FromStr for Signature {
+    type Err = ParseSignatureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = bs58::decode(s).into_vec().map_err(|_| ParseSignatureError::Invalid)?;
+        let bytes: [u8; SIGNATURE_BYTES] =
+            bytes.try_into().map_err(|_| ParseSignatureError::WrongSize)?;
+        Ok(Self(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_from_str_round_trip() {
+        let signature = Signature::new_from_array([3; 64]);
+        assert_eq!(Signature::from_str(&signature.to_string()).unwrap(), signature);
+    }
+}
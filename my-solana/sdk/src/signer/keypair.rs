@@ -0,0 +1,265 @@
+use crate::pubkey::Pubkey;
+use crate::signature::Signature;
+use crate::signer::{EncodableKey, SeedDerivable, Signer, SignerError};
+use ed25519_dalek::Signer as DalekSigner;
+use std::error;
+use std::io::{Read, Write};
+
+/// An ed25519 keypair held in memory.
+pub struct Keypair(ed25519_dalek::SigningKey);
+
+impl Keypair {
+    /// Generates a new random keypair.
+    pub fn new() -> Self {
+        Self(ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng))
+    }
+
+    /// Recovers a keypair from a 32-byte secret seed.
+    pub fn from_seed(seed: &[u8; 32]) -> Self {
+        Self(ed25519_dalek::SigningKey::from_bytes(seed))
+    }
+
+    /// Recovers a keypair from 64 bytes: the secret seed followed by the
+    /// public key, the layout used by keypair files.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SignerError> {
+        let keypair_bytes: [u8; 64] = bytes
+            .try_into()
+            .map_err(|_| SignerError::Custom("keypair must be 64 bytes".to_string()))?;
+        let keypair = ed25519_dalek::SigningKey::from_keypair_bytes(&keypair_bytes)
+            .map_err(|_| SignerError::KeypairPubkeyMismatch)?;
+        Ok(Self(keypair))
+    }
+
+    /// The secret seed followed by the public key.
+    pub fn to_bytes(&self) -> [u8; 64] {
+        self.0.to_keypair_bytes()
+    }
+
+    /// The 32-byte secret seed.
+    pub fn secret_bytes(&self) -> [u8; 32] {
+        self.0.to_bytes()
+    }
+}
+
+impl Default for Keypair {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Signer for Keypair {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        Ok(Pubkey::new_from_array(self.0.verifying_key().to_bytes()))
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        Ok(self.0.sign(message).into())
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+impl EncodableKey for Keypair {
+    /// Reads a keypair file: a JSON array of the 64 keypair bytes.
+    fn read<R: Read>(reader: &mut R) -> Result<Self, Box<dyn error::Error>> {
+        let bytes: Vec<u8> = serde_json::from_reader(reader)?;
+        Ok(Self::from_bytes(&bytes)?)
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<String, Box<dyn error::Error>> {
+        let serialized = serde_json::to_string(&self.to_bytes().to_vec())?;
+        writer.write_all(serialized.as_bytes())?;
+        Ok(serialized)
+    }
+}
+
+impl SeedDerivable for Keypair {
+    fn from_seed(seed: &[u8]) -> Result<Self, Box<dyn error::Error>> {
+        if seed.len() < 32 {
+            return Err("seed is too short, expected at least 32 bytes".into());
+        }
+        let seed: &[u8; 32] = seed[..32].try_into().expect("length checked above");
+        Ok(Self::from_seed(seed))
+    }
+
+    fn from_seed_phrase_and_passphrase(
+        seed_phrase: &str,
+        passphrase: &str,
+    ) -> Result<Self, Box<dyn error::Error>> {
+        keypair_from_seed_phrase_and_passphrase(seed_phrase, passphrase)
+    }
+}
+
+/// Tunables of the seed-phrase derivation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SeedDerivationConfig {
+    /// Number of PBKDF2 rounds the seed phrase is stretched with.
+    pub pbkdf2_rounds: u32,
+}
+
+impl SeedDerivationConfig {
+    /// The BIP-39 standard derivation: 2048 rounds.
+    pub const BIP39: Self = Self { pbkdf2_rounds: 2048 };
+
+    /// Whether keys derived under this config can be recovered by other
+    /// BIP-39 tools.
+    pub fn is_bip39_compatible(&self) -> bool {
+        *self == Self::BIP39
+    }
+}
+
+impl Default for SeedDerivationConfig {
+    fn default() -> Self {
+        Self::BIP39
+    }
+}
+
+/// Derives the 64-byte seed for a seed phrase: PBKDF2-HMAC-SHA512 with the
+/// passphrase mixed into the salt, as in BIP-39.
+pub fn generate_seed_from_seed_phrase_and_passphrase(
+    seed_phrase: &str,
+    passphrase: &str,
+) -> Vec<u8> {
+    generate_seed_with_rounds(
+        seed_phrase,
+        passphrase,
+        SeedDerivationConfig::BIP39.pbkdf2_rounds,
+    )
+}
+
+/// Like [`generate_seed_from_seed_phrase_and_passphrase`] with a
+/// caller-chosen round count. Any count other than the BIP-39 standard 2048
+/// produces a seed no other BIP-39 tool can recover.
+pub fn generate_seed_with_rounds(seed_phrase: &str, passphrase: &str, rounds: u32) -> Vec<u8> {
+    const PBKDF2_BYTES: usize = 64;
+    let salt = format!("mnemonic{passphrase}");
+    let mut seed = vec![0u8; PBKDF2_BYTES];
+    pbkdf2::pbkdf2_hmac::<sha2::Sha512>(
+        seed_phrase.as_bytes(),
+        salt.as_bytes(),
+        rounds,
+        &mut seed,
+    );
+    seed
+}
+
+pub fn keypair_from_seed_phrase_and_passphrase(
+    seed_phrase: &str,
+    passphrase: &str,
+) -> Result<Keypair, Box<dyn error::Error>> {
+    keypair_from_seed_phrase_and_passphrase_with_config(
+        seed_phrase,
+        passphrase,
+        &SeedDerivationConfig::default(),
+    )
+}
+
+pub fn keypair_from_seed_phrase_and_passphrase_with_config(
+    seed_phrase: &str,
+    passphrase: &str,
+    config: &SeedDerivationConfig,
+) -> Result<Keypair, Box<dyn error::Error>> {
+    SeedDerivable::from_seed(&generate_seed_with_rounds(
+        seed_phrase,
+        passphrase,
+        config.pbkdf2_rounds,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_verify() {
+        let keypair = Keypair::new();
+        let message = b"hello";
+        let signature = keypair.sign_message(message);
+        assert!(signature.verify(&keypair.pubkey(), message));
+        assert!(!signature.verify(&keypair.pubkey(), b"other"));
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let keypair = Keypair::new();
+        let restored = Keypair::from_bytes(&keypair.to_bytes()).unwrap();
+        assert_eq!(restored.pubkey(), keypair.pubkey());
+    }
+
+    #[test]
+    fn test_encodable_key_round_trip() {
+        let keypair = Keypair::new();
+        let mut buffer = Vec::new();
+        let serialized = keypair.write(&mut buffer).unwrap();
+        assert_eq!(serialized.as_bytes(), &buffer[..]);
+        let restored = Keypair::read(&mut &buffer[..]).unwrap();
+        assert_eq!(restored.pubkey(), keypair.pubkey());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_to_file_restricts_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        let path = std::env::temp_dir().join("keypair_perms_test.json");
+        // Pre-create the file with wide permissions; writing the key must
+        // narrow them, not keep them.
+        std::fs::write(&path, b"stale").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        let keypair = Keypair::new();
+        keypair.write_to_file(&path).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        let restored = Keypair::read_from_file(&path).unwrap();
+        assert_eq!(restored.pubkey(), keypair.pubkey());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_seed_phrase_derivation_is_deterministic() {
+        let phrase = "lend ability amused industry toe sweet plastic vacuum cereal mobile sad drill";
+        let keypair = keypair_from_seed_phrase_and_passphrase(phrase, "secret").unwrap();
+        let same = keypair_from_seed_phrase_and_passphrase(phrase, "secret").unwrap();
+        assert_eq!(keypair.pubkey(), same.pubkey());
+        // The passphrase salts the derivation.
+        let other = keypair_from_seed_phrase_and_passphrase(phrase, "other").unwrap();
+        assert_ne!(keypair.pubkey(), other.pubkey());
+    }
+
+    #[test]
+    fn test_default_derivation_stays_bip39() {
+        let phrase = "lend ability amused industry toe sweet plastic vacuum cereal mobile sad drill";
+        // Pinned BIP-39 seed for the phrase above with passphrase "secret";
+        // keys in the wild depend on the default derivation never changing.
+        let expected_seed = "92fdba417745c53a00e158926dac6b28adddd19c79ef271c0444e3ce08b1a973\
+                             514ce9697f26e80b7d45520738f0dc0ee384b71a3dc41f46b29b6f3bb92f78ce";
+        let seed = generate_seed_from_seed_phrase_and_passphrase(phrase, "secret");
+        let seed_hex: String = seed.iter().map(|byte| format!("{byte:02x}")).collect();
+        assert_eq!(seed_hex, expected_seed.replace(char::is_whitespace, ""));
+        assert_eq!(
+            seed,
+            generate_seed_with_rounds(phrase, "secret", SeedDerivationConfig::BIP39.pbkdf2_rounds)
+        );
+
+        let keypair = keypair_from_seed_phrase_and_passphrase(phrase, "secret").unwrap();
+        assert_eq!(keypair.pubkey().to_string(), "GesGxYgmdtmCE2eJVv9w6eZUU7fuzQeeMipAnn6zf14Y");
+    }
+
+    #[test]
+    fn test_custom_round_count_changes_the_key() {
+        let phrase = "lend ability amused industry toe sweet plastic vacuum cereal mobile sad drill";
+        let config = SeedDerivationConfig { pbkdf2_rounds: 4096 };
+        assert!(!config.is_bip39_compatible());
+        assert_ne!(
+            generate_seed_with_rounds(phrase, "secret", config.pbkdf2_rounds),
+            generate_seed_from_seed_phrase_and_passphrase(phrase, "secret"),
+        );
+        let standard = keypair_from_seed_phrase_and_passphrase(phrase, "secret").unwrap();
+        let custom =
+            keypair_from_seed_phrase_and_passphrase_with_config(phrase, "secret", &config).unwrap();
+        assert_ne!(standard.pubkey(), custom.pubkey());
+    }
+}
+
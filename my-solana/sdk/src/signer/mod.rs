@@ -0,0 +1,148 @@
+use crate::pubkey::Pubkey;
+use crate::signature::Signature;
+use std::error;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+pub mod keypair;
+pub mod presigner;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum SignerError {
+    #[error("keypair-pubkey mismatch")]
+    KeypairPubkeyMismatch,
+    #[error("presigner verification failed")]
+    PresignerError,
+    #[error("custom error: {0}")]
+    Custom(String),
+}
+
+/// An abstraction over anything that can produce a signature for a message:
+/// an in-memory keypair, a hardware wallet, or a signature computed earlier
+/// on another machine (see [`presigner::Presigner`]).
+pub trait Signer {
+    /// The public key this signer signs for. Panics on failure.
+    fn pubkey(&self) -> Pubkey {
+        self.try_pubkey().unwrap()
+    }
+
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError>;
+
+    /// Signs `message`. Panics on failure.
+    fn sign_message(&self, message: &[u8]) -> Signature {
+        self.try_sign_message(message).unwrap()
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError>;
+
+    /// Whether signing requires user interaction, e.g. a hardware wallet.
+    fn is_interactive(&self) -> bool;
+}
+
+impl<T: Signer + ?Sized> Signer for Box<T> {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        self.as_ref().try_pubkey()
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        self.as_ref().try_sign_message(message)
+    }
+
+    fn is_interactive(&self) -> bool {
+        self.as_ref().is_interactive()
+    }
+}
+
+/// Collects the pubkeys of all signers, failing on the first signer that
+/// cannot produce one.
+pub fn collect_pubkeys(signers: &[&dyn Signer]) -> Result<Vec<Pubkey>, SignerError> {
+    signers.iter().map(|signer| signer.try_pubkey()).collect()
+}
+
+/// Removes signers with duplicate pubkeys, keeping the first occurrence; a
+/// transaction must not list the same signer twice.
+pub fn unique_signers(signers: Vec<&dyn Signer>) -> Vec<&dyn Signer> {
+    let mut seen = std::collections::HashSet::new();
+    signers.into_iter().filter(|signer| seen.insert(signer.pubkey())).collect()
+}
+
+/// A key that can be serialized to and deserialized from a reader/writer,
+/// e.g. a keypair file on disk.
+pub trait EncodableKey: Sized {
+    fn read<R: Read>(reader: &mut R) -> Result<Self, Box<dyn error::Error>>;
+
+    fn read_from_file<F: AsRef<Path>>(path: F) -> Result<Self, Box<dyn error::Error>> {
+        let mut file = File::open(path.as_ref())?;
+        Self::read(&mut file)
+    }
+
+    /// Writes the key, returning its serialized form.
+    fn write<W: Write>(&self, writer: &mut W) -> Result<String, Box<dyn error::Error>>;
+
+    /// Writes the key to a file readable only by the current user.
+    ///
+    /// On unix the file is created with mode `0o600` and the mode is
+    /// re-applied even if the file already existed with wider permissions.
+    /// On other platforms the restriction is best effort: the file is
+    /// created with the platform defaults, which on Windows means the
+    /// directory's inherited ACLs decide who can read it.
+    fn write_to_file<F: AsRef<Path>>(&self, path: F) -> Result<String, Box<dyn error::Error>> {
+        #[cfg(unix)]
+        let mut file = {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+            let file = std::fs::OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .mode(0o600)
+                .open(path.as_ref())?;
+            file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+            file
+        };
+        #[cfg(not(unix))]
+        let mut file = File::create(path.as_ref())?;
+        self.write(&mut file)
+    }
+}
+
+/// A key that can be derived from a seed or a seed phrase.
+pub trait SeedDerivable: Sized {
+    fn from_seed(seed: &[u8]) -> Result<Self, Box<dyn error::Error>>;
+
+    fn from_seed_phrase_and_passphrase(
+        seed_phrase: &str,
+        passphrase: &str,
+    ) -> Result<Self, Box<dyn error::Error>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::keypair::Keypair;
+
+    #[test]
+    fn test_collect_pubkeys() {
+        let alice = Keypair::new();
+        let bob = Keypair::new();
+        let boxed: Box<dyn Signer> = Box::new(Keypair::from_bytes(&bob.to_bytes()).unwrap());
+        let pubkeys = collect_pubkeys(&[&alice, &boxed]).unwrap();
+        assert_eq!(pubkeys, vec![alice.pubkey(), bob.pubkey()]);
+    }
+
+    #[test]
+    fn test_unique_signers_dedupes_by_pubkey() {
+        let alice = Keypair::new();
+        let bob = Keypair::new();
+        let alice_again = Keypair::from_bytes(&alice.to_bytes()).unwrap();
+        let unique = unique_signers(vec![&alice, &bob, &alice_again]);
+        assert_eq!(collect_pubkeys(&unique).unwrap(), vec![alice.pubkey(), bob.pubkey()]);
+    }
+
+    #[test]
+    fn test_unique_signers_keeps_distinct_signers() {
+        let signers: Vec<Keypair> = (0..3).map(|_| Keypair::new()).collect();
+        let unique = unique_signers(signers.iter().map(|s| s as &dyn Signer).collect());
+        assert_eq!(unique.len(), 3);
+    }
+}
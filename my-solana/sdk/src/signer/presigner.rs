@@ -0,0 +1,55 @@
+use crate::pubkey::Pubkey;
+use crate::signature::Signature;
+use crate::signer::{Signer, SignerError};
+
+/// A signature produced earlier, typically on an air-gapped machine, wrapped
+/// so it can stand in wherever a [`Signer`] is expected.
+///
+/// Signing only succeeds if the stored signature actually verifies over the
+/// message being signed, so a presigner for one message cannot be replayed on
+/// another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Presigner {
+    pubkey: Pubkey,
+    signature: Signature,
+}
+
+impl Presigner {
+    pub fn new(pubkey: &Pubkey, signature: &Signature) -> Self {
+        Self { pubkey: *pubkey, signature: *signature }
+    }
+}
+
+impl Signer for Presigner {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        Ok(self.pubkey)
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        if self.signature.verify(&self.pubkey, message) {
+            Ok(self.signature)
+        } else {
+            Err(SignerError::PresignerError)
+        }
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer::keypair::Keypair;
+
+    #[test]
+    fn test_presigner_only_signs_its_message() {
+        let keypair = Keypair::new();
+        let message = b"offline";
+        let signature = keypair.sign_message(message);
+        let presigner = Presigner::new(&keypair.pubkey(), &signature);
+        assert_eq!(presigner.try_sign_message(message), Ok(signature));
+        assert_eq!(presigner.try_sign_message(b"replay"), Err(SignerError::PresignerError));
+    }
+}
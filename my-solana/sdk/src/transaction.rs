@@ -0,0 +1,141 @@
+use crate::hash::RecentBlockhash;
+use crate::message::Message;
+use crate::pubkey::Pubkey;
+use crate::signature::Signature;
+use crate::signer::{Signer, SignerError};
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum TransactionError {
+    #[error("signer {0} is not expected by the message")]
+    UnknownSigner(Pubkey),
+    #[error("signature verification failed for {0}")]
+    InvalidSignature(Pubkey),
+    #[error("missing signature for {0}")]
+    MissingSignature(Pubkey),
+    #[error(transparent)]
+    SignerError(#[from] SignerError),
+}
+
+/// A signed message. Signatures are ordered to match
+/// `message.signer_pubkeys`; unsigned positions hold the default signature.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transaction {
+    pub signatures: Vec<Signature>,
+    pub message: Message,
+}
+
+impl Transaction {
+    pub fn new_unsigned(message: Message) -> Self {
+        let signatures = vec![Signature::default(); message.signer_pubkeys.len()];
+        Self { signatures, message }
+    }
+
+    /// Signs the message with each signer, anchoring it to the given recent
+    /// blockhash. Panics on failure; see [`Transaction::try_sign`].
+    pub fn sign(&mut self, signers: &[&dyn Signer], recent_blockhash: RecentBlockhash) {
+        self.try_sign(signers, recent_blockhash).unwrap()
+    }
+
+    /// Signs the message with each signer, anchoring it to the given recent
+    /// blockhash.
+    ///
+    /// The blockhash is taken explicitly rather than fetched so that offline
+    /// workflows can supply one obtained out of band. Changing the blockhash
+    /// invalidates any previously collected signatures, so they are reset.
+    pub fn try_sign(
+        &mut self,
+        signers: &[&dyn Signer],
+        recent_blockhash: RecentBlockhash,
+    ) -> Result<(), TransactionError> {
+        if self.message.recent_blockhash != recent_blockhash {
+            self.message.recent_blockhash = recent_blockhash;
+            self.signatures = vec![Signature::default(); self.message.signer_pubkeys.len()];
+        }
+        let serialized = self.message.serialize();
+        for signer in signers {
+            let pubkey = signer.try_pubkey()?;
+            let position = self
+                .message
+                .signer_position(&pubkey)
+                .ok_or(TransactionError::UnknownSigner(pubkey))?;
+            self.signatures[position] = signer.try_sign_message(&serialized)?;
+        }
+        Ok(())
+    }
+
+    /// Checks that every expected signer has provided a valid signature.
+    pub fn verify(&self) -> Result<(), TransactionError> {
+        let serialized = self.message.serialize();
+        for (pubkey, signature) in self.message.signer_pubkeys.iter().zip(&self.signatures) {
+            if *signature == Signature::default() {
+                return Err(TransactionError::MissingSignature(*pubkey));
+            }
+            if !signature.verify(pubkey, &serialized) {
+                return Err(TransactionError::InvalidSignature(*pubkey));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_signed(&self) -> bool {
+        self.verify().is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::Instruction;
+    use crate::signer::keypair::Keypair;
+
+    fn transfer_like_message(signers: Vec<Pubkey>) -> Message {
+        Message::new(
+            vec![Instruction::new(Pubkey::new_from_array([9; 32]), signers.clone(), vec![1])],
+            signers,
+        )
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let keypair = Keypair::new();
+        let mut tx = Transaction::new_unsigned(transfer_like_message(vec![keypair.pubkey()]));
+        let blockhash = RecentBlockhash::new_from_array([5; 32]);
+        tx.try_sign(&[&keypair], blockhash).unwrap();
+        assert!(tx.is_signed());
+    }
+
+    #[test]
+    fn test_unknown_signer_rejected() {
+        let keypair = Keypair::new();
+        let other = Keypair::new();
+        let mut tx = Transaction::new_unsigned(transfer_like_message(vec![keypair.pubkey()]));
+        let blockhash = RecentBlockhash::new_from_array([5; 32]);
+        assert_eq!(
+            tx.try_sign(&[&other], blockhash),
+            Err(TransactionError::UnknownSigner(other.pubkey()))
+        );
+    }
+
+    #[test]
+    fn test_new_blockhash_resets_signatures() {
+        let keypair = Keypair::new();
+        let mut tx = Transaction::new_unsigned(transfer_like_message(vec![keypair.pubkey()]));
+        tx.try_sign(&[&keypair], RecentBlockhash::new_from_array([5; 32])).unwrap();
+        let signature = tx.signatures[0];
+        tx.try_sign(&[&keypair], RecentBlockhash::new_from_array([6; 32])).unwrap();
+        assert_ne!(tx.signatures[0], signature);
+        assert!(tx.is_signed());
+    }
+
+    #[test]
+    fn test_verify_reports_missing_signature() {
+        let keypair = Keypair::new();
+        let other = Keypair::new();
+        let mut tx = Transaction::new_unsigned(transfer_like_message(vec![
+            keypair.pubkey(),
+            other.pubkey(),
+        ]));
+        tx.try_sign(&[&keypair], RecentBlockhash::new_from_array([5; 32])).unwrap();
+        assert_eq!(tx.verify(), Err(TransactionError::MissingSignature(other.pubkey())));
+    }
+}